@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+
+/// Function applied to each neuron's weighted sum.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Activation {
+    #[default]
+    Relu,
+    Tanh,
+    Sigmoid,
+    Identity,
+}
+
+impl Activation {
+    pub const ALL: [Self; 4] = [Self::Relu, Self::Tanh, Self::Sigmoid, Self::Identity];
+
+    pub fn apply(self, x: f64) -> f64 {
+        match self {
+            Self::Relu => x.max(0.0),
+            Self::Tanh => x.tanh(),
+            Self::Sigmoid => 1.0 / (1.0 + (-x).exp()),
+            Self::Identity => x,
+        }
+    }
+
+    pub(crate) fn apply_f32(self, x: f32) -> f32 {
+        match self {
+            Self::Relu => x.max(0.0),
+            Self::Tanh => x.tanh(),
+            Self::Sigmoid => 1.0 / (1.0 + (-x).exp()),
+            Self::Identity => x,
+        }
+    }
+
+    /// Decodes a continuous gene: rounded to the nearest integer, which
+    /// indexes `ALL`, wrapping around at both ends. Small mutations
+    /// therefore rarely change the activation.
+    pub fn from_gene(gene: f64) -> Self {
+        let idx = gene.round().rem_euclid(Self::ALL.len() as f64);
+        // NaN genes decode to the default
+        Self::ALL.get(idx as usize).copied().unwrap_or_default()
+    }
+
+    /// Gene that decodes back to `self`.
+    pub fn to_gene(self) -> f64 {
+        Self::ALL
+            .iter()
+            .position(|&activation| activation == self)
+            .unwrap() as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gene_round_trip() {
+        for activation in Activation::ALL {
+            assert_eq!(Activation::from_gene(activation.to_gene()), activation);
+            assert_eq!(
+                Activation::from_gene(activation.to_gene() + 0.3),
+                activation
+            );
+        }
+        assert_eq!(Activation::from_gene(-1.0), Activation::Identity);
+        assert_eq!(Activation::from_gene(5.2), Activation::Tanh);
+        assert_eq!(Activation::from_gene(f64::NAN), Activation::Relu);
+    }
+
+    #[test]
+    fn test_apply() {
+        assert_eq!(Activation::Relu.apply(-2.0), 0.0);
+        assert_eq!(Activation::Identity.apply(-2.0), -2.0);
+        approx::assert_relative_eq!(Activation::Sigmoid.apply(0.0), 0.5);
+        approx::assert_relative_eq!(Activation::Tanh.apply(1.0), 1.0_f64.tanh());
+    }
+}
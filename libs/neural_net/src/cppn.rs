@@ -0,0 +1,145 @@
+use serde::{Deserialize, Serialize};
+
+use crate::activation::Activation;
+use crate::layer::Layer;
+use crate::mlp::MLP;
+use crate::neuron::Neuron;
+
+/// Where the neurons of a network sit, for `Cppn` to generate its weights
+/// from: the inputs followed by each layer, layer `l` of `L` at depth
+/// `-1 + 2l/L`, each neuron at a point of its layer's plane.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Substrate {
+    layers: Vec<Vec<[f64; 2]>>,
+}
+
+/// Compositional pattern producing network: a small network mapping the
+/// positions of two neurons to the weight between them, so a large network
+/// can be encoded by a few genes and neighboring neurons get similar
+/// weights, as in HyperNEAT.
+///
+/// Takes the source's and the target's `[x, y, depth]`, and outputs the
+/// weight and the target's bias, which is read with the source at the
+/// origin.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Cppn {
+    mlp: MLP,
+}
+
+impl Substrate {
+    /// Panics without at least the inputs and one layer.
+    pub fn new(layers: Vec<Vec<[f64; 2]>>) -> Self {
+        assert!(layers.len() >= 2, "Substrate needs inputs and a layer");
+        Self { layers }
+    }
+
+    /// `neurons` points spread evenly along x from -1 to 1, at `y`.
+    pub fn row(neurons: usize, y: f64) -> Vec<[f64; 2]> {
+        (0..neurons)
+            .map(|neuron| [-1.0 + (2 * neuron + 1) as f64 / neurons as f64, y])
+            .collect()
+    }
+
+    /// Input count followed by every layer's size, like `MLP::topology`.
+    pub fn topology(&self) -> (usize, Vec<usize>) {
+        let nouts = self.layers[1..].iter().map(Vec::len).collect();
+        (self.layers[0].len(), nouts)
+    }
+
+    fn depth(&self, layer: usize) -> f64 {
+        -1.0 + 2.0 * layer as f64 / (self.layers.len() - 1) as f64
+    }
+}
+
+impl Cppn {
+    pub const INPUTS: usize = 6;
+    pub const OUTPUTS: usize = 2;
+
+    /// Topology of a CPPN with `hidden_layers`, like `MLP::topology`.
+    pub fn topology(hidden_layers: &[usize]) -> (usize, Vec<usize>) {
+        let mut layers = hidden_layers.to_vec();
+        layers.push(Self::OUTPUTS);
+        (Self::INPUTS, layers)
+    }
+
+    /// Reads the weights and biases of a CPPN with `hidden_layers`, like
+    /// `MLP::from_weight_and_biases`. Every layer uses tanh, so generated
+    /// weights and biases are in [-1, 1].
+    pub fn from_weight_and_biases(
+        hidden_layers: &[usize],
+        weights: impl IntoIterator<Item = f64>,
+    ) -> Self {
+        let (nin, nouts) = Self::topology(hidden_layers);
+        let mlp = MLP::from_weight_and_biases(nin, &nouts, weights);
+        let activations = vec![Activation::Tanh; nouts.len()];
+        Self {
+            mlp: mlp.with_activations(&activations),
+        }
+    }
+
+    pub fn weights_and_biases(&self) -> Vec<f64> {
+        self.mlp.weights_and_biases()
+    }
+
+    /// The network laid out on `substrate`, with the default activations.
+    pub fn generate(&self, substrate: &Substrate) -> MLP {
+        let layers = substrate
+            .layers
+            .windows(2)
+            .enumerate()
+            .map(|(idx, pair)| {
+                let [sources, targets] = [&pair[0], &pair[1]];
+                let source_depth = substrate.depth(idx);
+                let target_depth = substrate.depth(idx + 1);
+                let neurons = targets
+                    .iter()
+                    .map(|&[x2, y2]| {
+                        let query = |[x1, y1]: [f64; 2], depth: f64| {
+                            self.mlp.forward(vec![x1, y1, depth, x2, y2, target_depth])
+                        };
+                        let weights = sources
+                            .iter()
+                            .map(|&source| query(source, source_depth)[0])
+                            .collect();
+                        Neuron::new(weights, query([0.0, 0.0], 0.0)[1])
+                    })
+                    .collect();
+                Layer::new(neurons)
+            })
+            .collect();
+        MLP::new(layers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate() {
+        let substrate = Substrate::new(vec![
+            Substrate::row(3, 0.0),
+            Substrate::row(4, 0.0),
+            Substrate::row(2, 0.0),
+        ]);
+        assert_eq!(substrate.topology(), (3, vec![4, 2]));
+
+        // Weight is tanh(x2 - x1), bias is tanh(0.5)
+        let cppn = Cppn::from_weight_and_biases(
+            &[],
+            [
+                [0.0, -1.0, 0.0, 0.0, 1.0, 0.0, 0.0],
+                [0.5, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+            ]
+            .concat(),
+        );
+        let mlp = cppn.generate(&substrate);
+        assert_eq!(mlp.topology(), substrate.topology());
+
+        // From the hidden neurons at x = -0.75 and -0.25 to the output at 0.5
+        let neuron = &mlp.layers[1].neurons[1];
+        approx::assert_relative_eq!(neuron.weights[0], 1.25_f64.tanh());
+        approx::assert_relative_eq!(neuron.weights[1], 0.75_f64.tanh());
+        approx::assert_relative_eq!(neuron.bias, 0.5_f64.tanh());
+    }
+}
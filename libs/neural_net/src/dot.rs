@@ -0,0 +1,80 @@
+//! Dot product used by `Neuron::forward`.
+//!
+//! Products are summed into four independent accumulators, which the
+//! compiler can keep in vector registers, and combined as
+//! `(a0 + a1) + (a2 + a3)` followed by the leftover elements. The wasm
+//! simd128 path (behind the `simd128` feature) uses the same order, so
+//! native and wasm builds produce bit-identical results.
+
+#[cfg(all(
+    feature = "simd128",
+    target_arch = "wasm32",
+    target_feature = "simd128"
+))]
+pub(crate) fn dot(a: &[f64], b: &[f64]) -> f64 {
+    use core::arch::wasm32::{f64x2, f64x2_add, f64x2_extract_lane, f64x2_mul, f64x2_splat};
+
+    assert_eq!(a.len(), b.len());
+
+    let mut acc01 = f64x2_splat(0.0);
+    let mut acc23 = f64x2_splat(0.0);
+    let chunks = a.chunks_exact(4).zip(b.chunks_exact(4));
+    for (a, b) in chunks {
+        acc01 = f64x2_add(acc01, f64x2_mul(f64x2(a[0], a[1]), f64x2(b[0], b[1])));
+        acc23 = f64x2_add(acc23, f64x2_mul(f64x2(a[2], a[3]), f64x2(b[2], b[3])));
+    }
+
+    let sum = (f64x2_extract_lane::<0>(acc01) + f64x2_extract_lane::<1>(acc01))
+        + (f64x2_extract_lane::<0>(acc23) + f64x2_extract_lane::<1>(acc23));
+    tail(sum, a, b)
+}
+
+#[cfg(not(all(
+    feature = "simd128",
+    target_arch = "wasm32",
+    target_feature = "simd128"
+)))]
+pub(crate) fn dot(a: &[f64], b: &[f64]) -> f64 {
+    assert_eq!(a.len(), b.len());
+
+    let mut acc = [0.0; 4];
+    let chunks = a.chunks_exact(4).zip(b.chunks_exact(4));
+    for (a, b) in chunks {
+        for lane in 0..4 {
+            acc[lane] += a[lane] * b[lane];
+        }
+    }
+
+    let sum = (acc[0] + acc[1]) + (acc[2] + acc[3]);
+    tail(sum, a, b)
+}
+
+/// Adds the products of the elements left over after the chunks of four.
+fn tail(sum: f64, a: &[f64], b: &[f64]) -> f64 {
+    let rest = a.len() - a.len() % 4;
+    a[rest..]
+        .iter()
+        .zip(&b[rest..])
+        .fold(sum, |sum, (x, y)| sum + x * y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dot() {
+        for len in 0..12 {
+            let a: Vec<f64> = (0..len).map(|idx| idx as f64 * 0.5 - 1.0).collect();
+            let b: Vec<f64> = (0..len).map(|idx| 2.0 - idx as f64 * 0.25).collect();
+            let expected: f64 = a.iter().zip(&b).map(|(x, y)| x * y).sum();
+            approx::assert_relative_eq!(dot(&a, &b), expected, epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_dot_length_mismatch() {
+        dot(&[1.0, 2.0], &[1.0]);
+    }
+}
@@ -1,22 +1,37 @@
-use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use vroom_rand::RngCore;
 
+use crate::activation::Activation;
 use crate::neuron::Neuron;
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Layer {
     pub(crate) neurons: Vec<Neuron>,
+    #[serde(default)]
+    pub(crate) activation: Activation,
 }
 
 impl Layer {
     pub fn new(neurons: Vec<Neuron>) -> Self {
-        Self { neurons }
+        Self {
+            neurons,
+            activation: Activation::default(),
+        }
+    }
+
+    pub fn with_activation(self, activation: Activation) -> Self {
+        Self { activation, ..self }
+    }
+
+    pub fn activation(&self) -> Activation {
+        self.activation
     }
 
     pub fn new_random(rng: &mut dyn RngCore, nin: usize, nout: usize, bias: f64) -> Self {
         let neurons = (0..nout)
             .map(|_| Neuron::new_random(rng, nin, bias))
             .collect();
-        Self { neurons }
+        Self::new(neurons)
     }
 
     pub fn from_weight_and_biases(
@@ -29,14 +44,30 @@ impl Layer {
             neurons.push(Neuron::from_weight_and_biases(nin, weights));
         }
 
-        Self { neurons }
+        Self::new(neurons)
     }
 
-    pub fn forward(&self, inputs: &[f64]) -> Vec<f64> {
+    /// Number of inputs, or 0 for a layer without neurons.
+    pub fn nin(&self) -> usize {
         self.neurons
-            .iter()
-            .map(|neuron| neuron.forward(inputs))
-            .collect()
+            .first()
+            .map_or(0, |neuron| neuron.weights.len())
+    }
+
+    pub fn forward(&self, inputs: &[f64]) -> Vec<f64> {
+        let mut outputs = Vec::with_capacity(self.neurons.len());
+        self.forward_into(inputs, &mut outputs);
+        outputs
+    }
+
+    /// Like `forward`, but writes into `outputs`, reusing its allocation.
+    pub fn forward_into(&self, inputs: &[f64], outputs: &mut Vec<f64>) {
+        outputs.clear();
+        outputs.extend(
+            self.neurons
+                .iter()
+                .map(|neuron| self.activation.apply(neuron.net_input(inputs))),
+        );
     }
 }
 
@@ -1,5 +1,20 @@
-pub use crate::mlp::MLP;
+pub use crate::activation::Activation;
+pub use crate::cppn::{Cppn, Substrate};
+pub use crate::mlp::{ForwardBuffers, MLP};
+pub use crate::modular::{ModularBrain, Module};
+pub use crate::plasticity::{HebbianRule, Plasticity};
+pub use crate::quantized::Precision;
+pub use crate::sharing::{Mirror, ParameterSharing};
+pub use crate::sparse::{SparseMLP, Sparsity};
 
+mod activation;
+mod cppn;
+mod dot;
 mod layer;
 mod mlp;
+mod modular;
 mod neuron;
+mod plasticity;
+mod quantized;
+mod sharing;
+mod sparse;
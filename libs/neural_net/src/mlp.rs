@@ -1,15 +1,79 @@
-use rand::RngCore;
+use std::cell::OnceCell;
 
+use serde::{Deserialize, Serialize};
+use vroom_rand::RngCore;
+
+use crate::activation::Activation;
 use crate::layer::Layer;
+use crate::plasticity::Plasticity;
+use crate::quantized::{Compact, Precision};
+use crate::sparse::Sparsity;
+
+/// Scratch space for `MLP::forward_into`, reused across calls.
+#[derive(Debug, Default)]
+pub struct ForwardBuffers {
+    current: Vec<f64>,
+    next: Vec<f64>,
+}
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct MLP {
     pub layers: Vec<Layer>,
+    #[serde(default)]
+    precision: Precision,
+    /// Reduced precision copy of `layers`, built on the first forward pass.
+    #[serde(skip)]
+    compact: OnceCell<Compact>,
 }
 
 impl MLP {
     pub fn new(layers: Vec<Layer>) -> Self {
-        Self { layers }
+        Self {
+            layers,
+            precision: Precision::default(),
+            compact: OnceCell::new(),
+        }
+    }
+
+    /// Runs inference in `precision`. Evolution still sees the full f64
+    /// weights through `weights_and_biases`.
+    pub fn with_precision(self, precision: Precision) -> Self {
+        Self {
+            precision,
+            compact: OnceCell::new(),
+            ..self
+        }
+    }
+
+    pub fn precision(&self) -> Precision {
+        self.precision
+    }
+
+    /// Input count followed by every layer's size, the arguments
+    /// `new_random` and `from_weight_and_biases` take.
+    pub fn topology(&self) -> (usize, Vec<usize>) {
+        let nin = self.layers.first().map_or(0, Layer::nin);
+        let nouts = self
+            .layers
+            .iter()
+            .map(|layer| layer.neurons.len())
+            .collect();
+        (nin, nouts)
+    }
+
+    /// Sets each layer's activation, first layer first.
+    pub fn with_activations(mut self, activations: &[Activation]) -> Self {
+        assert_eq!(activations.len(), self.layers.len());
+
+        for (layer, &activation) in self.layers.iter_mut().zip(activations) {
+            layer.activation = activation;
+        }
+        self.compact = OnceCell::new();
+        self
+    }
+
+    pub fn activations(&self) -> Vec<Activation> {
+        self.layers.iter().map(Layer::activation).collect()
     }
 
     pub fn new_random(rng: &mut dyn RngCore, mut nin: usize, nouts: &[usize], bias: f64) -> Self {
@@ -21,7 +85,7 @@ impl MLP {
                 layer
             })
             .collect();
-        Self { layers }
+        Self::new(layers)
     }
 
     pub fn from_weight_and_biases(
@@ -37,13 +101,84 @@ impl MLP {
             nin = *nout;
         }
 
-        Self { layers }
+        Self::new(layers)
     }
 
     pub fn forward(&self, inputs: Vec<f64>) -> Vec<f64> {
-        self.layers
-            .iter()
-            .fold(inputs, |inputs, layer| layer.forward(&inputs))
+        match self.precision {
+            Precision::F64 => self
+                .layers
+                .iter()
+                .fold(inputs, |inputs, layer| layer.forward(&inputs)),
+            precision => self
+                .compact
+                .get_or_init(|| Compact::new(&self.layers, precision))
+                .forward(&inputs),
+        }
+    }
+
+    /// Like `forward`, but runs in `buffers` so repeated calls don't allocate
+    /// (at `Precision::F64`).
+    pub fn forward_into<'a>(&self, inputs: &[f64], buffers: &'a mut ForwardBuffers) -> &'a [f64] {
+        if self.precision != Precision::F64 {
+            buffers.current = self.forward(inputs.to_vec());
+            return &buffers.current;
+        }
+
+        buffers.current.clear();
+        buffers.current.extend_from_slice(inputs);
+        for layer in &self.layers {
+            layer.forward_into(&buffers.current, &mut buffers.next);
+            std::mem::swap(&mut buffers.current, &mut buffers.next);
+        }
+        &buffers.current
+    }
+
+    /// Like `forward_into`, then changes every weight by its rule in
+    /// `plasticity` according to what flowed through it on this pass.
+    ///
+    /// Panics unless running at `Precision::F64`.
+    pub fn forward_plastic<'a>(
+        &mut self,
+        inputs: &[f64],
+        plasticity: &Plasticity,
+        buffers: &'a mut ForwardBuffers,
+    ) -> &'a [f64] {
+        assert_eq!(self.precision, Precision::F64, "Plasticity needs F64");
+
+        let mut rules = plasticity.rules().iter();
+        buffers.current.clear();
+        buffers.current.extend_from_slice(inputs);
+        for layer in &mut self.layers {
+            layer.forward_into(&buffers.current, &mut buffers.next);
+            for (neuron, &post) in layer.neurons.iter_mut().zip(&buffers.next) {
+                for (weight, &pre) in neuron.weights.iter_mut().zip(&buffers.current) {
+                    let rule = rules.next().expect("Not enough rules");
+                    *weight = (*weight + rule.delta(pre, post))
+                        .clamp(-Plasticity::MAX_WEIGHT, Plasticity::MAX_WEIGHT);
+                }
+            }
+            std::mem::swap(&mut buffers.current, &mut buffers.next);
+        }
+        &buffers.current
+    }
+
+    /// Zeroes every weight whose magnitude is below `threshold`. Biases are
+    /// kept. Use `SparseMLP` to run the pruned network efficiently.
+    pub fn prune(&mut self, threshold: f64) -> Sparsity {
+        for neuron in self.layers.iter_mut().flat_map(|layer| &mut layer.neurons) {
+            neuron.prune(threshold);
+        }
+        self.compact = OnceCell::new();
+        self.sparsity()
+    }
+
+    pub fn sparsity(&self) -> Sparsity {
+        let neurons = self.layers.iter().flat_map(|layer| &layer.neurons);
+        Sparsity {
+            zeros: neurons.clone().map(|neuron| neuron.zeros()).sum(),
+            total: neurons.map(|neuron| neuron.weights.len()).sum(),
+        }
     }
 
     pub fn weights_and_biases(&self) -> Vec<f64> {
@@ -74,6 +209,7 @@ mod tests {
     fn test_new_random() {
         let mut rng = ChaCha8Rng::from_seed(Default::default());
         let mlp = MLP::new_random(&mut rng, 1, &[3, 2], 1.0);
+        assert_eq!(mlp.topology(), (1, vec![3, 2]));
 
         let layer0 = &mlp.layers[0];
         assert_eq!(layer0.neurons.len(), 3);
@@ -121,6 +257,69 @@ mod tests {
         approx::assert_relative_eq!(layer1.neurons[0].bias, 1.0);
     }
 
+    #[test]
+    fn test_forward_into() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mlp = MLP::new_random(&mut rng, 3, &[5, 4, 2], 0.1);
+        let mut buffers = ForwardBuffers::default();
+
+        for inputs in [[0.1, 0.2, 0.3], [1.0, -1.0, 0.5]] {
+            let expected = mlp.forward(inputs.to_vec());
+            assert_eq!(mlp.forward_into(&inputs, &mut buffers), expected.as_slice());
+        }
+    }
+
+    #[test]
+    fn test_reduced_precision_forward() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let inputs = vec![0.3, 0.7, 0.1, 0.9];
+        let expected = MLP::new_random(&mut rng, 4, &[8, 2], 0.1).forward(inputs.clone());
+
+        for (precision, epsilon) in [(Precision::F32, 1e-6), (Precision::I8, 0.05)] {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let mlp = MLP::new_random(&mut rng, 4, &[8, 2], 0.1).with_precision(precision);
+            let actual = mlp.forward(inputs.clone());
+            approx::assert_abs_diff_eq!(actual.as_slice(), expected.as_slice(), epsilon = epsilon);
+        }
+    }
+
+    #[test]
+    fn test_activations() {
+        let layer0 = Layer::new(vec![Neuron::new(vec![1.0], -1.0)]);
+        let layer1 = Layer::new(vec![Neuron::new(vec![2.0], 0.0)]);
+        let mlp = MLP::new(vec![layer0, layer1])
+            .with_activations(&[Activation::Identity, Activation::Tanh]);
+
+        assert_eq!(
+            mlp.activations(),
+            vec![Activation::Identity, Activation::Tanh]
+        );
+        // layer0 output: [-0.5], layer1 output: [tanh(-1.0)]
+        let expected = (-1.0_f64).tanh();
+        approx::assert_relative_eq!(mlp.forward(vec![0.5])[0], expected);
+        let mlp = mlp.with_precision(Precision::F32);
+        approx::assert_relative_eq!(mlp.forward(vec![0.5])[0], expected, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_prune() {
+        let layer0 = Layer::new(vec![
+            Neuron::new(vec![2.0, 0.01], 0.0),
+            Neuron::new(vec![-0.05, 2.0], 1.0),
+        ]);
+        let layer1 = Layer::new(vec![Neuron::new(vec![0.5, -0.5], 0.1)]);
+        let mut mlp = MLP::new(vec![layer0, layer1]);
+
+        assert_eq!(mlp.sparsity(), Sparsity { zeros: 0, total: 6 });
+        let sparsity = mlp.prune(0.1);
+        assert_eq!(sparsity, Sparsity { zeros: 2, total: 6 });
+        approx::assert_relative_eq!(sparsity.fraction(), 1.0 / 3.0);
+        assert_eq!(
+            mlp.weights_and_biases(),
+            vec![0.0, 2.0, 0.0, 1.0, 0.0, 2.0, 0.1, 0.5, -0.5]
+        );
+    }
+
     #[test]
     fn test_forward() {
         let layer0 = Layer::new(vec![
@@ -0,0 +1,182 @@
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+use vroom_rand::RngCore;
+
+use crate::layer::Layer;
+use crate::mlp::MLP;
+use crate::neuron::Neuron;
+
+/// One network of a `ModularBrain`, seeing only a slice of the inputs.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Module {
+    pub inputs: Range<usize>,
+    pub mlp: MLP,
+}
+
+/// Independent networks side by side, each reading its own slice of the
+/// inputs, with their outputs concatenated in order. Its weights and biases
+/// are every module's one after another, so a module's genes can be
+/// evolved, or left alone, separately from the others'.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ModularBrain {
+    nin: usize,
+    modules: Vec<Module>,
+}
+
+impl ModularBrain {
+    /// Panics if a module reads past `nin` inputs or its network doesn't
+    /// take as many inputs as its slice has.
+    pub fn new(nin: usize, modules: Vec<Module>) -> Self {
+        for module in &modules {
+            assert!(module.inputs.end <= nin, "Module inputs out of range");
+            assert_eq!(
+                module.mlp.topology().0,
+                module.inputs.len(),
+                "Module input count differs from its slice"
+            );
+        }
+        Self { nin, modules }
+    }
+
+    /// Modules with the given input slices and layer sizes, like
+    /// `MLP::new_random`.
+    pub fn new_random(
+        rng: &mut dyn RngCore,
+        nin: usize,
+        modules: &[(Range<usize>, Vec<usize>)],
+        bias: f64,
+    ) -> Self {
+        let modules = modules
+            .iter()
+            .map(|(inputs, nouts)| Module {
+                inputs: inputs.clone(),
+                mlp: MLP::new_random(rng, inputs.len(), nouts, bias),
+            })
+            .collect();
+        Self::new(nin, modules)
+    }
+
+    /// Reads every module's weights and biases in turn, like
+    /// `MLP::from_weight_and_biases`.
+    pub fn from_weight_and_biases(
+        nin: usize,
+        modules: &[(Range<usize>, Vec<usize>)],
+        weights: impl IntoIterator<Item = f64>,
+    ) -> Self {
+        let mut weights = weights.into_iter();
+        let modules = modules
+            .iter()
+            .map(|(inputs, nouts)| Module {
+                inputs: inputs.clone(),
+                mlp: MLP::from_weight_and_biases(inputs.len(), nouts, &mut weights),
+            })
+            .collect();
+        Self::new(nin, modules)
+    }
+
+    pub fn modules(&self) -> &[Module] {
+        &self.modules
+    }
+
+    pub fn weights_and_biases(&self) -> Vec<f64> {
+        self.modules
+            .iter()
+            .flat_map(|module| module.mlp.weights_and_biases())
+            .collect()
+    }
+
+    /// Where `module`'s genes are in `weights_and_biases`.
+    pub fn module_genes(&self, module: usize) -> Range<usize> {
+        let len = |module: &Module| module.mlp.weights_and_biases().len();
+        let start = self.modules[..module].iter().map(len).sum();
+        start..start + len(&self.modules[module])
+    }
+
+    pub fn forward(&self, inputs: &[f64]) -> Vec<f64> {
+        assert_eq!(inputs.len(), self.nin);
+
+        self.modules
+            .iter()
+            .flat_map(|module| module.mlp.forward(inputs[module.inputs.clone()].to_vec()))
+            .collect()
+    }
+
+    /// A single network computing the same function, with zero weights
+    /// between modules, to run wherever an `MLP` is expected. Layers take
+    /// the first module's activations.
+    ///
+    /// Panics unless every module has the same number of layers.
+    pub fn to_mlp(&self) -> MLP {
+        let depth = self
+            .modules
+            .first()
+            .map_or(0, |module| module.mlp.layers.len());
+        assert!(
+            self.modules
+                .iter()
+                .all(|module| module.mlp.layers.len() == depth),
+            "Modules must be equally deep"
+        );
+
+        let mut layers = Vec::with_capacity(depth);
+        // Where each module's previous layer starts and ends in the merged one
+        let mut spans: Vec<Range<usize>> = self
+            .modules
+            .iter()
+            .map(|module| module.inputs.clone())
+            .collect();
+        let mut nin = self.nin;
+        for layer in 0..depth {
+            let mut neurons = Vec::new();
+            for (module, span) in self.modules.iter().zip(&mut spans) {
+                let start = neurons.len();
+                for neuron in &module.mlp.layers[layer].neurons {
+                    let mut weights = vec![0.0; nin];
+                    weights[span.clone()].copy_from_slice(&neuron.weights);
+                    neurons.push(Neuron::new(weights, neuron.bias));
+                }
+                *span = start..neurons.len();
+            }
+            nin = neurons.len();
+            let activation = self.modules[0].mlp.layers[layer].activation();
+            layers.push(Layer::new(neurons).with_activation(activation));
+        }
+        MLP::new(layers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_to_mlp() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let modules = [(0..3, vec![4, 1]), (2..5, vec![2, 2])];
+        let brain = ModularBrain::new_random(&mut rng, 5, &modules, 0.1);
+
+        let mlp = brain.to_mlp();
+        assert_eq!(mlp.topology(), (5, vec![6, 3]));
+        let inputs = [0.3, -0.2, 0.8, 0.5, 0.1];
+        approx::assert_relative_eq!(
+            mlp.forward(inputs.to_vec()).as_slice(),
+            brain.forward(&inputs).as_slice()
+        );
+    }
+
+    #[test]
+    fn test_genes() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let modules = [(0..2, vec![3]), (2..3, vec![1])];
+        let brain = ModularBrain::new_random(&mut rng, 3, &modules, 0.1);
+
+        let genes = brain.weights_and_biases();
+        assert_eq!(genes.len(), 3 * 3 + 2);
+        assert_eq!(brain.module_genes(1), 9..11);
+        let rebuilt = ModularBrain::from_weight_and_biases(3, &modules, genes.clone());
+        assert_eq!(rebuilt.weights_and_biases(), genes);
+    }
+}
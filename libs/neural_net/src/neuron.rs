@@ -1,6 +1,11 @@
-use rand::{Rng, RngCore};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use vroom_rand::RngCore;
 
-#[derive(Debug)]
+use crate::activation::Activation;
+use crate::dot::dot;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Neuron {
     pub(crate) weights: Vec<f64>,
     pub(crate) bias: f64,
@@ -32,18 +37,31 @@ impl Neuron {
         }
     }
 
-    pub fn forward(&self, inputs: &[f64]) -> f64 {
+    /// Zeroes weights whose magnitude is below `threshold`, returning how
+    /// many weights are now zero.
+    pub fn prune(&mut self, threshold: f64) -> usize {
+        for weight in &mut self.weights {
+            if weight.abs() < threshold {
+                *weight = 0.0;
+            }
+        }
+        self.zeros()
+    }
+
+    pub fn zeros(&self) -> usize {
+        self.weights.iter().filter(|&&weight| weight == 0.0).count()
+    }
+
+    /// Weighted sum of `inputs` plus the bias, before the activation.
+    pub(crate) fn net_input(&self, inputs: &[f64]) -> f64 {
         assert_eq!(inputs.len(), self.weights.len());
 
-        let dot_product: f64 = inputs
-            .iter()
-            .zip(&self.weights)
-            .map(|(input, weight)| input * weight)
-            .sum();
-        let output = dot_product + self.bias;
-        // TODO: separate activation function logic
-        // ReLU
-        output.max(0.0)
+        dot(inputs, &self.weights) + self.bias
+    }
+
+    /// Output with the default ReLU activation; layers apply their own.
+    pub fn forward(&self, inputs: &[f64]) -> f64 {
+        Activation::Relu.apply(self.net_input(inputs))
     }
 }
 
@@ -82,6 +100,14 @@ mod tests {
         approx::assert_relative_eq!(neuron.forward(&[4.0]), 2.0);
     }
 
+    #[test]
+    fn test_prune() {
+        let mut neuron = Neuron::new(vec![0.05, -0.5, -0.01, 0.2], 0.01);
+        assert_eq!(neuron.prune(0.1), 2);
+        assert_eq!(neuron.weights, vec![0.0, -0.5, 0.0, 0.2]);
+        approx::assert_relative_eq!(neuron.bias, 0.01);
+    }
+
     #[test]
     #[should_panic]
     fn test_forward_wrong_input_size() {
@@ -0,0 +1,152 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use vroom_rand::RngCore;
+
+use crate::mlp::MLP;
+
+/// How one connection's weight changes while the network runs: by
+/// `eta * (a * pre * post + b * pre + c * post + d)`, where `pre` is the
+/// connection's input and `post` the output of the neuron it feeds.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct HebbianRule {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    pub eta: f64,
+}
+
+/// Hebbian rules for every connection of an `MLP`, in the order of its
+/// weights in `MLP::weights_and_biases`. Biases don't change.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Plasticity {
+    rules: Vec<HebbianRule>,
+    /// Weights and biases of the network before it learned anything.
+    initial: Vec<f64>,
+}
+
+impl HebbianRule {
+    /// Genes per rule.
+    pub const GENES: usize = 5;
+
+    /// Coefficients in [-1, 1] and a learning rate in [0, 0.1], small
+    /// enough for weights to drift over many steps rather than jump.
+    pub fn new_random(rng: &mut dyn RngCore) -> Self {
+        Self {
+            a: rng.gen_range(-1.0..=1.0),
+            b: rng.gen_range(-1.0..=1.0),
+            c: rng.gen_range(-1.0..=1.0),
+            d: rng.gen_range(-1.0..=1.0),
+            eta: rng.gen_range(0.0..=0.1),
+        }
+    }
+
+    pub fn from_genes(genes: &[f64]) -> Self {
+        assert_eq!(genes.len(), Self::GENES);
+        Self {
+            a: genes[0],
+            b: genes[1],
+            c: genes[2],
+            d: genes[3],
+            eta: genes[4],
+        }
+    }
+
+    pub fn to_genes(self) -> [f64; Self::GENES] {
+        [self.a, self.b, self.c, self.d, self.eta]
+    }
+
+    pub fn delta(&self, pre: f64, post: f64) -> f64 {
+        self.eta * (self.a * pre * post + self.b * pre + self.c * post + self.d)
+    }
+}
+
+impl Plasticity {
+    /// Learned weights are kept within this magnitude so runaway rules
+    /// saturate instead of overflowing.
+    pub const MAX_WEIGHT: f64 = 10.0;
+
+    /// Rules for `mlp`'s connections, one after another.
+    pub fn new(mlp: &MLP, rules: Vec<HebbianRule>) -> Self {
+        assert_eq!(rules.len(), mlp.sparsity().total, "One rule per weight");
+        Self {
+            rules,
+            initial: mlp.weights_and_biases(),
+        }
+    }
+
+    pub fn new_random(rng: &mut dyn RngCore, mlp: &MLP) -> Self {
+        let rules = (0..mlp.sparsity().total)
+            .map(|_| HebbianRule::new_random(rng))
+            .collect();
+        Self::new(mlp, rules)
+    }
+
+    /// Reads `HebbianRule::GENES` genes per connection of `mlp`.
+    pub fn from_genes(mlp: &MLP, genes: &[f64]) -> Self {
+        let rules = genes
+            .chunks(HebbianRule::GENES)
+            .map(HebbianRule::from_genes)
+            .collect();
+        Self::new(mlp, rules)
+    }
+
+    pub fn genes(&self) -> Vec<f64> {
+        self.rules.iter().flat_map(|rule| rule.to_genes()).collect()
+    }
+
+    pub fn rules(&self) -> &[HebbianRule] {
+        &self.rules
+    }
+
+    /// Weights and biases of the network these rules were made for, before
+    /// any learning.
+    pub fn initial_weights_and_biases(&self) -> &[f64] {
+        &self.initial
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layer::Layer;
+    use crate::neuron::Neuron;
+    use crate::ForwardBuffers;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_genes_roundtrip() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mlp = MLP::new_random(&mut rng, 3, &[4, 2], 0.1);
+        let plasticity = Plasticity::new_random(&mut rng, &mlp);
+
+        let genes = plasticity.genes();
+        assert_eq!(genes.len(), (3 * 4 + 4 * 2) * HebbianRule::GENES);
+        assert_eq!(Plasticity::from_genes(&mlp, &genes), plasticity);
+    }
+
+    #[test]
+    fn test_forward_plastic() {
+        let mut mlp = MLP::new(vec![Layer::new(vec![Neuron::new(vec![1.0, 0.5], 0.0)])]);
+        let rule = HebbianRule {
+            a: 1.0,
+            eta: 0.5,
+            ..HebbianRule::default()
+        };
+        let plasticity = Plasticity::new(&mlp, vec![rule, HebbianRule::default()]);
+        let mut buffers = ForwardBuffers::default();
+
+        // Output is computed before learning: 2.0 * 1.0 + 4.0 * 0.5
+        assert_eq!(
+            mlp.forward_plastic(&[2.0, 4.0], &plasticity, &mut buffers),
+            [4.0]
+        );
+        // First weight grows by 0.5 * 2.0 * 4.0, the second stays
+        assert_eq!(mlp.weights_and_biases(), vec![0.0, 5.0, 0.5]);
+        assert_eq!(plasticity.initial_weights_and_biases(), [0.0, 1.0, 0.5]);
+
+        mlp.forward_plastic(&[100.0, 0.0], &plasticity, &mut buffers);
+        assert_eq!(mlp.weights_and_biases()[1], Plasticity::MAX_WEIGHT);
+    }
+}
@@ -0,0 +1,149 @@
+use serde::{Deserialize, Serialize};
+
+use crate::activation::Activation;
+use crate::layer::Layer;
+
+/// Numeric precision an MLP runs inference in. Weights are always kept as
+/// f64 for evolution; lower precisions use a compact copy built on the first
+/// forward pass.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Precision {
+    #[default]
+    F64,
+    F32,
+    /// Weights quantized to 8 bits with one f32 scale per neuron.
+    I8,
+}
+
+/// Reduced precision copy of an MLP's layers.
+#[derive(Clone, Debug)]
+pub(crate) enum Compact {
+    F32(Vec<F32Layer>),
+    I8(Vec<I8Layer>),
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct F32Layer {
+    nin: usize,
+    activation: Activation,
+    biases: Vec<f32>,
+    /// Row-major `nout x nin` weights.
+    weights: Vec<f32>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct I8Layer {
+    nin: usize,
+    activation: Activation,
+    biases: Vec<f32>,
+    scales: Vec<f32>,
+    /// Row-major `nout x nin` weights, each `weight / scale`.
+    weights: Vec<i8>,
+}
+
+impl Compact {
+    /// Panics for `Precision::F64`, which doesn't need a compact copy.
+    pub(crate) fn new(layers: &[Layer], precision: Precision) -> Self {
+        match precision {
+            Precision::F64 => panic!("F64 MLPs run on their own weights"),
+            Precision::F32 => Self::F32(layers.iter().map(F32Layer::new).collect()),
+            Precision::I8 => Self::I8(layers.iter().map(I8Layer::new).collect()),
+        }
+    }
+
+    pub(crate) fn forward(&self, inputs: &[f64]) -> Vec<f64> {
+        let inputs: Vec<f32> = inputs.iter().map(|&input| input as f32).collect();
+        let outputs = match self {
+            Self::F32(layers) => layers
+                .iter()
+                .fold(inputs, |inputs, layer| layer.forward(&inputs)),
+            Self::I8(layers) => layers
+                .iter()
+                .fold(inputs, |inputs, layer| layer.forward(&inputs)),
+        };
+        outputs.into_iter().map(f64::from).collect()
+    }
+}
+
+impl F32Layer {
+    fn new(layer: &Layer) -> Self {
+        Self {
+            nin: layer.nin(),
+            activation: layer.activation,
+            biases: layer
+                .neurons
+                .iter()
+                .map(|neuron| neuron.bias as f32)
+                .collect(),
+            weights: layer
+                .neurons
+                .iter()
+                .flat_map(|neuron| neuron.weights.iter().map(|&weight| weight as f32))
+                .collect(),
+        }
+    }
+
+    fn forward(&self, inputs: &[f32]) -> Vec<f32> {
+        assert_eq!(inputs.len(), self.nin);
+
+        self.biases
+            .iter()
+            .zip(self.weights.chunks_exact(self.nin.max(1)))
+            .map(|(bias, weights)| {
+                let dot_product: f32 = inputs.iter().zip(weights).map(|(x, w)| x * w).sum();
+                self.activation.apply_f32(dot_product + bias)
+            })
+            .collect()
+    }
+}
+
+impl I8Layer {
+    fn new(layer: &Layer) -> Self {
+        let mut scales = Vec::with_capacity(layer.neurons.len());
+        let mut weights = Vec::with_capacity(layer.neurons.len() * layer.nin());
+        for neuron in &layer.neurons {
+            let max = neuron
+                .weights
+                .iter()
+                .fold(0.0, |max: f64, weight| max.max(weight.abs()));
+            let scale = if max > 0.0 { max / 127.0 } else { 1.0 };
+            scales.push(scale as f32);
+            weights.extend(
+                neuron
+                    .weights
+                    .iter()
+                    .map(|weight| (weight / scale).round() as i8),
+            );
+        }
+
+        Self {
+            nin: layer.nin(),
+            activation: layer.activation,
+            biases: layer
+                .neurons
+                .iter()
+                .map(|neuron| neuron.bias as f32)
+                .collect(),
+            scales,
+            weights,
+        }
+    }
+
+    fn forward(&self, inputs: &[f32]) -> Vec<f32> {
+        assert_eq!(inputs.len(), self.nin);
+
+        self.biases
+            .iter()
+            .zip(&self.scales)
+            .zip(self.weights.chunks_exact(self.nin.max(1)))
+            .map(|((bias, scale), weights)| {
+                let dot_product: f32 = inputs
+                    .iter()
+                    .zip(weights)
+                    .map(|(&x, &w)| x * f32::from(w))
+                    .sum();
+                self.activation.apply_f32(dot_product * scale + bias)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,231 @@
+use crate::mlp::MLP;
+
+/// Mirror image of a vector as a signed permutation: element `i` of the
+/// image is `sign * x[source]`, with `(source, sign)` the `i`th entry.
+/// Mirroring twice must give back the original vector.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mirror {
+    map: Vec<(usize, f64)>,
+}
+
+/// Where one weight or bias of an MLP comes from in a shared genome.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Tie {
+    Gene(usize),
+    Negated(usize),
+    /// Forced to zero, e.g. a weight that must equal its own negation.
+    Zero,
+}
+
+/// Map from a genome onto an MLP's weights and biases (in
+/// `MLP::weights_and_biases` order) where several parameters share one
+/// gene, shrinking the genome.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParameterSharing {
+    ties: Vec<Tie>,
+    /// Parameter each gene is read back from by `compress`.
+    sources: Vec<usize>,
+}
+
+impl Mirror {
+    /// Panics unless `map` is a signed involution with signs of ±1.
+    pub fn new(map: Vec<(usize, f64)>) -> Self {
+        for (idx, &(source, sign)) in map.iter().enumerate() {
+            assert!(sign == 1.0 || sign == -1.0, "Signs must be 1 or -1");
+            assert_eq!(map.get(source), Some(&(idx, sign)), "Not an involution");
+        }
+        Self { map }
+    }
+
+    pub fn identity(len: usize) -> Self {
+        Self::new((0..len).map(|idx| (idx, 1.0)).collect())
+    }
+
+    /// Reverses the order of the elements, e.g. left and right receptors.
+    pub fn reverse(len: usize) -> Self {
+        Self::new((0..len).map(|idx| (len - 1 - idx, 1.0)).collect())
+    }
+
+    /// Mirror of the concatenation of a vector mirrored by `self` and one
+    /// mirrored by `other`.
+    pub fn chain(mut self, other: Mirror) -> Self {
+        let offset = self.map.len();
+        self.map.extend(
+            other
+                .map
+                .into_iter()
+                .map(|(source, sign)| (source + offset, sign)),
+        );
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn apply(&self, values: &[f64]) -> Vec<f64> {
+        assert_eq!(values.len(), self.map.len());
+        self.map
+            .iter()
+            .map(|&(source, sign)| sign * values[source])
+            .collect()
+    }
+}
+
+impl ParameterSharing {
+    /// Ties the parameters of an MLP so that mirroring its inputs with
+    /// `mirrors[0]` mirrors the pre-activations of each layer `k` with
+    /// `mirrors[k + 1]`. Layer sizes are the mirrors' lengths.
+    ///
+    /// Paired parameters share a gene, possibly negated; a parameter that
+    /// is its own pair with a negative sign is fixed at zero.
+    pub fn mirror(mirrors: &[Mirror]) -> Self {
+        assert!(mirrors.len() >= 2, "Need an input and an output mirror");
+
+        let mut ties: Vec<Option<Tie>> = Vec::new();
+        let mut sources = Vec::new();
+        for pair in mirrors.windows(2) {
+            let (inputs, outputs) = (&pair[0], &pair[1]);
+            let offset = ties.len();
+            let neuron_len = inputs.len() + 1;
+            ties.resize(offset + outputs.len() * neuron_len, None);
+
+            for (neuron, &(mirror_neuron, neuron_sign)) in outputs.map.iter().enumerate() {
+                // Column 0 is the bias, then one column per input
+                let columns = std::iter::once((0, neuron_sign)).chain(
+                    inputs
+                        .map
+                        .iter()
+                        .map(|&(mirror_input, sign)| (mirror_input + 1, neuron_sign * sign)),
+                );
+                for (column, (mirror_column, sign)) in columns.enumerate() {
+                    let param = offset + neuron * neuron_len + column;
+                    if ties[param].is_some() {
+                        continue;
+                    }
+                    let mirror_param = offset + mirror_neuron * neuron_len + mirror_column;
+                    if param == mirror_param && sign < 0.0 {
+                        ties[param] = Some(Tie::Zero);
+                        continue;
+                    }
+
+                    let gene = sources.len();
+                    sources.push(param);
+                    ties[param] = Some(Tie::Gene(gene));
+                    if mirror_param != param {
+                        ties[mirror_param] = Some(if sign > 0.0 {
+                            Tie::Gene(gene)
+                        } else {
+                            Tie::Negated(gene)
+                        });
+                    }
+                }
+            }
+        }
+
+        Self {
+            ties: ties.into_iter().map(Option::unwrap).collect(),
+            sources,
+        }
+    }
+
+    pub fn num_genes(&self) -> usize {
+        self.sources.len()
+    }
+
+    /// Number of weights and biases in the MLP.
+    pub fn num_parameters(&self) -> usize {
+        self.ties.len()
+    }
+
+    /// Weights and biases encoded by `genes`.
+    pub fn expand(&self, genes: &[f64]) -> Vec<f64> {
+        assert_eq!(genes.len(), self.num_genes());
+        self.ties
+            .iter()
+            .map(|tie| match *tie {
+                Tie::Gene(gene) => genes[gene],
+                Tie::Negated(gene) => -genes[gene],
+                Tie::Zero => 0.0,
+            })
+            .collect()
+    }
+
+    /// Genes of weights and biases that already respect the sharing, the
+    /// inverse of `expand`. Otherwise each gene is read from the first
+    /// parameter sharing it.
+    pub fn compress(&self, parameters: &[f64]) -> Vec<f64> {
+        assert_eq!(parameters.len(), self.num_parameters());
+        self.sources
+            .iter()
+            .map(|&param| parameters[param])
+            .collect()
+    }
+}
+
+impl MLP {
+    /// Like `from_weight_and_biases`, with the weights and biases decoded
+    /// from a shared genome.
+    pub fn from_shared_weight_and_biases(
+        nin: usize,
+        nouts: &[usize],
+        genes: &[f64],
+        sharing: &ParameterSharing,
+    ) -> Self {
+        Self::from_weight_and_biases(nin, nouts, sharing.expand(genes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_mirrored_mlp_commutes_with_mirror() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let input_mirror = Mirror::reverse(4).chain(Mirror::identity(1));
+        let hidden_mirror = Mirror::reverse(3);
+        let output_mirror = Mirror::new(vec![(2, 1.0), (1, 1.0), (0, 1.0)]);
+        let sharing =
+            ParameterSharing::mirror(&[input_mirror.clone(), hidden_mirror, output_mirror.clone()]);
+        assert_eq!(sharing.num_parameters(), 3 * 6 + 3 * 4);
+        assert!(sharing.num_genes() < sharing.num_parameters() * 2 / 3);
+
+        let genes: Vec<f64> = (0..sharing.num_genes())
+            .map(|_| rng.gen_range(-1.0..=1.0))
+            .collect();
+        let mlp = MLP::from_shared_weight_and_biases(5, &[3, 3], &genes, &sharing);
+        assert_eq!(sharing.compress(&mlp.weights_and_biases()), genes);
+
+        let inputs = vec![0.1, 0.9, 0.4, 0.3, 0.7];
+        let mirrored = mlp.forward(input_mirror.apply(&inputs));
+        let expected = output_mirror.apply(&mlp.forward(inputs));
+        approx::assert_relative_eq!(mirrored.as_slice(), expected.as_slice());
+    }
+
+    #[test]
+    fn test_negated_ties() {
+        // One input that flips sign, one output that flips sign
+        let sharing = ParameterSharing::mirror(&[
+            Mirror::new(vec![(0, -1.0)]),
+            Mirror::new(vec![(1, 1.0), (0, 1.0)]),
+            Mirror::new(vec![(0, -1.0)]),
+        ]);
+
+        let parameters = sharing.expand(&[1.0, 2.0, 3.0]);
+        // Hidden: [b, w], [b, -w]; output: [0, v, -v]
+        assert_eq!(parameters, vec![1.0, 2.0, 1.0, -2.0, 0.0, 3.0, -3.0]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_mirror_must_be_involution() {
+        Mirror::new(vec![(1, 1.0), (2, 1.0), (0, 1.0)]);
+    }
+}
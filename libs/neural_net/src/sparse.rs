@@ -0,0 +1,127 @@
+use serde::{Deserialize, Serialize};
+
+use crate::activation::Activation;
+use crate::mlp::MLP;
+
+/// Share of an MLP's weights (biases excluded) that are zero.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Sparsity {
+    pub zeros: usize,
+    pub total: usize,
+}
+
+/// MLP that only stores non-zero weights, for running pruned brains.
+/// Produces the same outputs as the dense MLP it was built from.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SparseMLP {
+    nin: usize,
+    layers: Vec<SparseLayer>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SparseLayer {
+    neurons: Vec<SparseNeuron>,
+    #[serde(default)]
+    activation: Activation,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SparseNeuron {
+    /// Input index and weight of every non-zero weight.
+    weights: Vec<(usize, f64)>,
+    bias: f64,
+}
+
+impl Sparsity {
+    pub fn fraction(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.zeros as f64 / self.total as f64
+        }
+    }
+}
+
+impl SparseMLP {
+    pub fn forward(&self, inputs: Vec<f64>) -> Vec<f64> {
+        assert_eq!(inputs.len(), self.nin);
+
+        self.layers.iter().fold(inputs, |inputs, layer| {
+            layer
+                .neurons
+                .iter()
+                .map(|neuron| {
+                    let dot_product: f64 = neuron
+                        .weights
+                        .iter()
+                        .map(|&(idx, weight)| inputs[idx] * weight)
+                        .sum();
+                    layer.activation.apply(dot_product + neuron.bias)
+                })
+                .collect()
+        })
+    }
+
+    /// Number of weights kept, biases excluded.
+    pub fn num_weights(&self) -> usize {
+        self.layers
+            .iter()
+            .flat_map(|layer| &layer.neurons)
+            .map(|neuron| neuron.weights.len())
+            .sum()
+    }
+}
+
+impl From<&MLP> for SparseMLP {
+    fn from(mlp: &MLP) -> Self {
+        let nin = mlp
+            .layers
+            .first()
+            .and_then(|layer| layer.neurons.first())
+            .map_or(0, |neuron| neuron.weights.len());
+        let layers = mlp
+            .layers
+            .iter()
+            .map(|layer| SparseLayer {
+                neurons: layer
+                    .neurons
+                    .iter()
+                    .map(|neuron| SparseNeuron {
+                        weights: neuron
+                            .weights
+                            .iter()
+                            .copied()
+                            .enumerate()
+                            .filter(|&(_, weight)| weight != 0.0)
+                            .collect(),
+                        bias: neuron.bias,
+                    })
+                    .collect(),
+                activation: layer.activation,
+            })
+            .collect();
+        Self { nin, layers }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_matches_dense_forward() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut mlp = MLP::new_random(&mut rng, 4, &[6, 2], 0.1);
+        let sparsity = mlp.prune(0.5);
+        let sparse = SparseMLP::from(&mlp);
+
+        assert_eq!(sparse.num_weights(), sparsity.total - sparsity.zeros);
+        let inputs = vec![0.3, -0.2, 0.9, 0.5];
+        approx::assert_relative_eq!(
+            sparse.forward(inputs.clone()).as_slice(),
+            mlp.forward(inputs).as_slice()
+        );
+    }
+}
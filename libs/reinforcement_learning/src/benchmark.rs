@@ -0,0 +1,163 @@
+pub use self::cart_pole::{CartPole, CartPoleBalance};
+pub use self::rastrigin::Rastrigin;
+pub use self::sphere::Sphere;
+pub use self::xor::Xor;
+
+use std::ops::RangeInclusive;
+
+use vroom_rand::RngCore;
+
+use crate::chromosome::Chromosome;
+use crate::crossover::Crossover;
+use crate::genetic_algorithm::GeneticAlgorithm;
+use crate::individual::Individual;
+use crate::mutation::Mutation;
+use crate::population::Population;
+use crate::population_init::{PopulationInit, RandomInit};
+use crate::selection::Selection;
+
+mod cart_pole;
+mod rastrigin;
+mod sphere;
+mod xor;
+
+/// Optimization task with a known optimum, for validating selection,
+/// crossover and mutation changes without running the full simulation.
+pub trait Benchmark {
+    fn num_genes(&self) -> usize;
+
+    /// Range that genes of the initial population are drawn from.
+    fn gene_range(&self) -> RangeInclusive<f64> {
+        -1.0..=1.0
+    }
+
+    /// Non-negative fitness, higher is better.
+    fn fitness(&self, rng: &mut dyn RngCore, chromosome: &Chromosome) -> f64;
+
+    /// Fitness of an optimal solution.
+    fn max_fitness(&self) -> f64;
+}
+
+/// Best and mean fitness of every generation of a benchmark run.
+#[derive(Clone, Debug, Default)]
+pub struct BenchmarkReport {
+    pub best_fitness: Vec<f64>,
+    pub mean_fitness: Vec<f64>,
+}
+
+struct BenchmarkIndividual {
+    chromosome: Chromosome,
+    fitness: f64,
+}
+
+impl Individual for BenchmarkIndividual {
+    fn from_chromosome(chromosome: Chromosome) -> Self {
+        Self {
+            chromosome,
+            fitness: 0.0,
+        }
+    }
+
+    fn as_chromosome(&self) -> &Chromosome {
+        &self.chromosome
+    }
+
+    fn fitness(&self) -> f64 {
+        self.fitness
+    }
+}
+
+/// Evolves a random population on `benchmark` for `generations` generations.
+pub fn run_benchmark<S, C, M>(
+    ga: &GeneticAlgorithm<S, C, M>,
+    benchmark: &dyn Benchmark,
+    rng: &mut dyn RngCore,
+    population_size: usize,
+    generations: usize,
+) -> BenchmarkReport
+where
+    S: Selection,
+    C: Crossover,
+    M: Mutation,
+{
+    let init = RandomInit::new(benchmark.num_genes(), benchmark.gene_range());
+    let mut population: Population<BenchmarkIndividual> = init
+        .init(rng, population_size)
+        .into_iter()
+        .map(|chromosome| evaluate(benchmark, rng, chromosome))
+        .collect();
+
+    let mut report = BenchmarkReport::default();
+    for _ in 0..generations {
+        population = ga
+            .evolve(rng, &population)
+            .into_iter()
+            .map(|individual| evaluate(benchmark, rng, individual.chromosome))
+            .collect();
+
+        report.best_fitness.push(population.best().fitness);
+        report.mean_fitness.push(population.mean_fitness());
+    }
+    report
+}
+
+fn evaluate(
+    benchmark: &dyn Benchmark,
+    rng: &mut dyn RngCore,
+    chromosome: Chromosome,
+) -> BenchmarkIndividual {
+    let fitness = benchmark.fitness(rng, &chromosome);
+    BenchmarkIndividual {
+        chromosome,
+        fitness,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crossover::UniformCrossover;
+    use crate::mutation::GaussianMutation;
+    use crate::selection::FitnessProportionateSelection;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn run(benchmark: &dyn Benchmark, generations: usize) -> BenchmarkReport {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let ga = GeneticAlgorithm::new(
+            FitnessProportionateSelection::new(),
+            UniformCrossover::new(),
+            GaussianMutation::new(0.1, 0.1),
+        );
+        run_benchmark(&ga, benchmark, &mut rng, 50, generations)
+    }
+
+    fn assert_improves(report: &BenchmarkReport) {
+        let first = report.mean_fitness[0];
+        let last = *report.mean_fitness.last().unwrap();
+        assert!(last > first, "mean fitness went from {first} to {last}");
+    }
+
+    #[test]
+    fn test_sphere() {
+        let benchmark = Sphere::new(5);
+        let report = run(&benchmark, 50);
+        assert_improves(&report);
+        assert!(*report.best_fitness.last().unwrap() > 0.9 * benchmark.max_fitness());
+    }
+
+    #[test]
+    fn test_rastrigin() {
+        assert_improves(&run(&Rastrigin::new(3), 50));
+    }
+
+    #[test]
+    fn test_xor() {
+        assert_improves(&run(&Xor, 100));
+    }
+
+    #[test]
+    fn test_cart_pole() {
+        assert_improves(&run(&CartPoleBalance::default(), 20));
+    }
+}
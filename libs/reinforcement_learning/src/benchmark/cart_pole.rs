@@ -0,0 +1,128 @@
+use rand::Rng;
+use vroom_rand::RngCore;
+
+use super::Benchmark;
+use crate::chromosome::Chromosome;
+use crate::environment::{rollout, Environment, Transition};
+
+const GRAVITY: f64 = 9.8;
+const CART_MASS: f64 = 1.0;
+const POLE_MASS: f64 = 0.1;
+const POLE_HALF_LENGTH: f64 = 0.5;
+const FORCE: f64 = 10.0;
+const TIME_STEP: f64 = 0.02;
+const MAX_POSITION: f64 = 2.4;
+const MAX_ANGLE: f64 = 12.0 * std::f64::consts::PI / 180.0;
+
+/// Classic cart-pole balancing task. The observation is
+/// `[position, velocity, angle, angular velocity]` and the cart is pushed
+/// right if the action is positive, left otherwise. Every step the pole
+/// stays up is worth a reward of 1.
+#[derive(Clone, Debug, Default)]
+pub struct CartPole {
+    state: [f64; 4],
+}
+
+impl Environment for CartPole {
+    fn observation_size(&self) -> usize {
+        4
+    }
+
+    fn action_size(&self) -> usize {
+        1
+    }
+
+    fn num_agents(&self) -> usize {
+        1
+    }
+
+    fn reset(&mut self, rng: &mut dyn RngCore) -> Vec<Vec<f64>> {
+        self.state = [(); 4].map(|_| rng.gen_range(-0.05..=0.05));
+        vec![self.state.to_vec()]
+    }
+
+    fn step(&mut self, _rng: &mut dyn RngCore, actions: &[Vec<f64>]) -> Transition {
+        let [x, x_dot, theta, theta_dot] = self.state;
+        let force = if actions[0][0] > 0.0 { FORCE } else { -FORCE };
+
+        let total_mass = CART_MASS + POLE_MASS;
+        let (sin, cos) = theta.sin_cos();
+        let temp = (force + POLE_MASS * POLE_HALF_LENGTH * theta_dot.powi(2) * sin) / total_mass;
+        let theta_acc = (GRAVITY * sin - cos * temp)
+            / (POLE_HALF_LENGTH * (4.0 / 3.0 - POLE_MASS * cos.powi(2) / total_mass));
+        let x_acc = temp - POLE_MASS * POLE_HALF_LENGTH * theta_acc * cos / total_mass;
+
+        self.state = [
+            x + TIME_STEP * x_dot,
+            x_dot + TIME_STEP * x_acc,
+            theta + TIME_STEP * theta_dot,
+            theta_dot + TIME_STEP * theta_acc,
+        ];
+        let done = self.state[0].abs() > MAX_POSITION || self.state[2].abs() > MAX_ANGLE;
+
+        Transition {
+            observations: vec![self.state.to_vec()],
+            rewards: vec![if done { 0.0 } else { 1.0 }],
+            done,
+        }
+    }
+}
+
+/// Balances a `CartPole` with a linear policy: four weights and a bias.
+pub struct CartPoleBalance {
+    max_steps: usize,
+}
+
+impl CartPoleBalance {
+    pub fn new(max_steps: usize) -> Self {
+        Self { max_steps }
+    }
+}
+
+impl Default for CartPoleBalance {
+    fn default() -> Self {
+        Self::new(500)
+    }
+}
+
+impl Benchmark for CartPoleBalance {
+    fn num_genes(&self) -> usize {
+        5
+    }
+
+    fn fitness(&self, rng: &mut dyn RngCore, chromosome: &Chromosome) -> f64 {
+        let policy = |_: usize, observation: &[f64]| {
+            let action: f64 = observation
+                .iter()
+                .zip(chromosome.iter())
+                .map(|(input, weight)| input * weight)
+                .sum();
+            vec![action + chromosome[4]]
+        };
+        rollout(&mut CartPole::default(), rng, policy, self.max_steps)[0]
+    }
+
+    fn max_fitness(&self) -> f64 {
+        self.max_steps as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_pole_falls_without_control() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let benchmark = CartPoleBalance::default();
+
+        let always_right = Chromosome::new(vec![0.0, 0.0, 0.0, 0.0, 1.0]);
+        assert!(benchmark.fitness(&mut rng, &always_right) < 50.0);
+
+        // Push towards the side the pole is falling to.
+        let balancing = Chromosome::new(vec![0.0, 0.0, 10.0, 1.0, 0.0]);
+        assert!(benchmark.fitness(&mut rng, &balancing) > 100.0);
+    }
+}
@@ -0,0 +1,63 @@
+use std::f64::consts::PI;
+use std::ops::RangeInclusive;
+
+use vroom_rand::RngCore;
+
+use super::Benchmark;
+use crate::chromosome::Chromosome;
+
+/// Highly multimodal function with its global optimum at all zeros.
+pub struct Rastrigin {
+    num_genes: usize,
+}
+
+impl Rastrigin {
+    pub fn new(num_genes: usize) -> Self {
+        Self { num_genes }
+    }
+}
+
+impl Benchmark for Rastrigin {
+    fn num_genes(&self) -> usize {
+        self.num_genes
+    }
+
+    fn gene_range(&self) -> RangeInclusive<f64> {
+        -5.12..=5.12
+    }
+
+    fn fitness(&self, _rng: &mut dyn RngCore, chromosome: &Chromosome) -> f64 {
+        let value: f64 = chromosome
+            .iter()
+            .map(|gene| gene.powi(2) - 10.0 * (2.0 * PI * gene).cos() + 10.0)
+            .sum();
+        1.0 / (1.0 + value)
+    }
+
+    fn max_fitness(&self) -> f64 {
+        1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_fitness() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let rastrigin = Rastrigin::new(2);
+        approx::assert_relative_eq!(
+            rastrigin.fitness(&mut rng, &Chromosome::new(vec![0.0; 2])),
+            1.0
+        );
+        // Local optimum at (1, 0)
+        approx::assert_relative_eq!(
+            rastrigin.fitness(&mut rng, &Chromosome::new(vec![1.0, 0.0])),
+            0.5,
+            epsilon = 1e-12
+        );
+    }
+}
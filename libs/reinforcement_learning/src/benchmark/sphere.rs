@@ -0,0 +1,51 @@
+use vroom_rand::RngCore;
+
+use super::Benchmark;
+use crate::chromosome::Chromosome;
+
+/// Minimizes the sum of squared genes; the optimum is all zeros.
+pub struct Sphere {
+    num_genes: usize,
+}
+
+impl Sphere {
+    pub fn new(num_genes: usize) -> Self {
+        Self { num_genes }
+    }
+}
+
+impl Benchmark for Sphere {
+    fn num_genes(&self) -> usize {
+        self.num_genes
+    }
+
+    fn fitness(&self, _rng: &mut dyn RngCore, chromosome: &Chromosome) -> f64 {
+        let value: f64 = chromosome.iter().map(|gene| gene.powi(2)).sum();
+        1.0 / (1.0 + value)
+    }
+
+    fn max_fitness(&self) -> f64 {
+        1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_fitness() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let sphere = Sphere::new(2);
+        approx::assert_relative_eq!(
+            sphere.fitness(&mut rng, &Chromosome::new(vec![0.0; 2])),
+            1.0
+        );
+        approx::assert_relative_eq!(
+            sphere.fitness(&mut rng, &Chromosome::new(vec![1.0, 2.0])),
+            1.0 / 6.0
+        );
+    }
+}
@@ -0,0 +1,64 @@
+use vroom_rand::RngCore;
+
+use super::Benchmark;
+use crate::chromosome::Chromosome;
+
+const CASES: [([f64; 2], f64); 4] = [
+    ([0.0, 0.0], 0.0),
+    ([0.0, 1.0], 1.0),
+    ([1.0, 0.0], 1.0),
+    ([1.0, 1.0], 0.0),
+];
+
+/// Fits a 2-2-1 ReLU network to XOR. Genes are laid out per neuron as bias
+/// followed by weights, like `MLP::weights_and_biases`.
+pub struct Xor;
+
+impl Xor {
+    pub fn forward(chromosome: &Chromosome, inputs: [f64; 2]) -> f64 {
+        let neuron = |offset: usize, inputs: &[f64]| -> f64 {
+            let sum: f64 = inputs
+                .iter()
+                .enumerate()
+                .map(|(idx, input)| input * chromosome[offset + 1 + idx])
+                .sum();
+            (sum + chromosome[offset]).max(0.0)
+        };
+
+        let hidden = [neuron(0, &inputs), neuron(3, &inputs)];
+        neuron(6, &hidden)
+    }
+}
+
+impl Benchmark for Xor {
+    fn num_genes(&self) -> usize {
+        9
+    }
+
+    fn fitness(&self, _rng: &mut dyn RngCore, chromosome: &Chromosome) -> f64 {
+        let error: f64 = CASES
+            .iter()
+            .map(|(inputs, expected)| (Self::forward(chromosome, *inputs) - expected).powi(2))
+            .sum();
+        1.0 / (1.0 + error)
+    }
+
+    fn max_fitness(&self) -> f64 {
+        1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_known_solution() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        // h1 = relu(x + y), h2 = relu(x + y - 1), out = relu(h1 - 2 * h2)
+        let chromosome = Chromosome::new(vec![0.0, 1.0, 1.0, -1.0, 1.0, 1.0, 0.0, 1.0, -2.0]);
+        approx::assert_relative_eq!(Xor.fitness(&mut rng, &chromosome), 1.0);
+    }
+}
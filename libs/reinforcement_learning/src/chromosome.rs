@@ -1,6 +1,8 @@
 use std::ops::Index;
 
-#[derive(Clone, Debug)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Chromosome {
     genes: Vec<f64>,
 }
@@ -14,6 +16,14 @@ impl Chromosome {
         self.genes.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.genes.is_empty()
+    }
+
+    pub fn as_slice(&self) -> &[f64] {
+        &self.genes
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = &f64> {
         self.genes.iter()
     }
@@ -0,0 +1,234 @@
+use half::f16;
+use serde::{Deserialize, Serialize};
+
+use crate::chromosome::Chromosome;
+
+/// How `CompressedChromosome` stores each gene.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GeneEncoding {
+    /// A half-precision float: 2 bytes, about 3 significant digits. Genes
+    /// beyond ±65504 are clamped to it rather than stored as infinities.
+    F16,
+    /// A byte spread evenly between the smallest and largest gene: 1 byte,
+    /// off by at most 1/510 of that range.
+    U8,
+}
+
+/// A lossy, compact copy of a chromosome for storing many of them, e.g. in
+/// archives of past champions or replays. Storing genes relative to a
+/// reference chromosome, such as a parent or the previous champion, keeps
+/// them small and so makes `GeneEncoding::U8` more precise.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CompressedChromosome {
+    /// Whether the genes are differences from a reference chromosome.
+    delta: bool,
+    genes: Genes,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+enum Genes {
+    F16(Vec<u16>),
+    /// Each gene is `offset + scale * value`.
+    U8 {
+        offset: f64,
+        scale: f64,
+        values: Vec<u8>,
+    },
+}
+
+impl CompressedChromosome {
+    /// Genes must be finite.
+    pub fn compress(chromosome: &Chromosome, encoding: GeneEncoding) -> Self {
+        Self {
+            delta: false,
+            genes: Genes::encode(&chromosome.iter().copied().collect::<Vec<_>>(), encoding),
+        }
+    }
+
+    /// Compresses the difference between `chromosome` and a `reference` of
+    /// the same length, to be restored with `decompress_delta`.
+    pub fn compress_delta(
+        chromosome: &Chromosome,
+        reference: &Chromosome,
+        encoding: GeneEncoding,
+    ) -> Self {
+        assert_eq!(chromosome.len(), reference.len());
+
+        let deltas: Vec<f64> = chromosome
+            .iter()
+            .zip(reference.iter())
+            .map(|(gene, reference)| gene - reference)
+            .collect();
+        Self {
+            delta: true,
+            genes: Genes::encode(&deltas, encoding),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.genes {
+            Genes::F16(genes) => genes.len(),
+            Genes::U8 { values, .. } => values.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_delta(&self) -> bool {
+        self.delta
+    }
+
+    /// Panics if the genes are relative to a reference.
+    pub fn decompress(&self) -> Chromosome {
+        assert!(!self.delta, "delta-compressed, use decompress_delta");
+        self.genes.decode().collect()
+    }
+
+    /// Panics unless the genes are relative to a reference of this length.
+    pub fn decompress_delta(&self, reference: &Chromosome) -> Chromosome {
+        assert!(self.delta, "not delta-compressed, use decompress");
+        assert_eq!(self.len(), reference.len());
+
+        self.genes
+            .decode()
+            .zip(reference.iter())
+            .map(|(delta, reference)| reference + delta)
+            .collect()
+    }
+}
+
+impl Genes {
+    fn encode(genes: &[f64], encoding: GeneEncoding) -> Self {
+        match encoding {
+            GeneEncoding::F16 => Self::F16(
+                genes
+                    .iter()
+                    .map(|&gene| {
+                        f16::from_f64(gene.clamp(f16::MIN.to_f64(), f16::MAX.to_f64())).to_bits()
+                    })
+                    .collect(),
+            ),
+            GeneEncoding::U8 => {
+                let (min, max) = genes
+                    .iter()
+                    .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &gene| {
+                        (min.min(gene), max.max(gene))
+                    });
+                if min > max {
+                    return Self::U8 {
+                        offset: 0.0,
+                        scale: 0.0,
+                        values: Vec::new(),
+                    };
+                }
+                let scale = (max - min) / f64::from(u8::MAX);
+                let values = genes
+                    .iter()
+                    .map(|gene| {
+                        if scale == 0.0 {
+                            0
+                        } else {
+                            ((gene - min) / scale).round() as u8
+                        }
+                    })
+                    .collect();
+                Self::U8 {
+                    offset: min,
+                    scale,
+                    values,
+                }
+            }
+        }
+    }
+
+    fn decode(&self) -> Box<dyn Iterator<Item = f64> + '_> {
+        match self {
+            Self::F16(genes) => Box::new(genes.iter().map(|&bits| f16::from_bits(bits).to_f64())),
+            Self::U8 {
+                offset,
+                scale,
+                values,
+            } => Box::new(
+                values
+                    .iter()
+                    .map(move |&value| offset + scale * f64::from(value)),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn max_error(expected: &Chromosome, actual: &Chromosome) -> f64 {
+        expected
+            .iter()
+            .zip(actual.iter())
+            .map(|(expected, actual)| (expected - actual).abs())
+            .fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let chromosome = Chromosome::new(vec![-1.5, -0.25, 0.0, 0.1, 0.7, 2.0]);
+
+        let f16 = CompressedChromosome::compress(&chromosome, GeneEncoding::F16).decompress();
+        assert!(max_error(&chromosome, &f16) < 1e-3);
+
+        let u8 = CompressedChromosome::compress(&chromosome, GeneEncoding::U8).decompress();
+        assert!(max_error(&chromosome, &u8) <= 3.5 / 510.0 + 1e-12);
+        assert_eq!(u8[0], -1.5);
+        assert_eq!(u8[5], 2.0);
+    }
+
+    #[test]
+    fn test_f16_clamps() {
+        let chromosome = Chromosome::new(vec![1e6, -1e6, 100.0]);
+        let f16 = CompressedChromosome::compress(&chromosome, GeneEncoding::F16).decompress();
+        assert_eq!(f16.as_slice(), [65504.0, -65504.0, 100.0]);
+    }
+
+    #[test]
+    fn test_delta_is_more_precise() {
+        let reference = Chromosome::new((0..100).map(|gene| f64::from(gene) / 10.0).collect());
+        let chromosome: Chromosome = reference
+            .iter()
+            .enumerate()
+            .map(|(idx, gene)| gene + if idx % 2 == 0 { 0.01 } else { -0.02 })
+            .collect();
+
+        let plain = CompressedChromosome::compress(&chromosome, GeneEncoding::U8);
+        let delta = CompressedChromosome::compress_delta(&chromosome, &reference, GeneEncoding::U8);
+
+        assert!(delta.is_delta());
+        let plain_error = max_error(&chromosome, &plain.decompress());
+        let delta_error = max_error(&chromosome, &delta.decompress_delta(&reference));
+        assert!(delta_error < 1e-12, "{delta_error}");
+        assert!(plain_error > 1e-3, "{plain_error}");
+    }
+
+    #[test]
+    fn test_constant_and_empty() {
+        let constant = Chromosome::new(vec![0.5; 4]);
+        let compressed = CompressedChromosome::compress(&constant, GeneEncoding::U8);
+        assert_eq!(
+            compressed.decompress().iter().copied().collect::<Vec<_>>(),
+            vec![0.5; 4]
+        );
+
+        let empty = CompressedChromosome::compress(&Chromosome::new(Vec::new()), GeneEncoding::U8);
+        assert!(empty.is_empty());
+        assert!(empty.decompress().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "use decompress_delta")]
+    fn test_decompress_delta_without_reference() {
+        let chromosome = Chromosome::new(vec![1.0]);
+        CompressedChromosome::compress_delta(&chromosome, &chromosome, GeneEncoding::F16)
+            .decompress();
+    }
+}
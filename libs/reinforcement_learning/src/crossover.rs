@@ -1,9 +1,18 @@
+pub use self::aligned_neuron_crossover::AlignedNeuronCrossover;
+pub use self::blend_crossover::BlendCrossover;
+pub use self::either_crossover::EitherCrossover;
+pub use self::layer_crossover::{LayerCrossover, Segment};
 pub use self::uniform_crossover::UniformCrossover;
 
-use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use vroom_rand::RngCore;
 
 use crate::chromosome::Chromosome;
 
+mod aligned_neuron_crossover;
+mod blend_crossover;
+mod either_crossover;
+mod layer_crossover;
 mod uniform_crossover;
 
 pub trait Crossover {
@@ -13,4 +22,153 @@ pub trait Crossover {
         chromosome1: &Chromosome,
         chromosome2: &Chromosome,
     ) -> Chromosome;
+
+    /// Same as `cross`, also reporting which parent each child gene came
+    /// from. The default infers provenance by comparing values, crediting
+    /// `chromosome1` whenever both parents agree; implementations that know
+    /// their choices should override it, drawing the same random numbers as
+    /// `cross`.
+    fn cross_traced(
+        &self,
+        rng: &mut dyn RngCore,
+        chromosome1: &Chromosome,
+        chromosome2: &Chromosome,
+    ) -> (Chromosome, CrossoverTrace) {
+        let child = self.cross(rng, chromosome1, chromosome2);
+        let genes = child
+            .iter()
+            .zip(chromosome1.iter())
+            .map(|(gene, first)| {
+                let parent = if gene == first {
+                    Parent::First
+                } else {
+                    Parent::Second
+                };
+                GeneOrigin::from(parent)
+            })
+            .collect();
+        (child, CrossoverTrace { genes })
+    }
+}
+
+impl<C: Crossover + ?Sized> Crossover for Box<C> {
+    fn cross(
+        &self,
+        rng: &mut dyn RngCore,
+        chromosome1: &Chromosome,
+        chromosome2: &Chromosome,
+    ) -> Chromosome {
+        (**self).cross(rng, chromosome1, chromosome2)
+    }
+
+    fn cross_traced(
+        &self,
+        rng: &mut dyn RngCore,
+        chromosome1: &Chromosome,
+        chromosome2: &Chromosome,
+    ) -> (Chromosome, CrossoverTrace) {
+        (**self).cross_traced(rng, chromosome1, chromosome2)
+    }
+}
+
+/// Which parent a child gene was copied from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Parent {
+    First,
+    Second,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GeneOrigin {
+    pub parent: Parent,
+    /// Whether mutation changed the gene after crossover.
+    pub mutated: bool,
+}
+
+impl From<Parent> for GeneOrigin {
+    fn from(parent: Parent) -> Self {
+        Self {
+            parent,
+            mutated: false,
+        }
+    }
+}
+
+/// Provenance of each gene of a child chromosome, one entry per gene.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrossoverTrace {
+    pub genes: Vec<GeneOrigin>,
+}
+
+impl CrossoverTrace {
+    /// Marks the genes that differ between the crossed `child` and its
+    /// `mutated` version.
+    pub fn record_mutation(&mut self, child: &Chromosome, mutated: &Chromosome) {
+        assert!(child.len() == self.genes.len() && mutated.len() == self.genes.len());
+
+        for (origin, (before, after)) in self.genes.iter_mut().zip(child.iter().zip(mutated.iter()))
+        {
+            origin.mutated |= before != after;
+        }
+    }
+
+    /// Number of genes taken from `parent`.
+    pub fn count(&self, parent: Parent) -> usize {
+        self.genes
+            .iter()
+            .filter(|origin| origin.parent == parent)
+            .count()
+    }
+
+    pub fn num_mutated(&self) -> usize {
+        self.genes.iter().filter(|origin| origin.mutated).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    /// Takes every gene from the second parent.
+    struct SecondParent;
+
+    impl Crossover for SecondParent {
+        fn cross(
+            &self,
+            _rng: &mut dyn RngCore,
+            _chromosome1: &Chromosome,
+            chromosome2: &Chromosome,
+        ) -> Chromosome {
+            chromosome2.clone()
+        }
+    }
+
+    #[test]
+    fn test_default_cross_traced() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let chromosome1 = Chromosome::new(vec![1.0, 2.0, 3.0]);
+        let chromosome2 = Chromosome::new(vec![1.0, -2.0, -3.0]);
+
+        let (_, trace) = SecondParent.cross_traced(&mut rng, &chromosome1, &chromosome2);
+
+        // Both parents agree on the first gene, so it's credited to the first.
+        assert_eq!(trace.count(Parent::First), 1);
+        assert_eq!(trace.count(Parent::Second), 2);
+    }
+
+    #[test]
+    fn test_record_mutation() {
+        let child = Chromosome::new(vec![1.0, 2.0, 3.0]);
+        let mutated = Chromosome::new(vec![1.0, 2.5, 3.0]);
+        let mut trace = CrossoverTrace {
+            genes: vec![GeneOrigin::from(Parent::First); 3],
+        };
+
+        trace.record_mutation(&child, &mutated);
+
+        assert_eq!(trace.num_mutated(), 1);
+        assert!(trace.genes[1].mutated);
+    }
 }
@@ -0,0 +1,99 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use vroom_rand::RngCore;
+
+use super::{Crossover, CrossoverTrace, GeneOrigin, Parent};
+use crate::chromosome::Chromosome;
+use crate::segmented_chromosome::GenomeLayout;
+
+/// Like `LayerCrossover` by neuron, but first lining up the second parent's
+/// hidden neurons with the first's via `GenomeLayout::align_neurons`.
+/// Networks that learned the same features in different neurons then swap
+/// like for like, instead of a child getting some features twice and
+/// others not at all.
+///
+/// Traces credit the second parent with its aligned genes, whose positions
+/// may differ from where they were in the parent.
+#[derive(Serialize, Deserialize)]
+pub struct AlignedNeuronCrossover {
+    layout: GenomeLayout,
+}
+
+impl AlignedNeuronCrossover {
+    pub fn new(layout: GenomeLayout) -> Self {
+        Self { layout }
+    }
+}
+
+impl Crossover for AlignedNeuronCrossover {
+    fn cross(
+        &self,
+        rng: &mut dyn RngCore,
+        chromosome1: &Chromosome,
+        chromosome2: &Chromosome,
+    ) -> Chromosome {
+        self.cross_traced(rng, chromosome1, chromosome2).0
+    }
+
+    fn cross_traced(
+        &self,
+        rng: &mut dyn RngCore,
+        chromosome1: &Chromosome,
+        chromosome2: &Chromosome,
+    ) -> (Chromosome, CrossoverTrace) {
+        assert!(chromosome1.len() == chromosome2.len());
+
+        let aligned = self.layout.align_neurons(chromosome1, chromosome2);
+        let mut pick = || {
+            if rng.gen_bool(0.5) {
+                Parent::First
+            } else {
+                Parent::Second
+            }
+        };
+        let mut parents = Vec::with_capacity(chromosome1.len());
+        for layer in self.layout.layers() {
+            for _ in 0..layer.neurons {
+                let parent = pick();
+                parents.resize(parents.len() + layer.neuron_len, parent);
+            }
+        }
+        while parents.len() < chromosome1.len() {
+            parents.push(pick());
+        }
+
+        let child = parents
+            .iter()
+            .zip(chromosome1.iter().zip(aligned.iter()))
+            .map(|(parent, (&x, &y))| match parent {
+                Parent::First => x,
+                Parent::Second => y,
+            })
+            .collect();
+        let genes = parents.into_iter().map(GeneOrigin::from).collect();
+        (child, CrossoverTrace { genes })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_cross_permuted_parents() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let layout = GenomeLayout::mlp(1, &[2, 1]);
+        // Hidden neurons (bias, weight) of [0.0, 1.0] and [5.0, -1.0], and an
+        // output neuron weighing them 2.0 and 3.0
+        let parent1 = Chromosome::new(vec![0.0, 1.0, 5.0, -1.0, 0.5, 2.0, 3.0]);
+        let parent2 = Chromosome::new(vec![5.0, -1.0, 0.0, 1.0, 0.5, 3.0, 2.0]);
+
+        let crossover = AlignedNeuronCrossover::new(layout);
+        for _ in 0..10 {
+            let child = crossover.cross(&mut rng, &parent1, &parent2);
+            assert_eq!(child.as_slice(), parent1.as_slice());
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+use vroom_rand::RngCore;
+
+use super::{Crossover, CrossoverTrace, GeneOrigin, Parent};
+use crate::chromosome::Chromosome;
+
+/// Interpolates between the parents, taking `weight` of the second parent
+/// and the rest of the first. For chromosomes holding network weights and
+/// biases the child's network computes a blend of its parents' layers.
+/// Draws nothing from `rng`.
+#[derive(Serialize, Deserialize)]
+pub struct BlendCrossover {
+    weight: f64,
+    /// Genes copied from the first parent instead, see `with_discrete`.
+    #[serde(default)]
+    discrete: Range<usize>,
+}
+
+impl BlendCrossover {
+    pub fn new(weight: f64) -> Self {
+        assert!((0.0..=1.0).contains(&weight));
+        Self {
+            weight,
+            discrete: 0..0,
+        }
+    }
+
+    /// Averages the parents.
+    pub fn mean() -> Self {
+        Self::new(0.5)
+    }
+
+    /// Copies `genes` from the first parent rather than blending them, for
+    /// genes that pick one of several options, such as a layer's
+    /// activation function, where values in between pick something neither
+    /// parent had.
+    pub fn with_discrete(self, genes: Range<usize>) -> Self {
+        Self {
+            discrete: genes,
+            ..self
+        }
+    }
+}
+
+impl Crossover for BlendCrossover {
+    fn cross(
+        &self,
+        _rng: &mut dyn RngCore,
+        chromosome1: &Chromosome,
+        chromosome2: &Chromosome,
+    ) -> Chromosome {
+        assert!(chromosome1.len() == chromosome2.len());
+
+        chromosome1
+            .iter()
+            .zip(chromosome2.iter())
+            .enumerate()
+            .map(|(gene, (&x, &y))| {
+                if self.discrete.contains(&gene) {
+                    x
+                } else {
+                    x + self.weight * (y - x)
+                }
+            })
+            .collect()
+    }
+
+    /// Credits every gene to the parent with the larger weight, the first on
+    /// a tie, and discrete genes to the first.
+    fn cross_traced(
+        &self,
+        rng: &mut dyn RngCore,
+        chromosome1: &Chromosome,
+        chromosome2: &Chromosome,
+    ) -> (Chromosome, CrossoverTrace) {
+        let child = self.cross(rng, chromosome1, chromosome2);
+        let parent = if self.weight > 0.5 {
+            Parent::Second
+        } else {
+            Parent::First
+        };
+        let genes = (0..child.len())
+            .map(|gene| {
+                if self.discrete.contains(&gene) {
+                    GeneOrigin::from(Parent::First)
+                } else {
+                    GeneOrigin::from(parent)
+                }
+            })
+            .collect();
+        (child, CrossoverTrace { genes })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_cross() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let chromosome1 = Chromosome::new(vec![0.0, 1.0, -2.0]);
+        let chromosome2 = Chromosome::new(vec![4.0, 1.0, 2.0]);
+
+        let child = BlendCrossover::new(0.25).cross(&mut rng, &chromosome1, &chromosome2);
+        assert!(child.iter().eq([1.0, 1.0, -1.0].iter()));
+
+        let (child, trace) =
+            BlendCrossover::mean().cross_traced(&mut rng, &chromosome1, &chromosome2);
+        assert!(child.iter().eq([2.0, 1.0, 0.0].iter()));
+        assert_eq!(trace.count(Parent::First), 3);
+    }
+
+    #[test]
+    fn test_discrete() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let chromosome1 = Chromosome::new(vec![0.0, 1.0, 0.0]);
+        let chromosome2 = Chromosome::new(vec![4.0, 3.0, 4.0]);
+
+        let (child, trace) = BlendCrossover::new(0.75).with_discrete(1..2).cross_traced(
+            &mut rng,
+            &chromosome1,
+            &chromosome2,
+        );
+        assert!(child.iter().eq([3.0, 1.0, 3.0].iter()));
+        assert_eq!(trace.count(Parent::First), 1);
+    }
+}
@@ -0,0 +1,83 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use vroom_rand::RngCore;
+
+use super::Crossover;
+use crate::chromosome::Chromosome;
+
+/// Uses `first` with the given probability and `second` otherwise, picked
+/// afresh for each child. A probability of 0 always uses `second` without
+/// drawing from `rng`, so a disabled `first` doesn't change the results.
+#[derive(Serialize, Deserialize)]
+pub struct EitherCrossover<A: Crossover, B: Crossover> {
+    probability: f64,
+    first: A,
+    second: B,
+}
+
+impl<A: Crossover, B: Crossover> EitherCrossover<A, B> {
+    pub fn new(probability: f64, first: A, second: B) -> Self {
+        assert!((0.0..=1.0).contains(&probability));
+        Self {
+            probability,
+            first,
+            second,
+        }
+    }
+}
+
+impl<A: Crossover, B: Crossover> Crossover for EitherCrossover<A, B> {
+    fn cross(
+        &self,
+        rng: &mut dyn RngCore,
+        chromosome1: &Chromosome,
+        chromosome2: &Chromosome,
+    ) -> Chromosome {
+        if self.probability > 0.0 && rng.gen_bool(self.probability) {
+            self.first.cross(rng, chromosome1, chromosome2)
+        } else {
+            self.second.cross(rng, chromosome1, chromosome2)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    /// Always returns a copy of one parent.
+    struct CopyParent(bool);
+
+    impl Crossover for CopyParent {
+        fn cross(
+            &self,
+            _rng: &mut dyn RngCore,
+            chromosome1: &Chromosome,
+            chromosome2: &Chromosome,
+        ) -> Chromosome {
+            if self.0 {
+                chromosome1.clone()
+            } else {
+                chromosome2.clone()
+            }
+        }
+    }
+
+    #[test]
+    fn test_cross() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let chromosome1 = Chromosome::new(vec![1.0]);
+        let chromosome2 = Chromosome::new(vec![-1.0]);
+        let mut first_count = |probability| {
+            let crosser = EitherCrossover::new(probability, CopyParent(true), CopyParent(false));
+            (0..100)
+                .filter(|_| crosser.cross(&mut rng, &chromosome1, &chromosome2)[0] > 0.0)
+                .count()
+        };
+
+        assert_eq!(first_count(1.0), 100);
+        assert_eq!(first_count(0.0), 0);
+    }
+}
@@ -0,0 +1,142 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use vroom_rand::RngCore;
+
+use super::{Crossover, CrossoverTrace, GeneOrigin, Parent};
+use crate::chromosome::Chromosome;
+use crate::segmented_chromosome::GenomeLayout;
+
+/// What `LayerCrossover` copies from one parent at a time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Segment {
+    Layer,
+    Neuron,
+}
+
+/// Like `UniformCrossover`, but copying whole layers or neurons of a
+/// `GenomeLayout` from either parent, so weights that work together stay
+/// together. Genes past the layout are picked one by one.
+#[derive(Serialize, Deserialize)]
+pub struct LayerCrossover {
+    layout: GenomeLayout,
+    segment: Segment,
+}
+
+impl LayerCrossover {
+    pub fn new(layout: GenomeLayout, segment: Segment) -> Self {
+        Self { layout, segment }
+    }
+}
+
+impl Crossover for LayerCrossover {
+    fn cross(
+        &self,
+        rng: &mut dyn RngCore,
+        chromosome1: &Chromosome,
+        chromosome2: &Chromosome,
+    ) -> Chromosome {
+        self.cross_traced(rng, chromosome1, chromosome2).0
+    }
+
+    fn cross_traced(
+        &self,
+        rng: &mut dyn RngCore,
+        chromosome1: &Chromosome,
+        chromosome2: &Chromosome,
+    ) -> (Chromosome, CrossoverTrace) {
+        assert!(chromosome1.len() == chromosome2.len());
+        assert!(chromosome1.len() >= self.layout.len());
+
+        let mut pick = || {
+            if rng.gen_bool(0.5) {
+                Parent::First
+            } else {
+                Parent::Second
+            }
+        };
+        let mut parents = Vec::with_capacity(chromosome1.len());
+        for layer in self.layout.layers() {
+            match self.segment {
+                Segment::Layer => {
+                    let parent = pick();
+                    parents.resize(parents.len() + layer.len(), parent);
+                }
+                Segment::Neuron => {
+                    for _ in 0..layer.neurons {
+                        let parent = pick();
+                        parents.resize(parents.len() + layer.neuron_len, parent);
+                    }
+                }
+            }
+        }
+        while parents.len() < chromosome1.len() {
+            parents.push(pick());
+        }
+
+        let child = parents
+            .iter()
+            .zip(chromosome1.iter().zip(chromosome2.iter()))
+            .map(|(parent, (&x, &y))| match parent {
+                Parent::First => x,
+                Parent::Second => y,
+            })
+            .collect();
+        let genes = parents.into_iter().map(GeneOrigin::from).collect();
+        (child, CrossoverTrace { genes })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    /// Whether every gene of each neuron came from the same parent.
+    fn neurons_intact(layout: &GenomeLayout, trace: &CrossoverTrace) -> bool {
+        layout.layers().iter().all(|layer| {
+            (0..layer.neurons).all(|neuron| {
+                let range = layer.neuron(neuron);
+                let first = trace.genes[range.start].parent;
+                trace.genes[range].iter().all(|gene| gene.parent == first)
+            })
+        })
+    }
+
+    #[test]
+    fn test_cross_keeps_segments_whole() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let layout = GenomeLayout::mlp(3, &[4, 2]);
+        let parent1 = Chromosome::new(vec![1.0; layout.len() + 2]);
+        let parent2 = Chromosome::new(vec![2.0; layout.len() + 2]);
+
+        for segment in [Segment::Layer, Segment::Neuron] {
+            let crossover = LayerCrossover::new(layout.clone(), segment);
+            for _ in 0..20 {
+                let (child, trace) = crossover.cross_traced(&mut rng, &parent1, &parent2);
+                assert_eq!(child.len(), parent1.len());
+                assert!(neurons_intact(&layout, &trace));
+                if segment == Segment::Layer {
+                    let layer = layout.layers()[0].range();
+                    assert!(child.as_slice()[layer.clone()]
+                        .iter()
+                        .all(|&gene| gene == child[layer.start]));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_cross_matches_cross_traced() {
+        let layout = GenomeLayout::mlp(2, &[2]);
+        let crossover = LayerCrossover::new(layout, Segment::Neuron);
+        let parent1: Chromosome = (0..6).map(f64::from).collect();
+        let parent2: Chromosome = (10..16).map(f64::from).collect();
+
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let child = crossover.cross(&mut rng, &parent1, &parent2);
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let (traced, _) = crossover.cross_traced(&mut rng, &parent1, &parent2);
+        assert_eq!(child.as_slice(), traced.as_slice());
+    }
+}
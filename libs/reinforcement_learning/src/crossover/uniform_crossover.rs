@@ -1,8 +1,11 @@
-use rand::{Rng, RngCore};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use vroom_rand::RngCore;
 
-use super::Crossover;
+use super::{Crossover, CrossoverTrace, GeneOrigin, Parent};
 use crate::chromosome::Chromosome;
 
+#[derive(Default, Serialize, Deserialize)]
 pub struct UniformCrossover;
 
 impl UniformCrossover {
@@ -26,6 +29,28 @@ impl Crossover for UniformCrossover {
             .map(|(&x, &y)| if rng.gen_bool(0.5) { x } else { y })
             .collect()
     }
+
+    fn cross_traced(
+        &self,
+        rng: &mut dyn RngCore,
+        chromosome1: &Chromosome,
+        chromosome2: &Chromosome,
+    ) -> (Chromosome, CrossoverTrace) {
+        assert!(chromosome1.len() == chromosome2.len());
+
+        let (child, genes) = chromosome1
+            .iter()
+            .zip(chromosome2.iter())
+            .map(|(&x, &y)| {
+                if rng.gen_bool(0.5) {
+                    (x, GeneOrigin::from(Parent::First))
+                } else {
+                    (y, GeneOrigin::from(Parent::Second))
+                }
+            })
+            .unzip();
+        (Chromosome::new(child), CrossoverTrace { genes })
+    }
 }
 
 #[cfg(test)]
@@ -55,6 +80,30 @@ mod tests {
         approx::assert_relative_eq!(actual_freq.as_slice(), expected_freq.as_slice());
     }
 
+    #[test]
+    fn test_cross_traced_matches_cross() {
+        let crosser = UniformCrossover::new();
+        let chromosome1 = Chromosome::new(vec![1.0; 50]);
+        let chromosome2 = Chromosome::new(vec![-1.0; 50]);
+
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let child = crosser.cross(&mut rng, &chromosome1, &chromosome2);
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let (traced, trace) = crosser.cross_traced(&mut rng, &chromosome1, &chromosome2);
+
+        assert!(child.iter().eq(traced.iter()));
+        for (gene, origin) in traced.iter().zip(&trace.genes) {
+            let expected = if *gene > 0.0 {
+                Parent::First
+            } else {
+                Parent::Second
+            };
+            assert_eq!(origin.parent, expected);
+        }
+        // Sum of the first cross in `test_cross`
+        assert_eq!(trace.count(Parent::First), 27);
+    }
+
     #[test]
     #[should_panic]
     fn test_different_chromosome_length() {
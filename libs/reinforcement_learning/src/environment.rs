@@ -0,0 +1,120 @@
+use vroom_rand::RngCore;
+
+/// Multi-agent environment that policies (e.g. evolved brains) can be
+/// optimized against, decoupled from any particular simulation.
+pub trait Environment {
+    /// Number of values in each agent's observation.
+    fn observation_size(&self) -> usize;
+
+    /// Number of values each agent's action must contain.
+    fn action_size(&self) -> usize;
+
+    fn num_agents(&self) -> usize;
+
+    /// Starts a new episode, returning each agent's first observation.
+    fn reset(&mut self, rng: &mut dyn RngCore) -> Vec<Vec<f64>>;
+
+    /// Applies one action per agent and advances the environment by a step.
+    fn step(&mut self, rng: &mut dyn RngCore, actions: &[Vec<f64>]) -> Transition;
+}
+
+/// Result of `Environment::step`.
+#[derive(Clone, Debug)]
+pub struct Transition {
+    pub observations: Vec<Vec<f64>>,
+    pub rewards: Vec<f64>,
+    pub done: bool,
+}
+
+/// Runs one episode of at most `max_steps` steps, choosing each agent's
+/// action with `policy(agent_idx, observation)`, and returns the total
+/// reward collected by each agent.
+pub fn rollout<E: Environment + ?Sized>(
+    env: &mut E,
+    rng: &mut dyn RngCore,
+    mut policy: impl FnMut(usize, &[f64]) -> Vec<f64>,
+    max_steps: usize,
+) -> Vec<f64> {
+    let mut observations = env.reset(rng);
+    let mut total_rewards = vec![0.0; env.num_agents()];
+
+    for _ in 0..max_steps {
+        let actions: Vec<Vec<f64>> = observations
+            .iter()
+            .enumerate()
+            .map(|(idx, observation)| policy(idx, observation))
+            .collect();
+        let transition = env.step(rng, &actions);
+        for (total, reward) in total_rewards.iter_mut().zip(&transition.rewards) {
+            *total += reward;
+        }
+        observations = transition.observations;
+        if transition.done {
+            break;
+        }
+    }
+
+    total_rewards
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    /// Single agent rewarded with its action, for `steps` steps.
+    struct EchoEnvironment {
+        steps: usize,
+        remaining: usize,
+    }
+
+    impl Environment for EchoEnvironment {
+        fn observation_size(&self) -> usize {
+            1
+        }
+
+        fn action_size(&self) -> usize {
+            1
+        }
+
+        fn num_agents(&self) -> usize {
+            1
+        }
+
+        fn reset(&mut self, _rng: &mut dyn RngCore) -> Vec<Vec<f64>> {
+            self.remaining = self.steps;
+            vec![vec![self.remaining as f64]]
+        }
+
+        fn step(&mut self, _rng: &mut dyn RngCore, actions: &[Vec<f64>]) -> Transition {
+            self.remaining -= 1;
+            Transition {
+                observations: vec![vec![self.remaining as f64]],
+                rewards: vec![actions[0][0]],
+                done: self.remaining == 0,
+            }
+        }
+    }
+
+    #[test]
+    fn test_rollout() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut env = EchoEnvironment {
+            steps: 3,
+            remaining: 0,
+        };
+
+        let rewards = rollout(
+            &mut env,
+            &mut rng,
+            |_, observation| observation.to_vec(),
+            10,
+        );
+        // Observations seen are 3, 2, 1 before the episode ends.
+        assert_eq!(rewards, vec![6.0]);
+
+        let rewards = rollout(&mut env, &mut rng, |_, _| vec![1.0], 2);
+        assert_eq!(rewards, vec![2.0]);
+    }
+}
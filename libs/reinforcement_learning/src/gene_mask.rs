@@ -0,0 +1,74 @@
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use crate::chromosome::Chromosome;
+
+/// Genes evolution leaves alone, e.g. a trained part of a network while
+/// another part evolves, or genes held fixed for an ablation study. Frozen
+/// genes are always inherited from the first parent and never mutated.
+/// Genes past the end of the mask evolve.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GeneMask {
+    frozen: Vec<bool>,
+}
+
+impl GeneMask {
+    /// `frozen[i]` tells whether gene `i` is frozen.
+    pub fn new(frozen: Vec<bool>) -> Self {
+        Self { frozen }
+    }
+
+    /// Also freezes `genes`.
+    pub fn freeze(mut self, genes: Range<usize>) -> Self {
+        if self.frozen.len() < genes.end {
+            self.frozen.resize(genes.end, false);
+        }
+        self.frozen[genes].fill(true);
+        self
+    }
+
+    pub fn is_frozen(&self, gene: usize) -> bool {
+        self.frozen.get(gene).copied().unwrap_or(false)
+    }
+
+    /// Number of frozen genes.
+    pub fn count(&self) -> usize {
+        self.frozen.iter().filter(|&&frozen| frozen).count()
+    }
+
+    /// `child` with its frozen genes reset to `parent1`'s.
+    pub fn apply(&self, parent1: &Chromosome, child: Chromosome) -> Chromosome {
+        assert!(child.len() == parent1.len());
+        if self.count() == 0 {
+            return child;
+        }
+
+        let genes = child.iter().zip(parent1.iter()).enumerate();
+        genes
+            .map(|(gene, (&evolved, &inherited))| {
+                if self.is_frozen(gene) {
+                    inherited
+                } else {
+                    evolved
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let mask = GeneMask::default().freeze(1..3);
+        assert_eq!(mask.count(), 2);
+        assert!(!mask.is_frozen(5));
+
+        let parent1 = Chromosome::new(vec![1.0; 5]);
+        let child = mask.apply(&parent1, Chromosome::new(vec![2.0; 5]));
+        assert_eq!(child.as_slice(), [2.0, 1.0, 1.0, 2.0, 2.0]);
+    }
+}
@@ -0,0 +1,101 @@
+use serde::{Deserialize, Serialize};
+
+use crate::individual::Individual;
+
+/// Mean and standard deviation of each gene position across a population,
+/// e.g. to see which weights of a brain have converged.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct GeneStatistics {
+    pub mean: Vec<f64>,
+    pub std: Vec<f64>,
+}
+
+impl GeneStatistics {
+    pub fn from_population<I: Individual>(population: &[I]) -> Self {
+        let Some(first) = population.first() else {
+            return Self::default();
+        };
+        let num_genes = first.as_chromosome().len();
+
+        let mut sum = vec![0.0; num_genes];
+        for individual in population {
+            for (sum, gene) in sum.iter_mut().zip(individual.as_chromosome().iter()) {
+                *sum += gene;
+            }
+        }
+        let n = population.len() as f64;
+        let mean: Vec<f64> = sum.iter().map(|sum| sum / n).collect();
+
+        // Summing squared deviations from the mean, rather than squared
+        // genes, keeps genes far from zero from losing their variance to
+        // rounding.
+        let mut var = vec![0.0; num_genes];
+        for individual in population {
+            for ((var, mean), gene) in var
+                .iter_mut()
+                .zip(&mean)
+                .zip(individual.as_chromosome().iter())
+            {
+                *var += (gene - mean).powi(2) / n;
+            }
+        }
+        let std = var.iter().map(|var| var.sqrt()).collect();
+        Self { mean, std }
+    }
+
+    pub fn len(&self) -> usize {
+        self.mean.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mean.is_empty()
+    }
+
+    /// Variance of each gene, averaged over genes.
+    pub fn mean_variance(&self) -> f64 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        self.std.iter().map(|std| std.powi(2)).sum::<f64>() / self.len() as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chromosome::Chromosome;
+    use crate::individual::TestIndividual;
+
+    #[test]
+    fn test_from_population() {
+        let population = vec![
+            TestIndividual::from_chromosome(Chromosome::new(vec![0.0, 1.0, 2.0])),
+            TestIndividual::from_chromosome(Chromosome::new(vec![2.0, 1.0, -2.0])),
+        ];
+
+        let stats = GeneStatistics::from_population(&population);
+
+        assert_eq!(stats.mean, vec![1.0, 1.0, 0.0]);
+        assert_eq!(stats.std, vec![1.0, 0.0, 2.0]);
+        approx::assert_relative_eq!(stats.mean_variance(), 5.0 / 3.0);
+    }
+
+    #[test]
+    fn test_genes_far_from_zero() {
+        let population = vec![
+            TestIndividual::from_chromosome(Chromosome::new(vec![1e9])),
+            TestIndividual::from_chromosome(Chromosome::new(vec![1e9 + 1.0])),
+        ];
+
+        let stats = GeneStatistics::from_population(&population);
+
+        assert_eq!(stats.std, vec![0.5]);
+    }
+
+    #[test]
+    fn test_empty_population() {
+        let stats = GeneStatistics::from_population::<TestIndividual>(&[]);
+        assert!(stats.is_empty());
+        assert_eq!(stats.mean_variance(), 0.0);
+    }
+}
@@ -1,11 +1,36 @@
-use rand::RngCore;
+use rand::distributions::WeightedError;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use tracing::Level;
+use vroom_rand::RngCore;
 
 pub use crate::chromosome::Chromosome;
-pub use crate::crossover::{Crossover, UniformCrossover};
+pub use crate::compressed_chromosome::{CompressedChromosome, GeneEncoding};
+pub use crate::crossover::{
+    AlignedNeuronCrossover, BlendCrossover, Crossover, CrossoverTrace, EitherCrossover, GeneOrigin,
+    LayerCrossover, Parent, Segment, UniformCrossover,
+};
+pub use crate::gene_mask::GeneMask;
+pub use crate::gene_statistics::GeneStatistics;
 pub use crate::individual::Individual;
-pub use crate::mutation::{GaussianMutation, Mutation};
-pub use crate::selection::{FitnessProportionateSelection, Selection};
+pub use crate::mate_choice::{AssortativeMating, MateChoice};
+pub use crate::mutation::{
+    ChainedMutation, GaussianMutation, LayerMutation, Mutation, Noise, NoiseSource,
+    ProbabilisticMutation,
+};
+pub use crate::operator_pool::{OperatorPool, OperatorStatistics};
+pub use crate::population::Population;
+pub use crate::population_init::{ChampionInit, MixedInit, PopulationInit, RandomInit};
+pub use crate::race::Race;
+pub use crate::segmented_chromosome::{GenomeLayout, LayerSegment, SegmentedChromosome};
+pub use crate::selection::{FitnessProportionateSelection, Selection, TournamentSelection};
 
+/// `GeneticAlgorithm` with operators picked at runtime.
+pub type DynGeneticAlgorithm =
+    GeneticAlgorithm<Box<dyn Selection>, Box<dyn Crossover>, Box<dyn Mutation>>;
+
+#[derive(Serialize, Deserialize)]
 pub struct GeneticAlgorithm<S, C, M>
 where
     S: Selection,
@@ -15,6 +40,14 @@ where
     selection_method: S,
     crossover_method: C,
     mutation_method: M,
+    #[serde(default)]
+    distinct_parents: bool,
+    /// Picks the second parent when set. Not serialized, and `Sync` so
+    /// children can be bred in parallel with `breed_child`.
+    #[serde(skip)]
+    mate_choice: Option<Box<dyn MateChoice + Send + Sync>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    gene_mask: Option<GeneMask>,
 }
 
 impl<S, C, M> GeneticAlgorithm<S, C, M>
@@ -28,23 +61,475 @@ where
             selection_method,
             crossover_method,
             mutation_method,
+            distinct_parents: false,
+            mate_choice: None,
+            gene_mask: None,
         }
     }
 
-    pub fn evolve<I: Individual>(&self, rng: &mut dyn RngCore, population: &[I]) -> Vec<I> {
-        (0..population.len())
+    /// Keeps the genes frozen in `gene_mask` as they are: copied from the
+    /// first parent by crossover and untouched by mutation.
+    pub fn with_gene_mask(self, gene_mask: GeneMask) -> Self {
+        Self {
+            gene_mask: Some(gene_mask),
+            ..self
+        }
+    }
+
+    pub fn gene_mask(&self) -> Option<&GeneMask> {
+        self.gene_mask.as_ref()
+    }
+
+    /// Never picks the same individual as both parents of a child, which
+    /// would just clone it, unless no other individual can be selected
+    /// (a population of one, or everyone else with zero fitness while the
+    /// first parent scored). When nobody scored, the second parent is any
+    /// other individual, uniformly.
+    pub fn with_distinct_parents(self) -> Self {
+        Self {
+            distinct_parents: true,
+            ..self
+        }
+    }
+
+    /// Selects only the first parent of each child with the selection
+    /// method, leaving the second to `mate_choice`, or to the first parent
+    /// itself when it finds no other mate. Overrides
+    /// `with_distinct_parents`.
+    pub fn with_mate_choice(self, mate_choice: Box<dyn MateChoice + Send + Sync>) -> Self {
+        Self {
+            mate_choice: Some(mate_choice),
+            ..self
+        }
+    }
+
+    pub fn evolve<I: Individual>(
+        &self,
+        rng: &mut dyn RngCore,
+        population: &Population<I>,
+    ) -> Vec<I> {
+        let _span = tracing::debug_span!("evolve", population = population.len()).entered();
+        self.breed_children(rng, population, population.len())
+    }
+
+    /// Same as `evolve`, but with `immigrant_fraction` of the new
+    /// generation, rounded, replaced by fresh chromosomes from `immigrants`
+    /// to keep some diversity. Immigrants come last, with the genes frozen
+    /// by `gene_mask` taken from the fittest individual.
+    pub fn evolve_with_immigrants<I: Individual>(
+        &self,
+        rng: &mut dyn RngCore,
+        population: &Population<I>,
+        immigrant_fraction: f64,
+        immigrants: &dyn PopulationInit,
+    ) -> Vec<I> {
+        assert!((0.0..=1.0).contains(&immigrant_fraction));
+
+        let num_immigrants = (population.len() as f64 * immigrant_fraction).round() as usize;
+        let _span = tracing::debug_span!(
+            "evolve",
+            population = population.len(),
+            immigrants = num_immigrants
+        )
+        .entered();
+        let mut children = self.breed_children(rng, population, population.len() - num_immigrants);
+        if num_immigrants > 0 {
+            let fittest = population.best().as_chromosome();
+            children.extend(
+                immigrants
+                    .init(rng, num_immigrants)
+                    .into_iter()
+                    .map(|immigrant| I::from_chromosome(self.mask(fittest, immigrant))),
+            );
+        }
+        children
+    }
+
+    /// A generation of `population_size` built from `seeds`, e.g. elites of
+    /// an earlier run, without evaluating anything first: cycling through
+    /// the seeds, the first copy of each is exact and the rest are mutated
+    /// with the mutation method.
+    pub fn evolve_from_seeds<I: Individual>(
+        &self,
+        rng: &mut dyn RngCore,
+        seeds: &[Chromosome],
+        population_size: usize,
+    ) -> Vec<I> {
+        assert!(!seeds.is_empty());
+
+        let _span =
+            tracing::debug_span!("evolve", population = population_size, seeds = seeds.len())
+                .entered();
+        let mut counters = Counters::new();
+        let children = (0..population_size)
+            .map(|idx| {
+                let seed = &seeds[idx % seeds.len()];
+                if idx < seeds.len() {
+                    return I::from_chromosome(seed.clone());
+                }
+                let mutated = self.mutate(rng, seed, seed);
+                counters.record(seed, &mutated);
+                I::from_chromosome(mutated)
+            })
+            .collect();
+        counters.emit();
+        children
+    }
+
+    fn breed_children<I: Individual>(
+        &self,
+        rng: &mut dyn RngCore,
+        population: &Population<I>,
+        cnt: usize,
+    ) -> Vec<I> {
+        let fitnesses = population.fitness_slice();
+        let mut counters = Counters::new();
+        let children = (0..cnt)
+            .map(|_| {
+                let [parent1, parent2] = self.select_parents(rng, population, fitnesses);
+                let parent1 = population[parent1].as_chromosome();
+                let child = self.cross(rng, parent1, population[parent2].as_chromosome());
+                let mutated = self.mutate(rng, parent1, &child);
+                counters.record(&child, &mutated);
+                I::from_chromosome(mutated)
+            })
+            .collect();
+        counters.emit();
+        children
+    }
+
+    /// Same as `evolve`, also returning each child's `CrossoverTrace` with
+    /// its mutated genes marked.
+    pub fn evolve_traced<I: Individual>(
+        &self,
+        rng: &mut dyn RngCore,
+        population: &Population<I>,
+    ) -> Vec<(I, CrossoverTrace)> {
+        let _span = tracing::debug_span!("evolve", population = population.len()).entered();
+        let fitnesses = population.fitness_slice();
+        let mut counters = Counters::new();
+        let children = (0..population.len())
             .map(|_| {
-                let parents = self.selection_method.select(rng, population, 2);
-                let child = self.crossover_method.cross(
+                let [parent1, parent2] = self.select_parents(rng, population, fitnesses);
+                let parent1 = population[parent1].as_chromosome();
+                let (child, mut trace) = self.crossover_method.cross_traced(
                     rng,
-                    &parents[0].as_chromosome(),
-                    &parents[1].as_chromosome(),
+                    parent1,
+                    population[parent2].as_chromosome(),
                 );
-                let mutated = self.mutation_method.mutate(rng, &child);
-                I::from_chromosome(mutated)
+                let child = self.mask(parent1, child);
+                if let Some(mask) = &self.gene_mask {
+                    for (gene, origin) in trace.genes.iter_mut().enumerate() {
+                        if mask.is_frozen(gene) {
+                            origin.parent = Parent::First;
+                        }
+                    }
+                }
+                let mutated = self.mutate(rng, parent1, &child);
+                counters.record(&child, &mutated);
+                trace.record_mutation(&child, &mutated);
+                (I::from_chromosome(mutated), trace)
             })
+            .collect();
+        counters.emit();
+        children
+    }
+
+    /// Same as `evolve`, but child `i` is bred with nothing but
+    /// `child_rng(seed, generation, i)`, so breeding children in any order,
+    /// e.g. in parallel with `breed_child`, gives the same generation.
+    pub fn evolve_seeded<I: Individual>(
+        &self,
+        seed: u64,
+        generation: u64,
+        population: &Population<I>,
+    ) -> Vec<I> {
+        let _span =
+            tracing::debug_span!("evolve", population = population.len(), generation).entered();
+        let fitnesses = population.fitness_slice();
+        (0..population.len())
+            .map(|child| self.breed_child(seed, generation, child, population, fitnesses))
             .collect()
     }
+
+    /// Child `child` of `evolve_seeded`, given the fitness of each
+    /// individual of `population`.
+    pub fn breed_child<I: Individual>(
+        &self,
+        seed: u64,
+        generation: u64,
+        child: usize,
+        population: &[I],
+        fitnesses: &[f64],
+    ) -> I {
+        let mut rng = child_rng(seed, generation, child as u64);
+        let [parent1, parent2] = self.select_parents(&mut rng, population, fitnesses);
+        I::from_chromosome(self.breed(
+            &mut rng,
+            population[parent1].as_chromosome(),
+            population[parent2].as_chromosome(),
+        ))
+    }
+
+    /// Indices of the two parents of one child, given the population and
+    /// the fitness of each of its individuals.
+    ///
+    /// Together with `breed`, lets callers run `evolve` step by step, e.g.
+    /// picking every pair up front and breeding them in parallel, without
+    /// handing over or cloning their individuals.
+    pub fn select_parents<I: Individual>(
+        &self,
+        rng: &mut dyn RngCore,
+        population: &[I],
+        fitnesses: &[f64],
+    ) -> [usize; 2] {
+        if let Some(mate_choice) = &self.mate_choice {
+            let parent1 = self.selection_method.select_indices(rng, fitnesses, 1)[0];
+            let chromosome = |idx: usize| population[idx].as_chromosome();
+            return match mate_choice.choose_mate(rng, &chromosome, fitnesses, parent1) {
+                Ok(parent2) => [parent1, parent2],
+                Err(WeightedError::AllWeightsZero) => [parent1, parent1],
+                Err(err) => panic!("Invalid fitnesses {fitnesses:?}: {err}"),
+            };
+        }
+        if !self.distinct_parents {
+            let parents = self.selection_method.select_indices(rng, fitnesses, 2);
+            return [parents[0], parents[1]];
+        }
+
+        let parent1 = self.selection_method.select_indices(rng, fitnesses, 1)[0];
+        if fitnesses.len() > 1 && fitnesses.iter().all(|&fitness| fitness == 0.0) {
+            let parent2 = (parent1 + rng.gen_range(1..fitnesses.len())) % fitnesses.len();
+            return [parent1, parent2];
+        }
+        let mut others = fitnesses.to_vec();
+        others[parent1] = 0.0;
+        if others.iter().all(|&fitness| fitness == 0.0) {
+            return [parent1, parent1];
+        }
+        let parent2 = self.selection_method.select_indices(rng, &others, 1)[0];
+        [parent1, parent2]
+    }
+
+    /// Crosses and mutates two parents into a child.
+    pub fn breed(
+        &self,
+        rng: &mut dyn RngCore,
+        parent1: &Chromosome,
+        parent2: &Chromosome,
+    ) -> Chromosome {
+        let child = self.cross(rng, parent1, parent2);
+        self.mutate(rng, parent1, &child)
+    }
+
+    fn cross(
+        &self,
+        rng: &mut dyn RngCore,
+        parent1: &Chromosome,
+        parent2: &Chromosome,
+    ) -> Chromosome {
+        let child = self.crossover_method.cross(rng, parent1, parent2);
+        self.mask(parent1, child)
+    }
+
+    fn mutate(
+        &self,
+        rng: &mut dyn RngCore,
+        parent1: &Chromosome,
+        child: &Chromosome,
+    ) -> Chromosome {
+        let mutated = self.mutation_method.mutate(rng, child);
+        self.mask(parent1, mutated)
+    }
+
+    /// `child` with the genes frozen by `gene_mask` reset to `parent1`'s.
+    fn mask(&self, parent1: &Chromosome, child: Chromosome) -> Chromosome {
+        match &self.gene_mask {
+            Some(mask) => mask.apply(parent1, child),
+            None => child,
+        }
+    }
+}
+
+/// Operators and parent fitness behind one child of
+/// `AdaptiveGeneticAlgorithm::evolve`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Origin {
+    pub crossover: usize,
+    pub mutation: usize,
+    /// Fitness of the fitter parent.
+    pub parent_fitness: f64,
+}
+
+/// Like `GeneticAlgorithm`, but picking the crossover and mutation for each
+/// child from pools of operators, and tracking which operators produce
+/// children that outperform their parents.
+#[derive(Serialize, Deserialize)]
+pub struct AdaptiveGeneticAlgorithm<S, C, M>
+where
+    S: Selection,
+    C: Crossover,
+    M: Mutation,
+{
+    selection_method: S,
+    crossover_methods: OperatorPool<C>,
+    mutation_methods: OperatorPool<M>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    gene_mask: Option<GeneMask>,
+}
+
+impl<S, C, M> AdaptiveGeneticAlgorithm<S, C, M>
+where
+    S: Selection,
+    C: Crossover,
+    M: Mutation,
+{
+    pub fn new(
+        selection_method: S,
+        crossover_methods: OperatorPool<C>,
+        mutation_methods: OperatorPool<M>,
+    ) -> Self {
+        Self {
+            selection_method,
+            crossover_methods,
+            mutation_methods,
+            gene_mask: None,
+        }
+    }
+
+    /// Keeps the genes frozen in `gene_mask` as they are, like
+    /// `GeneticAlgorithm::with_gene_mask`.
+    pub fn with_gene_mask(self, gene_mask: GeneMask) -> Self {
+        Self {
+            gene_mask: Some(gene_mask),
+            ..self
+        }
+    }
+
+    pub fn gene_mask(&self) -> Option<&GeneMask> {
+        self.gene_mask.as_ref()
+    }
+
+    pub fn crossover_methods(&self) -> &OperatorPool<C> {
+        &self.crossover_methods
+    }
+
+    pub fn mutation_methods(&self) -> &OperatorPool<M> {
+        &self.mutation_methods
+    }
+
+    /// Returns the children along with the `Origin` of each, to be passed to
+    /// `record_outcomes` once the children have been evaluated.
+    pub fn evolve<I: Individual>(
+        &self,
+        rng: &mut dyn RngCore,
+        population: &Population<I>,
+    ) -> (Vec<I>, Vec<Origin>) {
+        let _span = tracing::debug_span!("evolve", population = population.len()).entered();
+        let fitnesses = population.fitness_slice();
+        let mut counters = Counters::new();
+        let children = (0..population.len())
+            .map(|_| {
+                let parents = self.selection_method.select_indices(rng, fitnesses, 2);
+                let parent1 = population[parents[0]].as_chromosome();
+                let crossover = self.crossover_methods.choose(rng);
+                let child = self.crossover_methods.operator(crossover).cross(
+                    rng,
+                    parent1,
+                    population[parents[1]].as_chromosome(),
+                );
+                let child = self.mask(parent1, child);
+                let mutation = self.mutation_methods.choose(rng);
+                let mutated = self.mutation_methods.operator(mutation).mutate(rng, &child);
+                let mutated = self.mask(parent1, mutated);
+                counters.record(&child, &mutated);
+                let origin = Origin {
+                    crossover,
+                    mutation,
+                    parent_fitness: fitnesses[parents[0]].max(fitnesses[parents[1]]),
+                };
+                (I::from_chromosome(mutated), origin)
+            })
+            .unzip();
+        counters.emit();
+        children
+    }
+
+    /// Credits each child's operators with a success if the evaluated child
+    /// beat its fitter parent, then adapts both pools.
+    pub fn record_outcomes<I: Individual>(&mut self, origins: &[Origin], population: &[I]) {
+        assert!(origins.len() == population.len());
+
+        for (origin, individual) in origins.iter().zip(population) {
+            let success = individual.fitness() > origin.parent_fitness;
+            self.crossover_methods.record(origin.crossover, success);
+            self.mutation_methods.record(origin.mutation, success);
+        }
+        self.crossover_methods.adapt();
+        self.mutation_methods.adapt();
+    }
+
+    /// `child` with the genes frozen by `gene_mask` reset to `parent1`'s.
+    fn mask(&self, parent1: &Chromosome, child: Chromosome) -> Chromosome {
+        match &self.gene_mask {
+            Some(mask) => mask.apply(parent1, child),
+            None => child,
+        }
+    }
+}
+
+/// RNG for breeding one child of one generation of a run, independent of
+/// the RNGs of every other child, generation and run.
+pub fn child_rng(seed: u64, generation: u64, child: u64) -> ChaCha8Rng {
+    let mut key = [0; 32];
+    key[..8].copy_from_slice(&seed.to_le_bytes());
+    key[8..16].copy_from_slice(&generation.to_le_bytes());
+    key[16..24].copy_from_slice(&child.to_le_bytes());
+    ChaCha8Rng::from_seed(key)
+}
+
+/// Tallies of one `evolve`, reported as a debug event. Comparing genes
+/// costs a pass over every child, so it's skipped unless someone listens.
+struct Counters {
+    enabled: bool,
+    children: usize,
+    mutated_children: usize,
+    mutated_genes: usize,
+}
+
+impl Counters {
+    fn new() -> Self {
+        Self {
+            enabled: tracing::enabled!(Level::DEBUG),
+            children: 0,
+            mutated_children: 0,
+            mutated_genes: 0,
+        }
+    }
+
+    fn record(&mut self, child: &Chromosome, mutated: &Chromosome) {
+        if !self.enabled {
+            return;
+        }
+        let genes = child
+            .iter()
+            .zip(mutated.iter())
+            .filter(|(gene, mutated)| gene != mutated)
+            .count();
+        self.children += 1;
+        self.mutated_children += usize::from(genes > 0);
+        self.mutated_genes += genes;
+    }
+
+    fn emit(&self) {
+        tracing::debug!(
+            // Every child has two parents, possibly the same one twice
+            selections = 2 * self.children,
+            mutated_children = self.mutated_children,
+            mutated_genes = self.mutated_genes,
+            "bred children"
+        );
+    }
 }
 
 #[cfg(test)]
@@ -72,24 +557,18 @@ mod tests {
             GaussianMutation::new(0.5, 1.0),
         );
 
-        let mut population = vec![
+        let mut population = Population::new(vec![
             create_individual(vec![0.0; 3]),
             create_individual(vec![3.0; 3]),
             create_individual(vec![1.0, 2.0, 3.0]),
-        ];
+        ]);
         for _ in 0..50 {
-            population = evolver.evolve(&mut rng, &population);
+            population = Population::new(evolver.evolve(&mut rng, &population));
         }
 
         let actual_population: Vec<Vec<f64>> = population
             .iter()
-            .map(|individual| {
-                individual
-                    .as_chromosome()
-                    .iter()
-                    .map(|gene| *gene)
-                    .collect()
-            })
+            .map(|individual| individual.as_chromosome().iter().copied().collect())
             .collect();
 
         // Sum of genes should get higher over time since TestIndividual's fitness
@@ -105,4 +584,360 @@ mod tests {
             approx::assert_relative_eq!(actual_genes.as_slice(), expected_genes.as_slice());
         }
     }
+
+    #[test]
+    fn test_evolve_traced_matches_evolve() {
+        let evolver = GeneticAlgorithm::new(
+            FitnessProportionateSelection::new(),
+            UniformCrossover::new(),
+            GaussianMutation::new(0.5, 1.0),
+        );
+        let population = Population::new(vec![
+            create_individual(vec![0.0; 3]),
+            create_individual(vec![3.0; 3]),
+            create_individual(vec![1.0, 2.0, 3.0]),
+        ]);
+
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let children = evolver.evolve(&mut rng, &population);
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let traced = evolver.evolve_traced(&mut rng, &population);
+
+        for (child, (traced, trace)) in children.iter().zip(&traced) {
+            assert!(child
+                .as_chromosome()
+                .iter()
+                .eq(traced.as_chromosome().iter()));
+            assert_eq!(trace.genes.len(), 3);
+        }
+        assert!(traced.iter().any(|(_, trace)| trace.num_mutated() > 0));
+    }
+
+    #[test]
+    fn test_select_parents_and_breed_match_evolve() {
+        let evolver = GeneticAlgorithm::new(
+            FitnessProportionateSelection::new(),
+            UniformCrossover::new(),
+            GaussianMutation::new(0.5, 1.0),
+        );
+        let population = Population::new(vec![
+            create_individual(vec![0.0; 3]),
+            create_individual(vec![3.0; 3]),
+            create_individual(vec![1.0, 2.0, 3.0]),
+        ]);
+        let fitnesses: Vec<f64> = population.iter().map(|i| i.fitness()).collect();
+
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let expected = evolver.evolve(&mut rng, &population);
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        for expected in &expected {
+            let [parent1, parent2] = evolver.select_parents(&mut rng, &population, &fitnesses);
+            let child = evolver.breed(
+                &mut rng,
+                population[parent1].as_chromosome(),
+                population[parent2].as_chromosome(),
+            );
+            assert!(expected.as_chromosome().iter().eq(child.iter()));
+        }
+    }
+
+    #[test]
+    fn test_evolve_from_seeds() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let evolver = GeneticAlgorithm::new(
+            FitnessProportionateSelection::new(),
+            UniformCrossover::new(),
+            GaussianMutation::new(1.0, 0.1),
+        );
+        let seeds = [
+            Chromosome::new(vec![1.0; 3]),
+            Chromosome::new(vec![-1.0; 3]),
+        ];
+
+        let population: Vec<TestIndividual> = evolver.evolve_from_seeds(&mut rng, &seeds, 5);
+
+        assert_eq!(population.len(), 5);
+        for (idx, individual) in population.iter().enumerate() {
+            let seed = &seeds[idx % 2];
+            let genes = individual.as_chromosome();
+            assert_eq!(genes.iter().eq(seed.iter()), idx < 2);
+            assert!(genes
+                .iter()
+                .zip(seed.iter())
+                .all(|(gene, seed)| (gene - seed).abs() < 1.0));
+        }
+    }
+
+    #[test]
+    fn test_gene_mask() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let evolver = GeneticAlgorithm::new(
+            FitnessProportionateSelection::new(),
+            UniformCrossover::new(),
+            GaussianMutation::new(1.0, 1.0),
+        )
+        .with_gene_mask(GeneMask::default().freeze(0..2));
+        let population: Population<TestIndividual> = (1..=8)
+            .map(|idx| create_individual(vec![f64::from(idx); 4]))
+            .collect();
+
+        for (child, trace) in evolver.evolve_traced(&mut rng, &population) {
+            let genes = child.as_chromosome().as_slice();
+            // Both frozen genes come unchanged from the same parent
+            assert_eq!(genes[0], genes[1]);
+            assert_eq!(genes[0], genes[0].round());
+            assert!(genes[2..].iter().all(|gene| gene != &gene.round()));
+            assert!(trace.genes[..2]
+                .iter()
+                .all(|origin| origin.parent == Parent::First && !origin.mutated));
+        }
+        for child in evolver.evolve(&mut rng, &population) {
+            let genes = child.as_chromosome().as_slice();
+            assert_eq!(genes[0], genes[1]);
+            assert_eq!(genes[0], genes[0].round());
+        }
+    }
+
+    #[test]
+    fn test_parallel_breed_child_matches_evolve_seeded() {
+        let evolver = GeneticAlgorithm::new(
+            FitnessProportionateSelection::new(),
+            UniformCrossover::new(),
+            GaussianMutation::new(0.5, 1.0),
+        );
+        let population: Population<TestIndividual> = (0..8)
+            .map(|idx| create_individual(vec![f64::from(idx); 4]))
+            .collect();
+        let fitnesses: Vec<f64> = population.iter().map(|i| i.fitness()).collect();
+
+        let expected = evolver.evolve_seeded(7, 3, &population);
+        let parallel: Vec<TestIndividual> = std::thread::scope(|scope| {
+            let children: Vec<_> = (0..population.len())
+                .rev()
+                .map(|child| {
+                    let (evolver, population, fitnesses) = (&evolver, &population, &fitnesses);
+                    scope.spawn(move || evolver.breed_child(7, 3, child, population, fitnesses))
+                })
+                .collect();
+            let mut children: Vec<_> = children
+                .into_iter()
+                .map(|child| child.join().unwrap())
+                .collect();
+            children.reverse();
+            children
+        });
+
+        for (expected, actual) in expected.iter().zip(&parallel) {
+            assert!(expected
+                .as_chromosome()
+                .iter()
+                .eq(actual.as_chromosome().iter()));
+        }
+        let next_generation = evolver.evolve_seeded(7, 4, &population);
+        assert!(expected
+            .iter()
+            .zip(&next_generation)
+            .any(|(a, b)| !a.as_chromosome().iter().eq(b.as_chromosome().iter())));
+    }
+
+    #[test]
+    fn test_distinct_parents() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let evolver = GeneticAlgorithm::new(
+            FitnessProportionateSelection::new(),
+            UniformCrossover::new(),
+            GaussianMutation::new(0.0, 0.0),
+        )
+        .with_distinct_parents();
+
+        let population: Population<_> = (0..3).map(|_| create_individual(vec![1.0])).collect();
+        for _ in 0..20 {
+            let [parent1, parent2] =
+                evolver.select_parents(&mut rng, &population, &[1.0, 100.0, 1.0]);
+            assert_ne!(parent1, parent2);
+        }
+        // Nobody else can be picked, so fall back to self-crossover
+        assert_eq!(
+            evolver.select_parents(&mut rng, &population[..1], &[1.0]),
+            [0, 0]
+        );
+        assert_eq!(
+            evolver.select_parents(&mut rng, &population, &[0.0, 2.0, 0.0]),
+            [1, 1]
+        );
+
+        // Nobody scored, so everyone is an equally good mate
+        let mut pairs = std::collections::BTreeSet::new();
+        for _ in 0..50 {
+            let [parent1, parent2] = evolver.select_parents(&mut rng, &population, &[0.0; 3]);
+            assert_ne!(parent1, parent2);
+            pairs.insert([parent1, parent2]);
+        }
+        assert_eq!(pairs.len(), 6);
+    }
+
+    #[test]
+    fn test_mate_choice() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let evolver = GeneticAlgorithm::new(
+            FitnessProportionateSelection::new(),
+            UniformCrossover::new(),
+            GaussianMutation::new(0.0, 0.0),
+        )
+        .with_mate_choice(Box::new(AssortativeMating::new(5.0)));
+        let population = Population::new(vec![
+            create_individual(vec![0.0]),
+            create_individual(vec![0.1]),
+            create_individual(vec![10.0]),
+            create_individual(vec![10.1]),
+        ]);
+        let fitnesses = [1.0; 4];
+
+        for _ in 0..20 {
+            let [parent1, parent2] = evolver.select_parents(&mut rng, &population, &fitnesses);
+            // Mates come from the same cluster
+            assert_eq!(parent1 / 2, parent2 / 2);
+            assert_ne!(parent1, parent2);
+        }
+        assert_eq!(
+            evolver.select_parents(&mut rng, &population, &[0.0, 2.0, 0.0, 0.0]),
+            [1, 1]
+        );
+    }
+
+    #[test]
+    fn test_evolve_with_immigrants() {
+        let evolver = GeneticAlgorithm::new(
+            FitnessProportionateSelection::new(),
+            UniformCrossover::new(),
+            GaussianMutation::new(0.0, 0.0),
+        );
+        let population: Population<_> = (0..4).map(|_| create_individual(vec![1.0; 3])).collect();
+        let immigrants = RandomInit::new(3, 5.0..=6.0);
+
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let children = evolver.evolve_with_immigrants(&mut rng, &population, 0.5, &immigrants);
+
+        let sums: Vec<f64> = children.iter().map(|child| child.fitness()).collect();
+        assert_eq!(&sums[..2], &[3.0, 3.0]);
+        assert!(sums[2..].iter().all(|&sum| sum >= 15.0));
+
+        // Immigrants keep the fittest individual's frozen genes
+        let population: Population<_> = (1..=4)
+            .map(|idx| create_individual(vec![f64::from(idx); 3]))
+            .collect();
+        let masked = GeneticAlgorithm::new(
+            FitnessProportionateSelection::new(),
+            UniformCrossover::new(),
+            GaussianMutation::new(0.0, 0.0),
+        )
+        .with_gene_mask(GeneMask::default().freeze(0..1));
+        let children = masked.evolve_with_immigrants(&mut rng, &population, 0.5, &immigrants);
+        for immigrant in &children[2..] {
+            let genes = immigrant.as_chromosome().as_slice();
+            assert_eq!(genes[0], 4.0);
+            assert!(genes[1..].iter().all(|&gene| gene >= 5.0));
+        }
+
+        // Without immigrants, the same as `evolve`
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let expected = evolver.evolve(&mut rng, &population);
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let actual = evolver.evolve_with_immigrants(&mut rng, &population, 0.0, &immigrants);
+        for (expected, actual) in expected.iter().zip(&actual) {
+            assert!(expected
+                .as_chromosome()
+                .iter()
+                .eq(actual.as_chromosome().iter()));
+        }
+    }
+
+    #[test]
+    fn test_dyn_evolve_matches_evolve() {
+        let evolver = GeneticAlgorithm::new(
+            FitnessProportionateSelection::new(),
+            UniformCrossover::new(),
+            GaussianMutation::new(0.5, 1.0),
+        );
+        let dyn_evolver: DynGeneticAlgorithm = GeneticAlgorithm::new(
+            Box::new(FitnessProportionateSelection::new()),
+            Box::new(UniformCrossover::new()),
+            Box::new(GaussianMutation::new(0.5, 1.0)),
+        );
+        let population = Population::new(vec![
+            create_individual(vec![0.0; 3]),
+            create_individual(vec![3.0; 3]),
+            create_individual(vec![1.0, 2.0, 3.0]),
+        ]);
+
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let expected = evolver.evolve(&mut rng, &population);
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let actual = dyn_evolver.evolve(&mut rng, &population);
+
+        for (expected, actual) in expected.iter().zip(&actual) {
+            assert!(expected
+                .as_chromosome()
+                .iter()
+                .eq(actual.as_chromosome().iter()));
+        }
+    }
+
+    #[test]
+    fn test_adaptive_evolve() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut evolver = AdaptiveGeneticAlgorithm::new(
+            FitnessProportionateSelection::new(),
+            OperatorPool::new(vec![UniformCrossover::new()]),
+            OperatorPool::adaptive(
+                vec![
+                    GaussianMutation::new(0.0, 0.0),
+                    GaussianMutation::new(1.0, 1.0),
+                ],
+                0.5,
+                0.05,
+            ),
+        );
+
+        let mut population = Population::new(vec![
+            create_individual(vec![1.0; 3]),
+            create_individual(vec![3.0; 3]),
+            create_individual(vec![1.0, 2.0, 3.0]),
+        ]);
+        for _ in 0..20 {
+            let (children, origins) = evolver.evolve(&mut rng, &population);
+            evolver.record_outcomes(&origins, &children);
+            population = Population::new(children);
+        }
+
+        // Without mutation a child can only beat its fitter parent by mixing
+        // genes, which rarely helps once the population has converged
+        let mutation_statistics = evolver.mutation_methods().statistics();
+        assert!(mutation_statistics[1].success_rate() > mutation_statistics[0].success_rate());
+        let probabilities = evolver.mutation_methods().probabilities();
+        assert!(probabilities[1] > probabilities[0]);
+        assert_eq!(evolver.crossover_methods().statistics()[0].uses, 60);
+    }
+
+    #[test]
+    fn test_adaptive_gene_mask() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let evolver = AdaptiveGeneticAlgorithm::new(
+            FitnessProportionateSelection::new(),
+            OperatorPool::new(vec![UniformCrossover::new()]),
+            OperatorPool::new(vec![GaussianMutation::new(1.0, 1.0)]),
+        )
+        .with_gene_mask(GeneMask::default().freeze(0..2));
+        let population: Population<TestIndividual> = (1..=8)
+            .map(|idx| create_individual(vec![f64::from(idx); 4]))
+            .collect();
+
+        let (children, _) = evolver.evolve(&mut rng, &population);
+        for child in children {
+            let genes = child.as_chromosome().as_slice();
+            assert_eq!(genes[0], genes[1]);
+            assert_eq!(genes[0], genes[0].round());
+            assert!(genes[2..].iter().all(|gene| gene != &gene.round()));
+        }
+    }
 }
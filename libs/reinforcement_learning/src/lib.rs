@@ -1,8 +1,19 @@
 pub use crate::genetic_algorithm::GeneticAlgorithm;
 
+pub mod benchmark;
 mod chromosome;
+mod compressed_chromosome;
 mod crossover;
+pub mod environment;
+mod gene_mask;
+mod gene_statistics;
 pub mod genetic_algorithm;
 mod individual;
+mod mate_choice;
 mod mutation;
+mod operator_pool;
+mod population;
+mod population_init;
+mod race;
+mod segmented_chromosome;
 mod selection;
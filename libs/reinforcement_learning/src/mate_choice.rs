@@ -0,0 +1,23 @@
+pub use self::assortative_mating::AssortativeMating;
+
+use rand::distributions::WeightedError;
+use vroom_rand::RngCore;
+
+use crate::chromosome::Chromosome;
+
+mod assortative_mating;
+
+/// Strategy for picking the second parent of a child once the first has
+/// been selected, e.g. by genetic similarity to it.
+pub trait MateChoice {
+    /// Index of the mate for individual `first`, given the fitness of every
+    /// individual and `chromosome(idx)` giving individual `idx`'s genes.
+    /// `AllWeightsZero` when no one but `first` could be picked.
+    fn choose_mate<'a>(
+        &self,
+        rng: &mut dyn RngCore,
+        chromosome: &dyn Fn(usize) -> &'a Chromosome,
+        fitnesses: &[f64],
+        first: usize,
+    ) -> Result<usize, WeightedError>;
+}
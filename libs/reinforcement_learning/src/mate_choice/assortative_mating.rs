@@ -0,0 +1,150 @@
+use std::cell::RefCell;
+
+use rand::distributions::WeightedError;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use vroom_rand::RngCore;
+
+use super::MateChoice;
+use crate::chromosome::Chromosome;
+
+/// Picks a mate other than the first parent with probability proportional
+/// to its fitness times `exp(-preference * distance)`, where `distance` is
+/// the root-mean-square gene difference to the first parent.
+///
+/// A positive `preference` favours similar mates, a negative one
+/// dissimilar mates, and zero is plain fitness-proportionate selection.
+#[derive(Serialize, Deserialize)]
+pub struct AssortativeMating {
+    preference: f64,
+}
+
+thread_local! {
+    /// Weights of the candidate mates, kept between children.
+    static WEIGHTS: RefCell<Vec<f64>> = const { RefCell::new(Vec::new()) };
+}
+
+impl AssortativeMating {
+    pub fn new(preference: f64) -> Self {
+        assert!(preference.is_finite());
+        Self { preference }
+    }
+}
+
+impl MateChoice for AssortativeMating {
+    fn choose_mate<'a>(
+        &self,
+        rng: &mut dyn RngCore,
+        chromosome: &dyn Fn(usize) -> &'a Chromosome,
+        fitnesses: &[f64],
+        first: usize,
+    ) -> Result<usize, WeightedError> {
+        WEIGHTS.with_borrow_mut(|weights| {
+            // In log space, then scaled so the largest weight is 1, since
+            // `exp(-preference * distance)` alone easily overflows
+            weights.clear();
+            for (idx, &fitness) in fitnesses.iter().enumerate() {
+                let weight = if idx == first {
+                    f64::NEG_INFINITY
+                } else {
+                    let distance = rms_distance(chromosome(first), chromosome(idx));
+                    fitness.ln() - self.preference * distance
+                };
+                if weight.is_nan() {
+                    return Err(WeightedError::InvalidWeight);
+                }
+                weights.push(weight);
+            }
+            let max = weights.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+            if max == f64::NEG_INFINITY {
+                return Err(WeightedError::AllWeightsZero);
+            }
+            if !max.is_finite() {
+                return Err(WeightedError::InvalidWeight);
+            }
+
+            let mut total = 0.0;
+            for weight in weights.iter_mut() {
+                *weight = (*weight - max).exp();
+                total += *weight;
+            }
+            let mut target = rng.gen_range(0.0..total);
+            for (idx, &weight) in weights.iter().enumerate() {
+                if target < weight {
+                    return Ok(idx);
+                }
+                target -= weight;
+            }
+            // Rounding left `target` past the last weight
+            Ok(weights.iter().rposition(|&weight| weight > 0.0).unwrap())
+        })
+    }
+}
+
+fn rms_distance(a: &Chromosome, b: &Chromosome) -> f64 {
+    if a.is_empty() {
+        return 0.0;
+    }
+    let sum_sq: f64 = a.iter().zip(b.iter()).map(|(x, y)| (x - y).powi(2)).sum();
+    (sum_sq / a.len() as f64).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn mate_counts(preference: f64) -> [usize; 3] {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let chromosomes = [
+            Chromosome::new(vec![0.0, 0.0]),
+            Chromosome::new(vec![0.5, 0.0]),
+            Chromosome::new(vec![4.0, 4.0]),
+        ];
+        let chromosome = |idx: usize| &chromosomes[idx];
+        let mating = AssortativeMating::new(preference);
+
+        (0..100).fold([0; 3], |mut counts, _| {
+            let mate = mating.choose_mate(&mut rng, &chromosome, &[1.0, 1.0, 1.0], 0);
+            counts[mate.unwrap()] += 1;
+            counts
+        })
+    }
+
+    #[test]
+    fn test_preference() {
+        let similar = mate_counts(2.0);
+        assert_eq!(similar[0], 0);
+        assert!(similar[1] > 90, "{similar:?}");
+
+        let dissimilar = mate_counts(-2.0);
+        assert_eq!(dissimilar[0], 0);
+        assert!(dissimilar[2] > 90, "{dissimilar:?}");
+
+        // Weights that would overflow `f64` on their own
+        assert_eq!(mate_counts(-1000.0), [0, 0, 100]);
+        assert_eq!(mate_counts(1000.0), [0, 100, 0]);
+    }
+
+    #[test]
+    fn test_no_other_mate() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let chromosome = Chromosome::new(vec![1.0]);
+        let chromosome = |_| &chromosome;
+        let mating = AssortativeMating::new(1.0);
+
+        assert_eq!(
+            mating.choose_mate(&mut rng, &chromosome, &[1.0], 0),
+            Err(WeightedError::AllWeightsZero)
+        );
+        assert_eq!(
+            mating.choose_mate(&mut rng, &chromosome, &[1.0, 0.0], 0),
+            Err(WeightedError::AllWeightsZero)
+        );
+        assert_eq!(
+            mating.choose_mate(&mut rng, &chromosome, &[1.0, f64::NAN], 0),
+            Err(WeightedError::InvalidWeight)
+        );
+    }
+}
@@ -1,11 +1,25 @@
+pub use self::chained_mutation::ChainedMutation;
 pub use self::gaussian_mutation::GaussianMutation;
+pub use self::layer_mutation::LayerMutation;
+pub use self::noise::{Noise, NoiseSource};
+pub use self::probabilistic_mutation::ProbabilisticMutation;
 
-use rand::RngCore;
+use vroom_rand::RngCore;
 
 use crate::chromosome::Chromosome;
 
+mod chained_mutation;
 mod gaussian_mutation;
+mod layer_mutation;
+mod noise;
+mod probabilistic_mutation;
 
 pub trait Mutation {
     fn mutate(&self, rng: &mut dyn RngCore, chromosome: &Chromosome) -> Chromosome;
 }
+
+impl<M: Mutation + ?Sized> Mutation for Box<M> {
+    fn mutate(&self, rng: &mut dyn RngCore, chromosome: &Chromosome) -> Chromosome {
+        (**self).mutate(rng, chromosome)
+    }
+}
@@ -0,0 +1,61 @@
+use vroom_rand::RngCore;
+
+use super::Mutation;
+use crate::chromosome::Chromosome;
+
+/// Applies each mutation in turn, feeding the output of one into the next.
+pub struct ChainedMutation {
+    mutations: Vec<Box<dyn Mutation>>,
+}
+
+impl ChainedMutation {
+    pub fn new(mutations: Vec<Box<dyn Mutation>>) -> Self {
+        Self { mutations }
+    }
+}
+
+impl Mutation for ChainedMutation {
+    fn mutate(&self, rng: &mut dyn RngCore, chromosome: &Chromosome) -> Chromosome {
+        self.mutations
+            .iter()
+            .fold(chromosome.clone(), |chromosome, mutation| {
+                mutation.mutate(rng, &chromosome)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mutation::GaussianMutation;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_mutate() {
+        let chromosome = Chromosome::new(vec![0.0; 10]);
+        let first = GaussianMutation::new(0.5, 1.0);
+        let second = GaussianMutation::new(1.0, 0.1);
+
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let once = first.mutate(&mut rng, &chromosome);
+        let expected = second.mutate(&mut rng, &once);
+
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let chained = ChainedMutation::new(vec![
+            Box::new(GaussianMutation::new(0.5, 1.0)),
+            Box::new(GaussianMutation::new(1.0, 0.1)),
+        ]);
+        let actual = chained.mutate(&mut rng, &chromosome);
+
+        assert!(expected.iter().eq(actual.iter()));
+    }
+
+    #[test]
+    fn test_empty_chain() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let chromosome = Chromosome::new(vec![1.0, 2.0]);
+        let actual = ChainedMutation::new(Vec::new()).mutate(&mut rng, &chromosome);
+        assert!(chromosome.iter().eq(actual.iter()));
+    }
+}
@@ -1,31 +1,51 @@
-use rand::{Rng, RngCore};
-use rand_distr::StandardNormal;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use vroom_rand::RngCore;
 
+use super::noise::{Noise, NoiseSource};
 use super::Mutation;
 use crate::chromosome::Chromosome;
 
-pub struct GaussianMutation {
+/// Adds noise scaled by `mutation_strength` to each gene with probability
+/// `mutation_rate`. The noise is standard normal unless replaced with
+/// `with_noise`.
+#[derive(Serialize, Deserialize)]
+pub struct GaussianMutation<N = Noise> {
     mutation_rate: f64,
     mutation_strength: f64,
+    #[serde(default)]
+    noise: N,
 }
 
 impl GaussianMutation {
     pub fn new(mutation_rate: f64, mutation_strength: f64) -> Self {
-        assert!(mutation_rate >= 0.0 && mutation_rate <= 1.0);
+        assert!((0.0..=1.0).contains(&mutation_rate));
         Self {
             mutation_rate,
             mutation_strength,
+            noise: Noise::Gaussian,
         }
     }
 }
 
-impl Mutation for GaussianMutation {
+impl<N: NoiseSource> GaussianMutation<N> {
+    /// Draws the noise from `noise` instead.
+    pub fn with_noise<M: NoiseSource>(self, noise: M) -> GaussianMutation<M> {
+        GaussianMutation {
+            mutation_rate: self.mutation_rate,
+            mutation_strength: self.mutation_strength,
+            noise,
+        }
+    }
+}
+
+impl<N: NoiseSource> Mutation for GaussianMutation<N> {
     fn mutate(&self, rng: &mut dyn RngCore, chromosome: &Chromosome) -> Chromosome {
         chromosome
             .iter()
             .map(|&x| {
                 if rng.gen_bool(self.mutation_rate) {
-                    let mutation: f64 = rng.sample(StandardNormal);
+                    let mutation = self.noise.sample(rng);
                     x + mutation * self.mutation_strength
                 } else {
                     x
@@ -95,6 +115,21 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_custom_noise() {
+        struct Constant;
+        impl NoiseSource for Constant {
+            fn sample(&self, _: &mut dyn RngCore) -> f64 {
+                0.5
+            }
+        }
+
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mutator = GaussianMutation::new(1.0, 2.0).with_noise(Constant);
+        let chromosome = Chromosome::new(vec![0.0; 3]);
+        assert_eq!(mutator.mutate(&mut rng, &chromosome).as_slice(), [1.0; 3]);
+    }
+
     pub mod max_mutation_rate {
         use super::*;
 
@@ -0,0 +1,102 @@
+use rand::Rng;
+use rand_distr::StandardNormal;
+use serde::{Deserialize, Serialize};
+use vroom_rand::RngCore;
+
+use super::Mutation;
+use crate::chromosome::Chromosome;
+use crate::segmented_chromosome::GenomeLayout;
+
+/// `GaussianMutation` with its own rate and strength for each layer of a
+/// `GenomeLayout`, e.g. to fine-tune the output layer while the hidden
+/// layers explore. Genes past the layout use the defaults.
+#[derive(Serialize, Deserialize)]
+pub struct LayerMutation {
+    layout: GenomeLayout,
+    /// Rate and strength of each layer.
+    layers: Vec<(f64, f64)>,
+    default: (f64, f64),
+}
+
+impl LayerMutation {
+    /// Mutates every layer with `mutation_rate` and `mutation_strength`
+    /// until overridden with `with_layer`.
+    pub fn new(layout: GenomeLayout, mutation_rate: f64, mutation_strength: f64) -> Self {
+        assert!((0.0..=1.0).contains(&mutation_rate));
+        let default = (mutation_rate, mutation_strength);
+        Self {
+            layers: vec![default; layout.layers().len()],
+            layout,
+            default,
+        }
+    }
+
+    pub fn with_layer(mut self, layer: usize, mutation_rate: f64, mutation_strength: f64) -> Self {
+        assert!((0.0..=1.0).contains(&mutation_rate));
+        self.layers[layer] = (mutation_rate, mutation_strength);
+        self
+    }
+}
+
+impl Mutation for LayerMutation {
+    fn mutate(&self, rng: &mut dyn RngCore, chromosome: &Chromosome) -> Chromosome {
+        assert!(chromosome.len() >= self.layout.len());
+
+        let rates = self
+            .layout
+            .layers()
+            .iter()
+            .zip(&self.layers)
+            .flat_map(|(layer, &rate)| std::iter::repeat_n(rate, layer.len()))
+            .chain(std::iter::repeat(self.default));
+        chromosome
+            .iter()
+            .zip(rates)
+            .map(|(&x, (mutation_rate, mutation_strength))| {
+                if rng.gen_bool(mutation_rate) {
+                    let mutation: f64 = rng.sample(StandardNormal);
+                    x + mutation * mutation_strength
+                } else {
+                    x
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mutation::GaussianMutation;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_per_layer_rates() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let layout = GenomeLayout::mlp(2, &[2, 1]);
+        let mutation = LayerMutation::new(layout.clone(), 0.0, 1.0).with_layer(1, 1.0, 1.0);
+        let chromosome = Chromosome::new(vec![0.0; layout.len() + 1]);
+
+        let mutated = mutation.mutate(&mut rng, &chromosome);
+
+        let hidden = layout.layers()[0].range();
+        let output = layout.layers()[1].range();
+        assert!(mutated.as_slice()[hidden].iter().all(|&gene| gene == 0.0));
+        assert!(mutated.as_slice()[output].iter().all(|&gene| gene != 0.0));
+        assert_eq!(mutated[layout.len()], 0.0);
+    }
+
+    #[test]
+    fn test_uniform_rates_match_gaussian_mutation() {
+        let layout = GenomeLayout::mlp(3, &[2]);
+        let chromosome = Chromosome::new(vec![0.5; layout.len() + 2]);
+
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let expected = GaussianMutation::new(0.5, 2.0).mutate(&mut rng, &chromosome);
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let actual = LayerMutation::new(layout, 0.5, 2.0).mutate(&mut rng, &chromosome);
+
+        assert_eq!(expected.as_slice(), actual.as_slice());
+    }
+}
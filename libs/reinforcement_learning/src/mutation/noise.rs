@@ -0,0 +1,60 @@
+use rand::Rng;
+use rand_distr::{Cauchy, StandardNormal};
+use serde::{Deserialize, Serialize};
+use vroom_rand::RngCore;
+
+/// Where the perturbation added to a mutated gene comes from, before it's
+/// scaled by the mutation strength. Implement it to experiment with other
+/// distributions without writing a new mutation operator.
+pub trait NoiseSource {
+    fn sample(&self, rng: &mut dyn RngCore) -> f64;
+}
+
+impl<N: NoiseSource + ?Sized> NoiseSource for Box<N> {
+    fn sample(&self, rng: &mut dyn RngCore) -> f64 {
+        (**self).sample(rng)
+    }
+}
+
+/// Built-in noise distributions, all centered on zero with unit scale.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Noise {
+    /// Standard normal.
+    #[default]
+    Gaussian,
+    /// Standard Cauchy, whose heavy tails occasionally make large jumps
+    /// out of local optima.
+    Cauchy,
+    /// Uniform in [-1, 1].
+    Uniform,
+}
+
+impl NoiseSource for Noise {
+    fn sample(&self, rng: &mut dyn RngCore) -> f64 {
+        match self {
+            Self::Gaussian => rng.sample(StandardNormal),
+            Self::Cauchy => rng.sample(Cauchy::new(0.0, 1.0).unwrap()),
+            Self::Uniform => rng.gen_range(-1.0..=1.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_sample() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let uniform: Vec<f64> = (0..100).map(|_| Noise::Uniform.sample(&mut rng)).collect();
+        assert!(uniform.iter().all(|noise| (-1.0..=1.0).contains(noise)));
+
+        // Heavy tails reach far more often than a normal distribution does
+        let far = |noise: Noise, rng: &mut ChaCha8Rng| {
+            (0..1000).filter(|_| noise.sample(rng).abs() > 5.0).count()
+        };
+        assert!(far(Noise::Cauchy, &mut rng) > 10 * far(Noise::Gaussian, &mut rng).max(1));
+    }
+}
@@ -0,0 +1,61 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use vroom_rand::RngCore;
+
+use super::Mutation;
+use crate::chromosome::Chromosome;
+
+/// Applies `inner` to a whole chromosome with the given probability,
+/// leaving it untouched otherwise.
+#[derive(Serialize, Deserialize)]
+pub struct ProbabilisticMutation<M: Mutation> {
+    probability: f64,
+    inner: M,
+}
+
+impl<M: Mutation> ProbabilisticMutation<M> {
+    pub fn new(probability: f64, inner: M) -> Self {
+        assert!((0.0..=1.0).contains(&probability));
+        Self { probability, inner }
+    }
+}
+
+impl<M: Mutation> Mutation for ProbabilisticMutation<M> {
+    fn mutate(&self, rng: &mut dyn RngCore, chromosome: &Chromosome) -> Chromosome {
+        if rng.gen_bool(self.probability) {
+            self.inner.mutate(rng, chromosome)
+        } else {
+            chromosome.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mutation::GaussianMutation;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn mutated_count(probability: f64) -> usize {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mutator = ProbabilisticMutation::new(probability, GaussianMutation::new(1.0, 1.0));
+        let chromosome = Chromosome::new(vec![0.0; 3]);
+        (0..100)
+            .filter(|_| {
+                mutator
+                    .mutate(&mut rng, &chromosome)
+                    .iter()
+                    .any(|&gene| gene != 0.0)
+            })
+            .count()
+    }
+
+    #[test]
+    fn test_mutate() {
+        assert_eq!(mutated_count(0.0), 0);
+        assert_eq!(mutated_count(1.0), 100);
+        let count = mutated_count(0.5);
+        assert!((30..70).contains(&count), "{count}");
+    }
+}
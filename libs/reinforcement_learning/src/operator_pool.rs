@@ -0,0 +1,220 @@
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use vroom_rand::RngCore;
+
+use crate::chromosome::Chromosome;
+use crate::crossover::Crossover;
+use crate::mutation::Mutation;
+
+/// How often an operator was used and how often its children outperformed
+/// their parents.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct OperatorStatistics {
+    pub uses: u32,
+    pub successes: u32,
+}
+
+impl OperatorStatistics {
+    pub fn success_rate(&self) -> f64 {
+        if self.uses == 0 {
+            0.0
+        } else {
+            self.successes as f64 / self.uses as f64
+        }
+    }
+}
+
+/// Several crossover or mutation operators, one of which is picked at
+/// random for each child.
+///
+/// By default the picks are uniform. With `adaptive`, `adapt` shifts the
+/// odds towards operators whose children have recently outperformed their
+/// parents (probability matching), never letting an operator drop below
+/// `min_probability`.
+#[derive(Serialize, Deserialize)]
+pub struct OperatorPool<T> {
+    operators: Vec<T>,
+    probabilities: Vec<f64>,
+    /// Running estimate of each operator's success rate.
+    quality: Vec<f64>,
+    /// Totals since the pool was created.
+    statistics: Vec<OperatorStatistics>,
+    /// Counts since the last `adapt`.
+    window: Vec<OperatorStatistics>,
+    adaptation_rate: f64,
+    min_probability: f64,
+}
+
+impl<T> OperatorPool<T> {
+    pub fn new(operators: Vec<T>) -> Self {
+        Self::adaptive(operators, 0.0, 0.0)
+    }
+
+    /// `adaptation_rate` is how much of each operator's quality estimate is
+    /// replaced by its latest success rate on every `adapt`.
+    pub fn adaptive(operators: Vec<T>, adaptation_rate: f64, min_probability: f64) -> Self {
+        assert!(!operators.is_empty());
+        assert!((0.0..=1.0).contains(&adaptation_rate));
+        assert!(min_probability >= 0.0 && min_probability * operators.len() as f64 <= 1.0);
+
+        let len = operators.len();
+        Self {
+            operators,
+            probabilities: vec![1.0 / len as f64; len],
+            quality: vec![1.0; len],
+            statistics: vec![OperatorStatistics::default(); len],
+            window: vec![OperatorStatistics::default(); len],
+            adaptation_rate,
+            min_probability,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.operators.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.operators.is_empty()
+    }
+
+    pub fn operator(&self, idx: usize) -> &T {
+        &self.operators[idx]
+    }
+
+    pub fn probabilities(&self) -> &[f64] {
+        &self.probabilities
+    }
+
+    pub fn statistics(&self) -> &[OperatorStatistics] {
+        &self.statistics
+    }
+
+    /// Index of the operator to use for the next child. A single operator is
+    /// picked without drawing from `rng`.
+    pub fn choose(&self, rng: &mut dyn RngCore) -> usize {
+        if self.operators.len() == 1 {
+            return 0;
+        }
+        let indices: Vec<usize> = (0..self.operators.len()).collect();
+        *indices
+            .choose_weighted(rng, |&idx| self.probabilities[idx])
+            .unwrap()
+    }
+
+    /// Records whether a child produced by operator `idx` outperformed its
+    /// parents.
+    pub fn record(&mut self, idx: usize, success: bool) {
+        for statistics in [&mut self.statistics[idx], &mut self.window[idx]] {
+            statistics.uses += 1;
+            statistics.successes += success as u32;
+        }
+    }
+
+    /// Updates the operator probabilities from the outcomes recorded since
+    /// the last call. Operators that weren't used keep their quality.
+    pub fn adapt(&mut self) {
+        if self.adaptation_rate == 0.0 {
+            self.window.fill(OperatorStatistics::default());
+            return;
+        }
+
+        for (quality, window) in self.quality.iter_mut().zip(&self.window) {
+            if window.uses > 0 {
+                *quality += self.adaptation_rate * (window.success_rate() - *quality);
+            }
+        }
+        self.window.fill(OperatorStatistics::default());
+
+        let total: f64 = self.quality.iter().sum();
+        let len = self.operators.len() as f64;
+        let spread = 1.0 - len * self.min_probability;
+        for (probability, quality) in self.probabilities.iter_mut().zip(&self.quality) {
+            let share = if total > 0.0 {
+                quality / total
+            } else {
+                1.0 / len
+            };
+            *probability = self.min_probability + spread * share;
+        }
+    }
+}
+
+impl<C: Crossover> Crossover for OperatorPool<C> {
+    fn cross(
+        &self,
+        rng: &mut dyn RngCore,
+        chromosome1: &Chromosome,
+        chromosome2: &Chromosome,
+    ) -> Chromosome {
+        let idx = self.choose(rng);
+        self.operators[idx].cross(rng, chromosome1, chromosome2)
+    }
+}
+
+impl<M: Mutation> Mutation for OperatorPool<M> {
+    fn mutate(&self, rng: &mut dyn RngCore, chromosome: &Chromosome) -> Chromosome {
+        let idx = self.choose(rng);
+        self.operators[idx].mutate(rng, chromosome)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mutation::GaussianMutation;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_uniform_pool_does_not_adapt() {
+        let mut pool = OperatorPool::new(vec![(), ()]);
+        pool.record(0, true);
+        pool.record(1, false);
+        pool.adapt();
+
+        assert_eq!(pool.probabilities(), &[0.5, 0.5]);
+        assert_eq!(pool.statistics()[0].success_rate(), 1.0);
+        assert_eq!(pool.statistics()[1].success_rate(), 0.0);
+    }
+
+    #[test]
+    fn test_adapt_favours_successful_operator() {
+        let mut pool = OperatorPool::adaptive(vec![(), ()], 0.5, 0.1);
+        for _ in 0..10 {
+            pool.record(0, true);
+            pool.record(1, false);
+            pool.adapt();
+        }
+
+        let probabilities = pool.probabilities();
+        approx::assert_relative_eq!(probabilities.iter().sum::<f64>(), 1.0);
+        assert!(probabilities[0] > 0.85);
+        approx::assert_relative_eq!(probabilities[1], 0.1, epsilon = 1e-3);
+        assert_eq!(pool.statistics()[0].uses, 10);
+    }
+
+    #[test]
+    fn test_choose_follows_probabilities() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut pool = OperatorPool::adaptive(vec![(), ()], 1.0, 0.0);
+        pool.record(1, true);
+        pool.record(0, false);
+        pool.adapt();
+
+        assert!((0..20).all(|_| pool.choose(&mut rng) == 1));
+    }
+
+    #[test]
+    fn test_single_operator_matches_operator() {
+        let pool = OperatorPool::new(vec![GaussianMutation::new(0.5, 1.0)]);
+        let mutation = GaussianMutation::new(0.5, 1.0);
+        let chromosome = Chromosome::new(vec![0.0; 10]);
+
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let expected = mutation.mutate(&mut rng, &chromosome);
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let actual = pool.mutate(&mut rng, &chromosome);
+
+        assert!(expected.iter().eq(actual.iter()));
+    }
+}
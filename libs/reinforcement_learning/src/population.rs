@@ -0,0 +1,167 @@
+use std::ops::Deref;
+
+use rand::seq::SliceRandom;
+use vroom_rand::RngCore;
+
+use crate::individual::Individual;
+
+/// Non-empty group of individuals whose chromosomes all have the same
+/// length. Dereferences to a slice, so it can be passed wherever `&[I]` is
+/// expected.
+pub struct Population<I: Individual> {
+    individuals: Vec<I>,
+    /// Fitness of each individual, computed once since individuals can't be
+    /// modified while in the population.
+    fitnesses: Vec<f64>,
+}
+
+impl<I: Individual> Population<I> {
+    /// Panics if `individuals` is empty or their chromosome lengths differ.
+    pub fn new(individuals: Vec<I>) -> Self {
+        assert!(!individuals.is_empty(), "Population must not be empty");
+        let num_genes = individuals[0].as_chromosome().len();
+        assert!(
+            individuals
+                .iter()
+                .all(|individual| individual.as_chromosome().len() == num_genes),
+            "Chromosome lengths differ"
+        );
+
+        let fitnesses = individuals.iter().map(Individual::fitness).collect();
+        Self {
+            individuals,
+            fitnesses,
+        }
+    }
+
+    pub fn into_inner(self) -> Vec<I> {
+        self.individuals
+    }
+
+    /// Length of every individual's chromosome.
+    pub fn num_genes(&self) -> usize {
+        self.individuals[0].as_chromosome().len()
+    }
+
+    /// Fitness of each individual, in population order.
+    pub fn fitness_slice(&self) -> &[f64] {
+        &self.fitnesses
+    }
+
+    pub fn best(&self) -> &I {
+        &self.individuals[self.best_index()]
+    }
+
+    pub fn worst(&self) -> &I {
+        &self.individuals[self.worst_index()]
+    }
+
+    pub fn best_index(&self) -> usize {
+        (0..self.fitnesses.len())
+            .max_by(|&a, &b| self.fitnesses[a].total_cmp(&self.fitnesses[b]))
+            .unwrap()
+    }
+
+    pub fn worst_index(&self) -> usize {
+        (0..self.fitnesses.len())
+            .min_by(|&a, &b| self.fitnesses[a].total_cmp(&self.fitnesses[b]))
+            .unwrap()
+    }
+
+    pub fn mean_fitness(&self) -> f64 {
+        self.fitnesses.iter().sum::<f64>() / self.fitnesses.len() as f64
+    }
+
+    /// Individuals from fittest to least fit.
+    pub fn sorted_by_fitness(&self) -> Vec<&I> {
+        let mut indices: Vec<usize> = (0..self.individuals.len()).collect();
+        indices.sort_by(|&a, &b| self.fitnesses[b].total_cmp(&self.fitnesses[a]));
+        indices
+            .into_iter()
+            .map(|idx| &self.individuals[idx])
+            .collect()
+    }
+
+    /// `cnt` distinct individuals picked uniformly at random, or all of them
+    /// in random order if `cnt` exceeds the population size.
+    pub fn sample(&self, rng: &mut dyn RngCore, cnt: usize) -> Vec<&I> {
+        self.individuals.choose_multiple(rng, cnt).collect()
+    }
+}
+
+impl<I: Individual> Deref for Population<I> {
+    type Target = [I];
+
+    fn deref(&self) -> &Self::Target {
+        &self.individuals
+    }
+}
+
+impl<I: Individual> FromIterator<I> for Population<I> {
+    fn from_iter<T: IntoIterator<Item = I>>(iter: T) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chromosome::Chromosome;
+    use crate::individual::TestIndividual;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn population() -> Population<TestIndividual> {
+        [vec![1.0, 1.0], vec![3.0, 2.0], vec![0.0, -1.0]]
+            .into_iter()
+            .map(|genes| TestIndividual::from_chromosome(Chromosome::new(genes)))
+            .collect()
+    }
+
+    #[test]
+    fn test_fitness_helpers() {
+        let population = population();
+
+        assert_eq!(population.fitness_slice(), &[2.0, 5.0, -1.0]);
+        assert_eq!(population.best().fitness(), 5.0);
+        assert_eq!(population.worst().fitness(), -1.0);
+        approx::assert_relative_eq!(population.mean_fitness(), 2.0);
+        let sorted: Vec<f64> = population
+            .sorted_by_fitness()
+            .iter()
+            .map(|individual| individual.fitness())
+            .collect();
+        assert_eq!(sorted, vec![5.0, 2.0, -1.0]);
+        assert_eq!(population.num_genes(), 2);
+    }
+
+    #[test]
+    fn test_sample() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let population = population();
+
+        let mut sampled: Vec<f64> = population
+            .sample(&mut rng, 5)
+            .iter()
+            .map(|individual| individual.fitness())
+            .collect();
+        sampled.sort_by(f64::total_cmp);
+        assert_eq!(sampled, vec![-1.0, 2.0, 5.0]);
+        assert_eq!(population.sample(&mut rng, 2).len(), 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_empty_population() {
+        Population::<TestIndividual>::new(Vec::new());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_different_chromosome_lengths() {
+        Population::new(vec![
+            TestIndividual::from_chromosome(Chromosome::new(vec![1.0])),
+            TestIndividual::from_chromosome(Chromosome::new(vec![1.0, 2.0])),
+        ]);
+    }
+}
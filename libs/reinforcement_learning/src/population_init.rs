@@ -0,0 +1,16 @@
+pub use self::champion_init::ChampionInit;
+pub use self::mixed_init::MixedInit;
+pub use self::random_init::RandomInit;
+
+use vroom_rand::RngCore;
+
+use crate::chromosome::Chromosome;
+
+mod champion_init;
+mod mixed_init;
+mod random_init;
+
+/// Strategy for creating a generation-zero population.
+pub trait PopulationInit {
+    fn init(&self, rng: &mut dyn RngCore, size: usize) -> Vec<Chromosome>;
+}
@@ -0,0 +1,85 @@
+use rand::Rng;
+use rand_distr::StandardNormal;
+use vroom_rand::RngCore;
+
+use super::PopulationInit;
+use crate::chromosome::Chromosome;
+
+/// Copies of previous champions, cycling through them. The first copy of
+/// each champion is exact; the rest get Gaussian noise with standard
+/// deviation `jitter` added to every gene.
+pub struct ChampionInit {
+    champions: Vec<Chromosome>,
+    jitter: f64,
+}
+
+impl ChampionInit {
+    pub fn new(champions: Vec<Chromosome>, jitter: f64) -> Self {
+        assert!(!champions.is_empty());
+        assert!(jitter >= 0.0);
+        Self { champions, jitter }
+    }
+}
+
+impl PopulationInit for ChampionInit {
+    fn init(&self, rng: &mut dyn RngCore, size: usize) -> Vec<Chromosome> {
+        (0..size)
+            .map(|idx| {
+                let champion = &self.champions[idx % self.champions.len()];
+                if idx < self.champions.len() || self.jitter == 0.0 {
+                    return champion.clone();
+                }
+                champion
+                    .iter()
+                    .map(|&gene| {
+                        let noise: f64 = rng.sample(StandardNormal);
+                        gene + noise * self.jitter
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn genes(chromosome: &Chromosome) -> Vec<f64> {
+        chromosome.iter().copied().collect()
+    }
+
+    #[test]
+    fn test_exact_copies() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let champions = vec![Chromosome::new(vec![1.0, 2.0]), Chromosome::new(vec![3.0])];
+        let population = ChampionInit::new(champions, 0.0).init(&mut rng, 5);
+
+        let actual: Vec<Vec<f64>> = population.iter().map(genes).collect();
+        assert_eq!(
+            actual,
+            vec![
+                vec![1.0, 2.0],
+                vec![3.0],
+                vec![1.0, 2.0],
+                vec![3.0],
+                vec![1.0, 2.0]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_jitter() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let champions = vec![Chromosome::new(vec![1.0; 3])];
+        let population = ChampionInit::new(champions, 0.1).init(&mut rng, 3);
+
+        assert_eq!(genes(&population[0]), vec![1.0; 3]);
+        for chromosome in &population[1..] {
+            assert_ne!(genes(chromosome), vec![1.0; 3]);
+            assert!(chromosome.iter().all(|gene| (gene - 1.0).abs() < 1.0));
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use vroom_rand::RngCore;
+
+use super::PopulationInit;
+use crate::chromosome::Chromosome;
+
+/// Splits the population between two strategies, e.g. champions and
+/// random individuals to keep some diversity.
+pub struct MixedInit<A, B>
+where
+    A: PopulationInit,
+    B: PopulationInit,
+{
+    first: A,
+    second: B,
+    first_fraction: f64,
+}
+
+impl<A, B> MixedInit<A, B>
+where
+    A: PopulationInit,
+    B: PopulationInit,
+{
+    /// `first_fraction` of the population, rounded, comes from `first`.
+    pub fn new(first: A, second: B, first_fraction: f64) -> Self {
+        assert!((0.0..=1.0).contains(&first_fraction));
+        Self {
+            first,
+            second,
+            first_fraction,
+        }
+    }
+}
+
+impl<A, B> PopulationInit for MixedInit<A, B>
+where
+    A: PopulationInit,
+    B: PopulationInit,
+{
+    fn init(&self, rng: &mut dyn RngCore, size: usize) -> Vec<Chromosome> {
+        let first_size = (size as f64 * self.first_fraction).round() as usize;
+        let mut population = self.first.init(rng, first_size);
+        population.extend(self.second.init(rng, size - first_size));
+        population
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::population_init::{ChampionInit, RandomInit};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_init() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let init = MixedInit::new(
+            ChampionInit::new(vec![Chromosome::new(vec![5.0; 2])], 0.0),
+            RandomInit::new(2, -1.0..=1.0),
+            0.25,
+        );
+        let population = init.init(&mut rng, 8);
+
+        assert_eq!(population.len(), 8);
+        let champions = population
+            .iter()
+            .filter(|chromosome| chromosome.iter().all(|&gene| gene == 5.0))
+            .count();
+        assert_eq!(champions, 2);
+    }
+}
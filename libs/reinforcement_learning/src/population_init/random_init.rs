@@ -0,0 +1,50 @@
+use std::ops::RangeInclusive;
+
+use rand::Rng;
+use vroom_rand::RngCore;
+
+use super::PopulationInit;
+use crate::chromosome::Chromosome;
+
+/// Genes drawn uniformly from `range`.
+pub struct RandomInit {
+    num_genes: usize,
+    range: RangeInclusive<f64>,
+}
+
+impl RandomInit {
+    pub fn new(num_genes: usize, range: RangeInclusive<f64>) -> Self {
+        Self { num_genes, range }
+    }
+}
+
+impl PopulationInit for RandomInit {
+    fn init(&self, rng: &mut dyn RngCore, size: usize) -> Vec<Chromosome> {
+        (0..size)
+            .map(|_| {
+                (0..self.num_genes)
+                    .map(|_| rng.gen_range(self.range.clone()))
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_init() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let population = RandomInit::new(4, -2.0..=2.0).init(&mut rng, 10);
+
+        assert_eq!(population.len(), 10);
+        for chromosome in &population {
+            assert_eq!(chromosome.len(), 4);
+            assert!(chromosome.iter().all(|gene| (-2.0..=2.0).contains(gene)));
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+
+use crate::chromosome::Chromosome;
+use crate::individual::Individual;
+
+/// Race-style selection support: the `top` fittest individuals are
+/// evaluated `rollouts` more times before parents are picked, so a single
+/// lucky evaluation doesn't decide who breeds. What an evaluation costs is
+/// up to the caller: the simulation reruns its whole population for each
+/// rollout, so there `top` only limits whose scores get averaged, not how
+/// much is simulated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Race {
+    pub top: usize,
+    pub rollouts: usize,
+}
+
+impl Race {
+    pub fn new(top: usize, rollouts: usize) -> Self {
+        Self { top, rollouts }
+    }
+
+    /// Whether any individual gets re-evaluated.
+    pub fn is_enabled(&self) -> bool {
+        self.top > 0 && self.rollouts > 0
+    }
+
+    /// Fitness of every individual of `population`, with the `top` fittest
+    /// averaged over their original fitness and `rollouts` more from
+    /// `evaluate`. `evaluate` gets the individual's index in `population`
+    /// and the rollout's index, so every candidate can be evaluated in the
+    /// same environments, under the same conditions as the original
+    /// evaluation; otherwise the averages mix incomparable scores.
+    pub fn fitnesses<I: Individual>(
+        &self,
+        population: &[I],
+        evaluate: &mut dyn FnMut(usize, &Chromosome, usize) -> f64,
+    ) -> Vec<f64> {
+        let mut fitnesses: Vec<f64> = population.iter().map(Individual::fitness).collect();
+        if !self.is_enabled() {
+            return fitnesses;
+        }
+
+        let mut ranked: Vec<usize> = (0..population.len()).collect();
+        ranked.sort_by(|&a, &b| fitnesses[b].total_cmp(&fitnesses[a]));
+        let _span =
+            tracing::debug_span!("race", top = self.top, rollouts = self.rollouts).entered();
+        for &idx in ranked.iter().take(self.top) {
+            let chromosome = population[idx].as_chromosome();
+            let total: f64 = (0..self.rollouts)
+                .map(|rollout| evaluate(idx, chromosome, rollout))
+                .sum();
+            fitnesses[idx] = (fitnesses[idx] + total) / (self.rollouts + 1) as f64;
+        }
+        fitnesses
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::individual::TestIndividual;
+
+    #[test]
+    fn test_fitnesses() {
+        let population: Vec<TestIndividual> = [1.0, 8.0, 2.0, 6.0]
+            .into_iter()
+            .map(|genes| TestIndividual::from_chromosome(Chromosome::new(vec![genes])))
+            .collect();
+
+        // Re-evaluations bring the two luckiest down
+        let mut evaluations = 0;
+        let fitnesses = Race::new(2, 2).fitnesses(&population, &mut |_, _, _| {
+            evaluations += 1;
+            2.0
+        });
+        assert_eq!(evaluations, 4);
+        assert_eq!(fitnesses, [1.0, 4.0, 2.0, 10.0 / 3.0]);
+    }
+}
@@ -0,0 +1,225 @@
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use crate::chromosome::Chromosome;
+
+/// Where each layer and neuron of a multilayer perceptron sits in its
+/// genome: layer after layer, each neuron's bias and input weights next to
+/// each other. Genes past the last layer, e.g. evolved activation
+/// functions, belong to no layer.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GenomeLayout {
+    layers: Vec<LayerSegment>,
+}
+
+/// Genes of one layer in a `GenomeLayout`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LayerSegment {
+    pub start: usize,
+    pub neurons: usize,
+    /// Genes per neuron: a bias and one weight per input.
+    pub neuron_len: usize,
+}
+
+impl LayerSegment {
+    pub fn len(&self) -> usize {
+        self.neurons * self.neuron_len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.start + self.len()
+    }
+
+    pub fn neuron(&self, neuron: usize) -> Range<usize> {
+        assert!(neuron < self.neurons);
+        let start = self.start + neuron * self.neuron_len;
+        start..start + self.neuron_len
+    }
+}
+
+impl GenomeLayout {
+    /// Layout of a network with `inputs` inputs and a layer of each of
+    /// `layer_sizes` neurons.
+    pub fn mlp(inputs: usize, layer_sizes: &[usize]) -> Self {
+        let mut start = 0;
+        let mut nin = inputs;
+        let layers = layer_sizes
+            .iter()
+            .map(|&neurons| {
+                let layer = LayerSegment {
+                    start,
+                    neurons,
+                    neuron_len: nin + 1,
+                };
+                start += layer.len();
+                nin = neurons;
+                layer
+            })
+            .collect();
+        Self { layers }
+    }
+
+    pub fn layers(&self) -> &[LayerSegment] {
+        &self.layers
+    }
+
+    /// Genes covered by the layers.
+    pub fn len(&self) -> usize {
+        self.layers.last().map_or(0, |layer| layer.range().end)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// `other` with the neurons of every hidden layer reordered to best
+    /// match those of `reference` by their bias and input weights, and the
+    /// next layer's weights reordered to match, so it encodes the same
+    /// network. Lines up networks that learned the same features in a
+    /// different order before crossing them.
+    pub fn align_neurons(&self, reference: &Chromosome, other: &Chromosome) -> Chromosome {
+        assert!(reference.len() >= self.len() && other.len() >= self.len());
+
+        let reference = reference.as_slice();
+        let mut genes = other.as_slice().to_vec();
+        for pair in self.layers.windows(2) {
+            let [layer, next] = [pair[0], pair[1]];
+            let order = match_neurons(&layer, reference, &genes);
+
+            let old = genes[layer.range()].to_vec();
+            for (neuron, &from) in order.iter().enumerate() {
+                let from = from * layer.neuron_len..(from + 1) * layer.neuron_len;
+                genes[layer.neuron(neuron)].copy_from_slice(&old[from]);
+            }
+            // Input weights come after each neuron's bias
+            for neuron in 0..next.neurons {
+                let weights = next.neuron(neuron).start + 1;
+                let old = genes[weights..weights + layer.neurons].to_vec();
+                for (input, &from) in order.iter().enumerate() {
+                    genes[weights + input] = old[from];
+                }
+            }
+        }
+        Chromosome::new(genes)
+    }
+}
+
+/// For each neuron of `layer` in `reference`, the neuron of `layer` in
+/// `genes` to put in its place, pairing up the closest neurons first.
+fn match_neurons(layer: &LayerSegment, reference: &[f64], genes: &[f64]) -> Vec<usize> {
+    let mut pairs: Vec<(f64, usize, usize)> = (0..layer.neurons)
+        .flat_map(|neuron| (0..layer.neurons).map(move |other| (neuron, other)))
+        .map(|(neuron, other)| {
+            let distance = reference[layer.neuron(neuron)]
+                .iter()
+                .zip(&genes[layer.neuron(other)])
+                .map(|(a, b)| (a - b).powi(2))
+                .sum();
+            (distance, neuron, other)
+        })
+        .collect();
+    pairs.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut order = vec![None; layer.neurons];
+    let mut taken = vec![false; layer.neurons];
+    for (_, neuron, other) in pairs {
+        if order[neuron].is_none() && !taken[other] {
+            order[neuron] = Some(other);
+            taken[other] = true;
+        }
+    }
+    order.into_iter().map(Option::unwrap).collect()
+}
+
+/// A chromosome seen as the layers and neurons of a `GenomeLayout`.
+#[derive(Clone, Copy, Debug)]
+pub struct SegmentedChromosome<'a> {
+    genes: &'a [f64],
+    layout: &'a GenomeLayout,
+}
+
+impl<'a> SegmentedChromosome<'a> {
+    /// Panics if `chromosome` is shorter than `layout`.
+    pub fn new(chromosome: &'a Chromosome, layout: &'a GenomeLayout) -> Self {
+        assert!(
+            chromosome.len() >= layout.len(),
+            "chromosome of {} genes doesn't fit a layout of {}",
+            chromosome.len(),
+            layout.len()
+        );
+        Self {
+            genes: chromosome.as_slice(),
+            layout,
+        }
+    }
+
+    pub fn num_layers(&self) -> usize {
+        self.layout.layers.len()
+    }
+
+    pub fn layer(&self, layer: usize) -> &'a [f64] {
+        &self.genes[self.layout.layers[layer].range()]
+    }
+
+    /// The bias of `neuron` of `layer`, followed by its input weights.
+    pub fn neuron(&self, layer: usize, neuron: usize) -> &'a [f64] {
+        &self.genes[self.layout.layers[layer].neuron(neuron)]
+    }
+
+    /// Genes past the last layer.
+    pub fn rest(&self) -> &'a [f64] {
+        &self.genes[self.layout.len()..]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mlp_layout() {
+        let layout = GenomeLayout::mlp(2, &[3, 1]);
+        assert_eq!(layout.len(), 3 * 3 + 4);
+        assert_eq!(layout.layers()[1].range(), 9..13);
+
+        let chromosome: Chromosome = (0..15).map(f64::from).collect();
+        let segmented = SegmentedChromosome::new(&chromosome, &layout);
+        assert_eq!(segmented.num_layers(), 2);
+        assert_eq!(segmented.neuron(0, 1), [3.0, 4.0, 5.0]);
+        assert_eq!(segmented.layer(1), [9.0, 10.0, 11.0, 12.0]);
+        assert_eq!(segmented.rest(), [13.0, 14.0]);
+    }
+
+    #[test]
+    fn test_align_neurons() {
+        let layout = GenomeLayout::mlp(2, &[3, 2]);
+        let reference: Chromosome = (0..layout.len() + 1)
+            .map(|gene| f64::from(gene as u32) * 0.1)
+            .collect();
+        // Hidden neurons 0, 1, 2 stored as 2, 0, 1
+        let mut permuted = reference.as_slice().to_vec();
+        for (neuron, from) in [2, 0, 1].into_iter().enumerate() {
+            permuted[neuron * 3..neuron * 3 + 3]
+                .copy_from_slice(&reference.as_slice()[from * 3..from * 3 + 3]);
+            for output in 0..2 {
+                let weights = 9 + output * 4 + 1;
+                permuted[weights + neuron] = reference[weights + from];
+            }
+        }
+
+        let aligned = layout.align_neurons(&reference, &Chromosome::new(permuted));
+        assert_eq!(aligned.as_slice(), reference.as_slice());
+    }
+
+    #[test]
+    #[should_panic(expected = "doesn't fit")]
+    fn test_short_chromosome() {
+        let layout = GenomeLayout::mlp(2, &[3]);
+        SegmentedChromosome::new(&Chromosome::new(vec![0.0; 8]), &layout);
+    }
+}
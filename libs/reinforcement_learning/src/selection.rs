@@ -1,16 +1,37 @@
 pub use self::fitness_proportionate_selection::FitnessProportionateSelection;
+pub use self::tournament_selection::TournamentSelection;
 
-use rand::RngCore;
+use vroom_rand::RngCore;
 
 use crate::individual::Individual;
+use crate::population::Population;
 
 mod fitness_proportionate_selection;
+mod tournament_selection;
 
 pub trait Selection {
+    /// Picks `cnt` individuals, with replacement, given the fitness of each
+    /// individual of the population. Returns their indices.
+    fn select_indices(&self, rng: &mut dyn RngCore, fitnesses: &[f64], cnt: u32) -> Vec<usize>;
+
     fn select<'a, I: Individual>(
         &self,
         rng: &mut dyn RngCore,
-        population: &'a [I],
+        population: &'a Population<I>,
         cnt: u32,
-    ) -> Vec<&'a I>;
+    ) -> Vec<&'a I>
+    where
+        Self: Sized,
+    {
+        self.select_indices(rng, population.fitness_slice(), cnt)
+            .into_iter()
+            .map(|idx| &population[idx])
+            .collect()
+    }
+}
+
+impl<S: Selection + ?Sized> Selection for Box<S> {
+    fn select_indices(&self, rng: &mut dyn RngCore, fitnesses: &[f64], cnt: u32) -> Vec<usize> {
+        (**self).select_indices(rng, fitnesses, cnt)
+    }
 }
@@ -1,8 +1,12 @@
-use rand::{seq::SliceRandom, RngCore};
+use rand::distributions::{WeightedError, WeightedIndex};
+use rand::prelude::Distribution;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use vroom_rand::RngCore;
 
 use super::Selection;
-use crate::individual::Individual;
 
+#[derive(Default, Serialize, Deserialize)]
 pub struct FitnessProportionateSelection;
 
 impl FitnessProportionateSelection {
@@ -12,42 +16,44 @@ impl FitnessProportionateSelection {
 }
 
 impl Selection for FitnessProportionateSelection {
-    fn select<'a, I: Individual>(
-        &self,
-        rng: &mut dyn RngCore,
-        population: &'a [I],
-        cnt: u32,
-    ) -> Vec<&'a I> {
-        assert!(!population.is_empty());
-
-        (0..cnt)
-            .map(|_| {
-                population
-                    .choose_weighted(rng, |chrom| chrom.fitness())
-                    .unwrap()
-            })
-            .collect()
+    fn select_indices(&self, rng: &mut dyn RngCore, fitnesses: &[f64], cnt: u32) -> Vec<usize> {
+        assert!(!fitnesses.is_empty());
+
+        match WeightedIndex::new(fitnesses) {
+            Ok(distribution) => (0..cnt).map(|_| distribution.sample(rng)).collect(),
+            // Nobody scored, e.g. an early generation that ate nothing, so
+            // there's nothing to prefer anyone for
+            Err(WeightedError::AllWeightsZero) => (0..cnt)
+                .map(|_| rng.gen_range(0..fitnesses.len()))
+                .collect(),
+            Err(err) => panic!("Invalid fitnesses {fitnesses:?}: {err}"),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::individual::TestIndividual;
+    use crate::chromosome::Chromosome;
+    use crate::individual::{Individual, TestIndividual};
+    use crate::population::Population;
     use rand::SeedableRng;
     use rand_chacha::ChaCha8Rng;
     use std::collections::BTreeMap;
 
+    /// Individuals of one gene each, which is their fitness.
+    fn population(fitnesses: &[f64]) -> Population<TestIndividual> {
+        fitnesses
+            .iter()
+            .map(|&fitness| TestIndividual::from_chromosome(Chromosome::new(vec![fitness])))
+            .collect()
+    }
+
     #[test]
     fn select_multiple() {
         let mut rng = ChaCha8Rng::from_seed(Default::default());
         let selector = FitnessProportionateSelection::new();
-        let population = vec![
-            TestIndividual::from_fitness(1.0),
-            TestIndividual::from_fitness(2.0),
-            TestIndividual::from_fitness(4.0),
-            TestIndividual::from_fitness(0.0),
-        ];
+        let population = population(&[1.0, 2.0, 4.0, 0.0]);
 
         let actual_freq: BTreeMap<i32, _> = selector
             .select(&mut rng, &population, 100)
@@ -65,12 +71,7 @@ mod tests {
     fn select_single() {
         let mut rng = ChaCha8Rng::from_seed(Default::default());
         let selector = FitnessProportionateSelection::new();
-        let population = vec![
-            TestIndividual::from_fitness(1.0),
-            TestIndividual::from_fitness(2.0),
-            TestIndividual::from_fitness(4.0),
-            TestIndividual::from_fitness(0.0),
-        ];
+        let population = population(&[1.0, 2.0, 4.0, 0.0]);
 
         let selected: Vec<Vec<&TestIndividual>> = (0..100)
             .map(|_| selector.select(&mut rng, &population, 1))
@@ -86,4 +87,37 @@ mod tests {
         let expected_freq = BTreeMap::from_iter([(1, 16), (2, 33), (4, 51)]);
         assert_eq!(actual_freq, expected_freq);
     }
+
+    #[test]
+    fn select_indices() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let selector: Box<dyn Selection> = Box::new(FitnessProportionateSelection::new());
+
+        let actual_freq = selector
+            .select_indices(&mut rng, &[1.0, 2.0, 4.0, 0.0], 100)
+            .into_iter()
+            .fold([0; 4], |mut freq, idx| {
+                freq[idx] += 1;
+                freq
+            });
+
+        assert_eq!(actual_freq, [16, 33, 51, 0]);
+    }
+
+    #[test]
+    fn select_all_zero() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let selector = FitnessProportionateSelection::new();
+
+        let actual_freq = selector
+            .select_indices(&mut rng, &[0.0; 4], 400)
+            .into_iter()
+            .fold([0; 4], |mut freq, idx| {
+                freq[idx] += 1;
+                freq
+            });
+
+        // Uniform
+        assert!(actual_freq.iter().all(|&freq| (70..130).contains(&freq)));
+    }
 }
@@ -0,0 +1,64 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use vroom_rand::RngCore;
+
+use super::Selection;
+
+/// Picks the fittest of `size` individuals drawn uniformly, with
+/// replacement. Only the order of fitnesses matters, so a few outliers
+/// don't take over the next generation.
+#[derive(Serialize, Deserialize)]
+pub struct TournamentSelection {
+    size: usize,
+}
+
+impl TournamentSelection {
+    pub fn new(size: usize) -> Self {
+        assert!(size > 0);
+        Self { size }
+    }
+}
+
+impl Selection for TournamentSelection {
+    fn select_indices(&self, rng: &mut dyn RngCore, fitnesses: &[f64], cnt: u32) -> Vec<usize> {
+        assert!(!fitnesses.is_empty());
+
+        (0..cnt)
+            .map(|_| {
+                (0..self.size)
+                    .map(|_| rng.gen_range(0..fitnesses.len()))
+                    .max_by(|&a, &b| fitnesses[a].total_cmp(&fitnesses[b]))
+                    .unwrap()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn frequencies(size: usize) -> [usize; 4] {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        TournamentSelection::new(size)
+            .select_indices(&mut rng, &[1.0, 2.0, 4.0, 0.0], 400)
+            .into_iter()
+            .fold([0; 4], |mut freq, idx| {
+                freq[idx] += 1;
+                freq
+            })
+    }
+
+    #[test]
+    fn size_one_is_uniform() {
+        assert!(frequencies(1).iter().all(|&freq| (70..130).contains(&freq)));
+    }
+
+    #[test]
+    fn larger_tournaments_favor_the_fittest() {
+        let freq = frequencies(3);
+        assert!(freq[2] > freq[1] && freq[1] > freq[0] && freq[0] > freq[3]);
+    }
+}
@@ -0,0 +1,162 @@
+use serde::{Deserialize, Serialize};
+
+use lib_neural_net as nn;
+
+use crate::config::PhysicsConfig;
+use crate::math::{self, Real};
+
+/// Motion an animal's brain asks for during one step. `Simulation::act`
+/// clamps both values to the physics limits before applying them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Action {
+    /// Speed for this step.
+    pub speed: f64,
+    /// Change in rotation for this step, in radians.
+    pub angular_accel: f64,
+}
+
+/// Maps a brain's outputs onto an `Action`.
+pub trait ActionDecoder {
+    /// Number of brain outputs the decoder reads.
+    fn outputs(&self) -> usize;
+
+    fn decode(&self, output: &[Real], speed: f64, physics: &PhysicsConfig) -> Action;
+
+    /// How the outputs map onto each other when the animal's view is
+    /// mirrored left to right, for symmetric brains. Defaults to leaving
+    /// every output unchanged.
+    fn output_mirror(&self) -> nn::Mirror {
+        nn::Mirror::identity(self.outputs())
+    }
+}
+
+/// `output[0]` accelerates, `output[1]` turns.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AccelerationControl;
+
+/// `output[0]` is the speed itself, `output[1]` turns.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct VelocityControl;
+
+/// The largest of three outputs picks turning left, going straight or
+/// turning right at full angular acceleration, always moving at full speed.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DiscreteControl;
+
+/// Control scheme used to turn brain outputs into movement.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Control {
+    #[default]
+    Acceleration,
+    Velocity,
+    Discrete,
+}
+
+impl Control {
+    pub fn decoder(&self) -> &'static dyn ActionDecoder {
+        match self {
+            Self::Acceleration => &AccelerationControl,
+            Self::Velocity => &VelocityControl,
+            Self::Discrete => &DiscreteControl,
+        }
+    }
+}
+
+impl ActionDecoder for AccelerationControl {
+    fn outputs(&self) -> usize {
+        2
+    }
+
+    /// Turning flips direction.
+    fn output_mirror(&self) -> nn::Mirror {
+        nn::Mirror::new(vec![(0, 1.0), (1, -1.0)])
+    }
+
+    fn decode(&self, output: &[Real], speed: f64, physics: &PhysicsConfig) -> Action {
+        Action {
+            speed: speed + math::wide(output[0]).clamp(-physics.max_accel, physics.max_accel),
+            angular_accel: math::wide(output[1]),
+        }
+    }
+}
+
+impl ActionDecoder for VelocityControl {
+    fn outputs(&self) -> usize {
+        2
+    }
+
+    /// Turning flips direction.
+    fn output_mirror(&self) -> nn::Mirror {
+        nn::Mirror::new(vec![(0, 1.0), (1, -1.0)])
+    }
+
+    fn decode(&self, output: &[Real], _speed: f64, _physics: &PhysicsConfig) -> Action {
+        Action {
+            speed: math::wide(output[0]),
+            angular_accel: math::wide(output[1]),
+        }
+    }
+}
+
+impl ActionDecoder for DiscreteControl {
+    fn outputs(&self) -> usize {
+        3
+    }
+
+    /// Left and right swap.
+    fn output_mirror(&self) -> nn::Mirror {
+        nn::Mirror::reverse(3)
+    }
+
+    fn decode(&self, output: &[Real], _speed: f64, physics: &PhysicsConfig) -> Action {
+        let choice = output
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(idx, _)| idx)
+            .expect("No outputs");
+        let direction = choice as f64 - 1.0;
+        Action {
+            speed: physics.max_speed,
+            angular_accel: direction * physics.max_angular_accel,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_acceleration_control() {
+        let physics = PhysicsConfig::default();
+        let action = AccelerationControl.decode(&[1.0, 0.3], 0.002, &physics);
+        approx::assert_relative_eq!(action.speed, 0.002 + physics.max_accel);
+        assert_eq!(action.angular_accel, math::wide(0.3));
+    }
+
+    #[test]
+    fn test_velocity_control() {
+        let physics = PhysicsConfig::default();
+        let action = VelocityControl.decode(&[0.004, 0.3], 0.002, &physics);
+        assert_eq!(action.speed, math::wide(0.004));
+        assert_eq!(action.angular_accel, math::wide(0.3));
+    }
+
+    #[test]
+    fn test_discrete_control() {
+        let physics = PhysicsConfig::default();
+        let decode = |output: &[Real]| DiscreteControl.decode(output, 0.002, &physics);
+
+        assert_eq!(
+            decode(&[0.9, 0.1, 0.2]).angular_accel,
+            -physics.max_angular_accel
+        );
+        assert_eq!(decode(&[0.0, 0.5, 0.2]).angular_accel, 0.0);
+        assert_eq!(
+            decode(&[0.0, 0.1, 0.2]).angular_accel,
+            physics.max_angular_accel
+        );
+        assert_eq!(decode(&[0.0, 0.0, 0.0]).speed, physics.max_speed);
+    }
+}
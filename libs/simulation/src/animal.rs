@@ -1,18 +1,120 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::ops::Range;
+
 use nalgebra as na;
-use rand::{Rng, RngCore};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use vroom_rand::RngCore;
 
 use lib_neural_net as nn;
 use lib_reinforcement_learning::genetic_algorithm as ga;
 
+use crate::behavior::Behavior;
+use crate::buffer::Buffer;
+use crate::config::{BrainEncoding, Config};
 use crate::eye::Eye;
+use crate::food::Food;
+use crate::game_mode::GameMode;
+use crate::hue::genome_hue;
+use crate::math::consts::PI;
+use crate::math::{self, Real};
+use crate::sensor::{NearestFood, Pose, Sensor, SensorKind};
+use crate::symmetry::Symmetry;
 
+#[derive(Serialize, Deserialize)]
 pub struct Animal {
-    pub(crate) position: na::Point2<f64>,
-    pub(crate) rotation: na::Rotation2<f64>,
+    /// Identifies the animal in its world, unlike its index in
+    /// `World::animals`, which shifts when animals before it are removed.
+    #[serde(default)]
+    pub(crate) id: u64,
+    pub(crate) position: na::Point2<Real>,
+    pub(crate) rotation: na::Rotation2<Real>,
     pub(crate) speed: f64,
+    /// How far the animal moved during the last step, before wrapping
+    /// around the world's edges.
+    #[serde(default)]
+    pub(crate) last_move: na::Vector2<Real>,
     pub(crate) consumed: u32,
+    pub(crate) carrying: bool,
+    pub(crate) deposited: u32,
+    /// Size of the animal's body for eating food.
+    #[serde(default = "Animal::default_radius")]
+    pub(crate) radius: Real,
     pub(crate) eye: Eye,
     pub(crate) brain: nn::MLP,
+    pub(crate) vision: Buffer,
+    pub(crate) hue: f64,
+    pub(crate) behavior: Behavior,
+    pub(crate) shaped_reward: f64,
+    /// Meals, or deposits when foraging, each decayed by
+    /// `reward.recency_decay` for every step since, when set. Replaces the
+    /// plain count in fitness.
+    #[serde(default)]
+    pub(crate) recent_score: Option<f64>,
+    /// Brain outputs acted on during the previous step, for stuck actuators.
+    pub(crate) outputs: Buffer,
+    /// Pose `vision` was computed from.
+    pub(crate) vision_pose: Option<VisionPose>,
+    /// Whether the chromosome ends with the brain's activation genes.
+    #[serde(default)]
+    pub(crate) activation_genes: bool,
+    /// Genes a symmetric brain's shared weights, a modular brain or a
+    /// CPPN-encoded brain were expanded from.
+    #[serde(default)]
+    pub(crate) shared_genes: Option<Vec<f64>>,
+    /// Rules `brain`'s weights change by during the animal's life.
+    #[serde(default)]
+    pub(crate) plasticity: Option<nn::Plasticity>,
+    /// Senses food instead of `eye` when set. `eye` still sets how often
+    /// vision is refreshed.
+    #[serde(default)]
+    pub(crate) nearest_food: Option<NearestFood>,
+    /// Readings from the steps before `vision`, newest first, for
+    /// `eye.frames`.
+    #[serde(default)]
+    pub(crate) history: Buffer,
+    /// Positions after the last `config.trail_length` moves, oldest first.
+    #[serde(default)]
+    pub(crate) trail: VecDeque<na::Point2<Real>>,
+    /// Pose the animal was placed in at the start of the generation, which
+    /// later rollouts start from, transformed.
+    #[serde(default)]
+    pub(crate) spawn_pose: Option<(na::Point2<Real>, na::Rotation2<Real>)>,
+    /// Fitness of each rollout of the generation finished so far, which
+    /// fitness averages with the current one's.
+    #[serde(default)]
+    pub(crate) rollout_fitness: Vec<f64>,
+    /// The animal's own copy of the food, which only it sees and eats, with
+    /// `Config::private_food`.
+    #[serde(default)]
+    pub(crate) private_food: Option<Vec<Food>>,
+    /// Replaces both built-in sensors when set. Not serialized.
+    #[serde(skip)]
+    pub(crate) custom_sensor: Option<Box<dyn Sensor>>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub(crate) struct VisionPose {
+    position: na::Point2<Real>,
+    angle: Real,
+    /// `World::food_version` at the time.
+    food_version: u64,
+}
+
+/// Chromosomes of freshly initialized random brains.
+pub(crate) struct RandomBrains {
+    /// Topology of the network the genes are the weights of: the brain's,
+    /// or the CPPN's with `BrainEncoding::Cppn`.
+    nin: usize,
+    layers: Vec<usize>,
+    /// Input slice and layer sizes of each module with `brain.modules`.
+    modules: Option<Vec<(Range<usize>, Vec<usize>)>>,
+    /// Number of random activation genes to append.
+    activation_genes: usize,
+    sharing: Option<nn::ParameterSharing>,
+    /// Append random Hebbian rules.
+    plasticity: bool,
 }
 
 pub struct AnimalIndividual {
@@ -21,57 +123,466 @@ pub struct AnimalIndividual {
 }
 
 impl Animal {
+    pub const DEFAULT_RADIUS: Real = 0.015;
+
     pub fn new(rng: &mut dyn RngCore, eye: Eye, brain: nn::MLP) -> Self {
+        let hue = genome_hue(brain.weights_and_biases());
         Self {
+            id: 0,
             position: rng.gen(),
-            rotation: rng.gen(),
+            rotation: math::random_rotation(rng),
             speed: 0.001,
+            last_move: na::Vector2::zeros(),
             consumed: 0,
+            carrying: false,
+            deposited: 0,
+            radius: Self::DEFAULT_RADIUS,
             eye,
             brain,
+            vision: Buffer::new(),
+            hue,
+            behavior: Behavior::default(),
+            shaped_reward: 0.0,
+            recent_score: None,
+            outputs: Buffer::new(),
+            vision_pose: None,
+            activation_genes: false,
+            shared_genes: None,
+            plasticity: None,
+            nearest_food: None,
+            history: Buffer::new(),
+            trail: VecDeque::new(),
+            spawn_pose: None,
+            rollout_fitness: Vec::new(),
+            private_food: None,
+            custom_sensor: None,
+        }
+    }
+
+    pub fn random(rng: &mut dyn RngCore, config: &Config) -> Self {
+        let chromosome = ga::PopulationInit::init(&RandomBrains::new(config), rng, 1)
+            .pop()
+            .unwrap();
+        Self::from_chromosome(rng, chromosome, config)
+    }
+
+    pub fn from_chromosome(
+        rng: &mut dyn RngCore,
+        chromosome: ga::Chromosome,
+        config: &Config,
+    ) -> Self {
+        let (nin, layers) = config.brain_topology();
+        let mut genes: Vec<f64> = chromosome.into_iter().collect();
+        let activations: Option<Vec<nn::Activation>> = config.brain.evolve_activations.then(|| {
+            genes
+                .split_off(genes.len() - layers.len())
+                .into_iter()
+                .map(nn::Activation::from_gene)
+                .collect()
+        });
+        let plasticity_genes = config
+            .brain
+            .plasticity
+            .then(|| genes.split_off(genes.len() - config.plasticity_genes()));
+        let sharing = config.brain_sharing();
+        let modules = config.brain_modules();
+        let mut brain = match (&sharing, &modules, config.brain.encoding) {
+            (Some(sharing), _, _) => {
+                nn::MLP::from_shared_weight_and_biases(nin, &layers, &genes, sharing)
+            }
+            (None, Some(modules), _) => {
+                nn::ModularBrain::from_weight_and_biases(nin, modules, genes.iter().copied())
+                    .to_mlp()
+            }
+            (None, None, BrainEncoding::Cppn) => nn::Cppn::from_weight_and_biases(
+                &config.brain.cppn_hidden_layers,
+                genes.iter().copied(),
+            )
+            .generate(&config.substrate()),
+            (None, None, BrainEncoding::Direct) => {
+                nn::MLP::from_weight_and_biases(nin, &layers, genes.iter().copied())
+            }
+        }
+        .with_precision(config.brain.precision);
+        if let Some(activations) = &activations {
+            brain = brain.with_activations(activations);
         }
+        let plasticity = plasticity_genes.map(|genes| nn::Plasticity::from_genes(&brain, &genes));
+        let mut animal = Self::new(rng, config.eye(), brain);
+        animal.radius = math::real(config.animal_radius);
+        animal.recent_score = (config.reward.recency_decay > 0.0).then_some(0.0);
+        animal.activation_genes = activations.is_some();
+        animal.plasticity = plasticity;
+        let encoded =
+            sharing.is_some() || modules.is_some() || config.brain.encoding == BrainEncoding::Cppn;
+        animal.shared_genes = encoded.then_some(genes);
+        animal.nearest_food =
+            (config.eye.sensor == SensorKind::NearestFood).then(|| config.nearest_food());
+        animal
     }
 
-    pub fn random(rng: &mut dyn RngCore) -> Self {
-        let eye = Eye::default();
-        let brain = nn::MLP::new_random(rng, eye.receptors, &[2 * eye.receptors, 2], 0.01);
-        Self::new(rng, eye, brain)
+    /// Whether `vision` can be reused: its sensor is `Sensor::cacheable`,
+    /// the food is unchanged and the animal has moved and turned less than
+    /// its eye's refresh thresholds since it was computed.
+    pub(crate) fn vision_is_fresh(&self, food_version: u64) -> bool {
+        if !self.sensor().cacheable() {
+            return false;
+        }
+        let angle = math::angle(&self.rotation);
+        self.vision_pose.is_some_and(|pose| {
+            pose.food_version == food_version
+                && math::distance(&pose.position, &self.position) < self.eye.refresh_distance
+                && na::wrap(angle - pose.angle, -PI, PI).abs() < self.eye.refresh_angle
+        })
+    }
+
+    /// Stores readings taken at the animal's current pose.
+    pub(crate) fn set_vision(&mut self, vision: Buffer, food_version: u64) {
+        self.vision = vision;
+        self.vision_pose = Some(VisionPose {
+            position: self.position,
+            angle: math::angle(&self.rotation),
+            food_version,
+        });
+    }
+
+    /// Appends the current vision followed by the `frames - 1` before it,
+    /// newest first, then remembers the current vision for the next step.
+    /// Steps before the animal's first one repeat its first vision.
+    pub(crate) fn push_vision_frames(&mut self, frames: usize, inputs: &mut impl Extend<Real>) {
+        let len = self.vision.len();
+        let past = len * (frames - 1);
+        if self.history.len() != past {
+            self.history.clear();
+            for _ in 1..frames {
+                self.history.extend_from_slice(&self.vision);
+            }
+        }
+
+        inputs.extend(self.vision.iter().copied());
+        inputs.extend(self.history.iter().copied());
+        if past > 0 {
+            self.history.copy_within(..past - len, len);
+            self.history[..len].copy_from_slice(&self.vision);
+        }
     }
 
-    pub fn from_chromosome(rng: &mut dyn RngCore, chromosome: ga::Chromosome) -> Self {
-        let eye = Eye::default();
-        let brain =
-            nn::MLP::from_weight_and_biases(eye.receptors, &[2 * eye.receptors, 2], chromosome);
-        Self::new(rng, eye, brain)
+    /// Records the finished rollout's fitness and starts the next from the
+    /// spawn pose transformed by `symmetry`, as if newly born: scores,
+    /// memories and learned weights are reset. Behavior keeps accumulating.
+    pub(crate) fn start_rollout(
+        &mut self,
+        mode: GameMode,
+        symmetry: Symmetry,
+        size: na::Vector2<Real>,
+    ) {
+        let fitness = mode.rollout_fitness(self);
+        self.rollout_fitness.push(fitness);
+        if let Some((position, rotation)) = self.spawn_pose {
+            self.position = symmetry.apply_position(position, size);
+            self.rotation = symmetry.apply_rotation(rotation);
+        }
+        self.speed = 0.001;
+        self.last_move = na::Vector2::zeros();
+        self.consumed = 0;
+        self.carrying = false;
+        self.deposited = 0;
+        self.shaped_reward = 0.0;
+        self.recent_score = self.recent_score.map(|_| 0.0);
+        self.outputs.clear();
+        self.vision_pose = None;
+        self.history.clear();
+        self.trail.clear();
+        self.behavior.last_meal_step = None;
+        if let Some(plasticity) = &self.plasticity {
+            let (nin, nouts) = self.brain.topology();
+            let activations = self.brain.activations();
+            let weights = plasticity.initial_weights_and_biases().iter().copied();
+            self.brain = nn::MLP::from_weight_and_biases(nin, &nouts, weights)
+                .with_activations(&activations);
+        }
     }
 
     pub fn as_chromosome(&self) -> ga::Chromosome {
-        ga::Chromosome::new(self.brain.weights_and_biases())
+        let mut genes = match (&self.shared_genes, &self.plasticity) {
+            (Some(genes), _) => genes.clone(),
+            (None, Some(plasticity)) => plasticity.initial_weights_and_biases().to_vec(),
+            (None, None) => self.brain.weights_and_biases(),
+        };
+        if let Some(plasticity) = &self.plasticity {
+            genes.extend(plasticity.genes());
+        }
+        if self.activation_genes {
+            genes.extend(
+                self.brain
+                    .activations()
+                    .into_iter()
+                    .map(nn::Activation::to_gene),
+            );
+        }
+        ga::Chromosome::new(genes)
     }
 
-    pub fn position(&self) -> na::Point2<f64> {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn position(&self) -> na::Point2<Real> {
         self.position
     }
 
-    pub fn rotation(&self) -> na::Rotation2<f64> {
+    pub fn rotation(&self) -> na::Rotation2<Real> {
         self.rotation
     }
 
     pub fn speed(&self) -> f64 {
         self.speed
     }
+
+    pub fn consumed(&self) -> u32 {
+        self.consumed
+    }
+
+    pub fn carrying(&self) -> bool {
+        self.carrying
+    }
+
+    pub fn deposited(&self) -> u32 {
+        self.deposited
+    }
+
+    pub fn radius(&self) -> Real {
+        self.radius
+    }
+
+    /// The animal's own food with `Config::private_food`.
+    pub fn private_food(&self) -> Option<&[Food]> {
+        self.private_food.as_deref()
+    }
+
+    fn default_radius() -> Real {
+        Self::DEFAULT_RADIUS
+    }
+
+    /// Hue in degrees derived from the animal's genome, so genetically
+    /// similar animals get similar colors.
+    pub fn hue(&self) -> f64 {
+        self.hue
+    }
+
+    /// Behavioral metrics accumulated during the current generation.
+    pub fn behavior(&self) -> &Behavior {
+        &self.behavior
+    }
+
+    /// Reward accumulated from reward shaping during the current generation.
+    pub fn shaped_reward(&self) -> f64 {
+        self.shaped_reward
+    }
+
+    /// Score from food with older meals or deposits decayed, see
+    /// `RewardConfig::recency_decay`. `None` without decay.
+    pub fn recent_score(&self) -> Option<f64> {
+        self.recent_score
+    }
+
+    pub fn eye(&self) -> &Eye {
+        &self.eye
+    }
+
+    pub fn brain(&self) -> &nn::MLP {
+        &self.brain
+    }
+
+    /// The rules `brain` learns by with `brain.plasticity`.
+    pub fn plasticity(&self) -> Option<&nn::Plasticity> {
+        self.plasticity.as_ref()
+    }
+
+    /// Brain input count followed by every layer's size, like
+    /// `Config::brain_topology`.
+    pub fn brain_topology(&self) -> (usize, Vec<usize>) {
+        self.brain.topology()
+    }
+
+    /// What the animal senses with: its custom sensor if it has one, then
+    /// the nearest-food sensor if configured, then its eye.
+    pub fn sensor(&self) -> &dyn Sensor {
+        match (&self.custom_sensor, &self.nearest_food) {
+            (Some(sensor), _) => sensor.as_ref(),
+            (None, Some(nearest_food)) => nearest_food,
+            (None, None) => &self.eye,
+        }
+    }
+
+    pub fn pose(&self) -> Pose {
+        Pose {
+            position: self.position,
+            rotation: self.rotation,
+            animal: None,
+        }
+    }
+
+    /// Sensor readings from the most recent call to `process_brains`,
+    /// empty until the animal has processed its first step.
+    pub fn vision(&self) -> &[Real] {
+        &self.vision
+    }
+
+    /// Recent positions, oldest first, up to `Config::trail_length`. They
+    /// jump across the world's edges where the animal wrapped around.
+    pub fn trail(&self) -> &VecDeque<na::Point2<Real>> {
+        &self.trail
+    }
+
+    /// Counts a meal, or a deposit when foraging, towards `recent_score`.
+    pub(crate) fn add_recent_score(&mut self) {
+        if let Some(score) = &mut self.recent_score {
+            *score += 1.0;
+        }
+    }
+
+    /// Appends the current position to `trail`, dropping the oldest ones
+    /// past `length`.
+    pub(crate) fn record_trail(&mut self, length: usize) {
+        if length == 0 {
+            self.trail.clear();
+            return;
+        }
+        while self.trail.len() >= length {
+            self.trail.pop_front();
+        }
+        self.trail.push_back(self.position);
+    }
+}
+
+/// A clone has no custom sensor; `Simulation` attaches one from its
+/// factory to animals that need it.
+impl Clone for Animal {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id,
+            position: self.position,
+            rotation: self.rotation,
+            speed: self.speed,
+            last_move: self.last_move,
+            consumed: self.consumed,
+            carrying: self.carrying,
+            deposited: self.deposited,
+            radius: self.radius,
+            eye: self.eye.clone(),
+            brain: self.brain.clone(),
+            vision: self.vision.clone(),
+            hue: self.hue,
+            behavior: self.behavior.clone(),
+            shaped_reward: self.shaped_reward,
+            recent_score: self.recent_score,
+            outputs: self.outputs.clone(),
+            // Vision from a custom sensor is stale without it
+            vision_pose: self.vision_pose.filter(|_| self.custom_sensor.is_none()),
+            activation_genes: self.activation_genes,
+            shared_genes: self.shared_genes.clone(),
+            plasticity: self.plasticity.clone(),
+            nearest_food: self.nearest_food.clone(),
+            history: self.history.clone(),
+            trail: self.trail.clone(),
+            spawn_pose: self.spawn_pose,
+            rollout_fitness: self.rollout_fitness.clone(),
+            private_food: self.private_food.clone(),
+            custom_sensor: None,
+        }
+    }
+}
+
+impl fmt::Debug for Animal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Animal")
+            .field("id", &self.id)
+            .field("position", &self.position)
+            .field("rotation", &self.rotation)
+            .field("speed", &self.speed)
+            .field("consumed", &self.consumed)
+            .field("carrying", &self.carrying)
+            .field("deposited", &self.deposited)
+            .field("radius", &self.radius)
+            .field("eye", &self.eye)
+            .field("brain_topology", &self.brain_topology())
+            .field("vision", &self.vision)
+            .field("hue", &self.hue)
+            .field("custom_sensor", &self.custom_sensor.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl RandomBrains {
+    pub(crate) fn new(config: &Config) -> Self {
+        let (_, brain_layers) = config.brain_topology();
+        let (nin, layers) = config
+            .cppn_topology()
+            .unwrap_or_else(|| config.brain_topology());
+        Self {
+            nin,
+            layers,
+            modules: config.brain_modules(),
+            activation_genes: if config.brain.evolve_activations {
+                brain_layers.len()
+            } else {
+                0
+            },
+            sharing: config.brain_sharing(),
+            plasticity: config.brain.plasticity,
+        }
+    }
+
+    /// Genes of a random single network, with its Hebbian rules if any.
+    fn random_mlp_genes(&self, rng: &mut dyn RngCore) -> Vec<f64> {
+        let brain = nn::MLP::new_random(rng, self.nin, &self.layers, 0.01);
+        let mut genes = brain.weights_and_biases();
+        if let Some(sharing) = &self.sharing {
+            genes = sharing.compress(&genes);
+        }
+        if self.plasticity {
+            genes.extend(nn::Plasticity::new_random(rng, &brain).genes());
+        }
+        genes
+    }
+}
+
+impl ga::PopulationInit for RandomBrains {
+    fn init(&self, rng: &mut dyn RngCore, size: usize) -> Vec<ga::Chromosome> {
+        (0..size)
+            .map(|_| {
+                let mut genes = match &self.modules {
+                    Some(modules) => nn::ModularBrain::new_random(rng, self.nin, modules, 0.01)
+                        .weights_and_biases(),
+                    None => self.random_mlp_genes(rng),
+                };
+                if self.activation_genes > 0 {
+                    let activations = random_activations(rng, self.activation_genes);
+                    genes.extend(activations.into_iter().map(nn::Activation::to_gene));
+                }
+                ga::Chromosome::new(genes)
+            })
+            .collect()
+    }
+}
+
+fn random_activations(rng: &mut dyn RngCore, num_layers: usize) -> Vec<nn::Activation> {
+    (0..num_layers)
+        .map(|_| nn::Activation::ALL[rng.gen_range(0..nn::Activation::ALL.len())])
+        .collect()
 }
 
 impl AnimalIndividual {
-    pub fn from_animal(animal: &Animal) -> Self {
+    pub fn from_animal(animal: &Animal, mode: GameMode) -> Self {
         Self {
             chromosome: animal.as_chromosome(),
-            fitness: animal.consumed as f64,
+            fitness: mode.fitness(animal),
         }
     }
 
-    pub fn into_animal(&self, rng: &mut dyn RngCore) -> Animal {
-        Animal::from_chromosome(rng, self.chromosome.clone())
+    pub fn into_animal(self, rng: &mut dyn RngCore, config: &Config) -> Animal {
+        Animal::from_chromosome(rng, self.chromosome, config)
     }
 }
 
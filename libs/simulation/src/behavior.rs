@@ -0,0 +1,252 @@
+use serde::{Deserialize, Serialize};
+
+use crate::math::Real;
+
+const TURN_BINS: usize = 9;
+
+/// Per-animal behavioral metrics accumulated over a generation.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Behavior {
+    pub(crate) distance_traveled: f64,
+    pub(crate) speed_sum: f64,
+    pub(crate) steps: u32,
+    pub(crate) turn_histogram: [u32; TURN_BINS],
+    pub(crate) first_food_step: Option<u32>,
+    #[serde(default)]
+    pub(crate) last_meal_step: Option<u32>,
+    /// Steps between consecutive meals, in order.
+    #[serde(default)]
+    pub(crate) meal_intervals: Vec<u32>,
+    /// Steps each receptor saw food, indexed like `Animal::vision`.
+    pub(crate) receptor_activations: Vec<u32>,
+    pub(crate) observations: u32,
+}
+
+/// Population-wide aggregates of `Behavior`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BehaviorStatistics {
+    pub mean_distance_traveled: f64,
+    pub mean_speed: f64,
+    /// Mean Shannon entropy, in bits, of each animal's turning distribution.
+    pub mean_turn_entropy: f64,
+    /// Mean step of the first meal, over animals that ate at all.
+    pub mean_time_to_first_food: Option<f64>,
+    pub fed_fraction: f64,
+    /// For each receptor index, the fraction of all animals' steps in which
+    /// that receptor saw food. Empty unless animals see with `Eye`
+    /// receptors, since other sensors' readings aren't receptors.
+    pub receptor_utilization: Vec<f64>,
+    /// Mean steps between an animal's consecutive meals, over every animal
+    /// that ate at least twice.
+    #[serde(default)]
+    pub mean_meal_interval: Option<f64>,
+    /// Steps between consecutive meals of all animals, counted in
+    /// power-of-two bins: bin `k` holds intervals from `2^k` to
+    /// `2^(k + 1) - 1`. Foragers that sweep the world eat at steady
+    /// intervals, while ambushers wait long and then eat in bursts.
+    #[serde(default)]
+    pub meal_interval_histogram: Vec<u32>,
+}
+
+impl Behavior {
+    pub(crate) fn record_move(&mut self, distance: f64, speed: f64) {
+        self.distance_traveled += distance;
+        self.speed_sum += speed;
+        self.steps += 1;
+    }
+
+    /// Records a turn, where `turn` is normalized to `[-1, 1]`.
+    pub(crate) fn record_turn(&mut self, turn: f64) {
+        let bin = ((turn + 1.0) / 2.0 * TURN_BINS as f64) as usize;
+        self.turn_histogram[bin.min(TURN_BINS - 1)] += 1;
+    }
+
+    pub(crate) fn record_meal(&mut self, step: u32) {
+        self.first_food_step.get_or_insert(step);
+        if let Some(last) = self.last_meal_step.replace(step) {
+            self.meal_intervals.push(step - last);
+        }
+    }
+
+    /// Records one step's receptor readings, counting receptors that saw
+    /// anything.
+    pub(crate) fn record_vision(&mut self, vision: &[Real]) {
+        if self.receptor_activations.len() < vision.len() {
+            self.receptor_activations.resize(vision.len(), 0);
+        }
+        for (count, &reading) in self.receptor_activations.iter_mut().zip(vision) {
+            if reading <= 1.0 {
+                *count += 1;
+            }
+        }
+        self.observations += 1;
+    }
+
+    pub fn distance_traveled(&self) -> f64 {
+        self.distance_traveled
+    }
+
+    pub fn mean_speed(&self) -> f64 {
+        if self.steps == 0 {
+            0.0
+        } else {
+            self.speed_sum / self.steps as f64
+        }
+    }
+
+    pub fn turn_entropy(&self) -> f64 {
+        let total: u32 = self.turn_histogram.iter().sum();
+        if total == 0 {
+            return 0.0;
+        }
+        self.turn_histogram
+            .iter()
+            .filter(|&&count| count > 0)
+            .map(|&count| {
+                let p = count as f64 / total as f64;
+                -p * p.log2()
+            })
+            .sum()
+    }
+
+    pub fn first_food_step(&self) -> Option<u32> {
+        self.first_food_step
+    }
+
+    pub fn receptor_activations(&self) -> &[u32] {
+        &self.receptor_activations
+    }
+
+    /// Steps between consecutive meals, in order.
+    pub fn meal_intervals(&self) -> &[u32] {
+        &self.meal_intervals
+    }
+}
+
+impl BehaviorStatistics {
+    pub fn from_behaviors<'a>(behaviors: impl ExactSizeIterator<Item = &'a Behavior>) -> Self {
+        let n = behaviors.len();
+        if n == 0 {
+            return Self::default();
+        }
+
+        let mut stats = Self::default();
+        let mut sum_first_food = 0.0;
+        let mut fed = 0;
+        let mut observations = 0;
+        let mut sum_intervals = 0;
+        let mut intervals = 0;
+        for behavior in behaviors {
+            observations += behavior.observations;
+            let activations = &behavior.receptor_activations;
+            if stats.receptor_utilization.len() < activations.len() {
+                stats.receptor_utilization.resize(activations.len(), 0.0);
+            }
+            for (total, &count) in stats.receptor_utilization.iter_mut().zip(activations) {
+                *total += count as f64;
+            }
+            stats.mean_distance_traveled += behavior.distance_traveled();
+            stats.mean_speed += behavior.mean_speed();
+            stats.mean_turn_entropy += behavior.turn_entropy();
+            if let Some(step) = behavior.first_food_step() {
+                sum_first_food += step as f64;
+                fed += 1;
+            }
+            for &interval in behavior.meal_intervals() {
+                // Meals are at least a step apart
+                let bin = interval.max(1).ilog2() as usize;
+                if stats.meal_interval_histogram.len() <= bin {
+                    stats.meal_interval_histogram.resize(bin + 1, 0);
+                }
+                stats.meal_interval_histogram[bin] += 1;
+                sum_intervals += u64::from(interval);
+                intervals += 1;
+            }
+        }
+
+        stats.mean_distance_traveled /= n as f64;
+        stats.mean_speed /= n as f64;
+        stats.mean_turn_entropy /= n as f64;
+        stats.mean_time_to_first_food = (fed > 0).then(|| sum_first_food / fed as f64);
+        stats.fed_fraction = fed as f64 / n as f64;
+        stats.mean_meal_interval = (intervals > 0).then(|| sum_intervals as f64 / intervals as f64);
+        if observations > 0 {
+            for utilization in &mut stats.receptor_utilization {
+                *utilization /= observations as f64;
+            }
+        }
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_turn_entropy() {
+        let mut behavior = Behavior::default();
+        approx::assert_relative_eq!(behavior.turn_entropy(), 0.0);
+
+        behavior.record_turn(0.0);
+        behavior.record_turn(0.0);
+        approx::assert_relative_eq!(behavior.turn_entropy(), 0.0);
+
+        behavior.record_turn(-1.0);
+        behavior.record_turn(1.0);
+        approx::assert_relative_eq!(behavior.turn_entropy(), 1.5);
+    }
+
+    #[test]
+    fn test_from_behaviors() {
+        let mut fed = Behavior::default();
+        fed.record_move(0.5, 0.004);
+        fed.record_move(0.5, 0.002);
+        fed.record_meal(10);
+        fed.record_meal(20);
+        let hungry = Behavior::default();
+
+        let stats = BehaviorStatistics::from_behaviors([fed, hungry].iter());
+
+        approx::assert_relative_eq!(stats.mean_distance_traveled, 0.5);
+        approx::assert_relative_eq!(stats.mean_speed, 0.0015);
+        assert_eq!(stats.mean_time_to_first_food, Some(10.0));
+        approx::assert_relative_eq!(stats.fed_fraction, 0.5);
+    }
+
+    #[test]
+    fn test_meal_intervals() {
+        let mut steady = Behavior::default();
+        for step in [10, 20, 30] {
+            steady.record_meal(step);
+        }
+        let mut bursty = Behavior::default();
+        for step in [5, 6, 300] {
+            bursty.record_meal(step);
+        }
+        assert_eq!(bursty.meal_intervals(), [1, 294]);
+
+        let stats = BehaviorStatistics::from_behaviors([steady, bursty].iter());
+
+        assert_eq!(
+            stats.mean_meal_interval,
+            Some((10.0 + 10.0 + 1.0 + 294.0) / 4.0)
+        );
+        assert_eq!(stats.meal_interval_histogram, [1, 0, 0, 2, 0, 0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn test_receptor_utilization() {
+        let mut left = Behavior::default();
+        left.record_vision(&[0.5, 2.0, 2.0]);
+        left.record_vision(&[0.1, 0.9, 2.0]);
+        let mut blind = Behavior::default();
+        blind.record_vision(&[2.0, 2.0, 2.0]);
+        blind.record_vision(&[2.0, 2.0, 2.0]);
+        assert_eq!(left.receptor_activations(), [2, 1, 0]);
+
+        let stats = BehaviorStatistics::from_behaviors([left, blind].iter());
+
+        assert_eq!(stats.receptor_utilization, [0.5, 0.25, 0.0]);
+    }
+}
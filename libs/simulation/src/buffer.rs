@@ -0,0 +1,19 @@
+//! Storage for the small per-animal vectors touched every step: vision,
+//! brain inputs and brain outputs. They hold `Real`s, so brain I/O is
+//! `f32` along with the world under the `f32-world` feature.
+//!
+//! With the `fixed-capacity` feature these live inline in a `SmallVec`, so
+//! a world whose eyes and brains fit within `INLINE_CAPACITY` never touches
+//! the heap for them, not even on the first step. Larger configs still work
+//! and spill to the heap.
+
+use crate::math::Real;
+
+/// Values kept inline before spilling to the heap.
+pub const INLINE_CAPACITY: usize = 16;
+
+#[cfg(feature = "fixed-capacity")]
+pub type Buffer = smallvec::SmallVec<[Real; INLINE_CAPACITY]>;
+
+#[cfg(not(feature = "fixed-capacity"))]
+pub type Buffer = Vec<Real>;
@@ -0,0 +1,97 @@
+//! Argument parsing and config loading shared by the command-line
+//! frontends, which all take an optional config file followed by options.
+
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::config::Config;
+
+/// Parses `[CONFIG] [options]`, returning the config path if one was given.
+/// `option` handles each `--name`, calling `value` to take the argument
+/// after it, and returns false for options it doesn't know.
+pub fn parse_args(
+    mut args: impl Iterator<Item = String>,
+    mut option: impl FnMut(&str, &mut dyn FnMut() -> Result<String, String>) -> Result<bool, String>,
+) -> Result<Option<String>, String> {
+    let mut config = None;
+    while let Some(arg) = args.next() {
+        if arg.starts_with("--") {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| format!("missing value for {arg}"))
+            };
+            if !option(&arg, &mut value)? {
+                return Err(format!("unknown option: {arg}"));
+            }
+        } else if config.is_none() {
+            config = Some(arg);
+        } else {
+            return Err(format!("unexpected argument: {arg}"));
+        }
+    }
+    Ok(config)
+}
+
+pub fn parse_value<T: FromStr>(value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("invalid value: {value}"))
+}
+
+/// Reads a `.toml` or `.json` config, telling them apart by extension.
+pub fn load_config(path: &str) -> Result<Config, String> {
+    let contents = fs::read_to_string(path).map_err(|err| format!("{path}: {err}"))?;
+    let config = match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+        Some("json") => Config::from_json_str(&contents),
+        Some("toml") => Config::from_toml_str(&contents),
+        _ => return Err(format!("{path}: expected a .toml or .json file")),
+    };
+    config.map_err(|err| format!("{path}: {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<(Option<String>, u32, bool), String> {
+        let (mut seed, mut json) = (0, false);
+        let config = parse_args(args.iter().map(|arg| arg.to_string()), |arg, value| {
+            match arg {
+                "--seed" => seed = parse_value(&value()?)?,
+                "--json" => json = true,
+                _ => return Ok(false),
+            }
+            Ok(true)
+        })?;
+        Ok((config, seed, json))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(parse(&[]), Ok((None, 0, false)));
+        assert_eq!(
+            parse(&["--seed", "3", "config.toml", "--json"]),
+            Ok((Some("config.toml".to_string()), 3, true))
+        );
+
+        assert_eq!(
+            parse(&["--seed"]),
+            Err("missing value for --seed".to_string())
+        );
+        assert_eq!(parse(&["--seed", "x"]), Err("invalid value: x".to_string()));
+        assert_eq!(
+            parse(&["--fps", "1"]),
+            Err("unknown option: --fps".to_string())
+        );
+        assert_eq!(
+            parse(&["a.toml", "b.toml"]),
+            Err("unexpected argument: b.toml".to_string())
+        );
+    }
+
+    #[test]
+    fn test_load_config() {
+        let err = load_config("config.yaml").unwrap_err();
+        assert!(err.starts_with("config.yaml: "));
+        assert!(load_config("../../does-not-exist.toml").is_err());
+    }
+}
@@ -0,0 +1,1987 @@
+use std::f64::consts::PI;
+use std::fmt;
+use std::hash::Hasher;
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use lib_neural_net::{self as nn, Precision};
+use lib_reinforcement_learning::genetic_algorithm as ga;
+
+use crate::action::Control;
+use crate::animal::{Animal, RandomBrains};
+use crate::eye::Eye;
+use crate::food::Food;
+use crate::game_mode::GameMode;
+use crate::generation_end::GenerationEnd;
+use crate::map::{Map, Zone};
+use crate::math;
+use crate::sensor::{NearestFood, Sensor, SensorKind};
+use crate::simulation::Phase;
+use crate::state_hash::StateHasher;
+use crate::symmetry::Symmetry;
+
+/// Largest `Config::heatmap_cells`. Every generation keeps a grid of meal
+/// locations, so the cap keeps statistics history from growing quadratically
+/// with it.
+pub const MAX_HEATMAP_CELLS: usize = 1024;
+
+/// Everything needed to set up a simulation. Missing fields take their
+/// default values when loading from TOML or JSON.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub num_animals: u8,
+    pub num_food: u8,
+    pub mode: GameMode,
+    pub control: Control,
+    /// Name of a bundled map, see `Map::bundled_names`.
+    pub map: Option<String>,
+    /// Regions where animals are placed at the start of each generation,
+    /// replacing the map's when not empty, e.g. a corner so animals have
+    /// to learn to cross the world. Read when the world is created.
+    pub spawn_zones: Vec<Zone>,
+    /// Regions where random food is placed and respawns, replacing the
+    /// map's when not empty. Read when the world is created.
+    pub food_zones: Vec<Zone>,
+    /// Size of the world, which wraps around at its edges. Eye range,
+    /// speeds and map coordinates are in the same units, so a wider world
+    /// is more room rather than a stretched one.
+    pub width: f64,
+    pub height: f64,
+    /// Sizes animals and food collide at, in world units.
+    pub animal_radius: f64,
+    pub food_radius: f64,
+    pub generation_steps: u32,
+    pub generation_end: GenerationEnd,
+    /// Steps without improvement that end a generation under
+    /// `GenerationEnd::FitnessPlateau`.
+    pub plateau_steps: u32,
+    /// Phases of a step, in the order they run. Each of `EatFood`,
+    /// `ProcessBrains` and `MoveAnimals` at most once.
+    pub pipeline: Vec<Phase>,
+    /// Times each generation is run before evolving, each from its starting
+    /// layout transformed by the symmetry listed, with fitness averaged over
+    /// them, e.g. `[Identity, MirrorX]` to cancel out a bias to one side.
+    /// Only animals and food are transformed, walls and the nest stay put.
+    pub rollouts: Vec<Symmetry>,
+    /// Gives every animal its own copy of the food at the start of each
+    /// generation, which only it sees and eats, with eaten food respawning
+    /// at the same places in the same order for everyone, so fitness
+    /// reflects brains rather than luck or competition. The world's food
+    /// then only shows the starting layout.
+    pub private_food: bool,
+    /// Most recent positions each animal remembers, for frontends to draw
+    /// motion trails from. Zero keeps none.
+    pub trail_length: usize,
+    /// Cells along each side of the world in `Simulation::heatmap`, at most
+    /// `MAX_HEATMAP_CELLS`.
+    pub heatmap_cells: usize,
+    pub eye: EyeConfig,
+    pub brain: BrainConfig,
+    pub mutation: MutationConfig,
+    pub physics: PhysicsConfig,
+    pub actuator: ActuatorConfig,
+    pub reward: RewardConfig,
+    pub race: RaceConfig,
+    /// Latest generations whose statistics keep per-gene statistics, which
+    /// grow with the genome; older generations drop them so long runs and
+    /// their snapshots stay small. Zero keeps them for every generation.
+    pub genome_history: usize,
+    pub operators: OperatorConfig,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EyeConfig {
+    pub fov_range: f64,
+    pub fov_angle: f64,
+    pub receptors: usize,
+    /// Standard deviation of the noise added to each normalized receptor
+    /// distance.
+    pub noise: f64,
+    /// Probability that a piece of food in view goes undetected.
+    pub dropout: f64,
+    /// Distance an animal can move before its vision is recomputed while
+    /// food is unchanged. Zero recomputes every step.
+    pub refresh_distance: f64,
+    /// Like `refresh_distance`, for turning, in radians.
+    pub refresh_angle: f64,
+    /// What animals perceive food with. `NearestFood` uses `fov_range` as
+    /// its range and ignores the other eye settings except the refresh
+    /// thresholds.
+    pub sensor: SensorKind,
+    /// Pieces of food seen by `SensorKind::NearestFood`.
+    pub nearest_food: usize,
+    /// Give `Receptors` eyes a second bank of `receptors` that see other
+    /// animals, so flocking or avoidance can evolve. Vision is then
+    /// recomputed every step regardless of the refresh thresholds.
+    pub animal_receptors: bool,
+    /// Number of most recent sensor readings the brain sees, newest first,
+    /// as a cheap stand-in for memory. Each frame adds the sensor's
+    /// readings to the brain inputs.
+    pub frames: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BrainConfig {
+    /// Sizes of the hidden layers; the output layer's size is set by the
+    /// control scheme.
+    pub hidden_layers: Vec<usize>,
+    /// Genome the initial population is built from instead of random
+    /// brains. Must match the topology's genome length.
+    pub seed_genome: Option<Vec<f64>>,
+    /// More genomes to warm-start from, e.g. champions of earlier runs.
+    /// Seeded together with `seed_genome`.
+    pub champions: Vec<Vec<f64>>,
+    /// Standard deviation of the noise added to copies of seeded genomes;
+    /// the first copy of each is exact.
+    pub jitter: f64,
+    /// Fraction of the initial population seeded from champions when there
+    /// are any; the rest get random brains.
+    pub champion_fraction: f64,
+    /// Precision brains run inference in.
+    pub precision: Precision,
+    /// Append one gene per layer to the genome selecting that layer's
+    /// activation function, see `Activation::from_gene`. Otherwise every
+    /// layer uses ReLU.
+    pub evolve_activations: bool,
+    /// Tie the brain's weights so it treats left and right alike, which
+    /// roughly halves the genome. See `Config::brain_sharing`.
+    pub symmetric: bool,
+    /// Append a Hebbian rule to the genome for every connection, see
+    /// `nn::HebbianRule`, and let weights change by it while the animal
+    /// lives. Children inherit the weights their parents were born with.
+    pub plasticity: bool,
+    pub encoding: BrainEncoding,
+    /// Sizes of the CPPN's hidden layers with `BrainEncoding::Cppn`.
+    pub cppn_hidden_layers: Vec<usize>,
+    /// Build the brain from independent networks instead, see
+    /// `nn::ModularBrain`, and ignore `hidden_layers`. Every module needs
+    /// as many hidden layers, and together they produce the control
+    /// scheme's outputs.
+    pub modules: Vec<ModuleConfig>,
+}
+
+/// One network of a modular brain.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModuleConfig {
+    /// Brain inputs the module reads, e.g. `{ start = 0, end = 10 }`.
+    pub inputs: Range<usize>,
+    pub hidden_layers: Vec<usize>,
+    /// Number of brain outputs the module produces, following those of the
+    /// modules before it.
+    pub outputs: usize,
+    /// Keep the module's genes as they are in the initial population while
+    /// the other modules evolve, see `Config::gene_mask`.
+    #[serde(default)]
+    pub frozen: bool,
+}
+
+/// How a genome encodes the brain's weights and biases.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BrainEncoding {
+    /// One gene per weight and bias.
+    #[default]
+    Direct,
+    /// Genes are the weights and biases of an `nn::Cppn` that generates the
+    /// brain from where its receptors and neurons are, see
+    /// `Config::substrate`. The genome stays the same size however many
+    /// receptors the eye has.
+    Cppn,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MutationConfig {
+    pub rate: f64,
+    pub strength: f64,
+    /// Fraction of each new generation replaced by random brains.
+    pub immigrants: f64,
+    /// Probability that a child's brain is the average of its parents'
+    /// brains rather than a uniform mix of their genes.
+    pub blend: f64,
+    /// Distribution mutations are drawn from, scaled by `strength`.
+    pub noise: ga::Noise,
+}
+
+/// Description of one config field, for frontends that build their settings
+/// UI from the config rather than hard-coding it.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct FieldSchema {
+    /// Dotted path, matching `ValidationError::field`.
+    pub field: &'static str,
+    pub kind: FieldKind,
+    /// Whether the field may be left unset (`null`).
+    pub optional: bool,
+    pub default: serde_json::Value,
+    pub description: &'static str,
+}
+
+/// Type of a config field along with the range `Config::validate` accepts.
+/// Bounds are inclusive unless noted.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FieldKind {
+    Integer {
+        min: u64,
+        max: Option<u64>,
+    },
+    Float {
+        min: f64,
+        max: Option<f64>,
+    },
+    /// Like `Float`, but `min` itself is rejected.
+    PositiveFloat {
+        max: Option<f64>,
+    },
+    Choice {
+        options: Vec<String>,
+    },
+    /// Distinct `options`, in order.
+    ChoiceList {
+        options: Vec<String>,
+    },
+    IntegerList {
+        min: u64,
+    },
+    FloatList,
+    /// List of genomes, each a list of floats.
+    GenomeList,
+    /// List of rectangles, each with `min` and `max` corners as `[x, y]`.
+    ZoneList,
+    /// List of brain modules, each with `inputs` (`start` and `end`),
+    /// `hidden_layers`, `outputs` and optionally `frozen`.
+    ModuleList,
+    Bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConfigError {
+    Parse(String),
+    Invalid(Vec<ValidationError>),
+}
+
+/// Movement limits and collision detection. Unlike the rest of the config
+/// these can be changed while a simulation is running, see
+/// `Simulation::set_physics`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PhysicsConfig {
+    pub min_speed: f64,
+    pub max_speed: f64,
+    /// Largest change in speed per step.
+    pub max_accel: f64,
+    /// Largest change in rotation per step, in radians.
+    pub max_angular_accel: f64,
+    /// Check for food along the whole path an animal moved during a step
+    /// rather than only where it ended up, so fast animals can't skip past
+    /// food between steps. Costs a little more per food check.
+    pub continuous_collision: bool,
+}
+
+/// Faults applied to brain outputs before they're acted on.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ActuatorConfig {
+    /// Standard deviation of the Gaussian noise added to each output.
+    pub noise: f64,
+    /// Probability that an output is stuck at its previous step's value.
+    pub stuck: f64,
+}
+
+/// Built-in reward shaping; every term is disabled at zero.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RewardConfig {
+    /// Fitness per unit of distance closed towards the nearest food.
+    pub food_approach: f64,
+    /// Fraction of the fitness from meals, or deposits when foraging, lost
+    /// every step, so food found lately outweighs early luck and sustained
+    /// foraging wins. At 1 only the last step's food counts. Shaped rewards
+    /// don't decay.
+    pub recency_decay: f64,
+}
+
+/// Race-style selection: the fittest animals of each generation are
+/// evaluated again, by rerunning the whole population in fresh
+/// environments, and selected by their mean fitness, so parents are picked
+/// for their brains rather than a lucky generation. Disabled at zero
+/// rollouts.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RaceConfig {
+    /// Animals re-evaluated, fittest first. Every rollout reruns the whole
+    /// population regardless, so this only limits whose scores are
+    /// averaged.
+    pub top: usize,
+    /// Extra evaluations of each, one whole generation rerun per
+    /// evaluation.
+    pub rollouts: usize,
+}
+
+/// Genetic operators used to breed each generation, see
+/// `Config::genetic_algorithm`. `Simulation::set_genetic_algorithm`
+/// overrides them with operators built in code.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OperatorConfig {
+    pub selection: SelectionKind,
+    /// Used for children that aren't a blend of their parents, see
+    /// `MutationConfig::blend`.
+    pub crossover: CrossoverKind,
+    pub mutation: MutationKind,
+    /// Animals drawn for each pick with `SelectionKind::Tournament`.
+    pub tournament_size: usize,
+}
+
+/// How parents are picked.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SelectionKind {
+    /// With probability proportional to fitness.
+    #[default]
+    FitnessProportionate,
+    /// The fittest of `OperatorConfig::tournament_size` animals drawn at
+    /// random; only the order of fitnesses matters.
+    Tournament,
+}
+
+/// How parents' genes are mixed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CrossoverKind {
+    /// Gene by gene.
+    #[default]
+    Uniform,
+    /// Whole brain layers at a time. Needs `Config::genome_layout`.
+    Layer,
+    /// Whole neurons at a time. Needs `Config::genome_layout`.
+    Neuron,
+    /// Whole neurons at a time, after lining up the second parent's hidden
+    /// neurons with the first's. Needs `Config::genome_layout`.
+    AlignedNeuron,
+}
+
+impl CrossoverKind {
+    fn needs_layout(self) -> bool {
+        self != Self::Uniform
+    }
+}
+
+/// How children's genes are mutated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MutationKind {
+    /// Every gene with probability `mutation.rate`.
+    #[default]
+    Gaussian,
+    /// Every gene of a child with probability `mutation.rate`, leaving
+    /// other children untouched.
+    WholeGenome,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidationError {
+    pub field: &'static str,
+    pub message: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            num_animals: 32,
+            num_food: 128,
+            mode: GameMode::default(),
+            control: Control::default(),
+            map: None,
+            spawn_zones: Vec::new(),
+            food_zones: Vec::new(),
+            width: 1.0,
+            height: 1.0,
+            animal_radius: math::wide(Animal::DEFAULT_RADIUS),
+            food_radius: math::wide(Food::DEFAULT_RADIUS),
+            generation_steps: 1000,
+            generation_end: GenerationEnd::default(),
+            plateau_steps: 200,
+            pipeline: Phase::DEFAULT_PIPELINE.to_vec(),
+            rollouts: vec![Symmetry::Identity],
+            private_food: false,
+            trail_length: 0,
+            heatmap_cells: 16,
+            eye: EyeConfig::default(),
+            brain: BrainConfig::default(),
+            mutation: MutationConfig::default(),
+            physics: PhysicsConfig::default(),
+            actuator: ActuatorConfig::default(),
+            reward: RewardConfig::default(),
+            race: RaceConfig::default(),
+            genome_history: 1,
+            operators: OperatorConfig::default(),
+        }
+    }
+}
+
+impl Default for OperatorConfig {
+    fn default() -> Self {
+        Self {
+            selection: SelectionKind::default(),
+            crossover: CrossoverKind::default(),
+            mutation: MutationKind::default(),
+            tournament_size: 2,
+        }
+    }
+}
+
+impl Default for EyeConfig {
+    fn default() -> Self {
+        let eye = Eye::default();
+        Self {
+            fov_range: math::wide(eye.fov_range),
+            fov_angle: math::wide(eye.fov_angle),
+            receptors: eye.receptors,
+            noise: eye.noise,
+            dropout: eye.dropout,
+            refresh_distance: math::wide(eye.refresh_distance),
+            refresh_angle: math::wide(eye.refresh_angle),
+            sensor: SensorKind::default(),
+            nearest_food: 3,
+            animal_receptors: eye.animal_receptors,
+            frames: 1,
+        }
+    }
+}
+
+impl Default for BrainConfig {
+    fn default() -> Self {
+        Self {
+            hidden_layers: vec![2 * EyeConfig::default().receptors],
+            seed_genome: None,
+            champions: Vec::new(),
+            jitter: 0.0,
+            champion_fraction: 1.0,
+            precision: Precision::default(),
+            evolve_activations: false,
+            symmetric: false,
+            plasticity: false,
+            encoding: BrainEncoding::default(),
+            cppn_hidden_layers: vec![8],
+            modules: Vec::new(),
+        }
+    }
+}
+
+impl Default for MutationConfig {
+    fn default() -> Self {
+        Self {
+            rate: 0.01,
+            strength: 0.2,
+            immigrants: 0.0,
+            blend: 0.0,
+            noise: ga::Noise::default(),
+        }
+    }
+}
+
+impl Default for PhysicsConfig {
+    fn default() -> Self {
+        Self {
+            min_speed: 0.001,
+            max_speed: 0.005,
+            max_accel: 0.2,
+            max_angular_accel: PI / 2.0,
+            continuous_collision: false,
+        }
+    }
+}
+
+impl Config {
+    pub fn from_toml_str(toml: &str) -> Result<Self, ConfigError> {
+        let config: Self =
+            toml::from_str(toml).map_err(|err| ConfigError::Parse(err.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_json_str(json: &str) -> Result<Self, ConfigError> {
+        let config: Self =
+            serde_json::from_str(json).map_err(|err| ConfigError::Parse(err.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Checks every field, reporting all problems at once.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut errors = Vec::new();
+        let mut check = |ok: bool, field: &'static str, message: String| {
+            if !ok {
+                errors.push(ValidationError { field, message });
+            }
+        };
+
+        check(
+            self.num_animals > 0,
+            "num_animals",
+            "must be at least 1".to_string(),
+        );
+        check(
+            self.width.is_finite() && self.width > 0.0,
+            "width",
+            format!("must be positive, got {}", self.width),
+        );
+        check(
+            self.height.is_finite() && self.height > 0.0,
+            "height",
+            format!("must be positive, got {}", self.height),
+        );
+        for (radius, field) in [
+            (self.animal_radius, "animal_radius"),
+            (self.food_radius, "food_radius"),
+        ] {
+            check(
+                radius.is_finite() && radius > 0.0,
+                field,
+                format!("must be positive, got {radius}"),
+            );
+        }
+        check(
+            self.generation_steps > 0,
+            "generation_steps",
+            "must be at least 1".to_string(),
+        );
+        check(
+            self.plateau_steps > 0,
+            "plateau_steps",
+            "must be at least 1".to_string(),
+        );
+        let distinct = self
+            .pipeline
+            .iter()
+            .enumerate()
+            .all(|(idx, phase)| !self.pipeline[..idx].contains(phase));
+        check(
+            !self.pipeline.is_empty() && !self.pipeline.contains(&Phase::Evolve) && distinct,
+            "pipeline",
+            format!(
+                "must list EatFood, ProcessBrains and MoveAnimals at most once each, got {:?}",
+                self.pipeline
+            ),
+        );
+        check(
+            !self.rollouts.is_empty(),
+            "rollouts",
+            "must list at least one symmetry".to_string(),
+        );
+        check(
+            (1..=MAX_HEATMAP_CELLS).contains(&self.heatmap_cells),
+            "heatmap_cells",
+            format!("must be between 1 and {MAX_HEATMAP_CELLS}"),
+        );
+        if let Some(map) = &self.map {
+            check(
+                Map::bundled(map).is_some(),
+                "map",
+                format!(
+                    "unknown map {map:?}, expected one of {:?}",
+                    Map::bundled_names().collect::<Vec<_>>()
+                ),
+            );
+        }
+        for (zones, field) in [
+            (&self.spawn_zones, "spawn_zones"),
+            (&self.food_zones, "food_zones"),
+        ] {
+            for zone in zones {
+                check(
+                    zone.is_valid(),
+                    field,
+                    format!("zone min {} must be below max {}", zone.min, zone.max),
+                );
+            }
+        }
+
+        check(
+            self.eye.fov_range.is_finite() && self.eye.fov_range > 0.0,
+            "eye.fov_range",
+            format!("must be positive, got {}", self.eye.fov_range),
+        );
+        check(
+            self.eye.fov_angle > 0.0 && self.eye.fov_angle <= 2.0 * PI,
+            "eye.fov_angle",
+            format!("must be in (0, 2π], got {}", self.eye.fov_angle),
+        );
+        check(
+            self.eye.receptors > 0,
+            "eye.receptors",
+            "must be at least 1".to_string(),
+        );
+        check(
+            self.eye.noise.is_finite() && self.eye.noise >= 0.0,
+            "eye.noise",
+            format!("must be non-negative, got {}", self.eye.noise),
+        );
+        check(
+            (0.0..=1.0).contains(&self.eye.dropout),
+            "eye.dropout",
+            format!("must be in [0, 1], got {}", self.eye.dropout),
+        );
+        check(
+            self.eye.refresh_distance.is_finite() && self.eye.refresh_distance >= 0.0,
+            "eye.refresh_distance",
+            format!("must be non-negative, got {}", self.eye.refresh_distance),
+        );
+        check(
+            self.eye.refresh_angle.is_finite() && self.eye.refresh_angle >= 0.0,
+            "eye.refresh_angle",
+            format!("must be non-negative, got {}", self.eye.refresh_angle),
+        );
+        check(
+            self.eye.sensor != SensorKind::NearestFood || self.eye.nearest_food > 0,
+            "eye.nearest_food",
+            "must be at least 1".to_string(),
+        );
+        check(
+            self.eye.frames > 0,
+            "eye.frames",
+            "must be at least 1".to_string(),
+        );
+
+        check(
+            self.brain.hidden_layers.iter().all(|&size| size > 0),
+            "brain.hidden_layers",
+            format!(
+                "layers must not be empty, got {:?}",
+                self.brain.hidden_layers
+            ),
+        );
+        if let Some(genome) = &self.brain.seed_genome {
+            check(
+                genome.len() == self.genome_length(),
+                "brain.seed_genome",
+                format!(
+                    "topology {:?} needs {} genes, got {}",
+                    self.brain_topology(),
+                    self.genome_length(),
+                    genome.len()
+                ),
+            );
+        }
+        check(
+            self.brain
+                .champions
+                .iter()
+                .all(|genome| genome.len() == self.genome_length()),
+            "brain.champions",
+            format!(
+                "topology {:?} needs {} genes per genome",
+                self.brain_topology(),
+                self.genome_length()
+            ),
+        );
+        check(
+            !self.brain.plasticity || !self.brain.symmetric,
+            "brain.plasticity",
+            "can't be combined with brain.symmetric".to_string(),
+        );
+        check(
+            !self.brain.plasticity || self.brain.precision == Precision::F64,
+            "brain.plasticity",
+            format!("needs F64 precision, got {:?}", self.brain.precision),
+        );
+        check(
+            self.brain.encoding == BrainEncoding::Direct
+                || !(self.brain.symmetric || self.brain.plasticity),
+            "brain.encoding",
+            "Cppn can't be combined with brain.symmetric or brain.plasticity".to_string(),
+        );
+        check(
+            self.brain.cppn_hidden_layers.iter().all(|&size| size > 0),
+            "brain.cppn_hidden_layers",
+            format!(
+                "layers must not be empty, got {:?}",
+                self.brain.cppn_hidden_layers
+            ),
+        );
+        if !self.brain.modules.is_empty() {
+            let (inputs, _) = self.brain_topology();
+            let depth = self.brain.modules[0].hidden_layers.len();
+            let outputs: usize = self.brain.modules.iter().map(|module| module.outputs).sum();
+            check(
+                self.brain.modules.iter().all(|module| {
+                    !module.inputs.is_empty()
+                        && module.inputs.end <= inputs
+                        && module.hidden_layers.len() == depth
+                        && module.hidden_layers.iter().all(|&size| size > 0)
+                        && module.outputs > 0
+                }),
+                "brain.modules",
+                format!(
+                    "modules must read some of the {inputs} inputs, produce outputs and have \
+                     equally many non-empty hidden layers"
+                ),
+            );
+            check(
+                outputs == self.control.decoder().outputs(),
+                "brain.modules",
+                format!(
+                    "modules must produce the control scheme's {} outputs, got {outputs}",
+                    self.control.decoder().outputs()
+                ),
+            );
+            check(
+                self.brain.encoding == BrainEncoding::Direct
+                    && !(self.brain.symmetric || self.brain.plasticity),
+                "brain.modules",
+                "can't be combined with Cppn, brain.symmetric or brain.plasticity".to_string(),
+            );
+        }
+        check(
+            self.brain.jitter.is_finite() && self.brain.jitter >= 0.0,
+            "brain.jitter",
+            format!("must be non-negative, got {}", self.brain.jitter),
+        );
+        check(
+            (0.0..=1.0).contains(&self.brain.champion_fraction),
+            "brain.champion_fraction",
+            format!("must be in [0, 1], got {}", self.brain.champion_fraction),
+        );
+
+        check(
+            (0.0..=1.0).contains(&self.mutation.rate),
+            "mutation.rate",
+            format!("must be in [0, 1], got {}", self.mutation.rate),
+        );
+        check(
+            self.mutation.strength.is_finite() && self.mutation.strength >= 0.0,
+            "mutation.strength",
+            format!("must be non-negative, got {}", self.mutation.strength),
+        );
+        check(
+            (0.0..=1.0).contains(&self.mutation.immigrants),
+            "mutation.immigrants",
+            format!("must be in [0, 1], got {}", self.mutation.immigrants),
+        );
+        check(
+            (0.0..=1.0).contains(&self.mutation.blend),
+            "mutation.blend",
+            format!("must be in [0, 1], got {}", self.mutation.blend),
+        );
+
+        check(
+            self.physics.min_speed.is_finite() && self.physics.min_speed >= 0.0,
+            "physics.min_speed",
+            format!("must be non-negative, got {}", self.physics.min_speed),
+        );
+        check(
+            self.physics.max_speed.is_finite() && self.physics.max_speed >= self.physics.min_speed,
+            "physics.max_speed",
+            format!(
+                "must be at least min_speed ({}), got {}",
+                self.physics.min_speed, self.physics.max_speed
+            ),
+        );
+        check(
+            self.physics.max_accel.is_finite() && self.physics.max_accel >= 0.0,
+            "physics.max_accel",
+            format!("must be non-negative, got {}", self.physics.max_accel),
+        );
+        check(
+            self.physics.max_angular_accel.is_finite() && self.physics.max_angular_accel > 0.0,
+            "physics.max_angular_accel",
+            format!("must be positive, got {}", self.physics.max_angular_accel),
+        );
+
+        check(
+            self.actuator.noise.is_finite() && self.actuator.noise >= 0.0,
+            "actuator.noise",
+            format!("must be non-negative, got {}", self.actuator.noise),
+        );
+        check(
+            (0.0..=1.0).contains(&self.actuator.stuck),
+            "actuator.stuck",
+            format!("must be in [0, 1], got {}", self.actuator.stuck),
+        );
+
+        check(
+            self.reward.food_approach.is_finite() && self.reward.food_approach >= 0.0,
+            "reward.food_approach",
+            format!("must be non-negative, got {}", self.reward.food_approach),
+        );
+        check(
+            (0.0..=1.0).contains(&self.reward.recency_decay),
+            "reward.recency_decay",
+            format!("must be in [0, 1], got {}", self.reward.recency_decay),
+        );
+
+        check(
+            !self.operators.crossover.needs_layout() || self.genome_layout().is_some(),
+            "operators.crossover",
+            format!(
+                "{:?} needs a genome layout, unavailable with brain.symmetric or brain.modules",
+                self.operators.crossover
+            ),
+        );
+        check(
+            self.operators.tournament_size > 0,
+            "operators.tournament_size",
+            "must be positive".to_string(),
+        );
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(errors))
+        }
+    }
+
+    /// Every config field with its type, accepted range and default, in the
+    /// order they appear in `Config`.
+    pub fn schema() -> Vec<FieldSchema> {
+        let defaults = serde_json::to_value(Self::default()).unwrap();
+        let field = |field: &'static str, kind, optional, description| {
+            let pointer = format!("/{}", field.replace('.', "/"));
+            FieldSchema {
+                field,
+                kind,
+                optional,
+                default: defaults.pointer(&pointer).cloned().unwrap_or_default(),
+                description,
+            }
+        };
+
+        vec![
+            field(
+                "num_animals",
+                FieldKind::Integer {
+                    min: 1,
+                    max: Some(u8::MAX.into()),
+                },
+                false,
+                "Number of animals in the population.",
+            ),
+            field(
+                "num_food",
+                FieldKind::Integer {
+                    min: 0,
+                    max: Some(u8::MAX.into()),
+                },
+                false,
+                "Number of food items in the world.",
+            ),
+            field(
+                "mode",
+                FieldKind::Choice {
+                    options: vec!["Eat".to_string(), "Forage".to_string()],
+                },
+                false,
+                "Objective the animals are evolved for.",
+            ),
+            field(
+                "control",
+                FieldKind::Choice {
+                    options: vec![
+                        "Acceleration".to_string(),
+                        "Velocity".to_string(),
+                        "Discrete".to_string(),
+                    ],
+                },
+                false,
+                "How brain outputs are turned into movement.",
+            ),
+            field(
+                "map",
+                FieldKind::Choice {
+                    options: Map::bundled_names().map(String::from).collect(),
+                },
+                true,
+                "Bundled map to load; an open world when unset.",
+            ),
+            field(
+                "spawn_zones",
+                FieldKind::ZoneList,
+                false,
+                "Regions animals start in, replacing the map's; anywhere when empty.",
+            ),
+            field(
+                "food_zones",
+                FieldKind::ZoneList,
+                false,
+                "Regions food is placed in, replacing the map's; anywhere when empty.",
+            ),
+            field(
+                "width",
+                FieldKind::PositiveFloat { max: None },
+                false,
+                "Width of the world, in world units.",
+            ),
+            field(
+                "height",
+                FieldKind::PositiveFloat { max: None },
+                false,
+                "Height of the world, in world units.",
+            ),
+            field(
+                "animal_radius",
+                FieldKind::PositiveFloat { max: None },
+                false,
+                "Radius of an animal's body, in world units.",
+            ),
+            field(
+                "food_radius",
+                FieldKind::PositiveFloat { max: None },
+                false,
+                "Radius of a piece of food, in world units; animals eat food they overlap.",
+            ),
+            field(
+                "generation_steps",
+                FieldKind::Integer {
+                    min: 1,
+                    max: Some(u32::MAX.into()),
+                },
+                false,
+                "Steps simulated before the population evolves.",
+            ),
+            field(
+                "generation_end",
+                FieldKind::Choice {
+                    options: vec![
+                        "Steps".to_string(),
+                        "FoodEaten".to_string(),
+                        "FitnessPlateau".to_string(),
+                        "Manual".to_string(),
+                    ],
+                },
+                false,
+                "When a generation ends; all but Manual also end after generation_steps.",
+            ),
+            field(
+                "plateau_steps",
+                FieldKind::Integer {
+                    min: 1,
+                    max: Some(u32::MAX.into()),
+                },
+                false,
+                "Steps without fitness improvement that end a FitnessPlateau generation.",
+            ),
+            field(
+                "pipeline",
+                FieldKind::ChoiceList {
+                    options: vec![
+                        "ProcessBrains".to_string(),
+                        "MoveAnimals".to_string(),
+                        "EatFood".to_string(),
+                    ],
+                },
+                false,
+                "Phases of a step, in the order they run.",
+            ),
+            field(
+                "rollouts",
+                FieldKind::ChoiceList {
+                    options: vec![
+                        "Identity".to_string(),
+                        "MirrorX".to_string(),
+                        "MirrorY".to_string(),
+                        "Rotate180".to_string(),
+                    ],
+                },
+                false,
+                "Symmetries of the starting layout each generation is run in, fitness averaged.",
+            ),
+            field(
+                "private_food",
+                FieldKind::Bool,
+                false,
+                "Give every animal its own copy of the food, respawning the same way for all.",
+            ),
+            field(
+                "trail_length",
+                FieldKind::Integer { min: 0, max: None },
+                false,
+                "Recent positions kept per animal for drawing trails; 0 keeps none.",
+            ),
+            field(
+                "heatmap_cells",
+                FieldKind::Integer {
+                    min: 1,
+                    max: Some(MAX_HEATMAP_CELLS as u64),
+                },
+                false,
+                "Cells along each side of the grid counting where animals spend time.",
+            ),
+            field(
+                "eye.fov_range",
+                FieldKind::PositiveFloat { max: None },
+                false,
+                "How far an animal can see, in world units.",
+            ),
+            field(
+                "eye.fov_angle",
+                FieldKind::PositiveFloat {
+                    max: Some(2.0 * PI),
+                },
+                false,
+                "Width of the field of view, in radians.",
+            ),
+            field(
+                "eye.receptors",
+                FieldKind::Integer { min: 1, max: None },
+                false,
+                "Number of photoreceptors the field of view is split into.",
+            ),
+            field(
+                "eye.noise",
+                FieldKind::Float {
+                    min: 0.0,
+                    max: None,
+                },
+                false,
+                "Standard deviation of the noise added to each receptor reading.",
+            ),
+            field(
+                "eye.dropout",
+                FieldKind::Float {
+                    min: 0.0,
+                    max: Some(1.0),
+                },
+                false,
+                "Probability that a piece of food in view goes undetected.",
+            ),
+            field(
+                "eye.refresh_distance",
+                FieldKind::Float {
+                    min: 0.0,
+                    max: None,
+                },
+                false,
+                "Distance an animal can move before its vision is recomputed; 0 recomputes every step.",
+            ),
+            field(
+                "eye.refresh_angle",
+                FieldKind::Float {
+                    min: 0.0,
+                    max: None,
+                },
+                false,
+                "Angle an animal can turn before its vision is recomputed; 0 recomputes every step.",
+            ),
+            field(
+                "eye.sensor",
+                FieldKind::Choice {
+                    options: vec!["Receptors".to_string(), "NearestFood".to_string()],
+                },
+                false,
+                "What animals perceive food with: eye receptors, or the positions of the nearest food.",
+            ),
+            field(
+                "eye.nearest_food",
+                FieldKind::Integer { min: 1, max: None },
+                false,
+                "Pieces of food seen by the NearestFood sensor.",
+            ),
+            field(
+                "eye.animal_receptors",
+                FieldKind::Bool,
+                false,
+                "Give eyes a second bank of receptors that see other animals.",
+            ),
+            field(
+                "eye.frames",
+                FieldKind::Integer { min: 1, max: None },
+                false,
+                "Number of most recent sensor readings the brain sees; each one adds inputs.",
+            ),
+            field(
+                "brain.hidden_layers",
+                FieldKind::IntegerList { min: 1 },
+                false,
+                "Sizes of the hidden layers.",
+            ),
+            field(
+                "brain.seed_genome",
+                FieldKind::FloatList,
+                true,
+                "Genome the initial population is built from instead of random brains.",
+            ),
+            field(
+                "brain.champions",
+                FieldKind::GenomeList,
+                false,
+                "More genomes to warm-start the initial population from.",
+            ),
+            field(
+                "brain.jitter",
+                FieldKind::Float {
+                    min: 0.0,
+                    max: None,
+                },
+                false,
+                "Noise added to copies of seeded genomes.",
+            ),
+            field(
+                "brain.champion_fraction",
+                FieldKind::Float {
+                    min: 0.0,
+                    max: Some(1.0),
+                },
+                false,
+                "Fraction of the initial population seeded from champions.",
+            ),
+            field(
+                "brain.precision",
+                FieldKind::Choice {
+                    options: vec!["F64".to_string(), "F32".to_string(), "I8".to_string()],
+                },
+                false,
+                "Numeric precision brains run inference in.",
+            ),
+            field(
+                "brain.evolve_activations",
+                FieldKind::Bool,
+                false,
+                "Evolve each layer's activation function along with the weights.",
+            ),
+            field(
+                "brain.symmetric",
+                FieldKind::Bool,
+                false,
+                "Mirror brains left to right, roughly halving the genome.",
+            ),
+            field(
+                "brain.plasticity",
+                FieldKind::Bool,
+                false,
+                "Evolve Hebbian rules that change the weights during each animal's life.",
+            ),
+            field(
+                "brain.encoding",
+                FieldKind::Choice {
+                    options: vec!["Direct".to_string(), "Cppn".to_string()],
+                },
+                false,
+                "Whether genes are the brain's weights or a CPPN generating them.",
+            ),
+            field(
+                "brain.cppn_hidden_layers",
+                FieldKind::IntegerList { min: 1 },
+                false,
+                "Sizes of the CPPN's hidden layers.",
+            ),
+            field(
+                "brain.modules",
+                FieldKind::ModuleList,
+                false,
+                "Independent networks the brain is built from instead of hidden_layers.",
+            ),
+            field(
+                "mutation.rate",
+                FieldKind::Float {
+                    min: 0.0,
+                    max: Some(1.0),
+                },
+                false,
+                "Probability of mutating each gene.",
+            ),
+            field(
+                "mutation.strength",
+                FieldKind::Float {
+                    min: 0.0,
+                    max: None,
+                },
+                false,
+                "Scale of the noise added to mutated genes.",
+            ),
+            field(
+                "mutation.immigrants",
+                FieldKind::Float {
+                    min: 0.0,
+                    max: Some(1.0),
+                },
+                false,
+                "Fraction of each new generation replaced by random brains.",
+            ),
+            field(
+                "mutation.blend",
+                FieldKind::Float {
+                    min: 0.0,
+                    max: Some(1.0),
+                },
+                false,
+                "Probability that a child averages its parents' brains instead of mixing their genes.",
+            ),
+            field(
+                "mutation.noise",
+                FieldKind::Choice {
+                    options: vec![
+                        "Gaussian".to_string(),
+                        "Cauchy".to_string(),
+                        "Uniform".to_string(),
+                    ],
+                },
+                false,
+                "Distribution mutations are drawn from; Cauchy makes occasional large jumps.",
+            ),
+            field(
+                "physics.min_speed",
+                FieldKind::Float {
+                    min: 0.0,
+                    max: None,
+                },
+                false,
+                "Slowest an animal can move, in world units per step.",
+            ),
+            field(
+                "physics.max_speed",
+                FieldKind::Float {
+                    min: 0.0,
+                    max: None,
+                },
+                false,
+                "Fastest an animal can move; must be at least min_speed.",
+            ),
+            field(
+                "physics.max_accel",
+                FieldKind::Float {
+                    min: 0.0,
+                    max: None,
+                },
+                false,
+                "Largest change in speed per step.",
+            ),
+            field(
+                "physics.max_angular_accel",
+                FieldKind::PositiveFloat { max: None },
+                false,
+                "Largest change in rotation per step, in radians.",
+            ),
+            field(
+                "physics.continuous_collision",
+                FieldKind::Bool,
+                false,
+                "Eat food anywhere along the path moved in a step, not only at its end.",
+            ),
+            field(
+                "actuator.noise",
+                FieldKind::Float {
+                    min: 0.0,
+                    max: None,
+                },
+                false,
+                "Standard deviation of the noise added to each brain output.",
+            ),
+            field(
+                "actuator.stuck",
+                FieldKind::Float {
+                    min: 0.0,
+                    max: Some(1.0),
+                },
+                false,
+                "Probability that a brain output is stuck at its previous value for a step.",
+            ),
+            field(
+                "reward.food_approach",
+                FieldKind::Float {
+                    min: 0.0,
+                    max: None,
+                },
+                false,
+                "Fitness per unit of distance closed towards the nearest food; 0 disables.",
+            ),
+            field(
+                "reward.recency_decay",
+                FieldKind::Float {
+                    min: 0.0,
+                    max: Some(1.0),
+                },
+                false,
+                "Fraction of the food score lost each step, favoring recent meals; 0 disables.",
+            ),
+            field(
+                "race.top",
+                FieldKind::Integer { min: 0, max: None },
+                false,
+                "Fittest animals of each generation evaluated again before selection.",
+            ),
+            field(
+                "race.rollouts",
+                FieldKind::Integer { min: 0, max: None },
+                false,
+                "Generations rerun to evaluate the raced animals again; 0 disables racing.",
+            ),
+            field(
+                "genome_history",
+                FieldKind::Integer { min: 0, max: None },
+                false,
+                "Latest generations whose statistics keep per-gene statistics; 0 keeps all.",
+            ),
+            field(
+                "operators.selection",
+                FieldKind::Choice {
+                    options: vec!["FitnessProportionate".to_string(), "Tournament".to_string()],
+                },
+                false,
+                "How parents are picked: in proportion to fitness, or the fittest of a few.",
+            ),
+            field(
+                "operators.crossover",
+                FieldKind::Choice {
+                    options: vec![
+                        "Uniform".to_string(),
+                        "Layer".to_string(),
+                        "Neuron".to_string(),
+                        "AlignedNeuron".to_string(),
+                    ],
+                },
+                false,
+                "What children take from either parent at a time: genes, layers or neurons.",
+            ),
+            field(
+                "operators.mutation",
+                FieldKind::Choice {
+                    options: vec!["Gaussian".to_string(), "WholeGenome".to_string()],
+                },
+                false,
+                "Whether mutation.rate applies to each gene or to each child's whole genome.",
+            ),
+            field(
+                "operators.tournament_size",
+                FieldKind::Integer { min: 1, max: None },
+                false,
+                "Animals drawn for each pick with tournament selection.",
+            ),
+        ]
+    }
+
+    /// How the initial population is created: random brains, or copies of
+    /// `brain.seed_genome` and `brain.champions` mixed with random brains
+    /// according to `brain.champion_fraction`.
+    pub fn population_init(&self) -> Box<dyn ga::PopulationInit> {
+        let random = RandomBrains::new(self);
+        let champions: Vec<ga::Chromosome> = self
+            .brain
+            .seed_genome
+            .iter()
+            .chain(&self.brain.champions)
+            .map(|genome| genome.iter().copied().collect())
+            .collect();
+        if champions.is_empty() {
+            return Box::new(random);
+        }
+
+        let champions = ga::ChampionInit::new(champions, self.brain.jitter);
+        if self.brain.champion_fraction == 1.0 {
+            Box::new(champions)
+        } else {
+            Box::new(ga::MixedInit::new(
+                champions,
+                random,
+                self.brain.champion_fraction,
+            ))
+        }
+    }
+
+    /// Not checked against `Eye::new`'s limits, since `validate` needs the
+    /// eye's topology before it has checked them itself.
+    pub fn eye(&self) -> Eye {
+        Eye::new_unchecked(
+            math::real(self.eye.fov_range),
+            math::real(self.eye.fov_angle),
+            self.eye.receptors,
+        )
+        .with_noise(self.eye.noise, self.eye.dropout)
+        .with_refresh(
+            math::real(self.eye.refresh_distance),
+            math::real(self.eye.refresh_angle),
+        )
+        .with_animal_receptors(self.eye.animal_receptors)
+    }
+
+    /// Sensor selected by `eye.sensor`. Unchecked like `eye`.
+    pub fn sensor(&self) -> Box<dyn Sensor> {
+        match self.eye.sensor {
+            SensorKind::Receptors => Box::new(self.eye()),
+            SensorKind::NearestFood => Box::new(self.nearest_food()),
+        }
+    }
+
+    pub(crate) fn nearest_food(&self) -> NearestFood {
+        NearestFood::new(self.eye.nearest_food, math::real(self.eye.fov_range))
+    }
+
+    /// Brain input count followed by every layer's size. With
+    /// `brain.modules`, each layer holds that layer of every module.
+    pub fn brain_topology(&self) -> (usize, Vec<usize>) {
+        let layers = match self.brain_modules() {
+            Some(modules) => {
+                let depth = modules.iter().map(|(_, layers)| layers.len()).max();
+                (0..depth.unwrap_or(0))
+                    .map(|layer| {
+                        modules
+                            .iter()
+                            .map(|(_, layers)| layers.get(layer).copied().unwrap_or(0))
+                            .sum()
+                    })
+                    .collect()
+            }
+            None => {
+                let mut layers = self.brain.hidden_layers.clone();
+                layers.push(self.control.decoder().outputs());
+                layers
+            }
+        };
+        (
+            self.mode.brain_inputs(&*self.sensor(), self.eye.frames),
+            layers,
+        )
+    }
+
+    /// With `brain.modules`, each module's input slice and layer sizes, as
+    /// `nn::ModularBrain` takes them. `None` otherwise.
+    pub fn brain_modules(&self) -> Option<Vec<(Range<usize>, Vec<usize>)>> {
+        if self.brain.modules.is_empty() {
+            return None;
+        }
+        let modules = self.brain.modules.iter().map(|module| {
+            let mut layers = module.hidden_layers.clone();
+            layers.push(module.outputs);
+            (module.inputs.clone(), layers)
+        });
+        Some(modules.collect())
+    }
+
+    /// Where the genes of `brain.modules[module]` are in the genome, e.g. to
+    /// evolve one module while leaving the others alone.
+    pub fn module_genes(&self, module: usize) -> Range<usize> {
+        let modules = self.brain_modules().expect("brain.modules is empty");
+        let genes = |(inputs, layers): &(Range<usize>, Vec<usize>)| mlp_genes(inputs.len(), layers);
+        let start = modules[..module].iter().map(genes).sum();
+        start..start + genes(&modules[module])
+    }
+
+    /// Breeds each generation with `operators`: children average their
+    /// parents' brains with probability `mutation.blend`, taking activation
+    /// genes from one parent, and use `operators.crossover` otherwise,
+    /// keeping genes in `gene_mask`.
+    pub fn genetic_algorithm(&self) -> ga::DynGeneticAlgorithm {
+        let operators = &self.operators;
+        let mutation = &self.mutation;
+        let selection: Box<dyn ga::Selection> = match operators.selection {
+            SelectionKind::FitnessProportionate => {
+                Box::new(ga::FitnessProportionateSelection::new())
+            }
+            SelectionKind::Tournament => {
+                Box::new(ga::TournamentSelection::new(operators.tournament_size))
+            }
+        };
+        let layout = || {
+            self.genome_layout()
+                .expect("Layout-based crossover with no genome layout")
+        };
+        let crossover: Box<dyn ga::Crossover> = match operators.crossover {
+            CrossoverKind::Uniform => Box::new(ga::UniformCrossover::new()),
+            CrossoverKind::Layer => Box::new(ga::LayerCrossover::new(layout(), ga::Segment::Layer)),
+            CrossoverKind::Neuron => {
+                Box::new(ga::LayerCrossover::new(layout(), ga::Segment::Neuron))
+            }
+            CrossoverKind::AlignedNeuron => Box::new(ga::AlignedNeuronCrossover::new(layout())),
+        };
+        let gaussian =
+            |rate| ga::GaussianMutation::new(rate, mutation.strength).with_noise(mutation.noise);
+        let mutation_op: Box<dyn ga::Mutation> = match operators.mutation {
+            MutationKind::Gaussian => Box::new(gaussian(mutation.rate)),
+            MutationKind::WholeGenome => {
+                Box::new(ga::ProbabilisticMutation::new(mutation.rate, gaussian(1.0)))
+            }
+        };
+        let evolver: ga::DynGeneticAlgorithm = ga::GeneticAlgorithm::new(
+            selection,
+            Box::new(ga::EitherCrossover::new(
+                mutation.blend,
+                ga::BlendCrossover::mean().with_discrete(self.activation_genes()),
+                crossover,
+            )),
+            mutation_op,
+        );
+        match self.gene_mask() {
+            Some(mask) => evolver.with_gene_mask(mask),
+            None => evolver,
+        }
+    }
+
+    /// Genes of the `frozen` `brain.modules`, which children inherit from
+    /// their first parent unmutated. `None` if no module is frozen.
+    pub fn gene_mask(&self) -> Option<ga::GeneMask> {
+        let frozen: Vec<usize> = (0..self.brain.modules.len())
+            .filter(|&module| self.brain.modules[module].frozen)
+            .collect();
+        if frozen.is_empty() {
+            return None;
+        }
+        let mask = frozen
+            .into_iter()
+            .fold(ga::GeneMask::default(), |mask, module| {
+                mask.freeze(self.module_genes(module))
+            });
+        Some(mask)
+    }
+
+    /// Stable hash of every setting, as serialized, to tell configs apart
+    /// in saved snapshots and replays.
+    pub fn fingerprint(&self) -> u64 {
+        fingerprint_json(&serde_json::to_value(self).unwrap())
+    }
+
+    /// Number of genes in a brain: weights and biases (shared ones counted
+    /// once, only each module's own with `brain.modules`, or the CPPN's with
+    /// `BrainEncoding::Cppn`), followed by
+    /// `plasticity_genes` and one activation gene per layer with
+    /// `brain.evolve_activations`.
+    pub fn genome_length(&self) -> usize {
+        let (nin, layers) = self.brain_topology();
+        let activation_genes = if self.brain.evolve_activations {
+            layers.len()
+        } else {
+            0
+        };
+        let weight_genes = if let Some((cppn_nin, cppn_layers)) = self.cppn_topology() {
+            mlp_genes(cppn_nin, &cppn_layers)
+        } else if let Some(sharing) = self.brain_sharing() {
+            sharing.num_genes()
+        } else if self.brain.modules.is_empty() {
+            mlp_genes(nin, &layers)
+        } else {
+            self.module_genes(self.brain.modules.len() - 1).end
+        };
+        weight_genes + self.plasticity_genes() + activation_genes
+    }
+
+    /// Genes picking each layer's activation with
+    /// `brain.evolve_activations`, at the end of the genome; empty
+    /// otherwise.
+    fn activation_genes(&self) -> Range<usize> {
+        if !self.brain.evolve_activations {
+            return 0..0;
+        }
+        let (_, layers) = self.brain_topology();
+        let end = self.genome_length();
+        end - layers.len()..end
+    }
+
+    /// Number of genes for the Hebbian rules of every connection with
+    /// `brain.plasticity`, otherwise 0.
+    pub fn plasticity_genes(&self) -> usize {
+        if !self.brain.plasticity {
+            return 0;
+        }
+        let (mut nin, layers) = self.brain_topology();
+        let connections: usize = layers
+            .into_iter()
+            .map(|nout| {
+                let connections = nout * nin;
+                nin = nout;
+                connections
+            })
+            .sum();
+        connections * nn::HebbianRule::GENES
+    }
+
+    /// With `BrainEncoding::Cppn`, the CPPN's input count followed by every
+    /// layer's size. `None` otherwise.
+    pub fn cppn_topology(&self) -> Option<(usize, Vec<usize>)> {
+        (self.brain.encoding == BrainEncoding::Cppn)
+            .then(|| nn::Cppn::topology(&self.brain.cppn_hidden_layers))
+    }
+
+    /// Where the brain's inputs and neurons sit for `BrainEncoding::Cppn`:
+    /// inputs per `GameMode::input_positions`, and every layer's neurons
+    /// spread out along x.
+    pub fn substrate(&self) -> nn::Substrate {
+        let (_, layers) = self.brain_topology();
+        let mut substrate = vec![self.mode.input_positions(&*self.sensor(), self.eye.frames)];
+        substrate.extend(layers.into_iter().map(|size| nn::Substrate::row(size, 0.0)));
+        nn::Substrate::new(substrate)
+    }
+
+    /// Where each brain layer sits in the genome, for layer-aware genetic
+    /// operators such as `ga::LayerCrossover`; with `BrainEncoding::Cppn`,
+    /// each CPPN layer. `None` with `brain.symmetric`, where genes are shared
+    /// across neurons, and with `brain.modules`.
+    pub fn genome_layout(&self) -> Option<ga::GenomeLayout> {
+        if self.brain.symmetric || !self.brain.modules.is_empty() {
+            return None;
+        }
+        let (inputs, layers) = self
+            .cppn_topology()
+            .unwrap_or_else(|| self.brain_topology());
+        Some(ga::GenomeLayout::mlp(inputs, &layers))
+    }
+
+    /// With `brain.symmetric`, how the genome maps onto the brain's weights
+    /// and biases: mirroring the inputs left to right (see
+    /// `GameMode::input_mirror`) mirrors every hidden layer end to end and the
+    /// outputs per `ActionDecoder::output_mirror`. `None` otherwise.
+    pub fn brain_sharing(&self) -> Option<nn::ParameterSharing> {
+        if !self.brain.symmetric {
+            return None;
+        }
+        let mut mirrors = vec![self.mode.input_mirror(&*self.sensor(), self.eye.frames)];
+        mirrors.extend(
+            self.brain
+                .hidden_layers
+                .iter()
+                .map(|&size| nn::Mirror::reverse(size)),
+        );
+        mirrors.push(self.control.decoder().output_mirror());
+        Some(nn::ParameterSharing::mirror(&mirrors))
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(err) => write!(f, "failed to parse config: {err}"),
+            Self::Invalid(errors) => {
+                write!(f, "invalid config:")?;
+                for error in errors {
+                    write!(f, "\n  {error}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// `Config::fingerprint` of a config as stored, so that configs saved
+/// before a setting was added still hash to what they were saved with.
+/// JSON objects keep their keys sorted, which makes the encoding canonical.
+pub(crate) fn fingerprint_json(config: &serde_json::Value) -> u64 {
+    let mut hasher = StateHasher::new();
+    hasher.write(&serde_json::to_vec(config).unwrap());
+    hasher.finish()
+}
+
+/// Number of weights and biases of an MLP with `nin` inputs and `layers`.
+fn mlp_genes(mut nin: usize, layers: &[usize]) -> usize {
+    layers
+        .iter()
+        .map(|&nout| {
+            let genes = nout * (nin + 1);
+            nin = nout;
+            genes
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    fn invalid_fields(result: Result<Config, ConfigError>) -> Vec<&'static str> {
+        match result {
+            Err(ConfigError::Invalid(errors)) => errors.iter().map(|error| error.field).collect(),
+            other => panic!("expected validation errors, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_defaults() {
+        let config = Config::from_toml_str("").unwrap();
+        assert_eq!(config, Config::default());
+        assert_eq!(Config::from_json_str("{}").unwrap(), Config::default());
+        // 10 inputs -> 20 hidden -> 2 outputs
+        assert_eq!(config.genome_length(), 20 * 11 + 2 * 21);
+        let layout = config.genome_layout().unwrap();
+        assert_eq!(layout.len(), config.genome_length());
+        assert_eq!(layout.layers()[1].neuron_len, 21);
+    }
+
+    #[test]
+    fn test_aligned_genome_is_the_same_brain() {
+        let mut rng = vroom_rand::seeded(0);
+        let config = Config::default();
+        let layout = config.genome_layout().unwrap();
+        let (inputs, layers) = config.brain_topology();
+        let random = |rng: &mut rand_chacha::ChaCha8Rng| -> ga::Chromosome {
+            (0..layout.len())
+                .map(|_| rng.gen_range(-1.0..1.0))
+                .collect()
+        };
+        let reference = random(&mut rng);
+        let other = random(&mut rng);
+
+        let aligned = layout.align_neurons(&reference, &other);
+        assert_ne!(aligned.as_slice(), other.as_slice());
+        let brain = |chromosome: &ga::Chromosome| {
+            nn::MLP::from_weight_and_biases(inputs, &layers, chromosome.iter().copied())
+        };
+        let input: Vec<f64> = (0..inputs).map(|_| rng.gen_range(-1.0..1.0)).collect();
+        let expected = brain(&other).forward(input.clone());
+        let actual = brain(&aligned).forward(input);
+        for (expected, actual) in expected.iter().zip(&actual) {
+            assert!((expected - actual).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_from_toml_str() {
+        let config = Config::from_toml_str(
+            r#"
+            num_animals = 10
+            mode = "Forage"
+
+            [eye]
+            receptors = 5
+
+            [brain]
+            hidden_layers = [8, 4]
+
+            [mutation]
+            noise = "Cauchy"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.num_animals, 10);
+        assert_eq!(config.mode, GameMode::Forage);
+        assert_eq!(config.eye.receptors, 5);
+        assert_eq!(config.brain_topology(), (8, vec![8, 4, 2]));
+        assert_eq!(config.mutation.noise, ga::Noise::Cauchy);
+    }
+
+    #[test]
+    fn test_control_sets_output_layer() {
+        let config = Config::from_toml_str(r#"control = "Discrete""#).unwrap();
+        assert_eq!(config.brain_topology(), (10, vec![20, 3]));
+    }
+
+    #[test]
+    fn test_validation_errors() {
+        let fields = invalid_fields(Config::from_json_str(
+            r#"{
+                "num_animals": 0,
+                "width": -1.0,
+                "eye": { "fov_angle": 7.0, "receptors": 0 },
+                "brain": { "hidden_layers": [4, 0] },
+                "mutation": { "rate": 1.5 }
+            }"#,
+        ));
+
+        assert_eq!(
+            fields,
+            vec![
+                "num_animals",
+                "width",
+                "eye.fov_angle",
+                "eye.receptors",
+                "brain.hidden_layers",
+                "mutation.rate"
+            ]
+        );
+
+        let fields = invalid_fields(Config::from_json_str(r#"{ "heatmap_cells": 2048 }"#));
+        assert_eq!(fields, vec!["heatmap_cells"]);
+
+        // Bounds are inclusive, like the schema's
+        assert!(Config::from_json_str(r#"{ "reward": { "recency_decay": 1.0 } }"#).is_ok());
+        let fields = invalid_fields(Config::from_json_str(
+            r#"{ "reward": { "recency_decay": 1.5 } }"#,
+        ));
+        assert_eq!(fields, vec!["reward.recency_decay"]);
+    }
+
+    #[test]
+    fn test_seed_genome_must_match_topology() {
+        let fields = invalid_fields(Config::from_json_str(
+            r#"{ "brain": { "hidden_layers": [1], "seed_genome": [0.0, 1.0] } }"#,
+        ));
+        assert_eq!(fields, vec!["brain.seed_genome"]);
+
+        // 10 inputs -> 1 hidden -> 2 outputs
+        let genome = vec![0.0; 11 + 2 * 2];
+        let json =
+            format!(r#"{{ "brain": {{ "hidden_layers": [1], "seed_genome": {genome:?} }} }}"#);
+        assert!(Config::from_json_str(&json).is_ok());
+    }
+
+    #[test]
+    fn test_schema_covers_every_field() {
+        fn leaves(prefix: String, value: &serde_json::Value, out: &mut Vec<String>) {
+            match value.as_object() {
+                Some(object) => {
+                    for (key, value) in object {
+                        let path = if prefix.is_empty() {
+                            key.clone()
+                        } else {
+                            format!("{prefix}.{key}")
+                        };
+                        leaves(path, value, out);
+                    }
+                }
+                None => out.push(prefix),
+            }
+        }
+
+        let mut fields = Vec::new();
+        leaves(
+            String::new(),
+            &serde_json::to_value(Config::default()).unwrap(),
+            &mut fields,
+        );
+        let schema = Config::schema();
+        let mut described: Vec<_> = schema.iter().map(|field| field.field.to_string()).collect();
+        fields.sort();
+        described.sort();
+        assert_eq!(described, fields);
+
+        let receptors = schema
+            .iter()
+            .find(|field| field.field == "eye.receptors")
+            .unwrap();
+        assert_eq!(receptors.default, serde_json::json!(10));
+        assert!(schema
+            .iter()
+            .filter(|field| field.optional)
+            .all(|field| field.default.is_null()));
+    }
+
+    #[test]
+    fn test_population_init() {
+        let mut rng = vroom_rand::seeded(0);
+        let genes = |chromosome: &ga::Chromosome| chromosome.iter().copied().collect::<Vec<_>>();
+
+        let random = Config::default().population_init().init(&mut rng, 4);
+        assert_eq!(random.len(), 4);
+        assert_ne!(genes(&random[0]), genes(&random[1]));
+
+        let champion = vec![0.5; Config::default().genome_length()];
+        let config = Config::from_json_str(&format!(
+            r#"{{ "brain": {{ "champions": [{champion:?}], "champion_fraction": 0.5 }} }}"#
+        ))
+        .unwrap();
+        let population = config.population_init().init(&mut rng, 4);
+        let seeded = population
+            .iter()
+            .filter(|chromosome| genes(chromosome) == champion)
+            .count();
+        assert_eq!(seeded, 2);
+    }
+
+    #[test]
+    fn test_operators() {
+        let config = Config::from_toml_str(
+            r#"
+            [operators]
+            selection = "Tournament"
+            crossover = "AlignedNeuron"
+            mutation = "WholeGenome"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.operators.selection, SelectionKind::Tournament);
+        assert_eq!(config.operators.crossover, CrossoverKind::AlignedNeuron);
+        assert_eq!(config.operators.mutation, MutationKind::WholeGenome);
+
+        let invalid = Config::from_toml_str(
+            r#"
+            brain = { symmetric = true }
+            operators = { crossover = "Layer", tournament_size = 0 }
+            "#,
+        );
+        assert_eq!(
+            invalid_fields(invalid),
+            ["operators.crossover", "operators.tournament_size"]
+        );
+    }
+
+    #[test]
+    fn test_symmetric_brain() {
+        let mut rng = vroom_rand::seeded(0);
+        let mut config = Config::from_toml_str(r#"control = "Discrete""#).unwrap();
+        let full_length = config.genome_length();
+        config.brain.symmetric = true;
+        assert!(config.genome_length() < full_length * 2 / 3);
+
+        let animal = crate::animal::Animal::random(&mut rng, &config);
+        assert_eq!(animal.as_chromosome().len(), config.genome_length());
+
+        // Mirroring the receptors mirrors the choice between turning left and
+        // right.
+        let inputs: Vec<f64> = (0..10).map(|idx| (idx as f64 * 0.37).sin()).collect();
+        let mirrored: Vec<f64> = inputs.iter().rev().copied().collect();
+        let mut expected = animal.brain.forward(inputs);
+        expected.reverse();
+        approx::assert_relative_eq!(
+            animal.brain.forward(mirrored).as_slice(),
+            expected.as_slice(),
+            epsilon = 1e-9
+        );
+    }
+
+    #[test]
+    fn test_plasticity_genes() {
+        let mut config = Config::default();
+        let length = config.genome_length();
+        config.brain.plasticity = true;
+        // 10 inputs -> 20 hidden -> 2 outputs
+        assert_eq!(config.plasticity_genes(), (10 * 20 + 20 * 2) * 5);
+        assert_eq!(config.genome_length(), length + config.plasticity_genes());
+
+        config.brain.symmetric = true;
+        config.brain.precision = Precision::I8;
+        assert_eq!(
+            invalid_fields(config.validate().map(|()| config)),
+            vec!["brain.plasticity", "brain.plasticity"]
+        );
+    }
+
+    #[test]
+    fn test_brain_modules() {
+        let config = Config::from_toml_str(
+            r#"
+            [[brain.modules]]
+            inputs = { start = 0, end = 10 }
+            hidden_layers = [8]
+            outputs = 1
+
+            [[brain.modules]]
+            inputs = { start = 3, end = 7 }
+            hidden_layers = [2]
+            outputs = 1
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.brain_topology(), (10, vec![10, 2]));
+        assert_eq!(config.module_genes(0), 0..8 * 11 + 9);
+        assert_eq!(config.module_genes(1), 97..97 + 2 * 5 + 3);
+        assert_eq!(config.genome_length(), 110);
+        assert!(config.genome_layout().is_none());
+
+        let mut invalid = config.clone();
+        invalid.brain.modules[1].outputs = 2;
+        invalid.brain.modules[1].hidden_layers = vec![2, 2];
+        assert_eq!(
+            invalid_fields(invalid.validate().map(|()| invalid.clone())),
+            vec!["brain.modules", "brain.modules"]
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(matches!(
+            Config::from_toml_str("unknown_field = 1"),
+            Err(ConfigError::Parse(_))
+        ));
+        assert!(matches!(
+            Config::from_json_str("{"),
+            Err(ConfigError::Parse(_))
+        ));
+    }
+}
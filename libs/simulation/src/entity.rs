@@ -0,0 +1,27 @@
+use crate::animal::Animal;
+use crate::food::Food;
+use crate::map::Wall;
+use crate::nest::Nest;
+
+/// Borrowed view of one thing in a `World`, see `World::entities`.
+#[derive(Clone, Copy)]
+pub enum Entity<'a> {
+    Animal(&'a Animal),
+    Food(&'a Food),
+    Nest(&'a Nest),
+    Wall(&'a Wall),
+}
+
+/// Walks a `World` through `World::visit`, for analytics or custom
+/// rendering. Every method does nothing by default, so visitors only
+/// implement what they care about. Indices are those of `World::animals`
+/// and `World::food`.
+pub trait WorldVisitor {
+    fn visit_animal(&mut self, _idx: usize, _animal: &Animal) {}
+
+    fn visit_food(&mut self, _idx: usize, _food: &Food) {}
+
+    fn visit_nest(&mut self, _nest: &Nest) {}
+
+    fn visit_wall(&mut self, _wall: &Wall) {}
+}
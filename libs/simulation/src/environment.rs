@@ -0,0 +1,143 @@
+use vroom_rand::RngCore;
+
+use lib_reinforcement_learning::environment::{Environment, Transition};
+
+use crate::behavior::Behavior;
+use crate::map::Zone;
+use crate::math::{self, Real};
+use crate::simulation::Simulation;
+
+/// Treats each animal as an agent: observations are brain inputs, actions
+/// are brain outputs, and rewards are the fitness gained during the step.
+/// The animals' own brains are ignored and the population never evolves.
+impl Environment for Simulation {
+    fn observation_size(&self) -> usize {
+        self.config
+            .mode
+            .brain_inputs(&*self.config.sensor(), self.config.eye.frames)
+    }
+
+    fn action_size(&self) -> usize {
+        self.config.control.decoder().outputs()
+    }
+
+    fn num_agents(&self) -> usize {
+        self.world.animals.len()
+    }
+
+    fn reset(&mut self, rng: &mut dyn RngCore) -> Vec<Vec<f64>> {
+        self.generation_steps = 0;
+        for animal in &mut self.world.animals {
+            animal.position = Zone::random_position(rng, &self.world.spawn_zones, self.world.size);
+            animal.rotation = math::random_rotation(rng);
+            animal.speed = 0.001;
+            animal.consumed = 0;
+            animal.carrying = false;
+            animal.deposited = 0;
+            animal.behavior = Behavior::default();
+            animal.shaped_reward = 0.0;
+            animal.recent_score = animal.recent_score.map(|_| 0.0);
+            animal.rollout_fitness.clear();
+            animal.history.clear();
+        }
+        for food in &mut self.world.food {
+            food.randomize_position(rng, &self.world.food_zones, self.world.size);
+        }
+        self.world.food_version += 1;
+        self.rollout = 0;
+        self.rollout_food = self.world.food.iter().map(|food| food.position).collect();
+        self.food_respawns.clear();
+        self.reset_generation_end();
+        self.deal_food();
+        observe_wide(self, rng)
+    }
+
+    fn step(&mut self, rng: &mut dyn RngCore, actions: &[Vec<f64>]) -> Transition {
+        let before: Vec<f64> = self.fitnesses();
+
+        self.generation_steps += 1;
+        let actions: Vec<Vec<Real>> = actions
+            .iter()
+            .map(|action| action.iter().map(|&value| math::real(value)).collect())
+            .collect();
+        self.act(&actions);
+        self.move_animals();
+        self.eat_food(rng);
+
+        let rewards = self
+            .fitnesses()
+            .iter()
+            .zip(&before)
+            .map(|(after, before)| after - before)
+            .collect();
+        Transition {
+            observations: observe_wide(self, rng),
+            rewards,
+            done: self.generation_steps >= self.config.generation_steps,
+        }
+    }
+}
+
+/// `Simulation::observe` in the `f64` agents work in.
+fn observe_wide(simulation: &mut Simulation, rng: &mut dyn RngCore) -> Vec<Vec<f64>> {
+    simulation
+        .observe(rng)
+        .into_iter()
+        .map(|inputs| inputs.into_iter().map(math::wide).collect())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::generation_end::GenerationEnd;
+    use lib_reinforcement_learning::environment::rollout;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_rollout_simulation() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut simulation = Simulation::random(&mut rng, 3, 100);
+        assert_eq!(simulation.observation_size(), 10);
+        assert_eq!(simulation.num_agents(), 3);
+        let generation_steps = simulation.config().generation_steps;
+
+        let rewards = rollout(
+            &mut simulation,
+            &mut rng,
+            |_, _| vec![1.0, 0.1],
+            generation_steps as usize * 2,
+        );
+
+        assert_eq!(rewards.len(), 3);
+        assert_eq!(simulation.generation_steps(), generation_steps);
+        assert_eq!(simulation.generation(), 0);
+        for (reward, animal) in rewards.iter().zip(simulation.world().animals()) {
+            approx::assert_relative_eq!(*reward, animal.consumed() as f64);
+        }
+    }
+
+    #[test]
+    fn test_reset_generation_end() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let config = Config {
+            num_animals: 3,
+            num_food: 100,
+            generation_end: GenerationEnd::FitnessPlateau,
+            ..Config::default()
+        };
+        let mut simulation = Simulation::from_config(&mut rng, config);
+        Environment::reset(&mut simulation, &mut rng);
+        for _ in 0..50 {
+            Environment::step(&mut simulation, &mut rng, &vec![vec![1.0, 0.1]; 3]);
+        }
+        assert!(simulation.fitnesses().iter().any(|&fitness| fitness > 0.0));
+        simulation.force_evolve();
+
+        // The new episode starts with no best fitness and no request
+        Environment::reset(&mut simulation, &mut rng);
+        assert_eq!(simulation.phase(), simulation.config().pipeline[0]);
+    }
+}
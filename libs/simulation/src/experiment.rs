@@ -0,0 +1,366 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::simulation::Simulation;
+
+/// Experiment definition shared by the wasm and native front ends: what to
+/// simulate, for how long, and when to stop early.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Experiment {
+    pub config: Config,
+    pub generations: u32,
+    /// Stops a run once a generation's max fitness reaches this value.
+    pub target_fitness: Option<f64>,
+    /// Stops a run after this many generations without a new best max
+    /// fitness.
+    pub patience: Option<u32>,
+    /// Stops a run once it has taken this many seconds, checked between
+    /// generations.
+    pub time_budget_secs: Option<f64>,
+    /// Stops a run once it has evaluated this many animals, counting every
+    /// animal of every generation.
+    pub max_evaluations: Option<u64>,
+}
+
+/// Why a run ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopReason {
+    /// Ran all of `Experiment::generations`.
+    Generations,
+    TargetFitness,
+    /// Went `Experiment::patience` generations without improving.
+    Stagnation,
+    TimeBudget,
+    EvaluationBudget,
+    /// Stopped from outside, e.g. by the user.
+    Interrupted,
+}
+
+/// Progress of one run, reported after each of its generations.
+#[derive(Clone, Debug)]
+pub struct Progress {
+    pub seed: u64,
+    pub generation: u32,
+    pub max_fitness: f64,
+    pub mean_fitness: f64,
+    /// Runs of the whole batch that have finished so far.
+    pub completed_runs: usize,
+    pub total_runs: usize,
+}
+
+/// Summary of one run.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RunResult {
+    pub seed: u64,
+    /// `None` while the run is still going.
+    pub stop_reason: Option<StopReason>,
+    /// Max fitness of each generation.
+    pub max_fitness: Vec<f64>,
+    /// Mean fitness of each generation.
+    pub mean_fitness: Vec<f64>,
+    /// Highest max fitness of any generation, 0 before the first.
+    pub best_fitness: f64,
+    /// Generation that reached `best_fitness`, counting from 1.
+    pub best_generation: u32,
+    /// Animals evaluated over all generations.
+    pub evaluations: u64,
+    pub elapsed_secs: f64,
+    /// Whether the run stopped at `Experiment::target_fitness`, as in
+    /// `stop_reason`.
+    #[serde(default)]
+    pub reached_target: bool,
+}
+
+impl RunResult {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            stop_reason: None,
+            max_fitness: Vec::new(),
+            mean_fitness: Vec::new(),
+            best_fitness: 0.0,
+            best_generation: 0,
+            evaluations: 0,
+            elapsed_secs: 0.0,
+            reached_target: false,
+        }
+    }
+
+    pub fn generations(&self) -> u32 {
+        self.max_fitness.len() as u32
+    }
+
+    /// Ends the run for `reason`, `elapsed` after it started.
+    pub fn finish(&mut self, reason: StopReason, elapsed: Duration) {
+        self.stop_reason = Some(reason);
+        self.reached_target = reason == StopReason::TargetFitness;
+        self.elapsed_secs = elapsed.as_secs_f64();
+    }
+
+    /// Adds a finished generation of `evaluations` animals.
+    pub fn record_generation(&mut self, max_fitness: f64, mean_fitness: f64, evaluations: u64) {
+        self.max_fitness.push(max_fitness);
+        self.mean_fitness.push(mean_fitness);
+        self.evaluations += evaluations;
+        if self.best_generation == 0 || max_fitness > self.best_fitness {
+            self.best_fitness = max_fitness;
+            self.best_generation = self.generations();
+        }
+    }
+}
+
+impl Default for Experiment {
+    fn default() -> Self {
+        Self {
+            config: Config::default(),
+            generations: 100,
+            target_fitness: None,
+            patience: None,
+            time_budget_secs: None,
+            max_evaluations: None,
+        }
+    }
+}
+
+impl Experiment {
+    /// Why a run should stop after the generations recorded in `result`,
+    /// `elapsed` into it, if it should. Checked before every generation, so
+    /// a zero budget runs none.
+    pub fn stop_reason(&self, result: &RunResult, elapsed: Duration) -> Option<StopReason> {
+        let generations = result.generations();
+        if let (Some(target), Some(&max_fitness)) = (self.target_fitness, result.max_fitness.last())
+        {
+            if max_fitness >= target {
+                return Some(StopReason::TargetFitness);
+            }
+        }
+        if self
+            .max_evaluations
+            .is_some_and(|budget| result.evaluations >= budget)
+        {
+            return Some(StopReason::EvaluationBudget);
+        }
+        if self
+            .time_budget_secs
+            .is_some_and(|budget| elapsed.as_secs_f64() >= budget)
+        {
+            return Some(StopReason::TimeBudget);
+        }
+        if self
+            .patience
+            .is_some_and(|patience| generations - result.best_generation >= patience)
+        {
+            return Some(StopReason::Stagnation);
+        }
+        if generations >= self.generations {
+            return Some(StopReason::Generations);
+        }
+        None
+    }
+
+    /// Runs a single seeded simulation until one of the stopping criteria
+    /// is met, calling `on_generation` with its generation number, max
+    /// fitness and mean fitness.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn run(&self, seed: u64, on_generation: impl FnMut(u32, f64, f64)) -> RunResult {
+        let started = std::time::Instant::now();
+        self.run_with_clock(seed, || started.elapsed(), on_generation)
+    }
+
+    /// Same as `run`, with `elapsed` telling how long the run has taken,
+    /// for targets without `std::time::Instant` such as the browser.
+    pub fn run_with_clock(
+        &self,
+        seed: u64,
+        mut elapsed: impl FnMut() -> Duration,
+        mut on_generation: impl FnMut(u32, f64, f64),
+    ) -> RunResult {
+        let mut rng = vroom_rand::seeded(seed);
+        let mut simulation = Simulation::from_config(&mut rng, self.config.clone());
+        let mut result = RunResult::new(seed);
+
+        loop {
+            if let Some(reason) = self.stop_reason(&result, elapsed()) {
+                result.finish(reason, elapsed());
+                break;
+            }
+            let stats = simulation.train(&mut rng);
+            result.record_generation(
+                stats.max_fitness,
+                stats.mean_fitness,
+                u64::from(self.config.num_animals),
+            );
+            on_generation(result.generations(), stats.max_fitness, stats.mean_fitness);
+        }
+        result
+    }
+
+    /// Runs one simulation per seed on up to `threads` worker threads,
+    /// returning results in the order of `seeds`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn run_parallel(
+        &self,
+        seeds: &[u64],
+        threads: usize,
+        on_progress: impl Fn(&Progress) + Sync,
+    ) -> Vec<RunResult> {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Mutex;
+
+        let next_run = AtomicUsize::new(0);
+        let completed_runs = AtomicUsize::new(0);
+        let results = Mutex::new(vec![None; seeds.len()]);
+
+        std::thread::scope(|scope| {
+            for _ in 0..threads.clamp(1, seeds.len().max(1)) {
+                scope.spawn(|| loop {
+                    let idx = next_run.fetch_add(1, Ordering::SeqCst);
+                    let Some(&seed) = seeds.get(idx) else {
+                        break;
+                    };
+
+                    let result = self.run(seed, |generation, max_fitness, mean_fitness| {
+                        on_progress(&Progress {
+                            seed,
+                            generation,
+                            max_fitness,
+                            mean_fitness,
+                            completed_runs: completed_runs.load(Ordering::SeqCst),
+                            total_runs: seeds.len(),
+                        });
+                    });
+                    completed_runs.fetch_add(1, Ordering::SeqCst);
+                    results.lock().unwrap()[idx] = Some(result);
+                });
+            }
+        });
+
+        results
+            .into_inner()
+            .unwrap()
+            .into_iter()
+            .map(|result| result.expect("Every run completes"))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn experiment() -> Experiment {
+        Experiment {
+            config: Config {
+                num_animals: 4,
+                num_food: 16,
+                ..Config::default()
+            },
+            generations: 2,
+            ..Experiment::default()
+        }
+    }
+
+    #[test]
+    fn test_parallel_matches_serial() {
+        let experiment = experiment();
+        let seeds = [1, 2, 3];
+
+        let progress_reports = AtomicUsize::new(0);
+        let parallel = experiment.run_parallel(&seeds, 2, |_| {
+            progress_reports.fetch_add(1, Ordering::SeqCst);
+        });
+
+        assert_eq!(progress_reports.into_inner(), 6);
+        for (seed, result) in seeds.iter().zip(&parallel) {
+            let serial = experiment.run(*seed, |_, _, _| {});
+            assert_eq!(result.seed, *seed);
+            assert_eq!(result.max_fitness, serial.max_fitness);
+            assert_eq!(result.mean_fitness, serial.mean_fitness);
+        }
+    }
+
+    #[test]
+    fn test_stops_at_target_fitness() {
+        let experiment = Experiment {
+            target_fitness: Some(0.0),
+            ..experiment()
+        };
+
+        let result = experiment.run(1, |_, _, _| {});
+
+        assert!(result.reached_target);
+        assert_eq!(result.max_fitness.len(), 1);
+    }
+
+    #[test]
+    fn test_run_with_clock() {
+        let experiment = Experiment {
+            time_budget_secs: Some(2.0),
+            ..experiment()
+        };
+        let mut ticks = 0;
+
+        let result = experiment.run_with_clock(
+            1,
+            || {
+                ticks += 1;
+                Duration::from_secs(ticks)
+            },
+            |_, _, _| {},
+        );
+
+        assert_eq!(result.stop_reason, Some(StopReason::TimeBudget));
+        assert!(!result.reached_target);
+        assert_eq!(result.generations(), 1);
+        assert_eq!(result.elapsed_secs, 3.0);
+
+        // Results saved without `reached_target` still load
+        let mut json = serde_json::to_value(&result).unwrap();
+        json.as_object_mut().unwrap().remove("reached_target");
+        let loaded: RunResult = serde_json::from_value(json).unwrap();
+        assert!(!loaded.reached_target);
+    }
+
+    #[test]
+    fn test_stop_reason() {
+        let experiment = Experiment {
+            generations: 10,
+            patience: Some(2),
+            max_evaluations: Some(24),
+            ..experiment()
+        };
+        let elapsed = Duration::ZERO;
+        let mut result = RunResult::new(0);
+        assert_eq!(experiment.stop_reason(&result, elapsed), None);
+
+        result.record_generation(1.0, 0.5, 4);
+        result.record_generation(2.0, 0.5, 4);
+        result.record_generation(2.0, 0.5, 4);
+        assert_eq!(result.best_generation, 2);
+        assert_eq!(experiment.stop_reason(&result, elapsed), None);
+        result.record_generation(1.5, 0.5, 4);
+        assert_eq!(
+            experiment.stop_reason(&result, elapsed),
+            Some(StopReason::Stagnation)
+        );
+
+        result.record_generation(3.0, 0.5, 8);
+        assert_eq!(
+            experiment.stop_reason(&result, elapsed),
+            Some(StopReason::EvaluationBudget)
+        );
+
+        let experiment = Experiment {
+            time_budget_secs: Some(1.0),
+            ..experiment
+        };
+        assert_eq!(
+            experiment.stop_reason(&RunResult::new(0), Duration::from_secs(1)),
+            Some(StopReason::TimeBudget)
+        );
+    }
+}
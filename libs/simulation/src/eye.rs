@@ -1,84 +1,315 @@
-use std::f64::consts::PI;
-
+use lib_neural_net as nn;
 use nalgebra as na;
+use rand::Rng;
+use rand_distr::StandardNormal;
+use serde::{Deserialize, Serialize};
+use vroom_rand::RngCore;
 
+use crate::buffer::Buffer;
+use crate::config::ValidationError;
 use crate::food::Food;
+use crate::math::consts::PI;
+use crate::math::{self, Real};
+use crate::sensor::{Pose, Sensor};
+use crate::world::World;
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Eye {
-    pub(crate) fov_range: f64,
-    pub(crate) fov_angle: f64,
+    pub(crate) fov_range: Real,
+    pub(crate) fov_angle: Real,
     pub(crate) receptors: usize,
+    /// Standard deviation of the Gaussian noise added to each detection's
+    /// normalized distance.
+    pub(crate) noise: f64,
+    /// Probability that a piece of food in view goes undetected.
+    pub(crate) dropout: f64,
+    /// How far an animal can move, and turn in radians, before its vision
+    /// is recomputed. While no food changes, smaller moves reuse the
+    /// previous readings. Zero recomputes every step.
+    pub(crate) refresh_distance: Real,
+    pub(crate) refresh_angle: Real,
+    /// Follow the food receptors with as many receptors that see other
+    /// animals instead.
+    #[serde(default)]
+    pub(crate) animal_receptors: bool,
 }
 
 impl Eye {
-    pub fn new(fov_range: f64, fov_angle: f64, receptors: usize) -> Self {
+    /// Fails unless `fov_range` is positive, `fov_angle` is in (0, 2π] and
+    /// there is at least one receptor, the same limits `Config::validate`
+    /// puts on the `eye` section.
+    pub fn new(
+        fov_range: Real,
+        fov_angle: Real,
+        receptors: usize,
+    ) -> Result<Self, ValidationError> {
+        if !(fov_range.is_finite() && fov_range > 0.0) {
+            return Err(ValidationError {
+                field: "fov_range",
+                message: format!("must be positive, got {fov_range}"),
+            });
+        }
+        if !(fov_angle > 0.0 && fov_angle <= 2.0 * PI) {
+            return Err(ValidationError {
+                field: "fov_angle",
+                message: format!("must be in (0, 2π], got {fov_angle}"),
+            });
+        }
+        if receptors == 0 {
+            return Err(ValidationError {
+                field: "receptors",
+                message: "must be at least 1".to_string(),
+            });
+        }
+
+        Ok(Self::new_unchecked(fov_range, fov_angle, receptors))
+    }
+
+    /// For configs that haven't been validated yet, which still need the
+    /// eye to work out their brain topology.
+    pub(crate) fn new_unchecked(fov_range: Real, fov_angle: Real, receptors: usize) -> Self {
         Self {
             fov_range,
             fov_angle,
             receptors,
+            noise: 0.0,
+            dropout: 0.0,
+            refresh_distance: 0.0,
+            refresh_angle: 0.0,
+            animal_receptors: false,
         }
     }
 
-    pub fn default() -> Self {
+    pub fn with_refresh(self, refresh_distance: Real, refresh_angle: Real) -> Self {
         Self {
-            fov_range: 0.5,
-            fov_angle: PI / 2.0,
-            receptors: 10,
+            refresh_distance,
+            refresh_angle,
+            ..self
         }
     }
 
+    pub fn with_noise(self, noise: f64, dropout: f64) -> Self {
+        Self {
+            noise,
+            dropout,
+            ..self
+        }
+    }
+
+    pub fn with_animal_receptors(self, animal_receptors: bool) -> Self {
+        Self {
+            animal_receptors,
+            ..self
+        }
+    }
+
+    pub fn fov_range(&self) -> Real {
+        self.fov_range
+    }
+
+    pub fn fov_angle(&self) -> Real {
+        self.fov_angle
+    }
+
+    pub fn receptors(&self) -> usize {
+        self.receptors
+    }
+
+    pub fn noise(&self) -> f64 {
+        self.noise
+    }
+
+    pub fn dropout(&self) -> f64 {
+        self.dropout
+    }
+
+    pub fn refresh_distance(&self) -> Real {
+        self.refresh_distance
+    }
+
+    pub fn refresh_angle(&self) -> Real {
+        self.refresh_angle
+    }
+
+    pub fn animal_receptors(&self) -> bool {
+        self.animal_receptors
+    }
+
+    /// Distance to the nearest food seen by each receptor, normalized by
+    /// `fov_range`, or 2.0 for receptors that see nothing. `rng` is only
+    /// used when the eye has noise or dropout. Food at a non-finite distance,
+    /// e.g. with NaN coordinates, isn't seen.
     pub fn process_vision(
         &self,
-        position: na::Point2<f64>,
-        rotation: na::Rotation2<f64>,
+        rng: &mut dyn RngCore,
+        position: na::Point2<Real>,
+        rotation: na::Rotation2<Real>,
+        food: &[Food],
+    ) -> Vec<Real> {
+        let mut receptors = Buffer::new();
+        self.process_vision_into(rng, position, rotation, food, &mut receptors);
+        receptors.to_vec()
+    }
+
+    /// Like `process_vision`, but writes into `receptors`, reusing its
+    /// allocation.
+    pub fn process_vision_into(
+        &self,
+        rng: &mut dyn RngCore,
+        position: na::Point2<Real>,
+        rotation: na::Rotation2<Real>,
         food: &[Food],
-    ) -> Vec<f64> {
-        let angle_per_receptor = self.fov_angle / self.receptors as f64;
-        let mut receptors = vec![2.0; self.receptors];
-
-        for f in food {
-            let displacement = f.position - position;
-            let dist = displacement.norm();
-            if dist > self.fov_range {
+        receptors: &mut Buffer,
+    ) {
+        receptors.clear();
+        receptors.resize(self.receptors, 2.0);
+        let targets = food.iter().map(|f| f.position);
+        self.detect(rng, position, rotation, targets, receptors);
+    }
+
+    /// Lowers each receptor's reading to the normalized distance of the
+    /// nearest target it sees.
+    fn detect(
+        &self,
+        rng: &mut dyn RngCore,
+        position: na::Point2<Real>,
+        rotation: na::Rotation2<Real>,
+        targets: impl Iterator<Item = na::Point2<Real>>,
+        receptors: &mut [Real],
+    ) {
+        let angle_per_receptor = self.fov_angle / self.receptors as Real;
+        for target in targets {
+            let displacement = target - position;
+            let dist = math::norm(&displacement);
+            if dist.is_nan() || dist > self.fov_range {
                 continue;
             }
 
-            let angle = na::Rotation2::rotation_between(&na::Vector2::x(), &displacement).angle();
-            let angle = na::wrap(angle - rotation.angle(), -PI, PI);
+            let angle = math::heading(&displacement);
+            let angle = na::wrap(angle - math::angle(&rotation), -PI, PI);
             let angle = angle + self.fov_angle / 2.0;
-            if angle < 0.0 || angle > self.fov_angle {
+            if !(0.0..=self.fov_angle).contains(&angle) {
                 continue;
             }
 
+            if self.dropout > 0.0 && rng.gen_bool(self.dropout) {
+                continue;
+            }
+            let mut dist = dist / self.fov_range;
+            if self.noise > 0.0 {
+                let noise: f64 = rng.sample(StandardNormal);
+                dist = (dist + math::real(noise * self.noise)).clamp(0.0, 1.0);
+            }
+
             let receptor_idx =
                 std::cmp::min((angle / angle_per_receptor) as usize, self.receptors - 1);
-            receptors[receptor_idx] = f64::min(receptors[receptor_idx], dist / self.fov_range);
+            receptors[receptor_idx] = Real::min(receptors[receptor_idx], dist);
+        }
+    }
+}
+
+/// With animal receptors, the food receptors' readings are followed by
+/// the animal receptors', which read like them. Noise and dropout apply to
+/// both.
+impl Sensor for Eye {
+    fn len(&self) -> usize {
+        if self.animal_receptors {
+            2 * self.receptors
+        } else {
+            self.receptors
+        }
+    }
+
+    /// Not with animal receptors, since other animals move on their own.
+    fn cacheable(&self) -> bool {
+        !self.animal_receptors
+    }
+
+    fn sense_into(&self, rng: &mut dyn RngCore, pose: Pose, world: &World, readings: &mut Buffer) {
+        let food = world.food_for(pose.animal);
+        self.process_vision_into(rng, pose.position, pose.rotation, food, readings);
+        if !self.animal_receptors {
+            return;
         }
 
-        receptors
+        readings.resize(2 * self.receptors, 2.0);
+        let others = world
+            .animals
+            .iter()
+            .enumerate()
+            .filter(|&(idx, _)| Some(idx) != pose.animal)
+            .map(|(_, animal)| animal.position);
+        self.detect(
+            rng,
+            pose.position,
+            pose.rotation,
+            others,
+            &mut readings[self.receptors..],
+        );
+    }
+
+    /// Animal receptors sit behind the food receptors.
+    fn positions(&self) -> Vec<[f64; 2]> {
+        let mut positions = nn::Substrate::row(self.receptors, 0.0);
+        if self.animal_receptors {
+            positions.extend(nn::Substrate::row(self.receptors, 0.5));
+        }
+        positions
+    }
+
+    /// Receptors swap sides within each bank.
+    fn mirror(&self) -> nn::Mirror {
+        let mirror = nn::Mirror::reverse(self.receptors);
+        if self.animal_receptors {
+            mirror.chain(nn::Mirror::reverse(self.receptors))
+        } else {
+            mirror
+        }
+    }
+}
+
+impl Default for Eye {
+    fn default() -> Self {
+        Self {
+            fov_range: 0.5,
+            fov_angle: PI / 2.0,
+            receptors: 10,
+            noise: 0.0,
+            dropout: 0.0,
+            refresh_distance: 0.0,
+            refresh_angle: 0.0,
+            animal_receptors: false,
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
 
     struct TestCase {
-        fov_range: f64,
-        fov_angle: f64,
+        fov_range: Real,
+        fov_angle: Real,
         receptors: usize,
-        x: f64,
-        y: f64,
-        rotation: f64,
+        x: Real,
+        y: Real,
+        rotation: Real,
         food: Vec<Food>,
         expected: &'static str,
     }
 
     impl TestCase {
         fn run(&self) {
-            let eye = Eye::new(self.fov_range, self.fov_angle, self.receptors);
+            // Some cases use parameters `Eye::new` rejects, to pin down the
+            // vision math at the edges.
+            let eye = Eye::new_unchecked(self.fov_range, self.fov_angle, self.receptors);
 
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
             let actual = eye.process_vision(
+                &mut rng,
                 na::Point2::new(self.x, self.y),
                 na::Rotation2::new(self.rotation),
                 &self.food,
@@ -103,6 +334,124 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_noise_and_dropout() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let food = vec![Food::new(na::Point2::new(0.2, 0.5))];
+        let position = na::Point2::new(0.0, 0.5);
+        let rotation = na::Rotation2::new(0.0);
+
+        let blind = Eye::new(0.5, PI / 2.0, 1).unwrap().with_noise(0.0, 1.0);
+        assert_eq!(
+            blind.process_vision(&mut rng, position, rotation, &food),
+            [2.0]
+        );
+
+        let noisy = Eye::new(0.5, PI / 2.0, 1).unwrap().with_noise(0.1, 0.0);
+        let readings: Vec<Real> = (0..100)
+            .map(|_| noisy.process_vision(&mut rng, position, rotation, &food)[0])
+            .collect();
+        assert!(readings.iter().all(|dist| (0.0..=1.0).contains(dist)));
+        assert!(readings.iter().any(|&dist| dist != readings[0]));
+        let mean = readings.iter().sum::<Real>() / readings.len() as Real;
+        approx::assert_abs_diff_eq!(mean, 0.4, epsilon = 0.05);
+    }
+
+    #[test]
+    fn test_animal_receptors() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let config = crate::config::Config {
+            num_animals: 3,
+            num_food: 0,
+            ..Default::default()
+        };
+        let mut world = World::random(&mut rng, &config);
+        world.food = vec![Food::new(na::Point2::new(0.7, 0.5))];
+        let positions = [(0.5, 0.5), (0.5, 0.7), (0.5, 0.4)];
+        for (animal, (x, y)) in world.animals.iter_mut().zip(positions) {
+            animal.position = na::Point2::new(x, y);
+        }
+        let eye = Eye::new(0.5, PI, 2).unwrap().with_animal_receptors(true);
+        let pose = Pose {
+            position: world.animals[0].position,
+            rotation: na::Rotation2::new(0.0),
+            animal: Some(0),
+        };
+
+        let readings = eye.sense(&mut rng, pose, &world);
+
+        assert_eq!(eye.len(), 4);
+        let expected = [2.0, 0.4, 0.2, 0.4];
+        for (actual, expected) in readings.iter().zip(expected) {
+            approx::assert_abs_diff_eq!(*actual, expected, epsilon = 1e-6);
+        }
+        let readings: Vec<f64> = readings.into_iter().map(math::wide).collect();
+        let mirrored = [readings[1], readings[0], readings[3], readings[2]];
+        assert_eq!(eye.mirror().apply(&readings), mirrored);
+    }
+
+    #[test]
+    fn test_new_validates() {
+        let field = |result: Result<Eye, ValidationError>| result.err().map(|err| err.field);
+
+        assert_eq!(field(Eye::new(0.5, PI, 3)), None);
+        assert_eq!(field(Eye::new(0.5, 2.0 * PI, 3)), None);
+        assert_eq!(field(Eye::new(0.0, PI, 3)), Some("fov_range"));
+        assert_eq!(field(Eye::new(Real::NAN, PI, 3)), Some("fov_range"));
+        assert_eq!(field(Eye::new(Real::INFINITY, PI, 3)), Some("fov_range"));
+        assert_eq!(field(Eye::new(0.5, -1.0, 3)), Some("fov_angle"));
+        assert_eq!(field(Eye::new(0.5, 7.0, 3)), Some("fov_angle"));
+        assert_eq!(field(Eye::new(0.5, Real::NAN, 3)), Some("fov_angle"));
+        assert_eq!(field(Eye::new(0.5, PI, 0)), Some("receptors"));
+    }
+
+    fn any_real() -> impl Strategy<Value = Real> {
+        prop_oneof![
+            4 => -2.0..2.0,
+            1 => proptest::num::f64::ANY,
+        ]
+        .prop_map(|value: f64| value as Real)
+    }
+
+    proptest! {
+        /// Any eye `Eye::new` accepts, looking from anywhere (NaN and
+        /// infinite coordinates included), gives one reading per receptor,
+        /// each in [0, 2].
+        #[test]
+        fn test_process_vision_readings_in_range(
+            fov_range in any_real(),
+            fov_angle in any_real(),
+            receptors in 0..16_usize,
+            noise in 0.0..1.0,
+            dropout in 0.0..=1.0,
+            x in any_real(),
+            y in any_real(),
+            rotation in any_real(),
+            food in proptest::collection::vec((any_real(), any_real()), 0..8),
+            seed in any::<u64>(),
+        ) {
+            let Ok(eye) = Eye::new(fov_range, fov_angle, receptors) else {
+                return Ok(());
+            };
+            let eye = eye.with_noise(noise, dropout);
+            let food: Vec<Food> = food
+                .into_iter()
+                .map(|(x, y)| Food::new(na::Point2::new(x, y)))
+                .collect();
+
+            let mut rng = vroom_rand::seeded(seed);
+            let readings = eye.process_vision(
+                &mut rng,
+                na::Point2::new(x, y),
+                na::Rotation2::new(rotation),
+                &food,
+            );
+
+            prop_assert_eq!(readings.len(), receptors);
+            prop_assert!(readings.iter().all(|dist| (0.0..=2.0).contains(dist)));
+        }
+    }
+
     mod test_fov_ranges {
         use super::*;
 
@@ -195,8 +544,12 @@ mod tests {
                 (PI / 180.0, "     o    "),
                 (PI / 2.0, ".    o   ."),
                 (PI, "o .  o . o"),
-                (2.0 * PI, "  o. o.o o"),
             ];
+            // The food directly behind sits on the wrap-around boundary,
+            // which f32 rounding puts on the other side.
+            let cases = cases
+                .into_iter()
+                .chain((!cfg!(feature = "f32-world")).then_some((2.0 * PI, "  o. o.o o")));
             for (fov_angle, expected) in cases {
                 let food = vec![
                     Food::new(na::Point2::new(1.0, 0.5)),
@@ -292,7 +645,7 @@ mod tests {
         #[test]
         fn test() {
             let cases = [
-                (((0.5, 0.0), " ")),
+                ((0.5, 0.0), " "),
                 ((0.5, 0.5), "O"),
                 ((0.2, 0.5), "."),
                 ((0.5, 1.0), " "),
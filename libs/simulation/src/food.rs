@@ -1,26 +1,57 @@
 use nalgebra as na;
-use rand::{Rng, RngCore};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use vroom_rand::RngCore;
 
+use crate::map::Zone;
+use crate::math::Real;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Food {
-    pub(crate) position: na::Point2<f64>,
+    pub(crate) position: na::Point2<Real>,
+    /// Animals eat the food once they overlap it.
+    #[serde(default = "Food::default_radius")]
+    pub(crate) radius: Real,
 }
 
 impl Food {
-    pub fn new(position: na::Point2<f64>) -> Self {
-        Self { position }
-    }
+    pub const DEFAULT_RADIUS: Real = 0.005;
 
-    pub fn new_random(rng: &mut dyn RngCore) -> Self {
+    pub fn new(position: na::Point2<Real>) -> Self {
         Self {
-            position: rng.gen(),
+            position,
+            radius: Self::DEFAULT_RADIUS,
         }
     }
 
-    pub fn randomize_position(&mut self, rng: &mut dyn RngCore) {
-        self.position = rng.gen();
+    pub fn new_random(rng: &mut dyn RngCore) -> Self {
+        Self::new(rng.gen())
+    }
+
+    pub fn with_radius(self, radius: Real) -> Self {
+        Self { radius, ..self }
     }
 
-    pub fn position(&self) -> na::Point2<f64> {
+    fn default_radius() -> Real {
+        Self::DEFAULT_RADIUS
+    }
+
+    /// Moves the food to a random position within `zones`, or anywhere in a
+    /// world of `size` if there are none.
+    pub fn randomize_position(
+        &mut self,
+        rng: &mut dyn RngCore,
+        zones: &[Zone],
+        size: na::Vector2<Real>,
+    ) {
+        self.position = Zone::random_position(rng, zones, size);
+    }
+
+    pub fn position(&self) -> na::Point2<Real> {
         self.position
     }
+
+    pub fn radius(&self) -> Real {
+        self.radius
+    }
 }
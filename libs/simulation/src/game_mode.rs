@@ -0,0 +1,105 @@
+use lib_neural_net as nn;
+use nalgebra as na;
+use serde::{Deserialize, Serialize};
+
+use crate::animal::Animal;
+use crate::math::{self, consts::PI, Real};
+use crate::nest::Nest;
+use crate::sensor::Sensor;
+
+/// Objective the animals are evolved for.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameMode {
+    /// Animals score by touching food.
+    #[default]
+    Eat,
+    /// Animals pick up food, which slows them down, and score by carrying it
+    /// back to the nest.
+    Forage,
+}
+
+impl GameMode {
+    /// Speed multiplier applied while an animal is carrying food.
+    pub const CARRY_SPEED_FACTOR: f64 = 0.5;
+
+    /// Number of brain inputs: the sensor's readings for each of `frames`
+    /// steps plus any mode-specific inputs.
+    pub fn brain_inputs(&self, sensor: &dyn Sensor, frames: usize) -> usize {
+        let readings = sensor.len() * frames;
+        match self {
+            Self::Eat => readings,
+            // carrying flag, distance to nest, angle to nest
+            Self::Forage => readings + 3,
+        }
+    }
+
+    /// Where each brain input sits for `BrainEncoding::Cppn`: every frame of
+    /// sensor readings per `Sensor::positions`, older frames further back
+    /// along y, then the extra inputs in a row of their own.
+    pub fn input_positions(&self, sensor: &dyn Sensor, frames: usize) -> Vec<[f64; 2]> {
+        let frame = sensor.positions();
+        let mut positions: Vec<[f64; 2]> = (0..frames)
+            .flat_map(|age| frame.iter().map(move |&[x, y]| [x, y - age as f64]))
+            .collect();
+        if let Self::Forage = self {
+            positions.extend(nn::Substrate::row(3, 1.0));
+        }
+        positions
+    }
+
+    /// How the brain inputs map onto each other when the animal's view is
+    /// mirrored left to right: every frame of sensor readings per
+    /// `Sensor::mirror`, and the angle to the nest flips sign.
+    pub fn input_mirror(&self, sensor: &dyn Sensor, frames: usize) -> nn::Mirror {
+        let frame = sensor.mirror();
+        let readings = (1..frames).fold(frame.clone(), |readings, _| readings.chain(frame.clone()));
+        match self {
+            Self::Eat => readings,
+            Self::Forage => readings.chain(nn::Mirror::new(vec![(0, 1.0), (1, 1.0), (2, -1.0)])),
+        }
+    }
+
+    /// Appends the mode-specific brain inputs that follow the sensor
+    /// readings.
+    pub(crate) fn push_extra_inputs(
+        &self,
+        animal: &Animal,
+        nest: Option<&Nest>,
+        inputs: &mut impl Extend<Real>,
+    ) {
+        let carrying = if animal.carrying { 1.0 } else { 0.0 };
+        match (self, nest) {
+            (Self::Forage, Some(nest)) => {
+                let displacement = nest.position - animal.position;
+                let dist = math::norm(&displacement);
+                let angle = if dist > 0.0 {
+                    let angle = math::heading(&displacement);
+                    na::wrap(angle - math::angle(&animal.rotation), -PI, PI)
+                } else {
+                    0.0
+                };
+                inputs.extend([carrying, dist, angle / PI]);
+            }
+            (Self::Forage, None) => inputs.extend([carrying, 0.0, 0.0]),
+            (Self::Eat, _) => {}
+        }
+    }
+
+    /// The animal's fitness averaged over its finished rollouts and the
+    /// current one.
+    pub fn fitness(&self, animal: &Animal) -> f64 {
+        let rollouts = &animal.rollout_fitness;
+        (rollouts.iter().sum::<f64>() + self.rollout_fitness(animal)) / (rollouts.len() + 1) as f64
+    }
+
+    /// The mode's score in the current rollout, decayed by recency if
+    /// configured, plus any shaped reward, floored at zero since selection
+    /// needs non-negative fitness.
+    pub(crate) fn rollout_fitness(&self, animal: &Animal) -> f64 {
+        let score = animal.recent_score.unwrap_or(match self {
+            Self::Eat => animal.consumed as f64,
+            Self::Forage => animal.deposited as f64,
+        });
+        (score + animal.shaped_reward).max(0.0)
+    }
+}
@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+/// When a generation ends and the population evolves.
+///
+/// The policy is checked at the end of each step. Once it holds, the
+/// population evolves right after that step, as part of the same call to
+/// `Simulation::step`, so a generation that ends after `n` steps takes `n`
+/// calls. With several `Config::rollouts`, the policy ends each rollout
+/// instead, and the population evolves after the last.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GenerationEnd {
+    /// After `Config::generation_steps` steps.
+    #[default]
+    Steps,
+    /// Once the animals have eaten as many meals as there are pieces of
+    /// food, or after `Config::generation_steps` steps. With
+    /// `Config::private_food`, once every animal has eaten as many meals as
+    /// its own copy has pieces.
+    FoodEaten,
+    /// Once the best fitness hasn't improved for `Config::plateau_steps`
+    /// steps, or after `Config::generation_steps` steps.
+    FitnessPlateau,
+    /// Only after `Simulation::force_evolve`.
+    Manual,
+}
@@ -1,10 +1,31 @@
-use lib_reinforcement_learning::genetic_algorithm::Individual;
+use lib_reinforcement_learning::genetic_algorithm::{GeneStatistics, Individual};
+use serde::{Deserialize, Serialize};
 
+use crate::behavior::BehaviorStatistics;
+use crate::heatmap::Heatmap;
+
+#[derive(Serialize, Deserialize)]
 pub struct GenerationStatistics {
     pub max_fitness: f64,
     pub min_fitness: f64,
     pub mean_fitness: f64,
     pub std_fitness: f64,
+    /// Mean Euclidean distance between every pair of chromosomes.
+    pub mean_pairwise_distance: f64,
+    /// Variance of each gene across the population, averaged over genes.
+    pub mean_gene_variance: f64,
+    /// Mean and standard deviation of each gene position, empty once the
+    /// generation is older than `Config::genome_history`.
+    pub genes: GeneStatistics,
+    pub behavior: BehaviorStatistics,
+    /// Where food was eaten, on a grid of `Config::heatmap_cells` cells
+    /// along each side.
+    #[serde(default)]
+    pub meal_locations: Heatmap,
+    /// Genome of the fittest animal, e.g. to compare generations with a
+    /// `Tournament`.
+    #[serde(default)]
+    pub champion: Vec<f64>,
 }
 
 impl GenerationStatistics {
@@ -14,22 +35,135 @@ impl GenerationStatistics {
         let mut max_fitness: f64 = 0.0;
         let mut min_fitness: f64 = 0.0;
         let mut sum_fitness: f64 = 0.0;
-        let mut sum_sq_fitness: f64 = 0.0;
         for individual in population {
             max_fitness = max_fitness.max(individual.fitness());
             min_fitness = min_fitness.min(individual.fitness());
             sum_fitness += individual.fitness();
-            sum_sq_fitness += individual.fitness().powi(2);
         }
 
         let mean_fitness = sum_fitness / population.len() as f64;
-        let var_fitness = (sum_sq_fitness / population.len() as f64) - mean_fitness.powi(2);
+        // From deviations from the mean, which unlike the mean of squares
+        // minus the squared mean can't round to a negative variance
+        let var_fitness = population
+            .iter()
+            .map(|individual| (individual.fitness() - mean_fitness).powi(2))
+            .sum::<f64>()
+            / population.len() as f64;
+        let genes = GeneStatistics::from_population(population);
+        let champion = population
+            .iter()
+            .max_by(|a, b| a.fitness().total_cmp(&b.fitness()))
+            .map(|individual| individual.as_chromosome().iter().copied().collect())
+            .unwrap_or_default();
 
         GenerationStatistics {
             max_fitness,
             min_fitness,
             mean_fitness,
             std_fitness: var_fitness.sqrt(),
+            mean_pairwise_distance: mean_pairwise_distance(population),
+            mean_gene_variance: genes.mean_variance(),
+            genes,
+            behavior: BehaviorStatistics::default(),
+            meal_locations: Heatmap::default(),
+            champion,
+        }
+    }
+
+    pub fn with_behavior(self, behavior: BehaviorStatistics) -> Self {
+        Self { behavior, ..self }
+    }
+
+    pub fn with_meal_locations(self, meal_locations: Heatmap) -> Self {
+        Self {
+            meal_locations,
+            ..self
+        }
+    }
+
+    /// Drops the statistics that grow with the genome, once the generation
+    /// is older than `Config::genome_history`.
+    pub(crate) fn forget_genomes(&mut self) {
+        self.genes = GeneStatistics::default();
+    }
+}
+
+fn mean_pairwise_distance<I: Individual>(population: &[I]) -> f64 {
+    let mut sum_dist = 0.0;
+    let mut pairs = 0;
+    for (idx, a) in population.iter().enumerate() {
+        for b in &population[idx + 1..] {
+            sum_dist += a
+                .as_chromosome()
+                .iter()
+                .zip(b.as_chromosome().iter())
+                .map(|(x, y)| (x - y).powi(2))
+                .sum::<f64>()
+                .sqrt();
+            pairs += 1;
         }
     }
+
+    if pairs == 0 {
+        0.0
+    } else {
+        sum_dist / pairs as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::animal::AnimalIndividual;
+    use lib_reinforcement_learning::genetic_algorithm::Chromosome;
+
+    fn individual(genes: Vec<f64>, fitness: f64) -> AnimalIndividual {
+        AnimalIndividual {
+            chromosome: Chromosome::new(genes),
+            fitness,
+        }
+    }
+
+    #[test]
+    fn test_from_population() {
+        let population = vec![
+            individual(vec![0.0, 0.0], 1.0),
+            individual(vec![3.0, 4.0], 2.0),
+            individual(vec![0.0, 4.0], 6.0),
+        ];
+
+        let stats = GenerationStatistics::from_population(&population);
+
+        approx::assert_relative_eq!(stats.max_fitness, 6.0);
+        approx::assert_relative_eq!(stats.mean_fitness, 3.0);
+        // Distances: 5, 4, 3
+        approx::assert_relative_eq!(stats.mean_pairwise_distance, 4.0);
+        assert_eq!(stats.champion, [0.0, 4.0]);
+        // Gene variances: 2.0, 32 / 9
+        approx::assert_relative_eq!(stats.mean_gene_variance, (2.0 + 32.0 / 9.0) / 2.0);
+        approx::assert_relative_eq!(stats.genes.mean[1], 8.0 / 3.0);
+        approx::assert_relative_eq!(stats.genes.std[0], 2.0_f64.sqrt());
+    }
+
+    #[test]
+    fn test_identical_population_has_no_diversity() {
+        let population = vec![
+            individual(vec![1.0, 2.0], 1.0),
+            individual(vec![1.0, 2.0], 1.0),
+        ];
+
+        let stats = GenerationStatistics::from_population(&population);
+
+        approx::assert_relative_eq!(stats.mean_pairwise_distance, 0.0);
+        approx::assert_relative_eq!(stats.mean_gene_variance, 0.0);
+    }
+
+    #[test]
+    fn test_fitness_far_from_zero() {
+        let population = vec![individual(vec![0.0], 1e9), individual(vec![0.0], 1e9 + 1.0)];
+
+        let stats = GenerationStatistics::from_population(&population);
+
+        assert_eq!(stats.std_fitness, 0.5);
+    }
 }
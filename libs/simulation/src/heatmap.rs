@@ -0,0 +1,97 @@
+use nalgebra as na;
+use serde::{Deserialize, Serialize};
+
+use crate::math::{self, Real};
+
+/// Coarse grid over the world counting animal-steps spent in each cell,
+/// to reveal where a population spends its time, e.g. hugging the edges
+/// it wraps around, which single trajectories hide.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Heatmap {
+    cols: usize,
+    rows: usize,
+    /// Row after row, from the top-left corner.
+    counts: Vec<u32>,
+}
+
+impl Heatmap {
+    pub fn new(cols: usize, rows: usize) -> Self {
+        Self {
+            cols,
+            rows,
+            counts: vec![0; cols * rows],
+        }
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Counts of every cell, row after row.
+    pub fn counts(&self) -> &[u32] {
+        &self.counts
+    }
+
+    pub fn count(&self, col: usize, row: usize) -> u32 {
+        self.counts[row * self.cols + col]
+    }
+
+    /// Sum of all counts.
+    pub fn total(&self) -> u64 {
+        self.counts.iter().map(|&count| u64::from(count)).sum()
+    }
+
+    /// Fraction of all counts in each cell, row after row; zeros while
+    /// empty.
+    pub fn fractions(&self) -> Vec<f64> {
+        let total = self.total().max(1) as f64;
+        self.counts
+            .iter()
+            .map(|&count| f64::from(count) / total)
+            .collect()
+    }
+
+    /// Counts a step spent at `position` in a world of `size`.
+    pub fn record(&mut self, position: na::Point2<Real>, size: na::Vector2<Real>) {
+        if self.counts.is_empty() {
+            return;
+        }
+        let cell = |coord: Real, extent: Real, cells: usize| {
+            let cell = math::wide(coord / extent) * cells as f64;
+            (cell.max(0.0) as usize).min(cells - 1)
+        };
+        let col = cell(position.x, size.x, self.cols);
+        let row = cell(position.y, size.y, self.rows);
+        self.counts[row * self.cols + col] += 1;
+    }
+
+    pub fn reset(&mut self) {
+        self.counts.fill(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record() {
+        let mut heatmap = Heatmap::new(4, 2);
+        let size = na::Vector2::new(2.0, 1.0);
+        heatmap.record(na::Point2::new(0.1, 0.1), size);
+        heatmap.record(na::Point2::new(1.9, 0.9), size);
+        heatmap.record(na::Point2::new(2.0, 1.0), size);
+
+        assert_eq!(heatmap.count(0, 0), 1);
+        assert_eq!(heatmap.count(3, 1), 2);
+        assert_eq!(heatmap.total(), 3);
+        assert_eq!(heatmap.fractions()[7], 2.0 / 3.0);
+
+        heatmap.reset();
+        assert_eq!(heatmap.total(), 0);
+    }
+}
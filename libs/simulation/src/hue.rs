@@ -0,0 +1,61 @@
+/// Derives a hue in degrees `[0, 360)` from a genome.
+///
+/// The genes are projected onto two fixed pseudo-random directions and the
+/// hue is the angle of the projected point, so similar genomes get similar
+/// hues and the mapping stays stable across generations.
+pub fn genome_hue(genes: impl IntoIterator<Item = f64>) -> f64 {
+    let (x, y) = genes
+        .into_iter()
+        .enumerate()
+        .fold((0.0, 0.0), |(x, y), (idx, gene)| {
+            (x + gene * projection(idx, 0), y + gene * projection(idx, 1))
+        });
+
+    if x == 0.0 && y == 0.0 {
+        return 0.0;
+    }
+    crate::math::atan2(y, x).to_degrees().rem_euclid(360.0)
+}
+
+/// Deterministic coefficient in `[-1, 1]` for gene `idx` along `axis`
+/// (SplitMix64 finalizer).
+fn projection(idx: usize, axis: u64) -> f64 {
+    let mut z = (idx as u64 * 2 + axis).wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    (z as f64 / u64::MAX as f64) * 2.0 - 1.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hue_distance(a: f64, b: f64) -> f64 {
+        let diff = (a - b).abs();
+        diff.min(360.0 - diff)
+    }
+
+    #[test]
+    fn test_hue_range() {
+        for genes in [vec![1.0; 20], vec![-3.0; 7], vec![0.5, -0.25, 2.0]] {
+            let hue = genome_hue(genes);
+            assert!((0.0..360.0).contains(&hue));
+        }
+        approx::assert_relative_eq!(genome_hue(vec![0.0; 5]), 0.0);
+    }
+
+    #[test]
+    fn test_similar_genomes_have_similar_hues() {
+        let genes: Vec<f64> = (0..50).map(|idx| (idx as f64).sin()).collect();
+        let mut nudged = genes.clone();
+        nudged[3] += 0.01;
+        let different: Vec<f64> = genes.iter().map(|gene| -gene).collect();
+
+        assert!(hue_distance(genome_hue(genes.clone()), genome_hue(nudged)) < 1.0);
+        approx::assert_relative_eq!(
+            hue_distance(genome_hue(genes), genome_hue(different)),
+            180.0
+        );
+    }
+}
@@ -1,12 +1,66 @@
+pub use crate::action::{
+    AccelerationControl, Action, ActionDecoder, Control, DiscreteControl, VelocityControl,
+};
 pub use crate::animal::Animal;
+pub use crate::behavior::{Behavior, BehaviorStatistics};
+pub use crate::buffer::{Buffer, INLINE_CAPACITY};
+pub use crate::config::{
+    ActuatorConfig, BrainConfig, BrainEncoding, Config, ConfigError, CrossoverKind, EyeConfig,
+    FieldKind, FieldSchema, ModuleConfig, MutationConfig, MutationKind, OperatorConfig,
+    PhysicsConfig, RaceConfig, RewardConfig, SelectionKind, ValidationError, MAX_HEATMAP_CELLS,
+};
+pub use crate::entity::{Entity, WorldVisitor};
+pub use crate::experiment::{Experiment, Progress, RunResult, StopReason};
+pub use crate::eye::Eye;
 pub use crate::food::Food;
+pub use crate::game_mode::GameMode;
+pub use crate::generation_end::GenerationEnd;
 pub use crate::generation_statistics::GenerationStatistics;
-pub use crate::simulation::Simulation;
+pub use crate::heatmap::Heatmap;
+pub use crate::map::{Map, MapError, Wall, Zone};
+pub use crate::math::Real;
+pub use crate::nest::Nest;
+pub use crate::recorder::Recorder;
+pub use crate::replay::{Checkpoint, Divergence, Replay};
+pub use crate::reward::{FoodApproach, RewardShaper};
+pub use crate::sensor::{NearestFood, Pose, Sensor, SensorFactory, SensorKind};
+pub use crate::simulation::{Phase, Simulation};
+pub use crate::snapshot::{SnapshotError, SnapshotHeader, CRATE_VERSION, SNAPSHOT_VERSION};
+pub use crate::symmetry::Symmetry;
+pub use crate::tournament::{Standing, Tournament};
 pub use crate::world::World;
+pub use crate::world_diff::{WorldChange, WorldDiff, WorldOutline};
+pub use lib_reinforcement_learning::genetic_algorithm::GeneStatistics;
+pub use vroom_rand;
 
+mod action;
 mod animal;
+mod behavior;
+mod buffer;
+pub mod cli;
+mod config;
+mod entity;
+mod environment;
+mod experiment;
 mod eye;
 mod food;
+mod game_mode;
+mod generation_end;
 mod generation_statistics;
+mod heatmap;
+mod hue;
+mod map;
+mod math;
+mod nest;
+pub mod payload;
+mod recorder;
+mod replay;
+mod reward;
+mod sensor;
 mod simulation;
+mod snapshot;
+mod state_hash;
+mod symmetry;
+mod tournament;
 mod world;
+mod world_diff;
@@ -0,0 +1,308 @@
+use std::fmt;
+
+use nalgebra as na;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use vroom_rand::RngCore;
+
+use crate::config::ValidationError;
+use crate::math::Real;
+use crate::nest::Nest;
+
+const BUNDLED_MAPS: &[(&str, &str)] = &[
+    ("open_field", include_str!("../maps/open_field.json")),
+    ("corridor", include_str!("../maps/corridor.json")),
+    ("maze", include_str!("../maps/maze.json")),
+];
+
+/// World layout description, loaded with `World::from_map`.
+///
+/// Empty zone lists mean the whole world, so an empty map is equivalent to
+/// `World::random`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Map {
+    pub name: String,
+    pub walls: Vec<Wall>,
+    /// Food placed at fixed positions, in addition to randomly placed food.
+    pub food: Vec<na::Point2<Real>>,
+    /// Regions where random food is placed and eaten food respawns.
+    pub food_zones: Vec<Zone>,
+    /// Regions where animals are placed at the start of each generation.
+    pub spawn_zones: Vec<Zone>,
+    pub nest: Option<Nest>,
+}
+
+/// Line segment that animals cannot move through.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Wall {
+    pub start: na::Point2<Real>,
+    pub end: na::Point2<Real>,
+}
+
+/// Axis-aligned rectangular region.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Zone {
+    pub min: na::Point2<Real>,
+    pub max: na::Point2<Real>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MapError {
+    Parse(String),
+    Invalid(Vec<ValidationError>),
+}
+
+impl Map {
+    pub fn from_json_str(json: &str) -> Result<Self, MapError> {
+        let map: Self =
+            serde_json::from_str(json).map_err(|err| MapError::Parse(err.to_string()))?;
+        map.validate()?;
+        Ok(map)
+    }
+
+    pub fn from_toml_str(toml: &str) -> Result<Self, MapError> {
+        let map: Self = toml::from_str(toml).map_err(|err| MapError::Parse(err.to_string()))?;
+        map.validate()?;
+        Ok(map)
+    }
+
+    /// Checks the geometry, reporting all problems at once, so a bad map
+    /// fails to load rather than panicking when the world is built.
+    pub fn validate(&self) -> Result<(), MapError> {
+        let mut errors = Vec::new();
+        let mut check = |ok: bool, field: &'static str, message: String| {
+            if !ok {
+                errors.push(ValidationError { field, message });
+            }
+        };
+
+        for wall in &self.walls {
+            check(
+                is_finite(wall.start) && is_finite(wall.end) && wall.start != wall.end,
+                "walls",
+                format!(
+                    "wall from {} to {} must have a finite length",
+                    wall.start, wall.end
+                ),
+            );
+        }
+        for &food in &self.food {
+            check(
+                is_finite(food),
+                "food",
+                format!("position {food} must be finite"),
+            );
+        }
+        for (zones, field) in [
+            (&self.food_zones, "food_zones"),
+            (&self.spawn_zones, "spawn_zones"),
+        ] {
+            for zone in zones {
+                check(
+                    zone.is_valid(),
+                    field,
+                    format!("zone min {} must be below max {}", zone.min, zone.max),
+                );
+            }
+        }
+        if let Some(nest) = &self.nest {
+            check(
+                is_finite(nest.position) && nest.radius.is_finite() && nest.radius > 0.0,
+                "nest",
+                format!(
+                    "nest at {} must have a positive radius, got {}",
+                    nest.position, nest.radius
+                ),
+            );
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(MapError::Invalid(errors))
+        }
+    }
+
+    /// Names of the maps shipped with the crate, from simplest to hardest.
+    pub fn bundled_names() -> impl Iterator<Item = &'static str> {
+        BUNDLED_MAPS.iter().map(|(name, _)| *name)
+    }
+
+    pub fn bundled(name: &str) -> Option<Self> {
+        BUNDLED_MAPS
+            .iter()
+            .find(|(bundled_name, _)| *bundled_name == name)
+            .map(|(_, json)| Self::from_json_str(json).expect("Invalid bundled map"))
+    }
+}
+
+impl Wall {
+    pub fn new(start: na::Point2<Real>, end: na::Point2<Real>) -> Self {
+        Self { start, end }
+    }
+
+    /// Whether the segment from `from` to `to` crosses this wall.
+    pub fn intersects(&self, from: na::Point2<Real>, to: na::Point2<Real>) -> bool {
+        fn orientation(a: na::Point2<Real>, b: na::Point2<Real>, c: na::Point2<Real>) -> Real {
+            (b - a).perp(&(c - a))
+        }
+
+        let d1 = orientation(self.start, self.end, from);
+        let d2 = orientation(self.start, self.end, to);
+        let d3 = orientation(from, to, self.start);
+        let d4 = orientation(from, to, self.end);
+        d1 * d2 <= 0.0 && d3 * d4 <= 0.0 && (d1 != 0.0 || d2 != 0.0)
+    }
+}
+
+impl Zone {
+    pub fn new(min: na::Point2<Real>, max: na::Point2<Real>) -> Self {
+        Self { min, max }
+    }
+
+    /// Whether the zone is finite and not empty, as `random_position`
+    /// needs.
+    pub fn is_valid(&self) -> bool {
+        is_finite(self.min)
+            && is_finite(self.max)
+            && self.min.x < self.max.x
+            && self.min.y < self.max.y
+    }
+
+    pub fn area(&self) -> Real {
+        (self.max.x - self.min.x) * (self.max.y - self.min.y)
+    }
+
+    pub fn contains(&self, point: na::Point2<Real>) -> bool {
+        (self.min.x..=self.max.x).contains(&point.x) && (self.min.y..=self.max.y).contains(&point.y)
+    }
+
+    /// Picks a uniformly random position across `zones`, or across a whole
+    /// world of `size` if there are none.
+    pub fn random_position(
+        rng: &mut dyn RngCore,
+        zones: &[Zone],
+        size: na::Vector2<Real>,
+    ) -> na::Point2<Real> {
+        if zones.is_empty() {
+            let unit: na::Point2<Real> = rng.gen();
+            return unit.coords.component_mul(&size).into();
+        }
+
+        let total_area: Real = zones.iter().map(Zone::area).sum();
+        let mut target = rng.gen_range(0.0..total_area);
+        let zone = zones
+            .iter()
+            .find(|zone| {
+                target -= zone.area();
+                target < 0.0
+            })
+            .unwrap_or(&zones[zones.len() - 1]);
+        na::Point2::new(
+            rng.gen_range(zone.min.x..=zone.max.x),
+            rng.gen_range(zone.min.y..=zone.max.y),
+        )
+    }
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(err) => write!(f, "failed to parse map: {err}"),
+            Self::Invalid(errors) => {
+                write!(f, "invalid map:")?;
+                for error in errors {
+                    write!(f, "\n  {error}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for MapError {}
+
+fn is_finite(point: na::Point2<Real>) -> bool {
+    point.x.is_finite() && point.y.is_finite()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_bundled_maps_parse() {
+        for name in Map::bundled_names() {
+            let map = Map::bundled(name).unwrap();
+            assert_eq!(map.name, name);
+        }
+        assert!(Map::bundled("missing").is_none());
+    }
+
+    #[test]
+    fn test_from_toml_str() {
+        let map = Map::from_toml_str(
+            r#"
+            name = "pen"
+            food = [[0.5, 0.5]]
+
+            [[walls]]
+            start = [0.2, 0.0]
+            end = [0.2, 1.0]
+
+            [[spawn_zones]]
+            min = [0.0, 0.0]
+            max = [0.2, 1.0]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(map.walls.len(), 1);
+        assert_eq!(map.spawn_zones[0].max, na::Point2::new(0.2, 1.0));
+    }
+
+    #[test]
+    fn test_validation_errors() {
+        let json = r#"{
+            "walls": [{"start": [0.5, 0.5], "end": [0.5, 0.5]}],
+            "food_zones": [{"min": [0.5, 0.0], "max": [0.5, 1.0]}],
+            "nest": {"position": [0.5, 0.5], "radius": 0.0}
+        }"#;
+        let Err(MapError::Invalid(errors)) = Map::from_json_str(json) else {
+            panic!("map should be invalid");
+        };
+        let fields: Vec<_> = errors.iter().map(|error| error.field).collect();
+        assert_eq!(fields, ["walls", "food_zones", "nest"]);
+
+        assert!(matches!(
+            Map::from_json_str("{\"walls\": 1}"),
+            Err(MapError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn test_wall_intersects() {
+        let wall = Wall::new(na::Point2::new(0.5, 0.0), na::Point2::new(0.5, 1.0));
+
+        assert!(wall.intersects(na::Point2::new(0.4, 0.5), na::Point2::new(0.6, 0.5)));
+        assert!(wall.intersects(na::Point2::new(0.4, 0.5), na::Point2::new(0.5, 0.5)));
+        assert!(!wall.intersects(na::Point2::new(0.1, 0.5), na::Point2::new(0.4, 0.5)));
+        assert!(!wall.intersects(na::Point2::new(0.4, 1.1), na::Point2::new(0.6, 1.1)));
+    }
+
+    #[test]
+    fn test_random_position_in_zones() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let zones = [
+            Zone::new(na::Point2::new(0.0, 0.0), na::Point2::new(0.1, 0.1)),
+            Zone::new(na::Point2::new(0.8, 0.8), na::Point2::new(1.0, 1.0)),
+        ];
+
+        for _ in 0..100 {
+            let position = Zone::random_position(&mut rng, &zones, na::Vector2::new(1.0, 1.0));
+            assert!(zones.iter().any(|zone| zone.contains(position)));
+        }
+    }
+}
@@ -0,0 +1,192 @@
+//! Transcendental math that feeds into simulation results.
+//!
+//! By default these defer to nalgebra and the platform's float functions.
+//! With the `strict-determinism` feature they go through `libm`'s software
+//! implementations instead, so native and wasm builds produce bit-identical
+//! trajectories for the same seed.
+//!
+//! World geometry is stored as `Real`, which is `f64` unless the `f32-world`
+//! feature is enabled.
+
+use nalgebra as na;
+use rand::{distributions::Uniform, Rng};
+use vroom_rand::RngCore;
+
+/// Scalar type of positions, rotations and other world geometry.
+#[cfg(not(feature = "f32-world"))]
+pub type Real = f64;
+
+/// Scalar type of positions, rotations and other world geometry.
+#[cfg(feature = "f32-world")]
+pub type Real = f32;
+
+#[cfg(not(feature = "f32-world"))]
+pub use std::f64::consts;
+
+#[cfg(feature = "f32-world")]
+pub use std::f32::consts;
+
+/// Converts a config or brain value to world precision.
+#[allow(clippy::unnecessary_cast)]
+pub fn real(value: f64) -> Real {
+    value as Real
+}
+
+/// Converts a world value to `f64` for brains, fitness and statistics.
+#[allow(clippy::unnecessary_cast)]
+pub fn wide(value: Real) -> f64 {
+    value as f64
+}
+
+/// Rotation by `angle` radians.
+pub fn rotation(angle: Real) -> na::Rotation2<Real> {
+    if cfg!(feature = "strict-determinism") {
+        let (sin, cos) = (sin(angle), cos(angle));
+        na::Rotation2::from_matrix_unchecked(na::Matrix2::new(cos, -sin, sin, cos))
+    } else {
+        na::Rotation2::new(angle)
+    }
+}
+
+/// Uniformly random rotation, consuming the same randomness as
+/// `rng.gen::<na::Rotation2<Real>>()`.
+pub fn random_rotation(rng: &mut dyn RngCore) -> na::Rotation2<Real> {
+    rotation(rng.sample(Uniform::new(0.0, consts::TAU)))
+}
+
+/// Angle of `rotation` in `(-PI, PI]`.
+pub fn angle(rotation: &na::Rotation2<Real>) -> Real {
+    let matrix = rotation.matrix();
+    real(atan2(wide(matrix[(1, 0)]), wide(matrix[(0, 0)])))
+}
+
+/// Angle between the x axis and `vector` in `(-PI, PI]`.
+pub fn heading(vector: &na::Vector2<Real>) -> Real {
+    if cfg!(feature = "strict-determinism") {
+        real(atan2(wide(vector.y), wide(vector.x)))
+    } else {
+        na::Rotation2::rotation_between(&na::Vector2::x(), vector).angle()
+    }
+}
+
+pub fn norm(vector: &na::Vector2<Real>) -> Real {
+    sqrt(vector.x * vector.x + vector.y * vector.y)
+}
+
+pub fn distance(a: &na::Point2<Real>, b: &na::Point2<Real>) -> Real {
+    norm(&(b - a))
+}
+
+/// Distance from `point` to the closest point of the segment from `from`
+/// to `to`.
+pub fn segment_distance(
+    point: na::Point2<Real>,
+    from: na::Point2<Real>,
+    to: na::Point2<Real>,
+) -> Real {
+    let segment = to - from;
+    let length_sq = segment.dot(&segment);
+    if length_sq == 0.0 {
+        return distance(&point, &from);
+    }
+    let t = ((point - from).dot(&segment) / length_sq).clamp(0.0, 1.0);
+    distance(&point, &(from + segment * t))
+}
+
+/// Wraps `point` around the edges of a world of `size`.
+pub fn wrap(point: na::Point2<Real>, size: na::Vector2<Real>) -> na::Point2<Real> {
+    na::Point2::new(
+        na::wrap(point.x, 0.0, size.x),
+        na::wrap(point.y, 0.0, size.y),
+    )
+}
+
+#[cfg(feature = "strict-determinism")]
+pub fn atan2(y: f64, x: f64) -> f64 {
+    libm::atan2(y, x)
+}
+
+#[cfg(not(feature = "strict-determinism"))]
+pub fn atan2(y: f64, x: f64) -> f64 {
+    y.atan2(x)
+}
+
+#[cfg(feature = "strict-determinism")]
+fn sqrt(x: Real) -> Real {
+    libm::Libm::<Real>::sqrt(x)
+}
+
+#[cfg(not(feature = "strict-determinism"))]
+fn sqrt(x: Real) -> Real {
+    x.sqrt()
+}
+
+#[cfg(feature = "strict-determinism")]
+fn sin(x: Real) -> Real {
+    libm::Libm::<Real>::sin(x)
+}
+
+#[cfg(feature = "strict-determinism")]
+fn cos(x: Real) -> Real {
+    libm::Libm::<Real>::cos(x)
+}
+
+#[cfg(not(feature = "strict-determinism"))]
+fn sin(x: Real) -> Real {
+    x.sin()
+}
+
+#[cfg(not(feature = "strict-determinism"))]
+fn cos(x: Real) -> Real {
+    x.cos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::consts::PI;
+    use super::*;
+
+    #[test]
+    fn test_rotation_roundtrip() {
+        for angle in [0.0, 0.5, -2.0, PI / 2.0, 3.0] {
+            let epsilon = if cfg!(feature = "f32-world") {
+                1e-6
+            } else {
+                1e-12
+            };
+            approx::assert_relative_eq!(super::angle(&rotation(angle)), angle, epsilon = epsilon);
+        }
+    }
+
+    #[test]
+    fn test_heading() {
+        approx::assert_relative_eq!(heading(&na::Vector2::new(1.0, 0.0)), 0.0);
+        approx::assert_relative_eq!(heading(&na::Vector2::new(0.0, 2.0)), PI / 2.0);
+        approx::assert_relative_eq!(heading(&na::Vector2::new(-1.0, 1.0)), 3.0 * PI / 4.0);
+    }
+
+    #[test]
+    fn test_wrap() {
+        let size = na::Vector2::new(2.0, 0.5);
+        let wrapped = wrap(na::Point2::new(2.25, -0.1), size);
+        approx::assert_relative_eq!(wrapped, na::Point2::new(0.25, 0.4), epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_distance() {
+        let a = na::Point2::new(0.0, 0.0);
+        let b = na::Point2::new(3.0, 4.0);
+        approx::assert_relative_eq!(distance(&a, &b), 5.0);
+    }
+
+    #[test]
+    fn test_segment_distance() {
+        let from = na::Point2::new(0.0, 0.0);
+        let to = na::Point2::new(2.0, 0.0);
+        let distance = |x, y| segment_distance(na::Point2::new(x, y), from, to);
+        approx::assert_relative_eq!(distance(1.0, 0.5), 0.5);
+        approx::assert_relative_eq!(distance(-3.0, 4.0), 5.0);
+        approx::assert_relative_eq!(distance(5.0, 4.0), 5.0);
+        approx::assert_relative_eq!(segment_distance(to, from, from), 2.0);
+    }
+}
@@ -0,0 +1,38 @@
+use nalgebra as na;
+use serde::{Deserialize, Serialize};
+
+use crate::math::{self, Real};
+
+/// Home base that animals deliver carried food to in `GameMode::Forage`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Nest {
+    pub(crate) position: na::Point2<Real>,
+    pub(crate) radius: Real,
+}
+
+impl Nest {
+    pub fn new(position: na::Point2<Real>, radius: Real) -> Self {
+        Self { position, radius }
+    }
+
+    pub fn position(&self) -> na::Point2<Real> {
+        self.position
+    }
+
+    pub fn radius(&self) -> Real {
+        self.radius
+    }
+
+    pub fn contains(&self, point: na::Point2<Real>) -> bool {
+        math::distance(&self.position, &point) < self.radius
+    }
+}
+
+impl Default for Nest {
+    fn default() -> Self {
+        Self {
+            position: na::Point2::new(0.5, 0.5),
+            radius: 0.05,
+        }
+    }
+}
@@ -0,0 +1,532 @@
+//! Serializable views of the simulation for frontends, shared by the wasm
+//! bindings and the server so both speak the same format: world snapshots,
+//! deltas between them and generation statistics.
+
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{BrainConfig, Config};
+use crate::math::Real;
+use crate::simulation::Simulation;
+use crate::world_diff::{WorldDiff, WorldOutline};
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GenerationStatistics {
+    max_fitness: f64,
+    min_fitness: f64,
+    mean_fitness: f64,
+    std_fitness: f64,
+    mean_pairwise_distance: f64,
+    mean_gene_variance: f64,
+    behavior: BehaviorStatistics,
+    meal_locations: Heatmap,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct BehaviorStatistics {
+    mean_distance_traveled: f64,
+    mean_speed: f64,
+    mean_turn_entropy: f64,
+    mean_time_to_first_food: Option<f64>,
+    fed_fraction: f64,
+    receptor_utilization: Vec<f64>,
+    mean_meal_interval: Option<f64>,
+    meal_interval_histogram: Vec<u32>,
+}
+
+/// Serializable `crate::Heatmap`: `counts` row after row.
+#[derive(Clone, Debug, Serialize)]
+pub struct Heatmap {
+    pub cols: usize,
+    pub rows: usize,
+    pub counts: Vec<u32>,
+}
+
+/// Snapshot of a world for rendering, with the fields a frontend draws.
+#[derive(Clone, Debug, Serialize)]
+pub struct World {
+    pub width: Real,
+    pub height: Real,
+    pub animals: Vec<Animal>,
+    pub food: Vec<Food>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nest: Option<Nest>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub walls: Vec<Wall>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Animal {
+    /// `crate::Animal::id`, e.g. for `Simulation::kill_animal`.
+    pub id: u64,
+    pub x: Real,
+    pub y: Real,
+    pub rotation: Real,
+    pub radius: Real,
+    pub hue: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vision: Option<Vision>,
+    /// `x, y` pairs of `crate::Animal::trail`, oldest first. Empty, and
+    /// left out, unless `Config::trail_length` is set.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub trail: Vec<[Real; 2]>,
+}
+
+/// Payload of `FrameStream::frame`, tagged with its `kind`.
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "kind")]
+pub enum Frame {
+    Keyframe {
+        frame: u64,
+        world: World,
+    },
+    /// Changes since frame `base`.
+    Delta {
+        frame: u64,
+        base: u64,
+        changes: Vec<WorldChange>,
+    },
+}
+
+/// Serializable `crate::WorldChange`, tagged with its `kind`. Added
+/// animals come with their payload.
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "kind")]
+pub enum WorldChange {
+    AnimalMoved {
+        idx: usize,
+        dx: Real,
+        dy: Real,
+        turn: Real,
+    },
+    AnimalAte {
+        idx: usize,
+        meals: u32,
+    },
+    AnimalDeposited {
+        idx: usize,
+        deposits: u32,
+    },
+    AnimalAdded {
+        idx: usize,
+        animal: Animal,
+    },
+    AnimalRemoved {
+        idx: usize,
+    },
+    FoodRespawned {
+        idx: usize,
+        x: Real,
+        y: Real,
+    },
+    FoodAdded {
+        idx: usize,
+        x: Real,
+        y: Real,
+        radius: Real,
+    },
+    FoodRemoved {
+        idx: usize,
+    },
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Vision {
+    pub fov_range: Real,
+    pub fov_angle: Real,
+    pub receptors: Vec<Real>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Food {
+    pub x: Real,
+    pub y: Real,
+    pub radius: Real,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Wall {
+    pub x1: Real,
+    pub y1: Real,
+    pub x2: Real,
+    pub y2: Real,
+}
+
+/// A brain saved by a frontend: its layer sizes and genome.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Brain {
+    pub inputs: usize,
+    pub layers: Vec<usize>,
+    pub genome: Vec<f64>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Nest {
+    pub x: Real,
+    pub y: Real,
+    pub radius: Real,
+}
+
+impl From<&crate::GenerationStatistics> for GenerationStatistics {
+    fn from(value: &crate::GenerationStatistics) -> Self {
+        GenerationStatistics {
+            max_fitness: value.max_fitness,
+            min_fitness: value.min_fitness,
+            mean_fitness: value.mean_fitness,
+            std_fitness: value.std_fitness,
+            mean_pairwise_distance: value.mean_pairwise_distance,
+            mean_gene_variance: value.mean_gene_variance,
+            behavior: BehaviorStatistics::from(&value.behavior),
+            meal_locations: Heatmap::from(&value.meal_locations),
+        }
+    }
+}
+
+impl World {
+    /// With `debug_vision`, includes each animal's eye geometry and current
+    /// receptor activations.
+    pub fn from_world(world: &crate::World, debug_vision: bool) -> Self {
+        let animals = world
+            .animals()
+            .iter()
+            .map(|animal| {
+                let mut payload = Animal::from(animal);
+                if debug_vision {
+                    payload.vision = Some(Vision::from(animal));
+                }
+                payload
+            })
+            .collect();
+        let food = world.food().iter().map(Food::from).collect();
+        let nest = world.nest().map(Nest::from);
+        let walls = world.walls().iter().map(Wall::from).collect();
+        Self {
+            width: world.width(),
+            height: world.height(),
+            animals,
+            food,
+            nest,
+            walls,
+        }
+    }
+}
+
+impl WorldChange {
+    /// `world` is the world after the change, for the payload of added
+    /// animals and food.
+    pub fn from_change(change: &crate::WorldChange, world: &crate::World) -> Self {
+        match *change {
+            crate::WorldChange::AnimalMoved { idx, delta, turn } => Self::AnimalMoved {
+                idx,
+                dx: delta.x,
+                dy: delta.y,
+                turn,
+            },
+            crate::WorldChange::AnimalAte { idx, meals } => Self::AnimalAte { idx, meals },
+            crate::WorldChange::AnimalDeposited { idx, deposits } => {
+                Self::AnimalDeposited { idx, deposits }
+            }
+            crate::WorldChange::AnimalAdded { idx } => Self::AnimalAdded {
+                idx,
+                animal: Animal::from(&world.animals()[idx]),
+            },
+            crate::WorldChange::AnimalRemoved { idx } => Self::AnimalRemoved { idx },
+            crate::WorldChange::FoodRespawned { idx, to, .. } => Self::FoodRespawned {
+                idx,
+                x: to.x,
+                y: to.y,
+            },
+            crate::WorldChange::FoodAdded { idx, position } => Self::FoodAdded {
+                idx,
+                x: position.x,
+                y: position.y,
+                radius: world.food()[idx].radius(),
+            },
+            crate::WorldChange::FoodRemoved { idx } => Self::FoodRemoved { idx },
+        }
+    }
+}
+
+impl Brain {
+    /// The current generation's fittest animal's brain.
+    pub fn champion(simulation: &Simulation) -> Option<Self> {
+        let champion = simulation.champion()?;
+        let (inputs, layers) = simulation.config().brain_topology();
+        Some(Self {
+            inputs,
+            layers,
+            genome: champion.as_chromosome().into_iter().collect(),
+        })
+    }
+
+    /// A brain config for `Simulation::reseed` that starts a population
+    /// from this brain. Its inputs and outputs must match `config`'s game
+    /// mode, eye and control scheme.
+    pub fn into_brain_config(self, config: &Config) -> Result<BrainConfig, String> {
+        let (inputs, layers) = config.brain_topology();
+        if self.inputs != inputs || self.layers.last() != layers.last() {
+            return Err(format!(
+                "brain has {} inputs and {:?} outputs, expected {inputs} and {:?}",
+                self.inputs,
+                self.layers.last(),
+                layers.last()
+            ));
+        }
+
+        let hidden_layers = self.layers[..self.layers.len() - 1].to_vec();
+        Ok(BrainConfig {
+            hidden_layers,
+            seed_genome: Some(self.genome),
+            champions: Vec::new(),
+            ..config.brain.clone()
+        })
+    }
+}
+
+impl From<&crate::Animal> for Animal {
+    fn from(animal: &crate::Animal) -> Self {
+        Self {
+            id: animal.id(),
+            x: animal.position().x,
+            y: animal.position().y,
+            rotation: animal.rotation().angle(),
+            radius: animal.radius(),
+            hue: animal.hue(),
+            vision: None,
+            trail: animal.trail().iter().map(|p| [p.x, p.y]).collect(),
+        }
+    }
+}
+
+impl From<&crate::BehaviorStatistics> for BehaviorStatistics {
+    fn from(value: &crate::BehaviorStatistics) -> Self {
+        BehaviorStatistics {
+            mean_distance_traveled: value.mean_distance_traveled,
+            mean_speed: value.mean_speed,
+            mean_turn_entropy: value.mean_turn_entropy,
+            mean_time_to_first_food: value.mean_time_to_first_food,
+            fed_fraction: value.fed_fraction,
+            receptor_utilization: value.receptor_utilization.clone(),
+            mean_meal_interval: value.mean_meal_interval,
+            meal_interval_histogram: value.meal_interval_histogram.clone(),
+        }
+    }
+}
+
+impl From<&crate::Animal> for Vision {
+    fn from(animal: &crate::Animal) -> Self {
+        Self {
+            fov_range: animal.eye().fov_range(),
+            fov_angle: animal.eye().fov_angle(),
+            receptors: animal.vision().to_vec(),
+        }
+    }
+}
+
+impl From<&crate::Food> for Food {
+    fn from(food: &crate::Food) -> Self {
+        Self {
+            x: food.position().x,
+            y: food.position().y,
+            radius: food.radius(),
+        }
+    }
+}
+
+impl From<&crate::Heatmap> for Heatmap {
+    fn from(heatmap: &crate::Heatmap) -> Self {
+        Self {
+            cols: heatmap.cols(),
+            rows: heatmap.rows(),
+            counts: heatmap.counts().to_vec(),
+        }
+    }
+}
+
+impl From<&crate::Nest> for Nest {
+    fn from(nest: &crate::Nest) -> Self {
+        Self {
+            x: nest.position().x,
+            y: nest.position().y,
+            radius: nest.radius(),
+        }
+    }
+}
+
+impl From<&crate::Wall> for Wall {
+    fn from(wall: &crate::Wall) -> Self {
+        Self {
+            x1: wall.start.x,
+            y1: wall.start.y,
+            x2: wall.end.x,
+            y2: wall.end.y,
+        }
+    }
+}
+
+/// Turns a running world into numbered frames for one frontend: the
+/// changes since the last frame it acknowledged with `ack`, or the whole
+/// world when there is none, a keyframe was requested or a new generation
+/// started, since hues change then.
+#[derive(Default)]
+pub struct FrameStream {
+    next: u64,
+    /// Frames sent since the acknowledged one, oldest first, at most
+    /// `MAX_PENDING_FRAMES`.
+    sent: VecDeque<Snapshot>,
+    /// Frame deltas are relative to.
+    acked: Option<Snapshot>,
+}
+
+/// Frames a `FrameStream` remembers until they are acknowledged, enough for
+/// half a second of latency at 60 frames per second.
+pub const MAX_PENDING_FRAMES: usize = 32;
+
+/// What a sent frame showed, enough to diff later frames against.
+struct Snapshot {
+    frame: u64,
+    generation: u32,
+    outline: WorldOutline,
+}
+
+impl FrameStream {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The next frame of `world`. Deltas don't carry debug vision.
+    pub fn frame(&mut self, world: &crate::World, generation: u32, debug_vision: bool) -> Frame {
+        let frame = self.next;
+        self.next += 1;
+
+        let payload = match &self.acked {
+            Some(acked) if acked.generation == generation => Frame::Delta {
+                frame,
+                base: acked.frame,
+                changes: WorldDiff::since(&acked.outline, world)
+                    .changes()
+                    .iter()
+                    .map(|change| WorldChange::from_change(change, world))
+                    .collect(),
+            },
+            _ => Frame::Keyframe {
+                frame,
+                world: World::from_world(world, debug_vision),
+            },
+        };
+        if self.sent.len() == MAX_PENDING_FRAMES {
+            self.sent.pop_front();
+        }
+        self.sent.push_back(Snapshot {
+            frame,
+            generation,
+            outline: WorldOutline::of(world),
+        });
+        payload
+    }
+
+    /// Records that the frontend has applied `frame`, so later deltas can be
+    /// relative to it. Returns false, changing nothing, for frames older
+    /// than the acknowledged one or the last `MAX_PENDING_FRAMES` sent.
+    pub fn ack(&mut self, frame: u64) -> bool {
+        let Some(idx) = self.sent.iter().position(|sent| sent.frame == frame) else {
+            return false;
+        };
+        self.acked = self.sent.drain(..=idx).next_back();
+        true
+    }
+
+    /// Makes the next frame a keyframe. Needed whenever animals are
+    /// removed from the middle of the list, e.g. killed, since deltas
+    /// match animals by index.
+    pub fn request_keyframe(&mut self) {
+        self.acked = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::Simulation;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn next_frame(frames: &mut FrameStream, simulation: &Simulation) -> Frame {
+        frames.frame(simulation.world(), simulation.generation(), false)
+    }
+
+    #[test]
+    fn test_frame_stream() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut simulation = Simulation::random(&mut rng, 2, 2);
+        let mut frames = FrameStream::new();
+
+        let frame = next_frame(&mut frames, &simulation);
+        assert!(matches!(frame, Frame::Keyframe { frame: 0, .. }));
+        // Unacknowledged, so still a keyframe
+        let frame = next_frame(&mut frames, &simulation);
+        assert!(matches!(frame, Frame::Keyframe { frame: 1, .. }));
+
+        assert!(frames.ack(1));
+        assert!(!frames.ack(0));
+        simulation.step(&mut rng);
+        let Frame::Delta {
+            frame,
+            base,
+            changes,
+        } = next_frame(&mut frames, &simulation)
+        else {
+            panic!("expected a delta");
+        };
+        assert_eq!((frame, base), (2, 1));
+        assert!(!changes.is_empty());
+
+        // Deltas stay relative to the acknowledged frame
+        let frame = next_frame(&mut frames, &simulation);
+        assert!(matches!(
+            frame,
+            Frame::Delta {
+                frame: 3,
+                base: 1,
+                ..
+            }
+        ));
+        assert!(frames.ack(2));
+        let frame = next_frame(&mut frames, &simulation);
+        assert!(matches!(frame, Frame::Delta { base: 2, .. }));
+
+        simulation.force_evolve();
+        simulation.step(&mut rng);
+        let frame = next_frame(&mut frames, &simulation);
+        assert!(matches!(frame, Frame::Keyframe { frame: 5, .. }));
+
+        frames.ack(5);
+        frames.request_keyframe();
+        let frame = next_frame(&mut frames, &simulation);
+        assert!(matches!(frame, Frame::Keyframe { frame: 6, .. }));
+    }
+
+    #[test]
+    fn test_frame_stream_kill() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut simulation = Simulation::random(&mut rng, 3, 2);
+        let mut frames = FrameStream::new();
+
+        next_frame(&mut frames, &simulation);
+        frames.ack(0);
+        assert!(simulation.kill_animal(0));
+        // Without a keyframe, the delta would move the remaining animals
+        // onto the wrong indices and never say one is gone
+        let Frame::Delta { changes, .. } = next_frame(&mut frames, &simulation) else {
+            panic!("expected a delta");
+        };
+        assert!(!changes.is_empty());
+
+        frames.request_keyframe();
+        let Frame::Keyframe { world, .. } = next_frame(&mut frames, &simulation) else {
+            panic!("expected a keyframe");
+        };
+        assert_eq!(world.animals.len(), 2);
+    }
+}
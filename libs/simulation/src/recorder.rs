@@ -0,0 +1,129 @@
+use std::fmt::Write;
+
+use crate::generation_statistics::GenerationStatistics;
+
+const COLUMNS: [&str; 13] = [
+    "generation",
+    "max_fitness",
+    "min_fitness",
+    "mean_fitness",
+    "std_fitness",
+    "mean_pairwise_distance",
+    "mean_gene_variance",
+    "mean_distance_traveled",
+    "mean_speed",
+    "mean_turn_entropy",
+    "mean_time_to_first_food",
+    "fed_fraction",
+    "mean_meal_interval",
+];
+
+/// Writes generation statistics as CSV, one row per generation, for both the
+/// native and the wasm front ends. Per-gene, per-receptor and gridded values
+/// are left out; a missing `mean_time_to_first_food` or
+/// `mean_meal_interval` is an empty field.
+#[derive(Clone, Debug)]
+pub struct Recorder {
+    csv: String,
+    rows: usize,
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        let mut csv = COLUMNS.join(",");
+        csv.push('\n');
+        Self { csv, rows: 0 }
+    }
+
+    /// CSV of a whole statistics history, numbering generations from 0.
+    pub fn from_history(history: &[GenerationStatistics]) -> Self {
+        let mut recorder = Self::new();
+        for (generation, stats) in history.iter().enumerate() {
+            recorder.record(generation, stats);
+        }
+        recorder
+    }
+
+    pub fn record(&mut self, generation: usize, stats: &GenerationStatistics) {
+        let behavior = &stats.behavior;
+        let first_food = behavior
+            .mean_time_to_first_food
+            .map_or_else(String::new, |steps| steps.to_string());
+        let meal_interval = behavior
+            .mean_meal_interval
+            .map_or_else(String::new, |steps| steps.to_string());
+        writeln!(
+            self.csv,
+            "{generation},{},{},{},{},{},{},{},{},{},{first_food},{},{meal_interval}",
+            stats.max_fitness,
+            stats.min_fitness,
+            stats.mean_fitness,
+            stats.std_fitness,
+            stats.mean_pairwise_distance,
+            stats.mean_gene_variance,
+            behavior.mean_distance_traveled,
+            behavior.mean_speed,
+            behavior.mean_turn_entropy,
+            behavior.fed_fraction,
+        )
+        .unwrap();
+        self.rows += 1;
+    }
+
+    /// Number of generations recorded.
+    pub fn len(&self) -> usize {
+        self.rows
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows == 0
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.csv
+    }
+
+    pub fn into_string(self) -> String {
+        self.csv
+    }
+}
+
+impl Default for Recorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::animal::AnimalIndividual;
+    use lib_reinforcement_learning::genetic_algorithm::Chromosome;
+
+    #[test]
+    fn test_from_history() {
+        let population = vec![
+            AnimalIndividual {
+                chromosome: Chromosome::new(vec![0.0]),
+                fitness: 1.0,
+            },
+            AnimalIndividual {
+                chromosome: Chromosome::new(vec![2.0]),
+                fitness: 3.0,
+            },
+        ];
+        let history = vec![
+            GenerationStatistics::from_population(&population),
+            GenerationStatistics::from_population(&population[1..]),
+        ];
+
+        let recorder = Recorder::from_history(&history);
+        let lines: Vec<&str> = recorder.as_str().lines().collect();
+
+        assert_eq!(recorder.len(), 2);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].split(',').count(), COLUMNS.len());
+        assert_eq!(lines[1], "0,3,0,2,1,2,1,0,0,0,,0,");
+        assert!(lines[2].starts_with("1,3,0,3,0,0,0,"));
+    }
+}
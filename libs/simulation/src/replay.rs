@@ -0,0 +1,133 @@
+use serde::{Deserialize, Serialize};
+use vroom_rand::ChaCha8Rng;
+
+use crate::config::Config;
+use crate::simulation::Simulation;
+use crate::snapshot::{self, SnapshotError};
+
+/// A seeded run that can be played back, with periodic `World::hash`
+/// checkpoints to detect where playback diverges from the recording.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    pub config: Config,
+    pub steps: u32,
+    pub checkpoints: Vec<Checkpoint>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub step: u32,
+    pub world_hash: u64,
+}
+
+/// First checkpoint at which playback didn't match the recording.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Divergence {
+    pub step: u32,
+    pub expected: u64,
+    pub actual: u64,
+}
+
+impl Replay {
+    /// Runs a new simulation for `steps` steps, storing a checkpoint every
+    /// `hash_interval` steps.
+    pub fn record(seed: u64, config: Config, steps: u32, hash_interval: u32) -> Self {
+        assert!(hash_interval > 0);
+
+        let mut replay = Self {
+            seed,
+            config,
+            steps,
+            checkpoints: Vec::new(),
+        };
+        let (mut rng, mut simulation) = replay.start();
+        for step in 1..=steps {
+            simulation.step(&mut rng);
+            if step % hash_interval == 0 {
+                replay.checkpoints.push(Checkpoint {
+                    step,
+                    world_hash: simulation.world().hash(),
+                });
+            }
+        }
+        replay
+    }
+
+    /// Replays the run, calling `on_step` after every step, and stops at the
+    /// first checkpoint that doesn't match.
+    pub fn play(
+        &self,
+        mut on_step: impl FnMut(u32, &Simulation),
+    ) -> Result<Simulation, Divergence> {
+        let (mut rng, mut simulation) = self.start();
+        let mut checkpoints = self.checkpoints.iter().peekable();
+        for step in 1..=self.steps {
+            simulation.step(&mut rng);
+            on_step(step, &simulation);
+
+            if let Some(checkpoint) = checkpoints.next_if(|checkpoint| checkpoint.step == step) {
+                let actual = simulation.world().hash();
+                if actual != checkpoint.world_hash {
+                    return Err(Divergence {
+                        step,
+                        expected: checkpoint.world_hash,
+                        actual,
+                    });
+                }
+            }
+        }
+        Ok(simulation)
+    }
+
+    /// The replay with a `SnapshotHeader`, to be loaded by `from_snapshot`.
+    pub fn to_snapshot(&self) -> Vec<u8> {
+        snapshot::save(self)
+    }
+
+    pub fn from_snapshot(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        snapshot::load(bytes)
+    }
+
+    pub fn verify(&self) -> Result<(), Divergence> {
+        self.play(|_, _| {}).map(|_| ())
+    }
+
+    fn start(&self) -> (ChaCha8Rng, Simulation) {
+        let mut rng = vroom_rand::seeded(self.seed);
+        let simulation = Simulation::from_config(&mut rng, self.config.clone());
+        (rng, simulation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> Config {
+        Config {
+            num_animals: 4,
+            num_food: 8,
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn test_replay_verifies() {
+        let replay = Replay::record(3, config(), 100, 10);
+
+        assert_eq!(replay.checkpoints.len(), 10);
+        assert_eq!(replay.verify(), Ok(()));
+    }
+
+    #[test]
+    fn test_replay_detects_divergence() {
+        let mut replay = Replay::record(3, config(), 100, 10);
+        replay.checkpoints[4].world_hash ^= 1;
+
+        let divergence = replay.verify().unwrap_err();
+
+        assert_eq!(divergence.step, 50);
+        assert_eq!(divergence.actual ^ 1, divergence.expected);
+    }
+}
@@ -0,0 +1,63 @@
+use nalgebra as na;
+
+use crate::animal::Animal;
+use crate::math::{self, Real};
+use crate::world::World;
+
+/// Per-step reward added to an animal's fitness on top of the game mode's
+/// objective, giving early generations something to select on before any
+/// animal manages to score.
+pub trait RewardShaper {
+    /// Reward for `animal` having moved from `before` during this step.
+    fn reward(&self, before: na::Point2<Real>, animal: &Animal, world: &World) -> f64;
+}
+
+/// Rewards getting closer to the nearest food, `weight` per unit of
+/// distance.
+#[derive(Clone, Copy, Debug)]
+pub struct FoodApproach {
+    pub weight: f64,
+}
+
+impl RewardShaper for FoodApproach {
+    fn reward(&self, before: na::Point2<Real>, animal: &Animal, world: &World) -> f64 {
+        let food = animal.private_food().unwrap_or(world.food());
+        let nearest = |position: &na::Point2<Real>| {
+            food.iter()
+                .map(|food| math::distance(position, &food.position))
+                .min_by(Real::total_cmp)
+        };
+        match (nearest(&before), nearest(&animal.position)) {
+            (Some(before), Some(after)) => self.weight * math::wide(before - after),
+            _ => 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_food_approach() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let config = Config {
+            num_animals: 1,
+            num_food: 2,
+            ..Config::default()
+        };
+        let mut world = World::random(&mut rng, &config);
+        world.food[0].position = na::Point2::new(0.5, 0.5);
+        world.food[1].position = na::Point2::new(0.9, 0.9);
+        world.animals[0].position = na::Point2::new(0.3, 0.5);
+
+        let shaper = FoodApproach { weight: 2.0 };
+        let closer = shaper.reward(na::Point2::new(0.2, 0.5), &world.animals[0], &world);
+        approx::assert_relative_eq!(closer, 0.2, epsilon = math::wide(Real::EPSILON));
+        let further = shaper.reward(na::Point2::new(0.4, 0.5), &world.animals[0], &world);
+        approx::assert_relative_eq!(further, -0.2, epsilon = math::wide(Real::EPSILON));
+    }
+}
@@ -0,0 +1,185 @@
+use lib_neural_net as nn;
+use nalgebra as na;
+use serde::{Deserialize, Serialize};
+use vroom_rand::RngCore;
+
+use crate::buffer::Buffer;
+use crate::math::consts::PI;
+use crate::math::{self, Real};
+use crate::world::World;
+
+/// Where an animal is and which way it faces.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pose {
+    pub position: na::Point2<Real>,
+    pub rotation: na::Rotation2<Real>,
+    /// Index of the sensing animal in `World::animals`, if it is one there,
+    /// so sensors of other animals can leave it out.
+    pub animal: Option<usize>,
+}
+
+/// Turns what an animal can perceive into the readings that make up the
+/// first of its brain inputs. Implement it for custom sensing, e.g. of
+/// walls or other animals, and install it with `Simulation::set_sensor`.
+pub trait Sensor {
+    /// Number of readings.
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Writes the readings for an animal at `pose` in `world` into
+    /// `readings`, reusing its allocation. The animal itself is among
+    /// `world`'s animals.
+    fn sense_into(&self, rng: &mut dyn RngCore, pose: Pose, world: &World, readings: &mut Buffer);
+
+    fn sense(&self, rng: &mut dyn RngCore, pose: Pose, world: &World) -> Vec<Real> {
+        let mut readings = Buffer::new();
+        self.sense_into(rng, pose, world, &mut readings);
+        readings.to_vec()
+    }
+
+    /// How the readings map onto each other when the animal's surroundings
+    /// are mirrored left to right, for `BrainConfig::symmetric`. The default
+    /// keeps every reading in place.
+    fn mirror(&self) -> nn::Mirror {
+        nn::Mirror::identity(self.len())
+    }
+
+    /// Whether readings depend on nothing but the animal's pose and the
+    /// food, so they can be reused while neither changes much, see
+    /// `EyeConfig::refresh_distance`. The default says no, since a custom
+    /// sensor may read anything.
+    fn cacheable(&self) -> bool {
+        false
+    }
+
+    /// Where each reading comes from, for `BrainEncoding::Cppn`: left to
+    /// right along x in [-1, 1], with different kinds of readings apart
+    /// along y. The default spreads the readings evenly along x.
+    fn positions(&self) -> Vec<[f64; 2]> {
+        nn::Substrate::row(self.len(), 0.0)
+    }
+}
+
+/// Builds a sensor for one animal, see `Simulation::set_sensor`.
+pub type SensorFactory = Box<dyn Fn() -> Box<dyn Sensor>>;
+
+/// Relative polar coordinates of the `count` nearest pieces of food within
+/// `range`, nearest first, as an alternative to the retina-like `Eye`. Each
+/// is a distance normalized by `range` followed by an angle relative to the
+/// animal's heading, normalized to [-1, 1]. Missing food reads as distance
+/// 2.0 and angle 0.0. Sees all around and has no noise.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NearestFood {
+    pub(crate) count: usize,
+    pub(crate) range: Real,
+}
+
+/// Which `Sensor` animals perceive food with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SensorKind {
+    /// `Eye` receptors, one reading per slice of the field of view.
+    #[default]
+    Receptors,
+    /// `NearestFood`, two readings per piece of food.
+    NearestFood,
+}
+
+impl NearestFood {
+    pub fn new(count: usize, range: Real) -> Self {
+        Self { count, range }
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn range(&self) -> Real {
+        self.range
+    }
+}
+
+impl Sensor for NearestFood {
+    fn len(&self) -> usize {
+        2 * self.count
+    }
+
+    fn cacheable(&self) -> bool {
+        true
+    }
+
+    fn sense_into(&self, _rng: &mut dyn RngCore, pose: Pose, world: &World, readings: &mut Buffer) {
+        readings.clear();
+        for _ in 0..self.count {
+            readings.extend([2.0, 0.0]);
+        }
+
+        for f in world.food_for(pose.animal) {
+            let displacement = f.position - pose.position;
+            let dist = math::norm(&displacement);
+            if dist.is_nan() || dist > self.range {
+                continue;
+            }
+            let dist = dist / self.range;
+
+            // Insertion into the readings, which stay sorted by distance
+            let Some(slot) = (0..self.count).find(|&slot| dist < readings[2 * slot]) else {
+                continue;
+            };
+            for moved in (slot + 1..self.count).rev() {
+                readings[2 * moved] = readings[2 * moved - 2];
+                readings[2 * moved + 1] = readings[2 * moved - 1];
+            }
+            let angle = math::heading(&displacement) - math::angle(&pose.rotation);
+            readings[2 * slot] = dist;
+            readings[2 * slot + 1] = na::wrap(angle, -PI, PI) / PI;
+        }
+    }
+
+    /// Angles flip sign.
+    fn mirror(&self) -> nn::Mirror {
+        nn::Mirror::new(
+            (0..self.len())
+                .map(|idx| (idx, if idx % 2 == 0 { 1.0 } else { -1.0 }))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::food::Food;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_nearest_food() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let sensor = NearestFood::new(3, 0.5);
+        let mut world = World::random(&mut rng, &Config::default());
+        world.food = vec![
+            // Out of range
+            Food::new(na::Point2::new(0.0, 0.0)),
+            // To the right, then nearer to the left
+            Food::new(na::Point2::new(0.5, 0.2)),
+            Food::new(na::Point2::new(0.5, 0.6)),
+        ];
+        let pose = Pose {
+            position: na::Point2::new(0.5, 0.5),
+            rotation: na::Rotation2::new(0.0),
+            animal: None,
+        };
+
+        let readings = sensor.sense(&mut rng, pose, &world);
+
+        let expected = [0.2, 0.5, 0.6, -0.5, 2.0, 0.0];
+        assert_eq!(readings.len(), sensor.len());
+        for (actual, expected) in readings.iter().zip(expected) {
+            approx::assert_abs_diff_eq!(*actual, expected, epsilon = 1e-6);
+        }
+    }
+}
@@ -1,140 +1,2335 @@
-use std::f64::consts::PI;
+use std::fmt;
+use std::hash::Hasher;
 
 use nalgebra as na;
-use rand::RngCore;
+use rand::Rng;
+use rand_distr::StandardNormal;
+use serde::{Deserialize, Serialize};
+use vroom_rand::RngCore;
 
-use lib_reinforcement_learning::genetic_algorithm as ga;
+use lib_neural_net as nn;
+use lib_reinforcement_learning::genetic_algorithm::{self as ga, Individual};
 
-use crate::animal::{Animal, AnimalIndividual};
+use crate::animal::{Animal, AnimalIndividual, RandomBrains};
+use crate::behavior::BehaviorStatistics;
+use crate::buffer::Buffer;
+use crate::config::{
+    BrainConfig, Config, ConfigError, OperatorConfig, PhysicsConfig, ValidationError,
+};
+use crate::food::Food;
+use crate::game_mode::GameMode;
+use crate::generation_end::GenerationEnd;
 use crate::generation_statistics::GenerationStatistics;
+use crate::heatmap::Heatmap;
+use crate::map::{Map, Zone};
+use crate::math::{self, Real};
+use crate::recorder::Recorder;
+use crate::reward::{FoodApproach, RewardShaper};
+use crate::sensor::{SensorFactory, SensorKind};
+use crate::snapshot::{self, SnapshotError};
+use crate::state_hash::StateHasher;
+use crate::tournament;
 use crate::world::World;
 
-const GENERATION_STEPS: u32 = 1000;
-const MIN_SPEED: f64 = 0.001;
-const MAX_SPEED: f64 = 0.005;
-const MAX_ACCEL: f64 = 0.2;
-const MAX_ANGULAR_ACCEL: f64 = PI / 2.0;
-
+#[derive(Serialize, Deserialize)]
 pub struct Simulation {
-    world: World,
-    evolver: ga::GeneticAlgorithm<
-        ga::FitnessProportionateSelection,
-        ga::UniformCrossover,
-        ga::GaussianMutation,
-    >,
+    pub(crate) world: World,
     generation: u32,
-    generation_steps: u32,
+    pub(crate) generation_steps: u32,
+    /// Index in `config.rollouts` of the rollout being run.
+    #[serde(default)]
+    pub(crate) rollout: u32,
+    /// Food positions at the start of the generation, which later rollouts
+    /// start from, transformed.
+    #[serde(default)]
+    pub(crate) rollout_food: Vec<na::Point2<Real>>,
+    /// Where eaten food respawns with `config.private_food`, drawn as
+    /// needed, so every animal's `n`th meal respawns at the same place.
+    #[serde(default)]
+    pub(crate) food_respawns: Vec<na::Point2<Real>>,
     generation_statistics: Vec<GenerationStatistics>,
+    /// Where animals have been during the current generation.
+    #[serde(default)]
+    heatmap: Heatmap,
+    /// `heatmap` of the previous generation.
+    #[serde(default)]
+    prev_heatmap: Option<Heatmap>,
+    /// Where food was eaten during the current generation, for
+    /// `GenerationStatistics::meal_locations`.
+    #[serde(default)]
+    meal_heatmap: Heatmap,
+    pub(crate) config: Config,
+    /// Animals removed during the current generation. They still take part
+    /// in selection, with the fitness they had when removed.
+    culled: Vec<Animal>,
+    /// Next phase of a step started with `step_phase`, or `None` between
+    /// steps.
+    pending_phase: Option<Phase>,
+    /// Best fitness seen this generation and the step it was first reached,
+    /// for `GenerationEnd::FitnessPlateau`.
+    best_fitness: f64,
+    best_fitness_step: u32,
+    /// Set by `force_evolve`.
+    evolve_requested: bool,
+    /// Shaper applied on top of `config.reward`. Not serialized.
+    #[serde(skip)]
+    reward_shaper: Option<Box<dyn RewardShaper>>,
+    /// Builds a custom sensor for every animal when set. Not serialized.
+    #[serde(skip)]
+    sensor_factory: Option<SensorFactory>,
+    /// Replaces `Config::genetic_algorithm` when set. Not serialized.
+    #[serde(skip)]
+    custom_evolver: Option<ga::DynGeneticAlgorithm>,
+    #[serde(skip)]
+    scratch: Scratch,
+}
+
+/// Part of a step, run one at a time by `Simulation::step_phase` in the
+/// order of `Config::pipeline`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Phase {
+    EatFood,
+    /// Senses and decides how to move.
+    ProcessBrains,
+    MoveAnimals,
+    /// Replaces the whole step at the end of a generation, or starts its
+    /// next rollout if it has any left. Not part of a pipeline.
+    Evolve,
+}
+
+impl Phase {
+    /// Sense, act and move, then eat whatever the move reached, so food is
+    /// eaten in the step that moved onto it.
+    pub const DEFAULT_PIPELINE: [Phase; 3] =
+        [Phase::ProcessBrains, Phase::MoveAnimals, Phase::EatFood];
+}
+
+/// Buffers reused from step to step so that steps within a generation
+/// don't allocate. Evolving a generation still does.
+#[derive(Default)]
+struct Scratch {
+    inputs: Buffer,
+    /// `inputs` widened for the brain, which always runs in `f64`.
+    wide_inputs: Vec<f64>,
+    forward: nn::ForwardBuffers,
+    actions: Vec<Buffer>,
+    positions: Vec<na::Point2<Real>>,
 }
 
 impl Simulation {
     pub fn random(rng: &mut dyn RngCore, num_animals: u8, num_food: u8) -> Self {
-        let evolver = ga::GeneticAlgorithm::new(
-            ga::FitnessProportionateSelection::new(),
-            ga::UniformCrossover::new(),
-            ga::GaussianMutation::new(0.01, 0.2),
-        );
+        let config = Config {
+            num_animals,
+            num_food,
+            ..Config::default()
+        };
+        Self::from_config(rng, config)
+    }
+
+    /// Creates a simulation on `config.map`, or an open world if unset.
+    ///
+    /// Panics if `config.map` isn't a bundled map; use `Config::validate` to
+    /// check configs that weren't loaded through `Config::from_*_str`.
+    pub fn from_config(rng: &mut dyn RngCore, config: Config) -> Self {
+        let map = match &config.map {
+            Some(name) => Map::bundled(name).expect("Unknown map"),
+            None => Map::default(),
+        };
+        Self::from_map(rng, &map, config)
+    }
+
+    pub fn from_map(rng: &mut dyn RngCore, map: &Map, config: Config) -> Self {
+        let world = World::from_map(rng, map, &config);
+        let heatmap = Heatmap::new(config.heatmap_cells, config.heatmap_cells);
+        let rollout_food = world.food.iter().map(|food| food.position).collect();
 
-        Self {
-            world: World::random(rng, num_animals, num_food),
-            evolver,
+        let mut simulation = Self {
+            world,
             generation: 0,
             generation_steps: 0,
+            rollout: 0,
+            rollout_food,
+            food_respawns: Vec::new(),
             generation_statistics: Vec::new(),
-        }
+            heatmap: heatmap.clone(),
+            prev_heatmap: None,
+            meal_heatmap: heatmap,
+            config,
+            culled: Vec::new(),
+            pending_phase: None,
+            best_fitness: 0.0,
+            best_fitness_step: 0,
+            evolve_requested: false,
+            reward_shaper: None,
+            sensor_factory: None,
+            custom_evolver: None,
+            scratch: Scratch::default(),
+        };
+        simulation.deal_food();
+        simulation
     }
 
     pub fn world(&self) -> &World {
         &self.world
     }
 
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// The full state, including every brain, with a `SnapshotHeader`
+    /// recording the versions and config it was saved with.
+    pub fn to_snapshot(&self) -> Vec<u8> {
+        snapshot::save(self)
+    }
+
+    /// Loads state from `to_snapshot`, or from before snapshots had a
+    /// header.
+    pub fn from_snapshot(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        let mut simulation: Self = snapshot::load(bytes)?;
+        // State saved before heatmaps has empty ones, which record nothing
+        let cells = simulation.config.heatmap_cells;
+        for heatmap in [&mut simulation.heatmap, &mut simulation.meal_heatmap] {
+            if heatmap.counts().is_empty() {
+                *heatmap = Heatmap::new(cells, cells);
+            }
+        }
+        Ok(simulation)
+    }
+
+    /// Replaces the movement limits, taking effect from the next step.
+    pub fn set_physics(&mut self, physics: PhysicsConfig) -> Result<(), ConfigError> {
+        self.update_config(|config| config.physics = physics)
+    }
+
+    /// Replaces the genetic operators, used from the next evolution on.
+    pub fn set_operators(&mut self, operators: OperatorConfig) -> Result<(), ConfigError> {
+        self.update_config(|config| config.operators = operators)
+    }
+
+    /// Changes how many steps a generation lasts. If the current generation
+    /// is already past the new length it evolves on the next step.
+    pub fn set_generation_length(&mut self, steps: u32) -> Result<(), ConfigError> {
+        self.update_config(|config| config.generation_steps = steps)
+    }
+
+    /// Changes how many recent positions animals remember, see
+    /// `Animal::trail`. Longer trails fill up over the next steps; shorter
+    /// ones are cut on the next step.
+    pub fn set_trail_length(&mut self, length: usize) -> Result<(), ConfigError> {
+        self.update_config(|config| config.trail_length = length)
+    }
+
+    /// Animal with the highest fitness so far this generation.
+    pub fn champion(&self) -> Option<&Animal> {
+        self.world.animals.iter().max_by(|a, b| {
+            let fitness = |animal| self.config.mode.fitness(animal);
+            fitness(a).total_cmp(&fitness(b))
+        })
+    }
+
+    /// Starts over with a new population built from `brain`, keeping the
+    /// world's layout and food. Generation counters and statistics are
+    /// reset.
+    pub fn reseed(&mut self, rng: &mut dyn RngCore, brain: BrainConfig) -> Result<(), ConfigError> {
+        self.update_config(|config| config.brain = brain)?;
+        self.world.spawn_animals(rng, &self.config);
+        self.attach_sensors();
+        self.generation = 0;
+        self.generation_steps = 0;
+        self.generation_statistics.clear();
+        self.culled.clear();
+        self.pending_phase = None;
+        self.reset_generation_end();
+        Ok(())
+    }
+
+    /// Drops a piece of food at `position`. Like any other food it respawns
+    /// elsewhere once eaten.
+    pub fn add_food(&mut self, position: na::Point2<Real>) {
+        let radius = math::real(self.config.food_radius);
+        let food = Food::new(position).with_radius(radius);
+        for private_food in self.private_foods() {
+            private_food.push(food.clone());
+        }
+        self.world.add_food(food);
+    }
+
+    pub fn clear_food(&mut self) {
+        for private_food in self.private_foods() {
+            private_food.clear();
+        }
+        self.world.clear_food();
+    }
+
+    fn private_foods(&mut self) -> impl Iterator<Item = &mut Vec<Food>> {
+        self.world
+            .animals
+            .iter_mut()
+            .filter_map(|animal| animal.private_food.as_mut())
+    }
+
+    /// Gives every animal its own copy of the world's food with
+    /// `config.private_food`, or takes them away without.
+    pub(crate) fn deal_food(&mut self) {
+        let private = self.config.private_food;
+        for animal in &mut self.world.animals {
+            animal.private_food = private.then(|| self.world.food.clone());
+        }
+    }
+
+    /// Adds an animal whose brain is built from `genome`, e.g. a saved
+    /// champion, at a random spawn position. It joins the population and is
+    /// evolved with the rest. Returns its `Animal::id`, or `None` if
+    /// `genome` doesn't match `Config::genome_length`.
+    pub fn add_animal_with_brain(
+        &mut self,
+        rng: &mut dyn RngCore,
+        genome: Vec<f64>,
+    ) -> Option<u64> {
+        if genome.len() != self.config.genome_length() {
+            return None;
+        }
+        let mut animal = Animal::from_chromosome(rng, genome.into_iter().collect(), &self.config);
+        self.world.place_animal(rng, &mut animal);
+        let id = self.world.new_animal_id();
+        animal.id = id;
+        animal.private_food = self.config.private_food.then(|| self.world.food.clone());
+        self.world.animals.push(animal);
+        self.attach_sensors();
+        Some(id)
+    }
+
+    /// Removes the animal with `Animal::id` `id` for the rest of the
+    /// generation. Returns false if there's no such animal.
+    pub fn kill_animal(&mut self, id: u64) -> bool {
+        match self.world.remove_animal(id) {
+            Some(animal) => {
+                self.culled.push(animal);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Installs a custom reward shaper whose per-step rewards are added to
+    /// the animals' fitness, alongside any shaping enabled in the config.
+    pub fn set_reward_shaper(&mut self, shaper: Option<Box<dyn RewardShaper>>) {
+        self.reward_shaper = shaper;
+    }
+
+    /// Gives every animal, current and future, a sensor built by `factory`
+    /// in place of the configured one. `None` goes back to the config's.
+    /// Fails, changing nothing, if the sensor's reading count differs from
+    /// the configured sensor's, since the brains are sized for that.
+    pub fn set_sensor(&mut self, factory: Option<SensorFactory>) -> Result<(), ConfigError> {
+        if let Some(factory) = &factory {
+            let (expected, actual) = (self.config.sensor().len(), factory().len());
+            if actual != expected {
+                return Err(ConfigError::Invalid(vec![ValidationError {
+                    field: "sensor",
+                    message: format!(
+                        "custom sensor has {actual} readings, brains expect {expected}"
+                    ),
+                }]));
+            }
+        }
+        self.sensor_factory = factory;
+        for animal in &mut self.world.animals {
+            animal.custom_sensor = None;
+        }
+        self.attach_sensors();
+        Ok(())
+    }
+
+    /// Whether animals see with `Eye` receptors, the only readings receptor
+    /// utilization is defined for.
+    fn senses_with_eye(&self) -> bool {
+        self.sensor_factory.is_none() && self.config.eye.sensor == SensorKind::Receptors
+    }
+
+    /// Gives animals without a custom sensor one from `sensor_factory`.
+    fn attach_sensors(&mut self) {
+        let Some(factory) = &self.sensor_factory else {
+            return;
+        };
+        for animal in &mut self.world.animals {
+            if animal.custom_sensor.is_none() {
+                animal.custom_sensor = Some(factory());
+                animal.vision_pose = None;
+            }
+        }
+    }
+
+    /// Installs a genetic algorithm with operators chosen at runtime, used
+    /// instead of `Config::genetic_algorithm`. `None` goes back to
+    /// the config's. Unless it has a gene mask of its own, it keeps the
+    /// genes in `Config::gene_mask`.
+    pub fn set_genetic_algorithm(&mut self, evolver: Option<ga::DynGeneticAlgorithm>) {
+        self.custom_evolver = evolver.map(|evolver| match self.config.gene_mask() {
+            Some(mask) if evolver.gene_mask().is_none() => evolver.with_gene_mask(mask),
+            _ => evolver,
+        });
+    }
+
+    fn update_config(&mut self, update: impl FnOnce(&mut Config)) -> Result<(), ConfigError> {
+        let mut config = self.config.clone();
+        update(&mut config);
+        config.validate()?;
+        self.config = config;
+        Ok(())
+    }
+
+    pub fn mode(&self) -> GameMode {
+        self.config.mode
+    }
+
     pub fn generation(&self) -> u32 {
         self.generation
     }
 
+    /// Steps taken in the current rollout of the generation.
     pub fn generation_steps(&self) -> u32 {
         self.generation_steps
     }
 
+    /// Index in `Config::rollouts` of the rollout being run.
+    pub fn rollout(&self) -> u32 {
+        self.rollout
+    }
+
+    /// Steps left before the current generation evolves at the end of the
+    /// last one, at most, counting rollouts still to run:
+    /// `GenerationEnd::FoodEaten` and `GenerationEnd::FitnessPlateau` can
+    /// end each sooner. `None` under `GenerationEnd::Manual`.
+    pub fn steps_remaining_in_generation(&self) -> Option<u32> {
+        (self.config.generation_end != GenerationEnd::Manual).then(|| {
+            let steps = self.config.generation_steps;
+            let rollouts_left =
+                (self.config.rollouts.len() as u32).saturating_sub(self.rollout + 1);
+            steps
+                .saturating_sub(self.generation_steps)
+                .saturating_add(rollouts_left.saturating_mul(steps))
+        })
+    }
+
     pub fn prev_generation_statistics(&self) -> Option<&GenerationStatistics> {
         self.generation_statistics.last()
     }
 
-    pub fn process_brains(&mut self) {
-        for animal in &mut self.world.animals {
-            let vision =
-                animal
-                    .eye
-                    .process_vision(animal.position, animal.rotation, &self.world.food);
-            let output = animal.brain.forward(vision);
+    pub fn generation_statistics(&self) -> &[GenerationStatistics] {
+        &self.generation_statistics
+    }
+
+    /// Steps animals have spent in each cell of the world during the
+    /// current generation, with `config.heatmap_cells` cells along each
+    /// side.
+    pub fn heatmap(&self) -> &Heatmap {
+        &self.heatmap
+    }
+
+    /// `heatmap` of the previous generation.
+    pub fn prev_heatmap(&self) -> Option<&Heatmap> {
+        self.prev_heatmap.as_ref()
+    }
+
+    /// Clears `heatmap`, e.g. to only count what happens after a change
+    /// made mid-generation. It is also cleared when a generation evolves.
+    pub fn reset_heatmap(&mut self) {
+        self.heatmap.reset();
+    }
+
+    /// The statistics history as CSV, see `Recorder`.
+    pub fn statistics_csv(&self) -> String {
+        Recorder::from_history(&self.generation_statistics).into_string()
+    }
+
+    /// Hash of the full dynamic state (world, generation counters), for
+    /// checking that two runs with the same seed stay in lockstep.
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = StateHasher::new();
+        hasher.write_u32(self.generation);
+        hasher.write_u32(self.generation_steps);
+        for animal in &self.world.animals {
+            hasher.write_real(animal.position.x);
+            hasher.write_real(animal.position.y);
+            hasher.write_real(animal.rotation.matrix()[(0, 0)]);
+            hasher.write_real(animal.rotation.matrix()[(1, 0)]);
+            hasher.write_f64(animal.speed);
+            hasher.write_u32(animal.consumed);
+            hasher.write_u8(animal.carrying as u8);
+            hasher.write_u32(animal.deposited);
+            if let Some(private_food) = &animal.private_food {
+                for food in private_food {
+                    hasher.write_real(food.position.x);
+                    hasher.write_real(food.position.y);
+                }
+            }
+        }
+        for food in &self.world.food {
+            hasher.write_real(food.position.x);
+            hasher.write_real(food.position.y);
+        }
+        for position in &self.food_respawns {
+            hasher.write_real(position.x);
+            hasher.write_real(position.y);
+        }
+        hasher.finish()
+    }
+
+    /// Current fitness of every animal.
+    pub fn fitnesses(&self) -> Vec<f64> {
+        self.world
+            .animals
+            .iter()
+            .map(|animal| self.config.mode.fitness(animal))
+            .collect()
+    }
+
+    pub fn process_brains(&mut self, rng: &mut dyn RngCore) {
+        let mut actions = std::mem::take(&mut self.scratch.actions);
+        actions.resize_with(self.world.animals.len(), Buffer::new);
+
+        self.world.update_visions(rng);
+        let eye = self.senses_with_eye();
+        let Scratch {
+            inputs,
+            wide_inputs,
+            forward,
+            ..
+        } = &mut self.scratch;
+        for (animal, output) in self.world.animals.iter_mut().zip(&mut actions) {
+            if eye {
+                animal.behavior.record_vision(&animal.vision);
+            }
+            inputs.clear();
+            animal.push_vision_frames(self.config.eye.frames, inputs);
+            self.config
+                .mode
+                .push_extra_inputs(animal, self.world.nest.as_ref(), inputs);
+
+            wide_inputs.clear();
+            wide_inputs.extend(inputs.iter().map(|&input| math::wide(input)));
+            let outputs = match &animal.plasticity {
+                Some(plasticity) => animal
+                    .brain
+                    .forward_plastic(wide_inputs, plasticity, forward),
+                None => animal.brain.forward_into(wide_inputs, forward),
+            };
+            output.clear();
+            output.extend(outputs.iter().map(|&value| math::real(value)));
+        }
+
+        self.apply_actuator_faults(rng, &mut actions);
+        self.act(&actions);
+        self.scratch.actions = actions;
+    }
+
+    /// Perturbs brain outputs according to `config.actuator`, remembering
+    /// what each animal ends up acting on.
+    fn apply_actuator_faults(&mut self, rng: &mut dyn RngCore, actions: &mut [Buffer]) {
+        let actuator = &self.config.actuator;
+        if actuator.noise == 0.0 && actuator.stuck == 0.0 {
+            return;
+        }
+
+        for (animal, output) in self.world.animals.iter_mut().zip(actions) {
+            for (idx, value) in output.iter_mut().enumerate() {
+                let previous = animal.outputs.get(idx).copied();
+                if let Some(previous) = previous.filter(|_| rng.gen_bool(actuator.stuck)) {
+                    *value = previous;
+                } else if actuator.noise > 0.0 {
+                    let noise: f64 = rng.sample(StandardNormal);
+                    *value += math::real(noise * actuator.noise);
+                }
+            }
+            animal.outputs.clone_from(output);
+        }
+    }
 
-            let speed_accel = output[0].clamp(-MAX_ACCEL, MAX_ACCEL);
-            let angular_accel = output[1].clamp(-MAX_ANGULAR_ACCEL, MAX_ANGULAR_ACCEL);
-            animal.speed = (animal.speed + speed_accel).clamp(MIN_SPEED, MAX_SPEED);
-            animal.rotation = na::Rotation2::new(animal.rotation.angle() + angular_accel);
+    /// Computes every animal's brain inputs, updating its stored vision.
+    pub fn observe(&mut self, rng: &mut dyn RngCore) -> Vec<Vec<Real>> {
+        self.world.update_visions(rng);
+        let eye = self.senses_with_eye();
+        self.world
+            .animals
+            .iter_mut()
+            .map(|animal| {
+                if eye {
+                    animal.behavior.record_vision(&animal.vision);
+                }
+                let mut inputs = Vec::new();
+                animal.push_vision_frames(self.config.eye.frames, &mut inputs);
+                self.config
+                    .mode
+                    .push_extra_inputs(animal, self.world.nest.as_ref(), &mut inputs);
+                inputs
+            })
+            .collect()
+    }
+
+    /// Applies each animal's brain outputs, decoded by the configured
+    /// control scheme.
+    pub fn act<A: AsRef<[Real]>>(&mut self, actions: &[A]) {
+        assert_eq!(actions.len(), self.world.animals.len());
+
+        let physics = &self.config.physics;
+        let decoder = self.config.control.decoder();
+        for (animal, output) in self.world.animals.iter_mut().zip(actions) {
+            let max_speed = if animal.carrying {
+                (physics.max_speed * GameMode::CARRY_SPEED_FACTOR).max(physics.min_speed)
+            } else {
+                physics.max_speed
+            };
+            let action = decoder.decode(output.as_ref(), animal.speed, physics);
+            let angular_accel = action
+                .angular_accel
+                .clamp(-physics.max_angular_accel, physics.max_angular_accel);
+            animal.speed = action.speed.clamp(physics.min_speed, max_speed);
+            animal.rotation =
+                math::rotation(math::angle(&animal.rotation) + math::real(angular_accel));
+            animal
+                .behavior
+                .record_turn(angular_accel / physics.max_angular_accel);
         }
     }
 
     pub fn move_animals(&mut self) {
+        let mut before = std::mem::take(&mut self.scratch.positions);
+        before.clear();
+        before.extend(self.world.animals.iter().map(|animal| animal.position));
+
+        let size = self.world.size;
+        let trail_length = self.config.trail_length;
         for animal in &mut self.world.animals {
             // Unit vector for default direction is (1.0, 0.0)
-            let displacement = animal.rotation * na::Vector2::x() * animal.speed;
-            animal.position += displacement;
-            animal.position.x = na::wrap(animal.position.x, 0.0, 1.0);
-            animal.position.y = na::wrap(animal.position.y, 0.0, 1.0);
+            let displacement = animal.rotation * na::Vector2::x() * math::real(animal.speed);
+            let target = animal.position + displacement;
+            if self
+                .world
+                .walls
+                .iter()
+                .any(|wall| wall.intersects(animal.position, target))
+            {
+                animal.behavior.record_move(0.0, animal.speed);
+                animal.last_move = na::Vector2::zeros();
+                animal.record_trail(trail_length);
+                continue;
+            }
+            animal
+                .behavior
+                .record_move(math::wide(math::norm(&displacement)), animal.speed);
+            animal.position = math::wrap(target, size);
+            animal.last_move = displacement;
+            animal.record_trail(trail_length);
+        }
+        for animal in &self.world.animals {
+            self.heatmap.record(animal.position, size);
         }
+
+        self.shape_rewards(&before);
+        self.scratch.positions = before;
     }
 
-    pub fn eat_food(&mut self, rng: &mut dyn RngCore) {
-        const ANIMAL_SIZE: f64 = 0.015;
-        const FOOD_SIZE: f64 = 0.005;
+    fn shape_rewards(&mut self, before: &[na::Point2<Real>]) {
+        let food_approach = FoodApproach {
+            weight: self.config.reward.food_approach,
+        };
+        let mut shapers: Vec<&dyn RewardShaper> = Vec::new();
+        if food_approach.weight != 0.0 {
+            shapers.push(&food_approach);
+        }
+        if let Some(shaper) = &self.reward_shaper {
+            shapers.push(shaper.as_ref());
+        }
+        if shapers.is_empty() {
+            return;
+        }
 
+        let rewards: Vec<f64> = self
+            .world
+            .animals
+            .iter()
+            .zip(before)
+            .map(|(animal, &before)| {
+                shapers
+                    .iter()
+                    .map(|shaper| shaper.reward(before, animal, &self.world))
+                    .sum()
+            })
+            .collect();
+        for (animal, reward) in self.world.animals.iter_mut().zip(rewards) {
+            animal.shaped_reward += reward;
+        }
+    }
+
+    pub fn eat_food(&mut self, rng: &mut dyn RngCore) {
+        let continuous_collision = self.config.physics.continuous_collision;
+        let decay = self.config.reward.recency_decay;
+        let size = self.world.size;
+        // Respawns are transformed like the rest of the rollout's layout
+        let symmetry = self
+            .config
+            .rollouts
+            .get(self.rollout as usize)
+            .copied()
+            .unwrap_or_default();
+        let mut eaten = false;
         for animal in &mut self.world.animals {
-            for food in &mut self.world.food {
-                let dist = na::distance(&animal.position, &food.position);
-                if dist < ANIMAL_SIZE + FOOD_SIZE {
+            if let Some(score) = &mut animal.recent_score {
+                *score *= 1.0 - decay;
+            }
+
+            if self.config.mode == GameMode::Forage && animal.carrying {
+                if let Some(nest) = &self.world.nest {
+                    if nest.contains(animal.position) {
+                        animal.carrying = false;
+                        animal.deposited += 1;
+                        animal.add_recent_score();
+                    }
+                }
+                continue;
+            }
+
+            // Where the animal came from this step, unwrapped, to catch
+            // food it passed over
+            let start = animal.position - animal.last_move;
+            let mut private_food = animal.private_food.take();
+            let private = private_food.is_some();
+            for food in private_food.as_mut().unwrap_or(&mut self.world.food) {
+                let dist = if continuous_collision {
+                    math::segment_distance(food.position, start, animal.position)
+                } else {
+                    math::distance(&animal.position, &food.position)
+                };
+                if dist < animal.radius + food.radius {
                     animal.consumed += 1;
-                    food.randomize_position(rng);
+                    animal.behavior.record_meal(self.generation_steps);
+                    self.meal_heatmap.record(food.position, self.world.size);
+                    if private {
+                        let meal = animal.consumed as usize - 1;
+                        while self.food_respawns.len() <= meal {
+                            let position = Zone::random_position(rng, &self.world.food_zones, size);
+                            self.food_respawns.push(position);
+                        }
+                        food.position = symmetry.apply_position(self.food_respawns[meal], size);
+                    } else {
+                        food.randomize_position(rng, &self.world.food_zones, size);
+                    }
+                    eaten = true;
+                    if self.config.mode == GameMode::Forage {
+                        animal.carrying = true;
+                        break;
+                    }
+                    animal.add_recent_score();
                 }
             }
+            animal.private_food = private_food;
+        }
+        if eaten {
+            self.world.food_version += 1;
         }
     }
 
     pub fn evolve(&mut self, rng: &mut dyn RngCore) {
+        let _span = tracing::debug_span!("evolve", generation = self.generation).entered();
         self.generation += 1;
         self.generation_steps = 0;
+        self.reset_generation_end();
 
-        let curr_population: Vec<AnimalIndividual> = self
-            .world
-            .animals
-            .iter()
-            .map(|animal| AnimalIndividual::from_animal(animal))
+        let culled = std::mem::take(&mut self.culled);
+        let animals = || self.world.animals.iter().chain(&culled);
+        let mut curr_population: Vec<AnimalIndividual> = animals()
+            .map(|animal| AnimalIndividual::from_animal(animal, self.config.mode))
             .collect();
-        self.generation_statistics
-            .push(GenerationStatistics::from_population(&curr_population));
+        let behaviors: Vec<_> = animals().map(|animal| &animal.behavior).collect();
+        let behavior = BehaviorStatistics::from_behaviors(behaviors.into_iter());
+        let cells = self.config.heatmap_cells;
+        let meal_locations = std::mem::replace(&mut self.meal_heatmap, Heatmap::new(cells, cells));
+        let statistics = GenerationStatistics::from_population(&curr_population)
+            .with_behavior(behavior)
+            .with_meal_locations(meal_locations);
+        tracing::debug!(
+            max_fitness = statistics.max_fitness,
+            mean_fitness = statistics.mean_fitness,
+            culled = culled.len(),
+            "generation over"
+        );
+        self.generation_statistics.push(statistics);
+        self.forget_old_genomes();
+        let heatmap = std::mem::replace(&mut self.heatmap, Heatmap::new(cells, cells));
+        self.prev_heatmap = Some(heatmap);
+
+        let race = ga::Race::new(self.config.race.top, self.config.race.rollouts);
+        if race.is_enabled() {
+            let seeds: Vec<u64> = (0..race.rollouts).map(|_| rng.gen()).collect();
+            let reruns: Vec<Vec<f64>> = seeds
+                .iter()
+                .map(|&seed| self.rerun_generation(&curr_population, seed))
+                .collect();
+            let fitnesses = race.fitnesses(&curr_population, &mut |idx, _, rollout| {
+                reruns[rollout][idx]
+            });
+            for (individual, fitness) in curr_population.iter_mut().zip(fitnesses) {
+                individual.fitness = fitness;
+            }
+        }
 
-        let new_population: Vec<Animal> = self
-            .evolver
-            .evolve(rng, &curr_population)
+        let population = ga::Population::new(curr_population);
+        let immigrants = RandomBrains::new(&self.config);
+        let immigrant_fraction = self.config.mutation.immigrants;
+        let children = match &self.custom_evolver {
+            Some(evolver) => {
+                evolver.evolve_with_immigrants(rng, &population, immigrant_fraction, &immigrants)
+            }
+            None => self.config.genetic_algorithm().evolve_with_immigrants(
+                rng,
+                &population,
+                immigrant_fraction,
+                &immigrants,
+            ),
+        };
+        let new_population: Vec<Animal> = children
             .into_iter()
-            .map(|individual| individual.into_animal(rng))
+            .map(|individual| {
+                let mut animal = individual.into_animal(rng, &self.config);
+                self.world.place_animal(rng, &mut animal);
+                animal.id = self.world.new_animal_id();
+                animal
+            })
             .collect();
 
         self.world.animals = new_population;
+        self.attach_sensors();
 
         for food in &mut self.world.food {
-            food.randomize_position(rng);
+            food.randomize_position(rng, &self.world.food_zones, self.world.size);
+        }
+        self.world.food_version += 1;
+        self.rollout = 0;
+        self.rollout_food = self.world.food.iter().map(|food| food.position).collect();
+        self.food_respawns.clear();
+        self.deal_food();
+    }
+
+    /// Fitness of every individual of `population` after living a whole
+    /// generation together again, in the environment `seed` makes, so
+    /// races compare scores earned at the same density and with the same
+    /// sensor and reward shaper as the generation being evolved.
+    fn rerun_generation(&mut self, population: &[AnimalIndividual], seed: u64) -> Vec<f64> {
+        let mut rng = vroom_rand::seeded(seed);
+        let mut rerun = Simulation::from_config(&mut rng, tournament::bounded_config(&self.config));
+        rerun.world.animals.clear();
+        for individual in population {
+            let genome = individual.as_chromosome().iter().copied().collect();
+            rerun
+                .add_animal_with_brain(&mut rng, genome)
+                .expect("Genome doesn't match the config");
+        }
+        rerun.reward_shaper = self.reward_shaper.take();
+        rerun.sensor_factory = self.sensor_factory.take();
+        rerun.attach_sensors();
+
+        let fitnesses = rerun.evaluate(&mut rng);
+        self.reward_shaper = rerun.reward_shaper.take();
+        self.sensor_factory = rerun.sensor_factory.take();
+        fitnesses
+    }
+
+    /// Whether the generation has rollouts to run after the current one.
+    /// Ending it with `force_evolve` skips them.
+    fn rollouts_left(&self) -> bool {
+        !self.evolve_requested && (self.rollout as usize + 1) < self.config.rollouts.len()
+    }
+
+    /// Starts the generation over with the same population, from its
+    /// starting layout transformed by the next symmetry in
+    /// `config.rollouts`. Food added since the generation started stays
+    /// where it is.
+    fn next_rollout(&mut self) {
+        let _span = tracing::debug_span!("next_rollout", rollout = self.rollout).entered();
+        self.rollout += 1;
+        self.generation_steps = 0;
+        self.reset_generation_end();
+
+        let symmetry = self.config.rollouts[self.rollout as usize];
+        let (mode, size) = (self.config.mode, self.world.size);
+        for animal in &mut self.world.animals {
+            animal.start_rollout(mode, symmetry, size);
+        }
+        for (food, &position) in self.world.food.iter_mut().zip(&self.rollout_food) {
+            food.position = symmetry.apply_position(position, size);
+        }
+        self.world.food_version += 1;
+        self.deal_food();
+    }
+
+    /// Drops the per-genome statistics of the generation that just became
+    /// older than `config.genome_history`.
+    fn forget_old_genomes(&mut self) {
+        let history = self.config.genome_history;
+        let len = self.generation_statistics.len();
+        if history > 0 && len > history {
+            self.generation_statistics[len - history - 1].forget_genomes();
+        }
+    }
+
+    /// Runs the rest of the current generation, through all its rollouts,
+    /// without evolving, and returns every animal's fitness. Never returns
+    /// under `GenerationEnd::Manual` unless `force_evolve` is called.
+    pub fn evaluate(&mut self, rng: &mut dyn RngCore) -> Vec<f64> {
+        while self.phase() != Phase::Evolve || self.rollouts_left() {
+            self.step_phase(rng);
+        }
+        self.fitnesses()
+    }
+
+    /// Steps until the current generation evolves, returning its statistics.
+    pub fn train(&mut self, rng: &mut dyn RngCore) -> &GenerationStatistics {
+        let generation = self.generation;
+        while self.generation == generation {
+            self.step(rng);
         }
+        self.generation_statistics.last().unwrap()
     }
 
+    /// Runs the rest of the current step, or a whole step between steps.
     pub fn step(&mut self, rng: &mut dyn RngCore) {
-        self.generation_steps += 1;
-        if self.generation_steps > GENERATION_STEPS {
-            self.evolve(rng);
+        self.step_phase(rng);
+        while self.pending_phase.is_some() {
+            self.step_phase(rng);
+        }
+    }
+
+    /// Makes the population evolve at the end of the current step, or as
+    /// the next step between steps, whatever `config.generation_end` says.
+    pub fn force_evolve(&mut self) {
+        self.evolve_requested = true;
+    }
+
+    /// Whether the current generation is over according to
+    /// `config.generation_end`. Checked at the end of every step, which
+    /// then evolves the population.
+    pub fn generation_over(&self) -> bool {
+        let steps_done = self.generation_steps >= self.config.generation_steps;
+        self.evolve_requested
+            || match self.config.generation_end {
+                GenerationEnd::Steps => steps_done,
+                GenerationEnd::FoodEaten if self.config.private_food => {
+                    // Each animal has a whole layout of its own to clear
+                    let cleared = !self.world.animals.is_empty()
+                        && self.world.animals.iter().all(|animal| {
+                            let food = animal.private_food().map_or(0, <[Food]>::len) as u32;
+                            food > 0 && animal.consumed >= food
+                        });
+                    steps_done || cleared
+                }
+                GenerationEnd::FoodEaten => {
+                    let meals: u32 = self
+                        .world
+                        .animals
+                        .iter()
+                        .chain(&self.culled)
+                        .map(|animal| animal.consumed)
+                        .sum();
+                    let food = self.world.food.len() as u32;
+                    steps_done || (food > 0 && meals >= food)
+                }
+                GenerationEnd::FitnessPlateau => {
+                    let stale = self.generation_steps.saturating_sub(self.best_fitness_step);
+                    steps_done || stale >= self.config.plateau_steps
+                }
+                GenerationEnd::Manual => false,
+            }
+    }
+
+    pub(crate) fn reset_generation_end(&mut self) {
+        self.best_fitness = 0.0;
+        self.best_fitness_step = 0;
+        self.evolve_requested = false;
+    }
+
+    fn track_best_fitness(&mut self) {
+        if self.config.generation_end != GenerationEnd::FitnessPlateau {
+            return;
+        }
+        let best = self
+            .world
+            .animals
+            .iter()
+            .map(|animal| self.config.mode.fitness(animal))
+            .fold(0.0, f64::max);
+        if best > self.best_fitness {
+            self.best_fitness = best;
+            self.best_fitness_step = self.generation_steps;
+        }
+    }
+
+    /// The phase the next call to `step_phase` runs.
+    pub fn phase(&self) -> Phase {
+        self.pending_phase.unwrap_or(if self.generation_over() {
+            Phase::Evolve
         } else {
-            self.eat_food(rng);
-            self.process_brains();
-            self.move_animals();
+            self.config.pipeline[0]
+        })
+    }
+
+    /// Runs a single phase of a step and returns it, so callers can act on
+    /// the world in between phases. A step runs the phases of
+    /// `config.pipeline` in order, followed by `Evolve` if that was the
+    /// generation's last step. A step between steps is just `Evolve` when
+    /// the generation was ended from outside, see `force_evolve`.
+    pub fn step_phase(&mut self, rng: &mut dyn RngCore) -> Phase {
+        let phase = self.phase();
+        let _span = tracing::trace_span!("step_phase", ?phase).entered();
+        if phase == Phase::Evolve {
+            if self.rollouts_left() {
+                self.next_rollout();
+            } else {
+                self.evolve(rng);
+            }
+            self.pending_phase = None;
+            return phase;
+        }
+
+        let pipeline = &self.config.pipeline;
+        let idx = pipeline
+            .iter()
+            .position(|&step| step == phase)
+            .expect("Pending phase not in the pipeline");
+        let next = pipeline.get(idx + 1).copied();
+        if idx == 0 {
+            self.generation_steps += 1;
+        }
+        match phase {
+            Phase::EatFood => self.eat_food(rng),
+            Phase::ProcessBrains => self.process_brains(rng),
+            Phase::MoveAnimals => self.move_animals(),
+            Phase::Evolve => unreachable!(),
+        }
+        self.pending_phase = next;
+        if next.is_none() {
+            // The generation's last step finishes before it evolves
+            self.track_best_fitness();
+            self.pending_phase = self.generation_over().then_some(Phase::Evolve);
+        }
+        phase
+    }
+}
+
+/// Leaves out the genetic algorithm and plug-ins. Not `Clone`, since
+/// plug-ins such as reward shapers and custom genetic algorithms are boxed
+/// trait objects; clone the `World` and `Config` instead.
+impl fmt::Debug for Simulation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Simulation")
+            .field("generation", &self.generation)
+            .field("generation_steps", &self.generation_steps)
+            .field("pending_phase", &self.pending_phase)
+            .field("config", &self.config)
+            .field("world", &self.world)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{
+        BrainEncoding, CrossoverKind, ModuleConfig, MutationKind, RaceConfig, SelectionKind,
+    };
+    use crate::eye::Eye;
+    use crate::sensor::Sensor;
+    use crate::symmetry::Symmetry;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_serde_roundtrip() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut simulation = Simulation::random(&mut rng, 4, 8);
+        for _ in 0..10 {
+            simulation.step(&mut rng);
+        }
+
+        let bytes = serde_json::to_vec(&simulation).unwrap();
+        let mut restored: Simulation = serde_json::from_slice(&bytes).unwrap();
+
+        let mut rng_copy = rng.clone();
+        simulation.step(&mut rng);
+        restored.step(&mut rng_copy);
+        for (expected, actual) in simulation
+            .world
+            .animals
+            .iter()
+            .zip(restored.world.animals.iter())
+        {
+            assert_eq!(expected.position, actual.position);
+            assert_eq!(expected.rotation, actual.rotation);
+        }
+        assert_eq!(simulation.generation_steps, restored.generation_steps);
+    }
+
+    #[test]
+    fn test_step_phases() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let config = Config {
+            num_animals: 4,
+            num_food: 8,
+            generation_steps: 2,
+            ..Config::default()
+        };
+        let mut stepped = Simulation::from_config(&mut rng.clone(), config.clone());
+        let mut phased = Simulation::from_config(&mut rng.clone(), config);
+        let mut phased_rng = rng.clone();
+
+        let mut phases = Vec::new();
+        for _ in 0..3 {
+            stepped.step(&mut rng);
+            loop {
+                let phase = phased.step_phase(&mut phased_rng);
+                phases.push(phase);
+                if phased.pending_phase.is_none() {
+                    break;
+                }
+            }
+            assert_eq!(phased.state_hash(), stepped.state_hash());
+        }
+        assert_eq!(
+            phases,
+            [
+                Phase::ProcessBrains,
+                Phase::MoveAnimals,
+                Phase::EatFood,
+                Phase::ProcessBrains,
+                Phase::MoveAnimals,
+                Phase::EatFood,
+                Phase::Evolve,
+                Phase::ProcessBrains,
+                Phase::MoveAnimals,
+                Phase::EatFood,
+            ]
+        );
+        assert_eq!(phased.generation(), 1);
+    }
+
+    #[test]
+    fn test_custom_pipeline() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let config = Config {
+            num_animals: 2,
+            num_food: 4,
+            pipeline: vec![Phase::EatFood, Phase::MoveAnimals],
+            ..Config::default()
+        };
+        let mut simulation = Simulation::from_config(&mut rng, config);
+
+        assert_eq!(simulation.step_phase(&mut rng), Phase::EatFood);
+        assert_eq!(simulation.generation_steps(), 1);
+        assert_eq!(simulation.step_phase(&mut rng), Phase::MoveAnimals);
+        assert_eq!(simulation.phase(), Phase::EatFood);
+        // Brains never ran
+        assert!(simulation.world.animals[0].vision().is_empty());
+
+        let mut config = simulation.config().clone();
+        config.pipeline = vec![Phase::MoveAnimals, Phase::Evolve, Phase::MoveAnimals];
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_generation_end() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let config = Config {
+            num_animals: 2,
+            num_food: 2,
+            generation_steps: 10,
+            generation_end: GenerationEnd::FoodEaten,
+            ..Config::default()
+        };
+        let mut simulation = Simulation::from_config(&mut rng, config);
+        simulation.world.animals[0].consumed = 1;
+        assert!(!simulation.generation_over());
+        simulation.world.animals[1].consumed = 1;
+        assert!(simulation.generation_over());
+
+        simulation.config.generation_end = GenerationEnd::FitnessPlateau;
+        simulation.config.plateau_steps = 3;
+        // The best fitness so far is recorded after the first step.
+        for _ in 0..3 {
+            simulation.step(&mut rng);
         }
+        assert!(!simulation.generation_over());
+        // The step that hits the plateau also evolves
+        simulation.step(&mut rng);
+        assert_eq!(simulation.generation(), 1);
+
+        simulation.config.generation_end = GenerationEnd::Manual;
+        simulation.generation_steps = 100;
+        assert!(!simulation.generation_over());
+        assert_eq!(simulation.steps_remaining_in_generation(), None);
+        simulation.force_evolve();
+        assert_eq!(simulation.phase(), Phase::Evolve);
+        simulation.step(&mut rng);
+        assert_eq!(simulation.generation(), 2);
+        assert!(!simulation.generation_over());
+    }
+
+    #[test]
+    fn test_mirrored_rollouts() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let config = Config {
+            num_animals: 2,
+            num_food: 3,
+            generation_steps: 2,
+            rollouts: vec![Symmetry::Identity, Symmetry::MirrorX],
+            ..Config::default()
+        };
+        let mut simulation = Simulation::from_config(&mut rng, config);
+        let spawn = simulation.world.animals[0].position;
+        let food: Vec<_> = simulation
+            .world
+            .food
+            .iter()
+            .map(|food| food.position)
+            .collect();
+        assert_eq!(simulation.steps_remaining_in_generation(), Some(4));
+
+        simulation.step(&mut rng);
+        for _ in 0..3 {
+            simulation.step_phase(&mut rng);
+        }
+        simulation.world.animals[0].consumed = 3;
+        assert_eq!(simulation.step_phase(&mut rng), Phase::Evolve);
+        assert_eq!((simulation.generation(), simulation.rollout()), (0, 1));
+        assert_eq!(simulation.generation_steps(), 0);
+        assert_eq!(simulation.steps_remaining_in_generation(), Some(2));
+
+        // Everyone starts over from the mirrored layout
+        let animal = &simulation.world.animals[0];
+        assert_eq!(animal.consumed, 0);
+        approx::assert_relative_eq!(animal.position.x, 1.0 - spawn.x);
+        approx::assert_relative_eq!(animal.position.y, spawn.y);
+        for (food, before) in simulation.world.food.iter().zip(&food) {
+            approx::assert_relative_eq!(food.position.x, 1.0 - before.x);
+        }
+
+        // Fitness is averaged over both rollouts
+        simulation.world.animals[0].consumed = 1;
+        assert_eq!(simulation.fitnesses()[0], 2.0);
+        simulation.step(&mut rng);
+        simulation.step(&mut rng);
+        assert_eq!((simulation.generation(), simulation.rollout()), (1, 0));
+        assert!(simulation.world.animals[0].rollout_fitness.is_empty());
+    }
+
+    #[test]
+    fn test_rollouts_weigh_equally() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut simulation = Simulation::random(&mut rng, 1, 0);
+        let size = simulation.world.size;
+        let animal = &mut simulation.world.animals[0];
+
+        for (score, symmetry) in [(3, Symmetry::MirrorX), (0, Symmetry::MirrorY)] {
+            animal.consumed = score;
+            animal.start_rollout(GameMode::Eat, symmetry, size);
+        }
+        assert_eq!(animal.rollout_fitness, [3.0, 0.0]);
+        assert_eq!(GameMode::Eat.fitness(animal), 1.0);
+    }
+
+    #[test]
+    fn test_race() {
+        /// Remembers the fitnesses it selects on.
+        struct Recording(Rc<RefCell<Vec<f64>>>);
+
+        impl ga::Selection for Recording {
+            fn select_indices(
+                &self,
+                rng: &mut dyn RngCore,
+                fitnesses: &[f64],
+                cnt: u32,
+            ) -> Vec<usize> {
+                *self.0.borrow_mut() = fitnesses.to_vec();
+                ga::FitnessProportionateSelection::new().select_indices(rng, fitnesses, cnt)
+            }
+        }
+
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let config = Config {
+            num_animals: 4,
+            num_food: 16,
+            generation_steps: 20,
+            race: RaceConfig {
+                top: 1,
+                rollouts: 1,
+            },
+            ..Config::default()
+        };
+        let mut simulation = Simulation::from_config(&mut rng, config);
+        let selected = Rc::new(RefCell::new(Vec::new()));
+        simulation.set_genetic_algorithm(Some(ga::GeneticAlgorithm::new(
+            Box::new(Recording(selected.clone())),
+            Box::new(ga::UniformCrossover::new()),
+            Box::new(ga::GaussianMutation::new(0.1, 0.1)),
+        )));
+        // A lucky generation the rerun won't repeat
+        simulation.world.animals[0].consumed = 1000;
+
+        // Reruns put the whole population back in the same conditions,
+        // repeatably
+        let population: Vec<AnimalIndividual> = simulation
+            .world
+            .animals
+            .iter()
+            .map(|animal| AnimalIndividual::from_animal(animal, simulation.config.mode))
+            .collect();
+        let seed: u64 = rng.clone().gen();
+        let rerun = simulation.rerun_generation(&population, seed);
+        assert_eq!(rerun.len(), 4);
+        assert_eq!(rerun, simulation.rerun_generation(&population, seed));
+
+        let lived = simulation.fitnesses();
+        simulation.evolve(&mut rng);
+        assert_eq!(simulation.world.animals.len(), 4);
+        // Statistics still report the generation as it was lived
+        assert_eq!(
+            simulation.prev_generation_statistics().unwrap().max_fitness,
+            1000.0
+        );
+        // Selection saw the raced fitness of the fittest, the rest as lived
+        let selected = selected.borrow();
+        assert_eq!(selected[0], (1000.0 + rerun[0]) / 2.0);
+        assert_eq!(selected[1..], lived[1..]);
+
+        // Reruns keep the simulation's reward shaper
+        struct Constant;
+        impl RewardShaper for Constant {
+            fn reward(&self, _: na::Point2<Real>, _: &Animal, _: &World) -> f64 {
+                0.5
+            }
+        }
+        simulation.set_reward_shaper(Some(Box::new(Constant)));
+        let rerun = simulation.rerun_generation(&population, seed);
+        assert!(rerun.iter().all(|&fitness| fitness >= 20.0 * 0.5));
+        assert!(simulation.reward_shaper.is_some());
+    }
+
+    #[test]
+    fn test_last_step_finishes_before_evolving() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let config = Config {
+            num_animals: 2,
+            num_food: 4,
+            generation_steps: 3,
+            ..Config::default()
+        };
+        let mut simulation = Simulation::from_config(&mut rng, config);
+
+        for remaining in [3, 2] {
+            assert_eq!(simulation.steps_remaining_in_generation(), Some(remaining));
+            simulation.step(&mut rng);
+        }
+        assert_eq!(simulation.steps_remaining_in_generation(), Some(1));
+        let phases: Vec<_> = (0..4).map(|_| simulation.step_phase(&mut rng)).collect();
+        assert_eq!(
+            phases,
+            [
+                Phase::ProcessBrains,
+                Phase::MoveAnimals,
+                Phase::EatFood,
+                Phase::Evolve
+            ]
+        );
+        assert_eq!(simulation.generation(), 1);
+        assert_eq!(simulation.steps_remaining_in_generation(), Some(3));
+
+        let config = Config {
+            num_animals: 2,
+            num_food: 4,
+            generation_steps: u32::MAX,
+            rollouts: vec![Symmetry::Identity, Symmetry::MirrorX],
+            ..Config::default()
+        };
+        let simulation = Simulation::from_config(&mut rng, config);
+        assert_eq!(simulation.steps_remaining_in_generation(), Some(u32::MAX));
+    }
+
+    #[test]
+    fn test_state_hash_tracks_seeded_runs() {
+        let mut rng1 = vroom_rand::seeded(7);
+        let mut rng2 = vroom_rand::seeded(7);
+        let mut simulation1 = Simulation::random(&mut rng1, 4, 8);
+        let mut simulation2 = Simulation::random(&mut rng2, 4, 8);
+
+        for _ in 0..50 {
+            assert_eq!(simulation1.state_hash(), simulation2.state_hash());
+            simulation1.step(&mut rng1);
+            simulation2.step(&mut rng2);
+        }
+
+        let before = simulation1.state_hash();
+        simulation1.step(&mut rng1);
+        assert_ne!(simulation1.state_hash(), before);
+    }
+
+    #[test]
+    fn test_live_physics_changes() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut simulation = Simulation::random(&mut rng, 2, 0);
+
+        let physics = PhysicsConfig {
+            min_speed: 0.002,
+            max_speed: 0.002,
+            ..PhysicsConfig::default()
+        };
+        simulation.set_physics(physics).unwrap();
+        simulation.act(&[vec![1.0, 0.0], vec![-1.0, 0.0]]);
+        for animal in &simulation.world.animals {
+            assert_eq!(animal.speed, 0.002);
+        }
+
+        let invalid = PhysicsConfig {
+            max_speed: 0.0,
+            ..PhysicsConfig::default()
+        };
+        assert!(simulation.set_physics(invalid).is_err());
+        assert_eq!(simulation.config.physics.max_speed, 0.002);
+
+        for _ in 0..5 {
+            simulation.step(&mut rng);
+        }
+        simulation.set_generation_length(3).unwrap();
+        simulation.step(&mut rng);
+        assert_eq!(simulation.generation(), 1);
+        assert!(simulation.set_generation_length(0).is_err());
+    }
+
+    #[test]
+    fn test_reward_shaping() {
+        struct Constant;
+        impl RewardShaper for Constant {
+            fn reward(&self, _: na::Point2<Real>, _: &Animal, _: &World) -> f64 {
+                0.5
+            }
+        }
+
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let config = Config {
+            num_animals: 1,
+            num_food: 1,
+            ..Config::default()
+        };
+        let mut simulation = Simulation::from_config(&mut rng, config);
+        simulation.world.food[0].position = na::Point2::new(0.5, 0.5);
+        let animal = &mut simulation.world.animals[0];
+        animal.position = na::Point2::new(0.2, 0.5);
+        animal.rotation = na::Rotation2::new(0.0);
+        animal.speed = 0.005;
+
+        simulation.move_animals();
+        assert_eq!(simulation.fitnesses(), vec![0.0]);
+
+        simulation.config.reward.food_approach = 10.0;
+        simulation.move_animals();
+        let epsilon = math::wide(Real::EPSILON);
+        approx::assert_relative_eq!(simulation.fitnesses()[0], 0.05, epsilon = epsilon);
+
+        simulation.set_reward_shaper(Some(Box::new(Constant)));
+        simulation.move_animals();
+        approx::assert_relative_eq!(simulation.fitnesses()[0], 0.6, epsilon = epsilon);
+    }
+
+    #[test]
+    fn test_actuator_faults() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut simulation = Simulation::random(&mut rng, 1, 0);
+        let actions = vec![Buffer::from_iter([0.1, 0.2])];
+        let mut faulty = actions.clone();
+        simulation.apply_actuator_faults(&mut rng, &mut faulty);
+        assert_eq!(faulty, actions);
+
+        simulation.config.actuator.noise = 0.5;
+        simulation.apply_actuator_faults(&mut rng, &mut faulty);
+        assert_ne!(faulty, actions);
+
+        simulation.config.actuator.stuck = 1.0;
+        let mut stuck = vec![Buffer::from_iter([1.0, 1.0])];
+        simulation.apply_actuator_faults(&mut rng, &mut stuck);
+        assert_eq!(stuck, faulty);
+    }
+
+    #[test]
+    fn test_killed_animals_still_evolve() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut simulation = Simulation::random(&mut rng, 3, 0);
+        simulation.world.animals[1].consumed = 5;
+
+        assert!(simulation.kill_animal(1));
+        assert!(!simulation.kill_animal(1));
+        let ids = |simulation: &Simulation| -> Vec<u64> {
+            simulation.world.animals.iter().map(Animal::id).collect()
+        };
+        assert_eq!(ids(&simulation), [0, 2]);
+
+        simulation.evolve(&mut rng);
+        assert_eq!(ids(&simulation), [3, 4, 5]);
+        assert!(simulation.culled.is_empty());
+        let stats = simulation.prev_generation_statistics().unwrap();
+        assert_eq!(stats.max_fitness, 5.0);
+    }
+
+    #[test]
+    fn test_custom_genetic_algorithm() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut simulation = Simulation::random(&mut rng, 3, 0);
+        simulation.set_genetic_algorithm(Some(ga::GeneticAlgorithm::new(
+            Box::new(ga::FitnessProportionateSelection::new()),
+            Box::new(ga::UniformCrossover::new()),
+            Box::new(ga::ProbabilisticMutation::new(
+                0.0,
+                ga::GaussianMutation::new(1.0, 1.0),
+            )),
+        )));
+        simulation.world.animals[0].consumed = 1;
+        let parent = simulation.world.animals[0].as_chromosome();
+
+        simulation.evolve(&mut rng);
+
+        // Only one animal can be selected and nothing gets mutated
+        for animal in &simulation.world.animals {
+            assert!(animal.as_chromosome().iter().eq(parent.iter()));
+        }
+    }
+
+    #[test]
+    fn test_immigrants() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut simulation = Simulation::random(&mut rng, 4, 0);
+        simulation.config.mutation.rate = 0.0;
+        simulation.config.mutation.immigrants = 0.5;
+        simulation.world.animals[0].consumed = 1;
+        let parent = simulation.world.animals[0].as_chromosome();
+
+        simulation.evolve(&mut rng);
+
+        let is_parent = |animal: &Animal| animal.as_chromosome().iter().eq(parent.iter());
+        let animals = &simulation.world.animals;
+        assert_eq!(animals.len(), 4);
+        assert!(animals[..2].iter().all(is_parent));
+        assert!(!animals[2..].iter().any(is_parent));
+    }
+
+    #[test]
+    fn test_blend_parents() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut simulation = Simulation::random(&mut rng, 4, 0);
+        simulation.config.mutation.rate = 0.0;
+        simulation.config.mutation.blend = 1.0;
+        simulation.world.animals[0].consumed = 1;
+        simulation.world.animals[1].consumed = 1;
+        let parents = [
+            simulation.world.animals[0].brain.weights_and_biases(),
+            simulation.world.animals[1].brain.weights_and_biases(),
+        ];
+        let blend: Vec<f64> = parents[0]
+            .iter()
+            .zip(&parents[1])
+            .map(|(a, b)| (a + b) / 2.0)
+            .collect();
+
+        simulation.evolve(&mut rng);
+
+        // Either parent may have been picked twice, which blends to a copy
+        let close = |a: &[f64], b: &[f64]| a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-12);
+        let mut blended = 0;
+        for animal in &simulation.world.animals {
+            let genes = animal.brain.weights_and_biases();
+            if close(&genes, &blend) {
+                blended += 1;
+            } else {
+                assert!(parents.iter().any(|parent| close(&genes, parent)));
+            }
+        }
+        assert!(blended > 0);
+    }
+
+    #[test]
+    fn test_blend_keeps_activations() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut config = Config {
+            num_animals: 4,
+            ..Config::default()
+        };
+        config.brain.evolve_activations = true;
+        config.mutation.rate = 0.0;
+        config.mutation.blend = 1.0;
+        let mut simulation = Simulation::from_config(&mut rng, config);
+        let genome_length = simulation.config.genome_length();
+        let parents = [[0.0, 0.0], [3.0, 3.0]];
+        for (idx, activations) in parents.iter().enumerate() {
+            let mut genes: Vec<f64> = simulation.world.animals[idx]
+                .as_chromosome()
+                .iter()
+                .copied()
+                .collect();
+            genes[genome_length - 2..].copy_from_slice(activations);
+            let mut animal =
+                Animal::from_chromosome(&mut rng, genes.into_iter().collect(), &simulation.config);
+            animal.consumed = 1;
+            simulation.world.animals[idx] = animal;
+        }
+
+        simulation.evolve(&mut rng);
+
+        // Averaging the genes would pick an activation neither parent had
+        for animal in &simulation.world.animals {
+            let chromosome = animal.as_chromosome();
+            let activations: Vec<f64> =
+                chromosome.iter().skip(genome_length - 2).copied().collect();
+            assert!(parents.iter().any(|parent| activations == parent));
+        }
+    }
+
+    #[test]
+    fn test_config_operators() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut simulation = Simulation::random(&mut rng, 4, 0);
+        simulation.config.mutation.rate = 0.0;
+        simulation.config.operators = OperatorConfig {
+            selection: SelectionKind::Tournament,
+            crossover: CrossoverKind::Neuron,
+            mutation: MutationKind::WholeGenome,
+            tournament_size: 64,
+        };
+        simulation.world.animals[0].consumed = 2;
+        simulation.world.animals[1].consumed = 1;
+        let parent = simulation.world.animals[0].as_chromosome();
+
+        simulation.evolve(&mut rng);
+
+        // Tournaments that large all but always draw the fittest, which
+        // proportionate selection would only pick two times in three
+        for animal in &simulation.world.animals {
+            assert!(animal.as_chromosome().iter().eq(parent.iter()));
+        }
+    }
+
+    #[test]
+    fn test_evolve_activations() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut config = Config {
+            num_animals: 8,
+            ..Config::default()
+        };
+        config.brain.evolve_activations = true;
+        let mut simulation = Simulation::from_config(&mut rng, config);
+
+        let genome_length = simulation.config.genome_length();
+        let animal = &simulation.world.animals[0];
+        let chromosome = animal.as_chromosome();
+        assert_eq!(chromosome.len(), genome_length);
+        let activations: Vec<_> = chromosome
+            .iter()
+            .skip(genome_length - 2)
+            .map(|&gene| nn::Activation::from_gene(gene))
+            .collect();
+        assert_eq!(activations, animal.brain.activations());
+
+        // Children's brains use the activations their genes pick
+        simulation.evolve(&mut rng);
+        for animal in &simulation.world.animals {
+            let activations: Vec<_> = animal
+                .as_chromosome()
+                .iter()
+                .skip(genome_length - 2)
+                .map(|&gene| nn::Activation::from_gene(gene))
+                .collect();
+            assert_eq!(activations, animal.brain.activations());
+        }
+    }
+
+    #[test]
+    fn test_plasticity() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut config = Config {
+            num_animals: 4,
+            ..Config::default()
+        };
+        config.brain.plasticity = true;
+        config.brain.evolve_activations = true;
+        let mut simulation = Simulation::from_config(&mut rng, config);
+
+        let genome_length = simulation.config.genome_length();
+        let chromosome = simulation.world.animals[0].as_chromosome();
+        assert_eq!(chromosome.len(), genome_length);
+        let born_with = simulation.world.animals[0].brain.weights_and_biases();
+
+        for _ in 0..10 {
+            simulation.step(&mut rng);
+        }
+
+        // Weights change during the animal's life but its genome doesn't
+        let animal = &simulation.world.animals[0];
+        assert_ne!(animal.brain.weights_and_biases(), born_with);
+        assert!(animal.as_chromosome().iter().eq(chromosome.iter()));
+
+        // Learned weights aren't inherited: children start from their genes
+        simulation.evolve(&mut rng);
+        for animal in &simulation.world.animals {
+            let born =
+                Animal::from_chromosome(&mut rng, animal.as_chromosome(), &simulation.config);
+            assert_eq!(
+                animal.brain.weights_and_biases(),
+                born.brain.weights_and_biases()
+            );
+        }
+    }
+
+    #[test]
+    fn test_cppn_encoding() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut config = Config {
+            num_animals: 4,
+            ..Config::default()
+        };
+        config.brain.encoding = BrainEncoding::Cppn;
+        let genome_length = config.genome_length();
+        config.eye.receptors *= 4;
+        config.brain.hidden_layers = vec![40];
+        assert_eq!(config.genome_length(), genome_length);
+        let mut simulation = Simulation::from_config(&mut rng, config);
+
+        let animal = &simulation.world.animals[0];
+        assert_eq!(animal.brain_topology(), simulation.config.brain_topology());
+        let chromosome = animal.as_chromosome();
+        assert_eq!(chromosome.len(), genome_length);
+        let rebuilt = Animal::from_chromosome(&mut rng, chromosome, &simulation.config);
+        assert_eq!(
+            rebuilt.brain.weights_and_biases(),
+            animal.brain.weights_and_biases()
+        );
+
+        // Children keep the full-size brain their CPPN genes describe
+        simulation.evolve(&mut rng);
+        for animal in &simulation.world.animals {
+            assert_eq!(animal.brain_topology(), simulation.config.brain_topology());
+            let rebuilt =
+                Animal::from_chromosome(&mut rng, animal.as_chromosome(), &simulation.config);
+            assert_eq!(
+                rebuilt.brain.weights_and_biases(),
+                animal.brain.weights_and_biases()
+            );
+        }
+    }
+
+    #[test]
+    fn test_modular_brain() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut config = Config {
+            num_animals: 4,
+            ..Config::default()
+        };
+        config.brain.modules = vec![
+            ModuleConfig {
+                inputs: 0..5,
+                hidden_layers: vec![4],
+                outputs: 1,
+                frozen: true,
+            },
+            ModuleConfig {
+                inputs: 5..10,
+                hidden_layers: vec![3],
+                outputs: 1,
+                frozen: false,
+            },
+        ];
+        config.mutation.rate = 1.0;
+        let mut simulation = Simulation::from_config(&mut rng, config);
+
+        let genome_length = simulation.config.genome_length();
+        let animal = &simulation.world.animals[0];
+        assert_eq!(animal.as_chromosome().len(), genome_length);
+        assert_eq!(animal.brain_topology(), (10, vec![7, 2]));
+        // The first module's hidden neurons don't see the second's inputs
+        let weights = animal.brain.weights_and_biases();
+        for neuron in 0..4 {
+            let inputs = neuron * 11 + 1..neuron * 11 + 11;
+            assert!(weights[inputs][5..].iter().all(|&weight| weight == 0.0));
+        }
+
+        // Only the second module evolves
+        let frozen = simulation.config.module_genes(0);
+        let parent = animal.as_chromosome();
+        simulation.world.animals[0].consumed = 1;
+        simulation.evolve(&mut rng);
+        for animal in &simulation.world.animals {
+            let child = animal.as_chromosome();
+            assert_eq!(child.len(), genome_length);
+            assert_eq!(
+                child.as_slice()[frozen.clone()],
+                parent.as_slice()[frozen.clone()]
+            );
+            assert_ne!(
+                child.as_slice()[frozen.end..],
+                parent.as_slice()[frozen.end..]
+            );
+        }
+
+        // So do immigrants' and a custom genetic algorithm's children
+        let is_frozen = |simulation: &Simulation| {
+            simulation.world.animals.iter().all(|animal| {
+                animal.as_chromosome().as_slice()[frozen.clone()]
+                    == parent.as_slice()[frozen.clone()]
+            })
+        };
+        simulation.config.mutation.immigrants = 0.5;
+        simulation.evolve(&mut rng);
+        assert!(is_frozen(&simulation));
+        simulation.set_genetic_algorithm(Some(ga::GeneticAlgorithm::new(
+            Box::new(ga::FitnessProportionateSelection::new()),
+            Box::new(ga::UniformCrossover::new()),
+            Box::new(ga::GaussianMutation::new(1.0, 1.0)),
+        )));
+        simulation.evolve(&mut rng);
+        assert!(is_frozen(&simulation));
+    }
+
+    #[test]
+    fn test_user_placed_entities() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut simulation = Simulation::random(&mut rng, 2, 4);
+
+        simulation.clear_food();
+        simulation.add_food(na::Point2::new(0.25, 0.75));
+        assert_eq!(simulation.world.food.len(), 1);
+        assert_eq!(
+            simulation.world.food[0].position,
+            na::Point2::new(0.25, 0.75)
+        );
+
+        let genome: Vec<f64> = simulation.world.animals[0]
+            .as_chromosome()
+            .into_iter()
+            .collect();
+        assert_eq!(
+            simulation.add_animal_with_brain(&mut rng, genome.clone()),
+            Some(2)
+        );
+        let added: Vec<f64> = simulation.world.animals[2]
+            .as_chromosome()
+            .into_iter()
+            .collect();
+        assert_eq!(added, genome);
+        assert_eq!(
+            simulation.add_animal_with_brain(&mut rng, genome[1..].to_vec()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_champion_reseed() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut simulation = Simulation::random(&mut rng, 3, 4);
+        simulation.step(&mut rng);
+
+        let genome: Vec<f64> = simulation
+            .champion()
+            .unwrap()
+            .as_chromosome()
+            .into_iter()
+            .collect();
+        let brain = BrainConfig {
+            seed_genome: Some(genome.clone()),
+            ..simulation.config.brain.clone()
+        };
+        simulation.reseed(&mut rng, brain).unwrap();
+
+        assert_eq!(simulation.generation_steps(), 0);
+        assert_eq!(simulation.world.animals.len(), 3);
+        for animal in &simulation.world.animals {
+            let actual: Vec<f64> = animal.as_chromosome().into_iter().collect();
+            assert_eq!(actual, genome);
+        }
+
+        let invalid = BrainConfig {
+            seed_genome: Some(vec![0.0]),
+            ..simulation.config.brain.clone()
+        };
+        assert!(simulation.reseed(&mut rng, invalid).is_err());
+    }
+
+    #[test]
+    fn test_genome_history() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let config = Config {
+            num_animals: 2,
+            num_food: 4,
+            generation_steps: 2,
+            genome_history: 2,
+            ..Config::default()
+        };
+        let mut simulation = Simulation::from_config(&mut rng, config);
+        while simulation.generation() < 3 {
+            simulation.step(&mut rng);
+        }
+
+        let kept: Vec<_> = simulation
+            .generation_statistics()
+            .iter()
+            .map(|stats| !stats.genes.is_empty())
+            .collect();
+        assert_eq!(kept, [false, true, true]);
+    }
+
+    #[test]
+    fn test_vision_refresh() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut simulation = Simulation::random(&mut rng, 1, 1);
+        let world = &mut simulation.world;
+        let animal = &mut world.animals[0];
+        animal.eye = Eye::default().with_refresh(0.01, 0.1);
+        animal.position = na::Point2::new(0.5, 0.5);
+        animal.rotation = na::Rotation2::new(0.0);
+        world.food[0].position = na::Point2::new(0.7, 0.5);
+
+        assert!(world.update_vision(0, &mut rng));
+        world.animals[0].position.x += 0.005;
+        assert!(!world.update_vision(0, &mut rng));
+        world.animals[0].rotation = na::Rotation2::new(0.2);
+        assert!(world.update_vision(0, &mut rng));
+
+        world.add_food(Food::new(na::Point2::new(0.6, 0.5)));
+        assert!(world.update_vision(0, &mut rng));
+        assert!(!world.update_vision(0, &mut rng));
+
+        // Custom sensors may read more than the food, so they always sense
+        struct Clock(std::cell::Cell<Real>);
+
+        impl Sensor for Clock {
+            fn len(&self) -> usize {
+                1
+            }
+
+            fn sense_into(
+                &self,
+                _: &mut dyn RngCore,
+                _: crate::sensor::Pose,
+                _: &World,
+                readings: &mut Buffer,
+            ) {
+                self.0.set(self.0.get() + 1.0);
+                readings.clear();
+                readings.push(self.0.get());
+            }
+        }
+
+        world.animals[0].custom_sensor = Some(Box::new(Clock(Default::default())));
+        assert!(world.update_vision(0, &mut rng));
+        assert!(world.update_vision(0, &mut rng));
+        assert_eq!(world.animals[0].vision(), [2.0]);
+    }
+
+    #[test]
+    fn test_custom_sensor() {
+        /// Senses how many other animals there are, once per eye receptor.
+        struct Crowd(usize);
+
+        impl Sensor for Crowd {
+            fn len(&self) -> usize {
+                self.0
+            }
+
+            fn sense_into(
+                &self,
+                _rng: &mut dyn RngCore,
+                _pose: crate::sensor::Pose,
+                world: &World,
+                readings: &mut Buffer,
+            ) {
+                readings.clear();
+                readings.resize(self.0, (world.animals.len() - 1) as Real);
+            }
+        }
+
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut simulation = Simulation::random(&mut rng, 3, 0);
+        let receptors = simulation.config.eye.receptors;
+        simulation
+            .set_sensor(Some(Box::new(move || Box::new(Crowd(receptors)))))
+            .unwrap();
+
+        simulation.step(&mut rng);
+        assert_eq!(simulation.world.animals[0].vision(), vec![2.0; receptors]);
+        simulation.force_evolve();
+        simulation.step(&mut rng);
+        simulation.step(&mut rng);
+        assert_eq!(simulation.world.animals[2].vision(), vec![2.0; receptors]);
+
+        simulation.set_sensor(None).unwrap();
+        simulation.step(&mut rng);
+        assert!(simulation.world.animals[0].custom_sensor.is_none());
+    }
+
+    #[test]
+    fn test_custom_sensor_must_match_brains() {
+        struct Blind;
+
+        impl Sensor for Blind {
+            fn len(&self) -> usize {
+                0
+            }
+
+            fn sense_into(
+                &self,
+                _: &mut dyn RngCore,
+                _: crate::sensor::Pose,
+                _: &World,
+                _: &mut Buffer,
+            ) {
+            }
+        }
+
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut simulation = Simulation::random(&mut rng, 1, 0);
+        assert!(simulation
+            .set_sensor(Some(Box::new(|| Box::new(Blind))))
+            .is_err());
+        assert!(simulation.world.animals[0].custom_sensor.is_none());
+    }
+
+    #[test]
+    fn test_nearest_food_sensor() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut config = Config {
+            num_animals: 4,
+            num_food: 8,
+            mode: GameMode::Forage,
+            ..Config::default()
+        };
+        config.eye.sensor = crate::sensor::SensorKind::NearestFood;
+        config.eye.nearest_food = 2;
+        assert_eq!(config.brain_topology().0, 2 * 2 + 3);
+        let mut simulation = Simulation::from_config(&mut rng, config);
+
+        simulation.step(&mut rng);
+        let animal = &simulation.world.animals[0];
+        assert_eq!(animal.vision().len(), 4);
+        assert!(animal.vision()[0] <= animal.vision()[2]);
+        assert!(animal.behavior.receptor_activations().is_empty());
+        simulation.force_evolve();
+        simulation.step(&mut rng);
+        assert_eq!(simulation.generation(), 1);
+        assert!(simulation.world.animals[0].nearest_food.is_some());
+    }
+
+    #[test]
+    fn test_vision_frames() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut config = Config {
+            num_animals: 1,
+            num_food: 8,
+            ..Config::default()
+        };
+        config.eye.frames = 3;
+        let receptors = config.eye.receptors;
+        assert_eq!(config.brain_topology().0, 3 * receptors);
+        let mut simulation = Simulation::from_config(&mut rng, config);
+
+        let first = simulation.observe(&mut rng).pop().unwrap();
+        assert_eq!(first.len(), 3 * receptors);
+        assert_eq!(first[..receptors], first[receptors..2 * receptors]);
+        assert_eq!(first[..receptors], first[2 * receptors..]);
+
+        simulation.world.animals[0].position = na::Point2::new(0.1, 0.9);
+        simulation.world.animals[0].vision_pose = None;
+        let second = simulation.observe(&mut rng).pop().unwrap();
+        assert_eq!(second[..receptors], *simulation.world.animals[0].vision());
+        assert_eq!(second[receptors..], first[..2 * receptors]);
+
+        simulation.step(&mut rng);
+    }
+
+    #[test]
+    fn test_animals_see_each_other() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut config = Config {
+            num_animals: 2,
+            num_food: 0,
+            ..Config::default()
+        };
+        config.eye.animal_receptors = true;
+        let receptors = config.eye.receptors;
+        let mut simulation = Simulation::from_config(&mut rng, config);
+        let animals = &mut simulation.world.animals;
+        animals[0].position = na::Point2::new(0.4, 0.5);
+        animals[0].rotation = na::Rotation2::new(0.0);
+        animals[1].position = na::Point2::new(0.6, 0.5);
+        animals[1].rotation = na::Rotation2::new(crate::math::consts::PI);
+
+        for observation in simulation.observe(&mut rng) {
+            let (food, animals) = observation.split_at(receptors);
+            assert!(food.iter().all(|&reading| reading == 2.0));
+            let seen: Vec<Real> = animals.iter().copied().filter(|&r| r < 2.0).collect();
+            assert_eq!(seen.len(), 1);
+            approx::assert_abs_diff_eq!(seen[0], 0.4, epsilon = 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_walls_block_movement() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let map = Map {
+            walls: vec![crate::map::Wall::new(
+                na::Point2::new(0.5, 0.0),
+                na::Point2::new(0.5, 1.0),
+            )],
+            ..Map::default()
+        };
+        let config = Config {
+            num_animals: 2,
+            num_food: 0,
+            ..Config::default()
+        };
+        let mut simulation = Simulation::from_map(&mut rng, &map, config);
+        for (animal, x) in simulation.world.animals.iter_mut().zip([0.499, 0.3]) {
+            animal.position = na::Point2::new(x, 0.5);
+            animal.rotation = na::Rotation2::new(0.0);
+            animal.speed = 0.005;
+        }
+
+        simulation.move_animals();
+
+        assert_eq!(
+            simulation.world.animals[0].position,
+            na::Point2::new(0.499, 0.5)
+        );
+        approx::assert_relative_eq!(simulation.world.animals[1].position.x, 0.305);
+    }
+
+    #[test]
+    fn test_trails() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let config = Config {
+            num_animals: 2,
+            num_food: 0,
+            trail_length: 3,
+            ..Config::default()
+        };
+        let mut simulation = Simulation::from_config(&mut rng, config);
+        for _ in 0..5 {
+            simulation.step(&mut rng);
+        }
+
+        let animal = &simulation.world.animals[0];
+        assert_eq!(animal.trail().len(), 3);
+        assert_eq!(*animal.trail().back().unwrap(), animal.position());
+        let payload = crate::payload::World::from_world(&simulation.world, false);
+        assert_eq!(payload.animals[0].trail.len(), 3);
+
+        simulation.set_trail_length(0).unwrap();
+        simulation.step(&mut rng);
+        assert!(simulation.world.animals[0].trail().is_empty());
+    }
+
+    #[test]
+    fn test_collision_radii() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let config = Config {
+            num_animals: 1,
+            num_food: 0,
+            animal_radius: 0.02,
+            food_radius: 0.03,
+            ..Config::default()
+        };
+        let mut simulation = Simulation::from_config(&mut rng, config);
+        let position = simulation.world.animals[0].position;
+        simulation.add_food(position + na::Vector2::new(0.045, 0.0));
+        assert_eq!(simulation.world.food[0].radius(), 0.03);
+
+        simulation.eat_food(&mut rng);
+        assert_eq!(simulation.world.animals[0].consumed, 1);
+
+        let payload = crate::payload::World::from_world(&simulation.world, false);
+        assert_eq!(payload.animals[0].radius, 0.02);
+    }
+
+    #[test]
+    fn test_private_food() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let config = Config {
+            num_animals: 2,
+            num_food: 0,
+            private_food: true,
+            ..Config::default()
+        };
+        let mut simulation = Simulation::from_config(&mut rng, config);
+        let food_position = na::Point2::new(0.5, 0.5);
+        simulation.add_food(food_position);
+        for animal in &mut simulation.world.animals {
+            animal.position = food_position;
+        }
+
+        // Both eat their own copy, which respawns at the same place
+        simulation.eat_food(&mut rng);
+        let animals = &simulation.world.animals;
+        assert!(animals.iter().all(|animal| animal.consumed == 1));
+        let respawned = animals[0].private_food().unwrap()[0].position;
+        assert_ne!(respawned, food_position);
+        assert_eq!(animals[1].private_food().unwrap()[0].position, respawned);
+        assert_eq!(simulation.world.food[0].position, food_position);
+        assert_eq!(simulation.world.food_for(Some(1))[0].position, respawned);
+
+        // Hashed, so replays notice diverging private food
+        let hash = simulation.state_hash();
+        simulation.world.animals[1].private_food.as_mut().unwrap()[0]
+            .position
+            .x += 0.1;
+        assert_ne!(simulation.state_hash(), hash);
+
+        // One meal each clears everyone's single piece, not one meal in all
+        simulation.config.generation_end = GenerationEnd::FoodEaten;
+        simulation.world.animals[1].consumed = 0;
+        assert!(!simulation.generation_over());
+        simulation.world.animals[1].consumed = 1;
+        assert!(simulation.generation_over());
+
+        simulation.evolve(&mut rng);
+        let layout = simulation.world.food[0].position;
+        for animal in &simulation.world.animals {
+            assert_eq!(animal.private_food().unwrap()[0].position, layout);
+        }
+    }
+
+    #[test]
+    fn test_continuous_collision() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let config = Config {
+            num_animals: 1,
+            num_food: 0,
+            ..Config::default()
+        };
+        let mut simulation = Simulation::from_config(&mut rng, config);
+        let animal = &mut simulation.world.animals[0];
+        animal.position = na::Point2::new(0.2, 0.5);
+        animal.rotation = na::Rotation2::new(0.0);
+        animal.speed = 0.1;
+        simulation.add_food(na::Point2::new(0.25, 0.5));
+
+        // Jumps from 0.2 right over the food to 0.3
+        simulation.move_animals();
+        simulation.eat_food(&mut rng);
+        assert_eq!(simulation.world.animals[0].consumed, 0);
+
+        simulation.config.physics.continuous_collision = true;
+        simulation.eat_food(&mut rng);
+        assert_eq!(simulation.world.animals[0].consumed, 1);
+    }
+
+    #[test]
+    fn test_recency_decay() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut config = Config {
+            num_animals: 2,
+            num_food: 0,
+            ..Config::default()
+        };
+        config.reward.recency_decay = 0.5;
+        let mut simulation = Simulation::from_config(&mut rng, config);
+        let position = simulation.world.animals[0].position;
+
+        simulation.add_food(position);
+        simulation.eat_food(&mut rng);
+        simulation.clear_food();
+        simulation.eat_food(&mut rng);
+        simulation.eat_food(&mut rng);
+
+        let animal = &simulation.world.animals[0];
+        assert_eq!(animal.consumed(), 1);
+        assert_eq!(animal.recent_score(), Some(0.25));
+        assert_eq!(simulation.fitnesses()[0], 0.25);
+        assert_eq!(simulation.world.animals[1].recent_score(), Some(0.0));
+    }
+
+    #[test]
+    fn test_heatmap() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let config = Config {
+            num_animals: 4,
+            num_food: 8,
+            generation_steps: 10,
+            heatmap_cells: 5,
+            ..Config::default()
+        };
+        let mut simulation = Simulation::from_config(&mut rng, config);
+        for _ in 0..3 {
+            simulation.step(&mut rng);
+        }
+        assert_eq!(simulation.heatmap().counts().len(), 25);
+        assert_eq!(simulation.heatmap().total(), 4 * 3);
+        assert!(simulation.prev_heatmap().is_none());
+
+        simulation.reset_heatmap();
+        assert_eq!(simulation.heatmap().total(), 0);
+
+        simulation.train(&mut rng);
+        assert_eq!(simulation.prev_heatmap().unwrap().total(), 4 * 7);
+        let meal_locations = &simulation
+            .prev_generation_statistics()
+            .unwrap()
+            .meal_locations;
+        assert_eq!(meal_locations.counts().len(), 25);
+        assert_eq!(simulation.heatmap().total(), 0);
+    }
+
+    #[test]
+    fn test_heatmap_from_old_snapshot() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let config = Config {
+            num_animals: 4,
+            heatmap_cells: 5,
+            ..Config::default()
+        };
+        let simulation = Simulation::from_config(&mut rng, config);
+        let mut snapshot: serde_json::Value =
+            serde_json::from_slice(&simulation.to_snapshot()).unwrap();
+        let state = snapshot["state"].as_object_mut().unwrap();
+        state.remove("heatmap").unwrap();
+        state.remove("meal_heatmap").unwrap();
+
+        let bytes = serde_json::to_vec(&snapshot).unwrap();
+        let mut loaded = Simulation::from_snapshot(&bytes).unwrap();
+        loaded.step(&mut rng);
+        assert_eq!(loaded.heatmap().counts().len(), 25);
+        assert_eq!(loaded.heatmap().total(), 4);
+        assert_eq!(loaded.meal_heatmap.counts().len(), 25);
+    }
+
+    #[test]
+    fn test_forage_pick_up_and_deposit() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let config = Config {
+            num_animals: 1,
+            num_food: 1,
+            mode: GameMode::Forage,
+            ..Config::default()
+        };
+        let mut simulation = Simulation::from_config(&mut rng, config);
+        let food_position = na::Point2::new(0.1, 0.1);
+        simulation.world.food[0].position = food_position;
+        simulation.world.animals[0].position = food_position;
+
+        simulation.eat_food(&mut rng);
+        let animal = &simulation.world.animals[0];
+        assert!(animal.carrying);
+        assert_eq!(animal.deposited, 0);
+
+        simulation.world.animals[0].position = simulation.world.nest.as_ref().unwrap().position;
+        simulation.eat_food(&mut rng);
+        let animal = &simulation.world.animals[0];
+        assert!(!animal.carrying);
+        assert_eq!(animal.deposited, 1);
+        approx::assert_relative_eq!(GameMode::Forage.fitness(animal), 1.0);
     }
 }
@@ -0,0 +1,345 @@
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::config;
+use crate::replay::Replay;
+use crate::simulation::Simulation;
+
+/// Layout of the snapshot envelope and of the state inside it. Bump it, and
+/// teach `migrate` the old layout, whenever saved state stops loading as is.
+pub const SNAPSHOT_VERSION: u32 = 3;
+
+/// Version of this crate, recorded in every snapshot.
+pub const CRATE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// What a snapshot says about where it came from, saved ahead of the state.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotHeader {
+    /// `SNAPSHOT_VERSION` of the build that saved it; 0 for state saved
+    /// before snapshots had a header.
+    pub version: u32,
+    /// `CRATE_VERSION` of the build that saved it, empty for version 0.
+    pub crate_version: String,
+    /// `Config::fingerprint` of the state's config when it was saved, 0 for
+    /// version 0.
+    pub config_hash: u64,
+}
+
+/// Why a snapshot couldn't be loaded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SnapshotError {
+    Parse(String),
+    /// Saved by a newer build, with a layout this one doesn't know.
+    UnsupportedVersion {
+        version: u32,
+        crate_version: String,
+    },
+    /// The config doesn't hash to what was saved, so the snapshot was
+    /// edited or corrupted.
+    ConfigMismatch {
+        expected: u64,
+        actual: u64,
+    },
+}
+
+/// State that can be saved as a snapshot: a `Simulation` or a `Replay`,
+/// with its `Config` under `config`.
+pub(crate) trait Snapshot: Serialize + DeserializeOwned {}
+
+#[derive(Serialize)]
+struct Envelope {
+    header: SnapshotHeader,
+    state: Value,
+}
+
+impl SnapshotHeader {
+    fn new(state: &Value) -> Self {
+        Self {
+            version: SNAPSHOT_VERSION,
+            crate_version: CRATE_VERSION.to_owned(),
+            config_hash: config::fingerprint_json(&state["config"]),
+        }
+    }
+
+    /// Header of a snapshot, without loading its state, e.g. to check it
+    /// was saved with the expected config.
+    pub fn read(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        split(parse(bytes)?).map(|(header, _)| header)
+    }
+}
+
+pub(crate) fn save<T: Snapshot>(state: &T) -> Vec<u8> {
+    let state = serde_json::to_value(state).unwrap();
+    let envelope = Envelope {
+        header: SnapshotHeader::new(&state),
+        state,
+    };
+    serde_json::to_vec(&envelope).unwrap()
+}
+
+pub(crate) fn load<T: Snapshot>(bytes: &[u8]) -> Result<T, SnapshotError> {
+    let (header, state) = split(parse(bytes)?)?;
+
+    // Hashing the config as stored rather than as loaded keeps settings
+    // added since it was saved out of the hash.
+    if header.version > 0 {
+        let actual = config::fingerprint_json(&state["config"]);
+        if actual != header.config_hash {
+            return Err(SnapshotError::ConfigMismatch {
+                expected: header.config_hash,
+                actual,
+            });
+        }
+    }
+    serde_json::from_value(migrate(&header, state)?)
+        .map_err(|err| SnapshotError::Parse(err.to_string()))
+}
+
+fn parse(bytes: &[u8]) -> Result<Value, SnapshotError> {
+    serde_json::from_slice(bytes).map_err(|err| SnapshotError::Parse(err.to_string()))
+}
+
+/// The header and state of a snapshot, or a version 0 header for bare state.
+fn split(value: Value) -> Result<(SnapshotHeader, Value), SnapshotError> {
+    match value {
+        Value::Object(mut object) if object.contains_key("header") => {
+            let header = serde_json::from_value(object.remove("header").unwrap())
+                .map_err(|err| SnapshotError::Parse(format!("bad header: {err}")))?;
+            let state = object
+                .remove("state")
+                .ok_or_else(|| SnapshotError::Parse("missing state".to_owned()))?;
+            Ok((header, state))
+        }
+        state => {
+            let header = SnapshotHeader {
+                version: 0,
+                crate_version: String::new(),
+                config_hash: 0,
+            };
+            Ok((header, state))
+        }
+    }
+}
+
+/// Upgrades state saved with `header.version` to the current layout.
+fn migrate(header: &SnapshotHeader, state: Value) -> Result<Value, SnapshotError> {
+    match header.version {
+        // Version 1 only added the header around the same state; version 2
+        // builds the genetic algorithm from the config instead of saving it;
+        // version 3 gives animals ids
+        0 | 1 => Ok(with_animal_ids(without_evolver(state))),
+        2 => Ok(with_animal_ids(state)),
+        SNAPSHOT_VERSION => Ok(state),
+        version => Err(SnapshotError::UnsupportedVersion {
+            version,
+            crate_version: header.crate_version.clone(),
+        }),
+    }
+}
+
+fn without_evolver(mut state: Value) -> Value {
+    if let Some(state) = state.as_object_mut() {
+        state.remove("evolver");
+    }
+    state
+}
+
+/// Numbers the animals of a simulation saved before they had ids, living
+/// ones first, then culled ones. Replays have no animals and are left
+/// alone.
+fn with_animal_ids(mut state: Value) -> Value {
+    let mut next_id = 0u64;
+    for animals in ["/world/animals", "/culled"] {
+        let Some(Value::Array(animals)) = state.pointer_mut(animals) else {
+            continue;
+        };
+        for animal in animals.iter_mut().filter_map(Value::as_object_mut) {
+            animal.insert("id".to_owned(), next_id.into());
+            next_id += 1;
+        }
+    }
+    if let Some(Value::Object(world)) = state.pointer_mut("/world") {
+        world.insert("next_animal_id".to_owned(), next_id.into());
+    }
+    state
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(err) => write!(f, "failed to parse snapshot: {err}"),
+            Self::UnsupportedVersion {
+                version,
+                crate_version,
+            } => write!(
+                f,
+                "snapshot version {version} was saved by version {crate_version}, \
+                 newer than this version {CRATE_VERSION} (snapshot version \
+                 {SNAPSHOT_VERSION}) can load"
+            ),
+            Self::ConfigMismatch { expected, actual } => write!(
+                f,
+                "snapshot config hashes to {actual:016x} instead of the saved \
+                 {expected:016x}; the snapshot was modified or corrupted"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl Snapshot for Simulation {}
+
+impl Snapshot for Replay {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    fn simulation() -> Simulation {
+        let mut rng = vroom_rand::seeded(0);
+        let config = Config {
+            num_animals: 2,
+            num_food: 4,
+            ..Config::default()
+        };
+        Simulation::from_config(&mut rng, config)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let simulation = simulation();
+        let bytes = simulation.to_snapshot();
+
+        let header = SnapshotHeader::read(&bytes).unwrap();
+        assert_eq!(header.version, SNAPSHOT_VERSION);
+        assert_eq!(header.crate_version, CRATE_VERSION);
+        assert_eq!(header.config_hash, simulation.config().fingerprint());
+
+        let loaded = Simulation::from_snapshot(&bytes).unwrap();
+        assert_eq!(loaded.state_hash(), simulation.state_hash());
+
+        let replay = Replay::record(1, simulation.config().clone(), 20, 10);
+        let loaded = Replay::from_snapshot(&replay.to_snapshot()).unwrap();
+        assert_eq!(loaded.verify(), Ok(()));
+    }
+
+    #[test]
+    fn test_migrate_bare_state() {
+        let simulation = simulation();
+        let bytes = serde_json::to_vec(&simulation).unwrap();
+
+        assert_eq!(SnapshotHeader::read(&bytes).unwrap().version, 0);
+        let loaded = Simulation::from_snapshot(&bytes).unwrap();
+        assert_eq!(loaded.state_hash(), simulation.state_hash());
+    }
+
+    #[test]
+    fn test_migrate_saved_evolver() {
+        let simulation = simulation();
+        let mut snapshot: Value = serde_json::from_slice(&simulation.to_snapshot()).unwrap();
+
+        // Version 1 saved the genetic algorithm, whose layout changed when
+        // children started blending their parents' brains
+        let evolvers = [
+            serde_json::json!({
+                "selection_method": null,
+                "crossover_method": null,
+                "mutation_method": { "mutation_rate": 0.01, "mutation_strength": 0.3 },
+            }),
+            serde_json::json!({
+                "selection_method": null,
+                "crossover_method": {
+                    "probability": 0.0,
+                    "first": { "weight": 0.5 },
+                    "second": null,
+                },
+                "mutation_method": { "mutation_rate": 0.01, "mutation_strength": 0.3 },
+            }),
+        ];
+        snapshot["header"]["version"] = 1.into();
+        for evolver in evolvers {
+            snapshot["state"]["evolver"] = evolver;
+            let bytes = serde_json::to_vec(&snapshot).unwrap();
+            let loaded = Simulation::from_snapshot(&bytes).unwrap();
+            assert_eq!(loaded.state_hash(), simulation.state_hash());
+        }
+    }
+
+    #[test]
+    fn test_migrate_animal_ids() {
+        let mut simulation = simulation();
+        assert!(simulation.kill_animal(0));
+        let mut snapshot: Value = serde_json::from_slice(&simulation.to_snapshot()).unwrap();
+
+        // As saved by a build from before animals had ids
+        snapshot["header"]["version"] = 2.into();
+        let state = &mut snapshot["state"];
+        state["world"]
+            .as_object_mut()
+            .unwrap()
+            .remove("next_animal_id");
+        state["world"]["animals"][0]
+            .as_object_mut()
+            .unwrap()
+            .remove("id");
+        state["culled"][0].as_object_mut().unwrap().remove("id");
+
+        let bytes = serde_json::to_vec(&snapshot).unwrap();
+        let mut loaded = Simulation::from_snapshot(&bytes).unwrap();
+        assert_eq!(loaded.world().animals()[0].id(), 0);
+        let id = loaded.add_animal_with_brain(
+            &mut vroom_rand::seeded(0),
+            vec![0.0; loaded.config().genome_length()],
+        );
+        assert_eq!(id, Some(2));
+    }
+
+    #[test]
+    fn test_reject() {
+        let mut snapshot: Value = serde_json::from_slice(&simulation().to_snapshot()).unwrap();
+
+        let mut edited = snapshot.clone();
+        edited["state"]["config"]["num_food"] = 5.into();
+        let err = Simulation::from_snapshot(&serde_json::to_vec(&edited).unwrap()).unwrap_err();
+        assert!(matches!(err, SnapshotError::ConfigMismatch { .. }));
+
+        snapshot["header"]["version"] = (SNAPSHOT_VERSION + 1).into();
+        let err = Simulation::from_snapshot(&serde_json::to_vec(&snapshot).unwrap()).unwrap_err();
+        assert_eq!(
+            err,
+            SnapshotError::UnsupportedVersion {
+                version: SNAPSHOT_VERSION + 1,
+                crate_version: CRATE_VERSION.to_owned(),
+            }
+        );
+
+        let err = Simulation::from_snapshot(b"{").unwrap_err();
+        assert!(matches!(err, SnapshotError::Parse(_)));
+    }
+
+    #[test]
+    fn test_load_older_config() {
+        let simulation = simulation();
+        let mut snapshot: Value = serde_json::from_slice(&simulation.to_snapshot()).unwrap();
+
+        // As saved by a build from before `trail_length` existed
+        let config = snapshot["state"]["config"].as_object_mut().unwrap();
+        config.remove("trail_length").unwrap();
+        let config_hash = config::fingerprint_json(&snapshot["state"]["config"]);
+        snapshot["header"]["crate_version"] = "0.0.0".into();
+        snapshot["header"]["config_hash"] = config_hash.into();
+
+        let bytes = serde_json::to_vec(&snapshot).unwrap();
+        let loaded = Simulation::from_snapshot(&bytes).unwrap();
+        assert_eq!(loaded.config(), simulation.config());
+
+        snapshot["state"]["config"]["num_food"] = 5.into();
+        let err = Simulation::from_snapshot(&serde_json::to_vec(&snapshot).unwrap()).unwrap_err();
+        assert!(matches!(err, SnapshotError::ConfigMismatch { .. }));
+    }
+}
@@ -0,0 +1,71 @@
+use std::hash::Hasher;
+
+use crate::math::{self, Real};
+
+/// FNV-1a hasher with a fixed, documented algorithm, so hashes are stable
+/// across platforms and Rust releases, unlike `DefaultHasher`.
+pub struct StateHasher {
+    state: u64,
+}
+
+impl StateHasher {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    pub fn new() -> Self {
+        Self {
+            state: Self::OFFSET_BASIS,
+        }
+    }
+
+    pub fn write_f64(&mut self, value: f64) {
+        self.write_u64(value.to_bits());
+    }
+
+    /// Hashes a world geometry value as the `f64` it widens to.
+    pub fn write_real(&mut self, value: Real) {
+        self.write_f64(math::wide(value));
+    }
+}
+
+impl Default for StateHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Hasher for StateHasher {
+    fn finish(&self) -> u64 {
+        self.state
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.state ^= byte as u64;
+            self.state = self.state.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fnv1a_reference_values() {
+        let hasher = StateHasher::new();
+        assert_eq!(hasher.finish(), 0xcbf2_9ce4_8422_2325);
+
+        let mut hasher = StateHasher::new();
+        hasher.write(b"a");
+        assert_eq!(hasher.finish(), 0xaf63_dc4c_8601_ec8c);
+    }
+}
@@ -0,0 +1,79 @@
+use nalgebra as na;
+use serde::{Deserialize, Serialize};
+
+use crate::math::Real;
+
+/// How the starting layout of a rollout is transformed from the
+/// generation's first, see `Config::rollouts`. Mirroring evaluates every
+/// brain on both sides of the same layout, so one that only turns one way
+/// doesn't get lucky.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Symmetry {
+    /// The layout as is.
+    #[default]
+    Identity,
+    /// Mirrored left to right.
+    MirrorX,
+    /// Mirrored top to bottom.
+    MirrorY,
+    /// Rotated half a turn about the world's center.
+    Rotate180,
+}
+
+impl Symmetry {
+    /// `position` in a world of `size`, transformed.
+    pub fn apply_position(
+        self,
+        position: na::Point2<Real>,
+        size: na::Vector2<Real>,
+    ) -> na::Point2<Real> {
+        let (x, y) = (position.x, position.y);
+        match self {
+            Self::Identity => position,
+            Self::MirrorX => na::Point2::new(size.x - x, y),
+            Self::MirrorY => na::Point2::new(x, size.y - y),
+            Self::Rotate180 => na::Point2::new(size.x - x, size.y - y),
+        }
+    }
+
+    /// Heading `rotation`, transformed. Built from the matrix so it's
+    /// exact, and applying a mirror twice gives the heading back.
+    pub fn apply_rotation(self, rotation: na::Rotation2<Real>) -> na::Rotation2<Real> {
+        let matrix = rotation.matrix();
+        let (cos, sin) = (matrix[(0, 0)], matrix[(1, 0)]);
+        let (cos, sin) = match self {
+            Self::Identity => (cos, sin),
+            Self::MirrorX => (-cos, sin),
+            Self::MirrorY => (cos, -sin),
+            Self::Rotate180 => (-cos, -sin),
+        };
+        na::Rotation2::from_matrix_unchecked(na::Matrix2::new(cos, -sin, sin, cos))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math;
+
+    #[test]
+    fn test_apply() {
+        let size = na::Vector2::new(2.0, 1.0);
+        let position = na::Point2::new(0.5, 0.25);
+        assert_eq!(
+            Symmetry::MirrorX.apply_position(position, size),
+            na::Point2::new(1.5, 0.25)
+        );
+        assert_eq!(
+            Symmetry::Rotate180.apply_position(position, size),
+            na::Point2::new(1.5, 0.75)
+        );
+
+        // Facing up and to the right
+        let rotation = math::rotation(math::consts::PI / 4.0);
+        let heading = |symmetry: Symmetry| symmetry.apply_rotation(rotation) * na::Vector2::x();
+        approx::assert_relative_eq!(heading(Symmetry::MirrorX).x, -heading(Symmetry::Identity).x);
+        approx::assert_relative_eq!(heading(Symmetry::MirrorY).y, -heading(Symmetry::Identity).y);
+        approx::assert_relative_eq!(heading(Symmetry::Rotate180), -heading(Symmetry::Identity));
+    }
+}
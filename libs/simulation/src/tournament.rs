@@ -0,0 +1,155 @@
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::generation_end::GenerationEnd;
+use crate::simulation::Simulation;
+
+/// Re-evaluates genomes, e.g. the champions of different generations, one
+/// at a time in the same seeded environments, so their fitness can be
+/// compared fairly. Live statistics can't tell a better brain from an
+/// easier generation.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Tournament {
+    /// Environment each genome is evaluated in, alone, for one generation.
+    /// Generations that only end manually run `generation_steps` steps.
+    pub config: Config,
+    /// One evaluation per seed, the same for every genome.
+    pub seeds: Vec<u64>,
+}
+
+/// Where a genome placed in a `Tournament`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Standing {
+    /// Index of the genome among those evaluated.
+    pub genome: usize,
+    /// Mean fitness over the tournament's seeds.
+    pub fitness: f64,
+}
+
+impl Tournament {
+    /// Mean fitness of each genome over the seeds, in order; a performance
+    /// curve when given a run's champions generation by generation.
+    ///
+    /// Returns `None` if a genome doesn't match `Config::genome_length`.
+    pub fn evaluate(&self, genomes: &[Vec<f64>]) -> Option<Vec<f64>> {
+        let config = solo_config(&self.config);
+        genomes
+            .iter()
+            .map(|genome| {
+                let mut total = 0.0;
+                for &seed in &self.seeds {
+                    total += evaluate_alone(&config, genome.clone(), seed)?;
+                }
+                Some(total / self.seeds.len().max(1) as f64)
+            })
+            .collect()
+    }
+
+    /// Every genome with its mean fitness, best first.
+    ///
+    /// Returns `None` if a genome doesn't match `Config::genome_length`.
+    pub fn rank(&self, genomes: &[Vec<f64>]) -> Option<Vec<Standing>> {
+        let mut standings: Vec<Standing> = self
+            .evaluate(genomes)?
+            .into_iter()
+            .enumerate()
+            .map(|(genome, fitness)| Standing { genome, fitness })
+            .collect();
+        standings.sort_by(|a, b| b.fitness.total_cmp(&a.fitness));
+        Some(standings)
+    }
+}
+
+/// `config` for evaluating a single animal for one generation.
+fn solo_config(config: &Config) -> Config {
+    let mut config = bounded_config(config);
+    config.num_animals = 1;
+    config
+}
+
+/// `config` with generations that end on their own, after
+/// `generation_steps` steps at most if they'd only end manually.
+pub(crate) fn bounded_config(config: &Config) -> Config {
+    let mut config = config.clone();
+    if config.generation_end == GenerationEnd::Manual {
+        config.generation_end = GenerationEnd::Steps;
+    }
+    config
+}
+
+/// Fitness of `genome` alone for one generation of `config`, from
+/// `solo_config`, in the environment `seed` makes; `None` if `genome`
+/// doesn't match `Config::genome_length`.
+fn evaluate_alone(config: &Config, genome: Vec<f64>, seed: u64) -> Option<f64> {
+    let mut rng = vroom_rand::seeded(seed);
+    let mut simulation = Simulation::from_config(&mut rng, config.clone());
+    simulation.world.animals.clear();
+    simulation.add_animal_with_brain(&mut rng, genome)?;
+    Some(simulation.evaluate(&mut rng)[0])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rank_champions() {
+        let mut rng = vroom_rand::seeded(0);
+        let config = Config {
+            num_animals: 4,
+            num_food: 16,
+            generation_steps: 50,
+            ..Config::default()
+        };
+        let mut simulation = Simulation::from_config(&mut rng, config.clone());
+        simulation.train(&mut rng);
+        let champion = simulation.generation_statistics()[0].champion.clone();
+        let idle = vec![0.0; config.genome_length()];
+
+        let tournament = Tournament {
+            config,
+            seeds: vec![1, 2],
+        };
+        let fitness = tournament
+            .evaluate(&[champion.clone(), champion.clone()])
+            .unwrap();
+        // Same genome, same environments
+        assert_eq!(fitness[0], fitness[1]);
+
+        let standings = tournament.rank(&[idle, champion]).unwrap();
+        assert_eq!(standings.len(), 2);
+        assert!(standings[0].fitness >= standings[1].fitness);
+
+        assert!(tournament.evaluate(&[vec![0.0]]).is_none());
+        assert!(tournament.rank(&[vec![0.0]]).is_none());
+    }
+
+    #[test]
+    fn test_default_config_keeps_every_champion() {
+        let mut rng = vroom_rand::seeded(0);
+        let config = Config {
+            num_animals: 2,
+            num_food: 4,
+            generation_steps: 2,
+            ..Config::default()
+        };
+        let mut simulation = Simulation::from_config(&mut rng, config);
+        let founders: Vec<Vec<f64>> = simulation
+            .world
+            .animals
+            .iter()
+            .map(|animal| animal.as_chromosome().iter().copied().collect())
+            .collect();
+        while simulation.generation() < 3 {
+            simulation.step(&mut rng);
+        }
+
+        // Champions outlive `genome_history`, gene for gene
+        let history = simulation.generation_statistics();
+        assert!(history.len() > simulation.config.genome_history);
+        assert!(founders.contains(&history[0].champion));
+        for stats in history {
+            assert_eq!(stats.champion.len(), simulation.config.genome_length());
+        }
+    }
+}
@@ -1,18 +1,141 @@
-use rand::RngCore;
+use std::hash::Hasher;
+
+use nalgebra as na;
+use serde::{Deserialize, Serialize};
+use vroom_rand::RngCore;
 
 use crate::animal::Animal;
+use crate::config::Config;
+use crate::entity::{Entity, WorldVisitor};
 use crate::food::Food;
+use crate::game_mode::GameMode;
+use crate::map::{Map, Wall, Zone};
+use crate::math::{self, Real};
+use crate::nest::Nest;
+use crate::sensor::Pose;
+use crate::state_hash::StateHasher;
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct World {
     pub(crate) animals: Vec<Animal>,
     pub(crate) food: Vec<Food>,
+    pub(crate) nest: Option<Nest>,
+    pub(crate) walls: Vec<Wall>,
+    pub(crate) food_zones: Vec<Zone>,
+    pub(crate) spawn_zones: Vec<Zone>,
+    /// Width and height; positions wrap around to stay within them.
+    pub(crate) size: na::Vector2<Real>,
+    /// Bumped whenever food is added, removed or moved, so animals know when
+    /// their cached vision is stale.
+    pub(crate) food_version: u64,
+    /// `Animal::id` of the next animal added.
+    #[serde(default)]
+    pub(crate) next_animal_id: u64,
 }
 
 impl World {
-    pub fn random(rng: &mut dyn RngCore, num_animals: u8, num_food: u8) -> Self {
-        let animals = (0..num_animals).map(|_| Animal::random(rng)).collect();
-        let food = (0..num_food).map(|_| Food::new_random(rng)).collect();
-        Self { animals, food }
+    pub fn random(rng: &mut dyn RngCore, config: &Config) -> Self {
+        Self::from_map(rng, &Map::default(), config)
+    }
+
+    pub fn from_map(rng: &mut dyn RngCore, map: &Map, config: &Config) -> Self {
+        let size = na::Vector2::new(math::real(config.width), math::real(config.height));
+        let food_radius = math::real(config.food_radius);
+        let nest = match config.mode {
+            GameMode::Eat => None,
+            GameMode::Forage => Some(map.nest.clone().unwrap_or_else(|| Nest {
+                position: (size / 2.0).into(),
+                ..Nest::default()
+            })),
+        };
+        let mut world = Self {
+            animals: Vec::new(),
+            food: map
+                .food
+                .iter()
+                .map(|&position| Food::new(position).with_radius(food_radius))
+                .collect(),
+            nest,
+            walls: map.walls.clone(),
+            food_zones: override_zones(&config.food_zones, &map.food_zones),
+            spawn_zones: override_zones(&config.spawn_zones, &map.spawn_zones),
+            size,
+            food_version: 0,
+            next_animal_id: 0,
+        };
+        world.spawn_animals(rng, config);
+        for _ in 0..config.num_food {
+            let position = Zone::random_position(rng, &world.food_zones, world.size);
+            world
+                .food
+                .push(Food::new(position).with_radius(food_radius));
+        }
+        world
+    }
+
+    /// Replaces the animals with a fresh population created according to
+    /// `Config::population_init`.
+    pub(crate) fn spawn_animals(&mut self, rng: &mut dyn RngCore, config: &Config) {
+        let chromosomes = config
+            .population_init()
+            .init(rng, config.num_animals.into());
+        self.animals = chromosomes
+            .into_iter()
+            .map(|chromosome| {
+                let mut animal = Animal::from_chromosome(rng, chromosome, config);
+                self.place_animal(rng, &mut animal);
+                animal.id = self.new_animal_id();
+                animal
+            })
+            .collect();
+    }
+
+    /// Unused `Animal::id` for an animal joining the world.
+    pub(crate) fn new_animal_id(&mut self) -> u64 {
+        let id = self.next_animal_id;
+        self.next_animal_id += 1;
+        id
+    }
+
+    /// Brings every animal's vision up to date, in order, before any of them
+    /// acts, so each senses the same world and the brains can then borrow
+    /// the animals mutably.
+    pub(crate) fn update_visions(&mut self, rng: &mut dyn RngCore) {
+        for idx in 0..self.animals.len() {
+            self.update_vision(idx, rng);
+        }
+    }
+
+    /// Recomputes the vision of the animal at `idx` with its sensor unless
+    /// it's still fresh. Returns whether it recomputed.
+    pub(crate) fn update_vision(&mut self, idx: usize, rng: &mut dyn RngCore) -> bool {
+        let animal = &self.animals[idx];
+        if animal.vision_is_fresh(self.food_version) {
+            return false;
+        }
+
+        // Taken out so the sensor can see the whole world, this animal
+        // included
+        let mut vision = std::mem::take(&mut self.animals[idx].vision);
+        let animal = &self.animals[idx];
+        let pose = Pose {
+            animal: Some(idx),
+            ..animal.pose()
+        };
+        animal.sensor().sense_into(rng, pose, self, &mut vision);
+        self.animals[idx].set_vision(vision, self.food_version);
+        true
+    }
+
+    /// Moves a newly created animal into a spawn zone, or stretches its
+    /// random unit-square position over the whole world if there are none.
+    pub(crate) fn place_animal(&self, rng: &mut dyn RngCore, animal: &mut Animal) {
+        if self.spawn_zones.is_empty() {
+            animal.position = animal.position.coords.component_mul(&self.size).into();
+        } else {
+            animal.position = Zone::random_position(rng, &self.spawn_zones, self.size);
+        }
+        animal.spawn_pose = Some((animal.position, animal.rotation));
     }
 
     pub fn animals(&self) -> &[Animal] {
@@ -22,4 +145,274 @@ impl World {
     pub fn food(&self) -> &[Food] {
         &self.food
     }
+
+    /// Food the animal at index `animal` sees and eats: its own with
+    /// `Config::private_food`, the world's otherwise.
+    pub fn food_for(&self, animal: Option<usize>) -> &[Food] {
+        animal
+            .and_then(|idx| self.animals.get(idx)?.private_food())
+            .unwrap_or(&self.food)
+    }
+
+    pub fn nest(&self) -> Option<&Nest> {
+        self.nest.as_ref()
+    }
+
+    pub fn walls(&self) -> &[Wall] {
+        &self.walls
+    }
+
+    /// Every animal, then every piece of food, the nest if there is one and
+    /// every wall.
+    pub fn entities(&self) -> impl Iterator<Item = Entity<'_>> {
+        let animals = self.animals.iter().map(Entity::Animal);
+        let food = self.food.iter().map(Entity::Food);
+        let nest = self.nest.iter().map(Entity::Nest);
+        let walls = self.walls.iter().map(Entity::Wall);
+        animals.chain(food).chain(nest).chain(walls)
+    }
+
+    /// Hands the entities to `visitor` in the order of `entities`.
+    pub fn visit(&self, visitor: &mut dyn WorldVisitor) {
+        for (idx, animal) in self.animals.iter().enumerate() {
+            visitor.visit_animal(idx, animal);
+        }
+        for (idx, food) in self.food.iter().enumerate() {
+            visitor.visit_food(idx, food);
+        }
+        if let Some(nest) = &self.nest {
+            visitor.visit_nest(nest);
+        }
+        for wall in &self.walls {
+            visitor.visit_wall(wall);
+        }
+    }
+
+    pub fn width(&self) -> Real {
+        self.size.x
+    }
+
+    pub fn height(&self) -> Real {
+        self.size.y
+    }
+
+    pub fn add_food(&mut self, food: Food) {
+        self.food.push(food);
+        self.food_version += 1;
+    }
+
+    pub fn clear_food(&mut self) {
+        self.food.clear();
+        self.food_version += 1;
+    }
+
+    /// Removes and returns the animal with `Animal::id` `id`, shifting later
+    /// animals down by one.
+    pub fn remove_animal(&mut self, id: u64) -> Option<Animal> {
+        let idx = self.animals.iter().position(|animal| animal.id == id)?;
+        Some(self.animals.remove(idx))
+    }
+
+    /// Hash of animal positions, rotations and consumed counters and of food
+    /// positions that doesn't depend on the order entities are stored in.
+    pub fn hash(&self) -> u64 {
+        let animals = self
+            .animals
+            .iter()
+            .map(|animal| {
+                let mut hasher = StateHasher::new();
+                hasher.write_real(animal.position.x);
+                hasher.write_real(animal.position.y);
+                hasher.write_real(animal.rotation.matrix()[(0, 0)]);
+                hasher.write_real(animal.rotation.matrix()[(1, 0)]);
+                hasher.write_u32(animal.consumed);
+                hasher.finish()
+            })
+            .fold(0u64, u64::wrapping_add);
+        let food = self
+            .food
+            .iter()
+            .map(|food| {
+                let mut hasher = StateHasher::new();
+                hasher.write_real(food.position.x);
+                hasher.write_real(food.position.y);
+                hasher.finish()
+            })
+            .fold(0u64, u64::wrapping_add);
+
+        let mut hasher = StateHasher::new();
+        hasher.write_u64(animals);
+        hasher.write_u64(food);
+        hasher.finish()
+    }
+
+    /// Returns the index of the animal nearest to `point`, ignoring animals
+    /// further than `radius` away.
+    pub fn animal_at(&self, point: na::Point2<Real>, radius: Real) -> Option<usize> {
+        self.animals
+            .iter()
+            .enumerate()
+            .map(|(idx, animal)| (idx, math::distance(&animal.position, &point)))
+            .filter(|&(_, dist)| dist <= radius)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(idx, _)| idx)
+    }
+}
+
+/// `config`'s zones, or `map`'s when it sets none.
+fn override_zones(config: &[Zone], map: &[Zone]) -> Vec<Zone> {
+    if config.is_empty() { map } else { config }.to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn world_with_animals_at(positions: &[(Real, Real)]) -> World {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let animals = positions
+            .iter()
+            .zip(0..)
+            .map(|(&(x, y), id)| {
+                let mut animal = Animal::random(&mut rng, &Config::default());
+                animal.id = id;
+                animal.position = na::Point2::new(x, y);
+                animal
+            })
+            .collect();
+        World {
+            animals,
+            food: Vec::new(),
+            nest: None,
+            walls: Vec::new(),
+            food_zones: Vec::new(),
+            spawn_zones: Vec::new(),
+            size: na::Vector2::new(1.0, 1.0),
+            food_version: 0,
+            next_animal_id: positions.len() as u64,
+        }
+    }
+
+    #[test]
+    fn test_entities_and_visit_agree() {
+        #[derive(Default)]
+        struct Census(Vec<&'static str>);
+
+        impl WorldVisitor for Census {
+            fn visit_animal(&mut self, _idx: usize, _animal: &Animal) {
+                self.0.push("animal");
+            }
+
+            fn visit_food(&mut self, _idx: usize, _food: &Food) {
+                self.0.push("food");
+            }
+
+            fn visit_wall(&mut self, _wall: &Wall) {
+                self.0.push("wall");
+            }
+        }
+
+        let mut world = world_with_animals_at(&[(0.1, 0.1), (0.5, 0.5)]);
+        world.food.push(Food::new(na::Point2::new(0.2, 0.2)));
+        world.nest = Some(Nest::default());
+        world.walls.push(Wall {
+            start: na::Point2::new(0.0, 0.0),
+            end: na::Point2::new(1.0, 0.0),
+        });
+
+        let mut census = Census::default();
+        world.visit(&mut census);
+        assert_eq!(census.0, ["animal", "animal", "food", "wall"]);
+
+        let kinds: Vec<&str> = world
+            .entities()
+            .map(|entity| match entity {
+                Entity::Animal(_) => "animal",
+                Entity::Food(_) => "food",
+                Entity::Nest(_) => "nest",
+                Entity::Wall(_) => "wall",
+            })
+            .collect();
+        assert_eq!(kinds, ["animal", "animal", "food", "nest", "wall"]);
+    }
+
+    #[test]
+    fn test_clone() {
+        let world = world_with_animals_at(&[(0.1, 0.1), (0.5, 0.5)]);
+        let clone = world.clone();
+        assert_eq!(clone.hash(), world.hash());
+        assert_eq!(
+            clone.animals[0].brain_topology(),
+            Config::default().brain_topology()
+        );
+    }
+
+    #[test]
+    fn test_hash_is_order_independent() {
+        let mut world = world_with_animals_at(&[(0.1, 0.1), (0.5, 0.5), (0.55, 0.5)]);
+        let hash = world.hash();
+
+        world.animals.reverse();
+        assert_eq!(world.hash(), hash);
+
+        world.animals[0].consumed += 1;
+        assert_ne!(world.hash(), hash);
+    }
+
+    #[test]
+    fn test_animal_at() {
+        let world = world_with_animals_at(&[(0.1, 0.1), (0.5, 0.5), (0.55, 0.5)]);
+
+        assert_eq!(world.animal_at(na::Point2::new(0.1, 0.12), 0.05), Some(0));
+        assert_eq!(world.animal_at(na::Point2::new(0.51, 0.5), 0.05), Some(1));
+        assert_eq!(world.animal_at(na::Point2::new(0.54, 0.5), 0.05), Some(2));
+        assert_eq!(world.animal_at(na::Point2::new(0.9, 0.9), 0.05), None);
+    }
+
+    #[test]
+    fn test_world_size() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let config = Config {
+            mode: GameMode::Forage,
+            width: 3.0,
+            height: 0.5,
+            ..Config::default()
+        };
+        let world = World::random(&mut rng, &config);
+
+        let positions = world
+            .animals
+            .iter()
+            .map(|animal| animal.position)
+            .chain(world.food.iter().map(|food| food.position));
+        for position in positions.clone() {
+            assert!((0.0..3.0).contains(&position.x));
+            assert!((0.0..0.5).contains(&position.y));
+        }
+        assert!(positions.clone().any(|position| position.x > 2.0));
+        assert_eq!(world.nest.unwrap().position, na::Point2::new(1.5, 0.25));
+    }
+
+    #[test]
+    fn test_config_zones_override_map() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let corner = Zone {
+            min: na::Point2::new(0.0, 0.0),
+            max: na::Point2::new(0.2, 0.2),
+        };
+        let config = Config {
+            spawn_zones: vec![corner.clone()],
+            ..Config::default()
+        };
+        let map = Map::bundled("corridor").unwrap();
+        let world = World::from_map(&mut rng, &map, &config);
+
+        assert_eq!(world.spawn_zones, [corner]);
+        assert_eq!(world.food_zones, map.food_zones);
+        for animal in &world.animals {
+            assert!(animal.position.x <= 0.2 && animal.position.y <= 0.2);
+        }
+    }
 }
@@ -0,0 +1,229 @@
+use nalgebra as na;
+
+use crate::math::consts::PI;
+use crate::math::{self, Real};
+use crate::world::World;
+
+/// One difference between two snapshots of a world, see `WorldDiff`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum WorldChange {
+    /// Animal `idx` moved by `delta` and turned by `turn` radians, in
+    /// [-π, π]. `delta` is the plain difference in position, so it spans
+    /// the world when the animal wrapped around an edge.
+    AnimalMoved {
+        idx: usize,
+        delta: na::Vector2<Real>,
+        turn: Real,
+    },
+    /// Animal `idx` ate `meals` pieces of food.
+    AnimalAte {
+        idx: usize,
+        meals: u32,
+    },
+    /// Animal `idx` delivered `deposits` pieces of food to the nest.
+    AnimalDeposited {
+        idx: usize,
+        deposits: u32,
+    },
+    AnimalAdded {
+        idx: usize,
+    },
+    AnimalRemoved {
+        idx: usize,
+    },
+    /// Food `idx` moved from `from` to `to`, which is what eating it does.
+    FoodRespawned {
+        idx: usize,
+        from: na::Point2<Real>,
+        to: na::Point2<Real>,
+    },
+    FoodAdded {
+        idx: usize,
+        position: na::Point2<Real>,
+    },
+    FoodRemoved {
+        idx: usize,
+    },
+}
+
+/// Changes between two snapshots of the same world, e.g. before and after a
+/// step, for tests, debugging and patching a frontend's copy of the world.
+///
+/// Animals and food are matched by index, so removing one from the middle
+/// shows up as changes to everything after it. Walls, zones and the nest
+/// are assumed not to change.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WorldDiff {
+    changes: Vec<WorldChange>,
+}
+
+/// The parts of a world `WorldDiff` compares: where animals and food are
+/// and what the animals have scored, without brains, vision or trails, so
+/// it's cheap to take every step and to keep around.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WorldOutline {
+    animals: Vec<AnimalOutline>,
+    food: Vec<na::Point2<Real>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct AnimalOutline {
+    position: na::Point2<Real>,
+    angle: Real,
+    consumed: u32,
+    deposited: u32,
+}
+
+impl WorldOutline {
+    pub fn of(world: &World) -> Self {
+        Self {
+            animals: world
+                .animals
+                .iter()
+                .map(|animal| AnimalOutline {
+                    position: animal.position,
+                    angle: math::angle(&animal.rotation),
+                    consumed: animal.consumed,
+                    deposited: animal.deposited,
+                })
+                .collect(),
+            food: world.food.iter().map(|food| food.position).collect(),
+        }
+    }
+}
+
+impl WorldDiff {
+    /// Animal changes come first, in index order, then food changes.
+    pub fn between(before: &World, after: &World) -> Self {
+        Self::since(&WorldOutline::of(before), after)
+    }
+
+    /// Changes from a world outlined by `before` to `after`, like
+    /// `between`.
+    pub fn since(before: &WorldOutline, after: &World) -> Self {
+        let mut changes = Vec::new();
+
+        for (idx, (old, new)) in before.animals.iter().zip(&after.animals).enumerate() {
+            let delta = new.position - old.position;
+            let turn = math::angle(&new.rotation) - old.angle;
+            if delta != na::Vector2::zeros() || turn != 0.0 {
+                changes.push(WorldChange::AnimalMoved {
+                    idx,
+                    delta,
+                    turn: na::wrap(turn, -PI, PI),
+                });
+            }
+            if new.consumed > old.consumed {
+                let meals = new.consumed - old.consumed;
+                changes.push(WorldChange::AnimalAte { idx, meals });
+            }
+            if new.deposited > old.deposited {
+                let deposits = new.deposited - old.deposited;
+                changes.push(WorldChange::AnimalDeposited { idx, deposits });
+            }
+        }
+        let (num_before, num_after) = (before.animals.len(), after.animals.len());
+        changes.extend((num_before..num_after).map(|idx| WorldChange::AnimalAdded { idx }));
+        changes.extend((num_after..num_before).map(|idx| WorldChange::AnimalRemoved { idx }));
+
+        for (idx, (&old, new)) in before.food.iter().zip(&after.food).enumerate() {
+            if old != new.position {
+                changes.push(WorldChange::FoodRespawned {
+                    idx,
+                    from: old,
+                    to: new.position,
+                });
+            }
+        }
+        let num_before = before.food.len();
+        changes.extend(
+            after
+                .food
+                .iter()
+                .enumerate()
+                .skip(num_before)
+                .map(|(idx, food)| WorldChange::FoodAdded {
+                    idx,
+                    position: food.position,
+                }),
+        );
+        changes.extend((after.food.len()..num_before).map(|idx| WorldChange::FoodRemoved { idx }));
+
+        Self { changes }
+    }
+
+    pub fn changes(&self) -> &[WorldChange] {
+        &self.changes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::Simulation;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_between() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut simulation = Simulation::random(&mut rng, 2, 2);
+        let world = &mut simulation.world;
+        world.animals[0].position = na::Point2::new(0.5, 0.5);
+        world.animals[1].position = na::Point2::new(0.1, 0.9);
+        world.animals[1].rotation = na::Rotation2::new(0.5);
+        world.food[0].position = na::Point2::new(0.5, 0.5);
+        world.food[1].position = na::Point2::new(0.9, 0.9);
+        let before = world.clone();
+        assert!(WorldDiff::between(&before, &before).is_empty());
+
+        simulation.eat_food(&mut rng);
+        let world = &mut simulation.world;
+        world.add_food(crate::Food::new(na::Point2::new(0.1, 0.1)));
+        world.animals[1].position.x += 0.25;
+        world.animals[1].rotation = na::Rotation2::new(0.0);
+
+        let diff = WorldDiff::between(&before, world);
+        let [ate, moved, respawned, added] = diff.changes() else {
+            panic!("expected 4 changes, got {diff:?}");
+        };
+        assert_eq!(*ate, WorldChange::AnimalAte { idx: 0, meals: 1 });
+        let WorldChange::AnimalMoved {
+            idx: 1,
+            delta,
+            turn,
+        } = *moved
+        else {
+            panic!("expected animal 1 to move, got {moved:?}");
+        };
+        approx::assert_abs_diff_eq!(delta.x, 0.25, epsilon = 1e-6);
+        approx::assert_abs_diff_eq!(delta.y, 0.0);
+        approx::assert_abs_diff_eq!(turn, -0.5, epsilon = 1e-6);
+        assert_eq!(
+            *respawned,
+            WorldChange::FoodRespawned {
+                idx: 0,
+                from: na::Point2::new(0.5, 0.5),
+                to: world.food[0].position,
+            }
+        );
+        assert_eq!(
+            *added,
+            WorldChange::FoodAdded {
+                idx: 2,
+                position: na::Point2::new(0.1, 0.1),
+            }
+        );
+
+        let mut after = before.clone();
+        after.remove_animal(1);
+        assert_eq!(
+            WorldDiff::between(&before, &after).changes(),
+            [WorldChange::AnimalRemoved { idx: 1 }]
+        );
+    }
+}
@@ -0,0 +1,61 @@
+//! Stepping a simulation within a generation should not allocate once its
+//! buffers are warm. Evolving the next generation still allocates its
+//! offspring's chromosomes and brains and the generation's statistics, so
+//! the steps measured here stay within the first generation.
+//! Lives in its own test binary because it installs a global allocator.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use lib_simulation::{Config, GameMode, Simulation};
+
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations_during(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+#[test]
+fn test_steps_do_not_allocate() {
+    for mode in [GameMode::Eat, GameMode::Forage] {
+        let mut rng = vroom_rand::seeded(1);
+        let config = Config {
+            num_animals: 16,
+            num_food: 32,
+            mode,
+            ..Config::default()
+        };
+        let mut simulation = Simulation::from_config(&mut rng, config);
+        for _ in 0..10 {
+            simulation.step(&mut rng);
+        }
+
+        let allocations = allocations_during(|| {
+            for _ in 0..100 {
+                simulation.step(&mut rng);
+            }
+        });
+        assert_eq!(allocations, 0, "{mode:?}");
+        assert_eq!(simulation.generation(), 0);
+    }
+}
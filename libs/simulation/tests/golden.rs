@@ -0,0 +1,110 @@
+//! Runs seeded simulations for a few generations and compares a digest of
+//! each (fitness curve and state hash per generation) against the golden
+//! files in `tests/golden`, so numeric behavior changes don't go unnoticed.
+//!
+//! After an intended behavior change, regenerate the files with
+//!
+//! ```text
+//! UPDATE_GOLDEN=1 cargo test -p lib_simulation --test golden
+//! UPDATE_GOLDEN=1 cargo test -p lib_simulation --test golden --features f32-world
+//! UPDATE_GOLDEN=1 cargo test -p lib_simulation --test golden --features strict-determinism
+//! UPDATE_GOLDEN=1 cargo test -p lib_simulation --test golden --features f32-world,strict-determinism
+//! ```
+//!
+//! and review the diff. Features that change the numbers, `f32-world` and
+//! `strict-determinism`, get their own files for each of the four ways to
+//! combine them; other features, like `fixed-capacity`, must not change
+//! them and share the files. Without `strict-determinism` the digests may
+//! differ between platforms whose math libraries round differently.
+
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::{env, fs};
+
+use lib_simulation::{Config, GameMode, Simulation};
+
+const GENERATIONS: usize = 4;
+
+fn digest(config: Config, seed: u64) -> String {
+    let mut rng = vroom_rand::seeded(seed);
+    let mut simulation = Simulation::from_config(&mut rng, config);
+
+    let mut digest = String::new();
+    for generation in 0..GENERATIONS {
+        let stats = simulation.train(&mut rng);
+        let (max, mean) = (stats.max_fitness, stats.mean_fitness);
+        writeln!(
+            digest,
+            "generation {generation}: max {max:?} mean {mean:?} hash {:016x}",
+            simulation.state_hash()
+        )
+        .unwrap();
+    }
+    digest
+}
+
+/// Golden file for `name` under the enabled features that affect results.
+fn golden_path(name: &str) -> PathBuf {
+    let mut variant = Vec::new();
+    if cfg!(feature = "f32-world") {
+        variant.push("f32-world");
+    }
+    if cfg!(feature = "strict-determinism") {
+        variant.push("strict-determinism");
+    }
+    let variant = if variant.is_empty() {
+        "default".to_string()
+    } else {
+        variant.join("+")
+    };
+
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{name}.{variant}.txt"))
+}
+
+fn check_golden(name: &str, actual: &str) {
+    let path = golden_path(name);
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+
+    let expected = fs::read_to_string(&path).unwrap_or_else(|err| {
+        panic!(
+            "{}: {err}; run with UPDATE_GOLDEN=1 to create it",
+            path.display()
+        )
+    });
+    assert!(
+        expected == actual,
+        "{} is out of date; if the change is intended, rerun with UPDATE_GOLDEN=1\n\
+         expected:\n{expected}\nactual:\n{actual}",
+        path.display()
+    );
+}
+
+/// Small enough to run quickly. Reward shaping makes sure some animals have
+/// fitness to select on, even in generations where none scores.
+fn config(mode: GameMode) -> Config {
+    let mut config = Config {
+        num_animals: 16,
+        num_food: 64,
+        mode,
+        generation_steps: 300,
+        ..Config::default()
+    };
+    config.reward.food_approach = 1.0;
+    config
+}
+
+#[test]
+fn test_eat_golden() {
+    check_golden("eat", &digest(config(GameMode::Eat), 0));
+}
+
+#[test]
+fn test_forage_golden() {
+    check_golden("forage", &digest(config(GameMode::Forage), 0));
+}
@@ -0,0 +1,216 @@
+//! Terminal dashboard for watching a single run with `--tui`: generation,
+//! fitness curves, population diversity and an ETA, refreshed while the
+//! simulation steps.
+
+use std::fs;
+use std::io;
+use std::time::{Duration, Instant};
+
+use lib_simulation as sim;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Gauge, Paragraph, Sparkline};
+use ratatui::{DefaultTerminal, Frame};
+use vroom_rand::ChaCha8Rng;
+
+/// How long to step the simulation between redraws.
+const FRAME: Duration = Duration::from_millis(50);
+
+const KEYS: &str = "s: save checkpoint  q: stop";
+
+/// A run being trained, and what the dashboard shows about it.
+struct Run<'a> {
+    experiment: &'a sim::Experiment,
+    seed: u64,
+    rng: ChaCha8Rng,
+    simulation: sim::Simulation,
+    result: sim::RunResult,
+    started: Instant,
+    /// Feedback on the last key press, e.g. where a checkpoint went.
+    message: String,
+}
+
+/// Trains `experiment` with `seed` like `Experiment::run`, until one of its
+/// stopping criteria is met or the user stops it, drawing the dashboard on
+/// the terminal.
+pub(crate) fn run(experiment: &sim::Experiment, seed: u64) -> Result<sim::RunResult, String> {
+    let mut terminal = ratatui::init();
+    let result = train(&mut terminal, experiment, seed);
+    ratatui::restore();
+    result.map_err(|err| err.to_string())
+}
+
+fn train(
+    terminal: &mut DefaultTerminal,
+    experiment: &sim::Experiment,
+    seed: u64,
+) -> io::Result<sim::RunResult> {
+    let mut rng = vroom_rand::seeded(seed);
+    let simulation = sim::Simulation::from_config(&mut rng, experiment.config.clone());
+    let mut run = Run {
+        experiment,
+        seed,
+        rng,
+        simulation,
+        result: sim::RunResult::new(seed),
+        started: Instant::now(),
+        message: String::new(),
+    };
+
+    loop {
+        if let Some(reason) = experiment.stop_reason(&run.result, run.started.elapsed()) {
+            return Ok(run.finish(reason));
+        }
+        run.step_for(FRAME);
+        terminal.draw(|frame| run.draw(frame))?;
+
+        while event::poll(Duration::ZERO)? {
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => {
+                    return Ok(run.finish(sim::StopReason::Interrupted))
+                }
+                KeyCode::Char('s') => run.save_checkpoint(),
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Run<'_> {
+    /// Steps for about `duration`, stopping early at the end of a
+    /// generation.
+    fn step_for(&mut self, duration: Duration) {
+        let deadline = Instant::now() + duration;
+        let generation = self.simulation.generation();
+        while Instant::now() < deadline {
+            self.simulation.step(&mut self.rng);
+            if self.simulation.generation() != generation {
+                let stats = self.simulation.prev_generation_statistics().unwrap();
+                self.result.record_generation(
+                    stats.max_fitness,
+                    stats.mean_fitness,
+                    u64::from(self.experiment.config.num_animals),
+                );
+                return;
+            }
+        }
+    }
+
+    fn finish(mut self, reason: sim::StopReason) -> sim::RunResult {
+        self.result.finish(reason, self.started.elapsed());
+        self.result
+    }
+
+    /// Writes the simulation state, loadable with the wasm and Python
+    /// `import_state`, into the working directory.
+    fn save_checkpoint(&mut self) {
+        let path = format!(
+            "vroom-seed{}-generation{}.json",
+            self.seed,
+            self.simulation.generation()
+        );
+        self.message = match fs::write(&path, self.simulation.to_snapshot()) {
+            Ok(()) => format!("saved {path}"),
+            Err(err) => format!("failed to save {path}: {err}"),
+        };
+    }
+
+    /// Generations done, counting the current one's steps.
+    fn progress(&self) -> f64 {
+        let steps = self.simulation.config().generation_steps.max(1);
+        let current = (self.simulation.generation_steps() as f64 / steps as f64).min(1.0);
+        self.result.max_fitness.len() as f64 + current
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [status, gauge, max, mean, diversity, footer] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Fill(2),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let generations = self.experiment.generations;
+        let elapsed = self.started.elapsed();
+        let progress = self.progress();
+        let eta = if progress > 0.0 {
+            let remaining = (generations as f64 - progress).max(0.0);
+            format_duration(elapsed.mul_f64(remaining / progress))
+        } else {
+            "-".to_string()
+        };
+        frame.render_widget(
+            Paragraph::new(format!(
+                "seed {}  generation {}/{generations}  elapsed {}  eta {eta}",
+                self.seed,
+                self.result.max_fitness.len(),
+                format_duration(elapsed),
+            )),
+            status,
+        );
+        frame.render_widget(
+            Gauge::default()
+                .gauge_style(Style::default().fg(Color::Cyan))
+                .ratio((progress / generations.max(1) as f64).min(1.0)),
+            gauge,
+        );
+
+        frame.render_widget(
+            fitness_sparkline(&self.result.max_fitness, max, Color::Red).block(
+                Block::bordered().title(format!(
+                    "max fitness (best {:.2} in generation {})",
+                    self.result.best_fitness, self.result.best_generation
+                )),
+            ),
+            max,
+        );
+        let last_mean = self.result.mean_fitness.last().copied().unwrap_or_default();
+        frame.render_widget(
+            fitness_sparkline(&self.result.mean_fitness, mean, Color::Blue)
+                .block(Block::bordered().title(format!("mean fitness (last {last_mean:.2})"))),
+            mean,
+        );
+
+        let diversity_line = match self.simulation.prev_generation_statistics() {
+            Some(stats) => format!(
+                "diversity: pairwise distance {:.3}  gene variance {:.4}",
+                stats.mean_pairwise_distance, stats.mean_gene_variance
+            ),
+            None => "diversity: -".to_string(),
+        };
+        frame.render_widget(Paragraph::new(diversity_line), diversity);
+        frame.render_widget(
+            Paragraph::new(Line::from(format!("{KEYS}  {}", self.message))),
+            footer,
+        );
+    }
+}
+
+/// The latest generations that fit into `area`. Sparklines take integers,
+/// so fitness is scaled up to keep some precision.
+fn fitness_sparkline(fitness: &[f64], area: Rect, color: Color) -> Sparkline<'static> {
+    let shown = usize::from(area.width.saturating_sub(2));
+    let data: Vec<u64> = fitness[fitness.len().saturating_sub(shown)..]
+        .iter()
+        .map(|fitness| (fitness.max(0.0) * 1000.0).round() as u64)
+        .collect();
+    Sparkline::default()
+        .data(data)
+        .style(Style::default().fg(color))
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
@@ -0,0 +1,158 @@
+use std::env;
+use std::process::ExitCode;
+
+use lib_simulation as sim;
+use sim::cli::{self, load_config, parse_value};
+
+#[cfg(feature = "dashboard")]
+mod dashboard;
+
+const USAGE: &str = "\
+usage: vroom [CONFIG] [options]
+
+CONFIG is a .toml or .json simulation config; defaults are used if omitted.
+
+options:
+  --generations N   generations per run (default 100)
+  --seeds N         number of runs, seeded 0..N (default 1)
+  --threads N       worker threads (default 1)
+  --target F        stop a run once its max fitness reaches F
+  --patience N      stop a run after N generations without a new best
+  --time-budget S   stop a run after S seconds
+  --max-evaluations N
+                    stop a run once it has evaluated N animals
+  --json            print the summary of every run as JSON
+  --tui             show a live dashboard of a single run instead of a log;
+                    press s to save a checkpoint, q to stop (needs the
+                    dashboard feature)";
+
+struct Args {
+    config: Option<String>,
+    generations: u32,
+    seeds: u64,
+    threads: usize,
+    target_fitness: Option<f64>,
+    patience: Option<u32>,
+    time_budget_secs: Option<f64>,
+    max_evaluations: Option<u64>,
+    json: bool,
+    tui: bool,
+}
+
+fn parse_args(args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args {
+        config: None,
+        generations: 100,
+        seeds: 1,
+        threads: 1,
+        target_fitness: None,
+        patience: None,
+        time_budget_secs: None,
+        max_evaluations: None,
+        json: false,
+        tui: false,
+    };
+
+    parsed.config = cli::parse_args(args, |arg, value| {
+        match arg {
+            "--generations" => parsed.generations = parse_value(&value()?)?,
+            "--seeds" => parsed.seeds = parse_value(&value()?)?,
+            "--threads" => parsed.threads = parse_value(&value()?)?,
+            "--target" => parsed.target_fitness = Some(parse_value(&value()?)?),
+            "--patience" => parsed.patience = Some(parse_value(&value()?)?),
+            "--time-budget" => parsed.time_budget_secs = Some(parse_value(&value()?)?),
+            "--max-evaluations" => parsed.max_evaluations = Some(parse_value(&value()?)?),
+            "--json" => parsed.json = true,
+            "--tui" => parsed.tui = true,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    })?;
+    if parsed.tui && parsed.seeds != 1 {
+        return Err("--tui shows a single run, so it can't be combined with --seeds".to_string());
+    }
+    Ok(parsed)
+}
+
+fn run(args: Args) -> Result<(), String> {
+    let config = match &args.config {
+        Some(path) => load_config(path)?,
+        None => sim::Config::default(),
+    };
+    let experiment = sim::Experiment {
+        config,
+        generations: args.generations,
+        target_fitness: args.target_fitness,
+        patience: args.patience,
+        time_budget_secs: args.time_budget_secs,
+        max_evaluations: args.max_evaluations,
+    };
+    if args.tui {
+        return run_dashboard(&experiment, args.json);
+    }
+    let seeds: Vec<u64> = (0..args.seeds).collect();
+
+    let results = experiment.run_parallel(&seeds, args.threads, |progress| {
+        eprintln!(
+            "seed {} generation {}: max {:.2} mean {:.2}",
+            progress.seed, progress.generation, progress.max_fitness, progress.mean_fitness
+        );
+    });
+
+    print_results(&results, args.json);
+    Ok(())
+}
+
+#[cfg(feature = "dashboard")]
+fn run_dashboard(experiment: &sim::Experiment, json: bool) -> Result<(), String> {
+    print_results(&[dashboard::run(experiment, 0)?], json);
+    Ok(())
+}
+
+#[cfg(not(feature = "dashboard"))]
+fn run_dashboard(_: &sim::Experiment, _: bool) -> Result<(), String> {
+    Err("--tui needs vroom built with --features dashboard".to_string())
+}
+
+fn print_results(results: &[sim::RunResult], json: bool) {
+    if json {
+        println!("{}", serde_json::to_string_pretty(results).unwrap());
+        return;
+    }
+    for result in results {
+        let reason = match result.stop_reason {
+            Some(sim::StopReason::Generations) | None => "",
+            Some(sim::StopReason::TargetFitness) => " (reached target)",
+            Some(sim::StopReason::Stagnation) => " (stopped improving)",
+            Some(sim::StopReason::TimeBudget) => " (out of time)",
+            Some(sim::StopReason::EvaluationBudget) => " (out of evaluations)",
+            Some(sim::StopReason::Interrupted) => " (interrupted)",
+        };
+        println!(
+            "seed {}: best {:.2} in generation {} of {} after {:.1}s{reason}",
+            result.seed,
+            result.best_fitness,
+            result.best_generation,
+            result.generations(),
+            result.elapsed_secs,
+        );
+    }
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{err}\n\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
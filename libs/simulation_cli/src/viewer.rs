@@ -0,0 +1,364 @@
+//! Native viewer for developing simulation features without the wasm and JS
+//! toolchain. Draws the same `payload::World` the web frontend gets from
+//! `render_state`, the fitness history and the brain of the selected
+//! animal, or of the current champion.
+
+use std::{env, process};
+
+use macroquad::prelude::*;
+
+use lib_simulation as sim;
+use sim::cli::{self, load_config, parse_value};
+use sim::payload::World;
+
+const USAGE: &str = "\
+usage: vroom-viewer [CONFIG] [options]
+
+Runs a simulation in a window. CONFIG is a .toml or .json simulation config;
+defaults are used if omitted. Click an animal to select it.
+
+options:
+  --seed N               seed for the simulation (default 0)
+  --steps-per-frame N    simulation steps per frame to start with (default 1)
+
+keys:
+  space      pause or resume
+  up, down   double or halve the steps per frame
+  escape     select the champion again";
+
+const BACKGROUND_COLOR: Color = Color::new(0.96, 0.96, 0.94, 1.0);
+const SELECTED_ANIMAL_COLOR: Color = Color::new(0.75, 0.22, 0.17, 1.0);
+const VISION_COLOR: Color = Color::new(0.75, 0.22, 0.17, 0.08);
+const RECEPTOR_COLOR: Color = Color::new(0.75, 0.22, 0.17, 0.35);
+const FOOD_COLOR: Color = Color::new(0.71, 0.65, 0.58, 1.0);
+const WALL_COLOR: Color = Color::new(0.2, 0.2, 0.2, 1.0);
+const NEST_COLOR: Color = Color::new(0.46, 0.55, 0.62, 0.3);
+const TEXT_COLOR: Color = Color::new(0.2, 0.2, 0.2, 1.0);
+const MAX_FITNESS_COLOR: Color = Color::new(0.75, 0.22, 0.17, 1.0);
+const MEAN_FITNESS_COLOR: Color = Color::new(0.46, 0.55, 0.62, 1.0);
+
+/// How far from an animal a click still selects it, in world units.
+const SELECT_RADIUS: sim::Real = 0.02;
+const PANEL_WIDTH: f32 = 420.0;
+const MARGIN: f32 = 16.0;
+
+struct Args {
+    config: Option<String>,
+    seed: u64,
+    steps_per_frame: u32,
+}
+
+fn parse_args(args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args {
+        config: None,
+        seed: 0,
+        steps_per_frame: 1,
+    };
+
+    parsed.config = cli::parse_args(args, |arg, value| {
+        match arg {
+            "--seed" => parsed.seed = parse_value(&value()?)?,
+            "--steps-per-frame" => parsed.steps_per_frame = parse_value(&value()?)?,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    })?;
+    Ok(parsed)
+}
+
+/// Where the world is drawn: world coordinates times `scale`, offset by
+/// `origin`.
+struct WorldView {
+    origin: Vec2,
+    scale: f32,
+}
+
+impl WorldView {
+    /// Fits the world into the window left of the panel, keeping its
+    /// aspect ratio.
+    fn fit(world: &sim::World) -> Self {
+        let width = screen_width() - PANEL_WIDTH - 3.0 * MARGIN;
+        let height = screen_height() - 2.0 * MARGIN;
+        let scale = (width / world.width() as f32).min(height / world.height() as f32);
+        Self {
+            origin: vec2(MARGIN, MARGIN),
+            scale,
+        }
+    }
+
+    fn to_screen(&self, x: sim::Real, y: sim::Real) -> Vec2 {
+        self.origin + vec2(x as f32, y as f32) * self.scale
+    }
+
+    fn to_world(&self, point: Vec2) -> (sim::Real, sim::Real) {
+        let point = (point - self.origin) / self.scale;
+        (point.x as sim::Real, point.y as sim::Real)
+    }
+}
+
+fn draw_world(world: &World, view: &WorldView, selected: Option<usize>) {
+    let corner = view.to_screen(0.0, 0.0);
+    let size = view.to_screen(world.width, world.height) - corner;
+    draw_rectangle(corner.x, corner.y, size.x, size.y, WHITE);
+
+    if let Some(nest) = &world.nest {
+        let center = view.to_screen(nest.x, nest.y);
+        draw_circle(
+            center.x,
+            center.y,
+            nest.radius as f32 * view.scale,
+            NEST_COLOR,
+        );
+    }
+    for wall in &world.walls {
+        let (start, end) = (
+            view.to_screen(wall.x1, wall.y1),
+            view.to_screen(wall.x2, wall.y2),
+        );
+        draw_line(start.x, start.y, end.x, end.y, 2.0, WALL_COLOR);
+    }
+    for food in &world.food {
+        let center = view.to_screen(food.x, food.y);
+        let radius = food.radius as f32 * view.scale;
+        draw_circle(center.x, center.y, radius, FOOD_COLOR);
+    }
+
+    if let Some(animal) = selected.and_then(|idx| world.animals.get(idx)) {
+        if let Some(vision) = &animal.vision {
+            draw_vision(animal, vision, view);
+        }
+    }
+    for (idx, animal) in world.animals.iter().enumerate() {
+        let color = if selected == Some(idx) {
+            SELECTED_ANIMAL_COLOR
+        } else {
+            macroquad::color::hsl_to_rgb(animal.hue as f32 / 360.0, 0.35, 0.5)
+        };
+        let center = view.to_screen(animal.x, animal.y);
+        let size = animal.radius as f32 * view.scale;
+        let rotation = animal.rotation as f32;
+        let corner = |angle: f32| center + Vec2::from_angle(angle) * size;
+        // Legs at ±140 degrees from the head
+        let leg = 14.0 * std::f32::consts::PI / 18.0;
+        draw_triangle(
+            corner(rotation),
+            corner(rotation + leg),
+            corner(rotation - leg),
+            color,
+        );
+    }
+}
+
+/// The vision cone, with each receptor's sector shaded by how close the
+/// food it sees is.
+fn draw_vision(animal: &sim::payload::Animal, vision: &sim::payload::Vision, view: &WorldView) {
+    let center = view.to_screen(animal.x, animal.y);
+    let range = vision.fov_range as f32 * view.scale;
+    let fov_angle = vision.fov_angle as f32;
+    let start = animal.rotation as f32 - fov_angle / 2.0;
+    fill_sector(center, range, start, start + fov_angle, VISION_COLOR);
+
+    let angle_per_receptor = fov_angle / vision.receptors.len() as f32;
+    for (idx, &activation) in vision.receptors.iter().enumerate() {
+        if activation > 1.0 {
+            continue;
+        }
+        let receptor_start = start + idx as f32 * angle_per_receptor;
+        fill_sector(
+            center,
+            activation as f32 * range,
+            receptor_start,
+            receptor_start + angle_per_receptor,
+            RECEPTOR_COLOR,
+        );
+    }
+}
+
+fn fill_sector(center: Vec2, radius: f32, start: f32, end: f32, color: Color) {
+    const SEGMENTS: usize = 16;
+    let step = (end - start) / SEGMENTS as f32;
+    for segment in 0..SEGMENTS {
+        let angle = start + segment as f32 * step;
+        draw_triangle(
+            center,
+            center + Vec2::from_angle(angle) * radius,
+            center + Vec2::from_angle(angle + step) * radius,
+            color,
+        );
+    }
+}
+
+/// Max and mean fitness of every finished generation.
+fn draw_fitness_chart(history: &[sim::GenerationStatistics], area: Rect) {
+    draw_rectangle_lines(area.x, area.y, area.w, area.h, 1.0, TEXT_COLOR);
+    draw_text(
+        "fitness (max, mean)",
+        area.x,
+        area.y - 6.0,
+        18.0,
+        TEXT_COLOR,
+    );
+    if history.len() < 2 {
+        return;
+    }
+
+    let max: Vec<f64> = history.iter().map(|stats| stats.max_fitness).collect();
+    let mean: Vec<f64> = history.iter().map(|stats| stats.mean_fitness).collect();
+    let top = max.iter().copied().fold(f64::MIN_POSITIVE, f64::max);
+    let point = |generation: usize, fitness: f64| {
+        vec2(
+            area.x + area.w * generation as f32 / (history.len() - 1) as f32,
+            area.y + area.h * (1.0 - (fitness / top) as f32),
+        )
+    };
+    for (series, color) in [(max, MAX_FITNESS_COLOR), (mean, MEAN_FITNESS_COLOR)] {
+        for (generation, pair) in series.windows(2).enumerate() {
+            let from = point(generation, pair[0]);
+            let to = point(generation + 1, pair[1]);
+            draw_line(from.x, from.y, to.x, to.y, 2.0, color);
+        }
+    }
+    draw_text(
+        &format!("{top:.2}"),
+        area.x + 4.0,
+        area.y + 16.0,
+        16.0,
+        TEXT_COLOR,
+    );
+}
+
+/// Neurons as columns of dots, connections colored by sign (blue positive,
+/// red negative) and opacity by magnitude.
+fn draw_brain(animal: &sim::Animal, title: &str, area: Rect) {
+    let brain = animal.brain();
+    draw_rectangle_lines(area.x, area.y, area.w, area.h, 1.0, TEXT_COLOR);
+    draw_text(title, area.x, area.y - 6.0, 18.0, TEXT_COLOR);
+
+    let (inputs, layers) = brain.topology();
+    let sizes: Vec<usize> = std::iter::once(inputs).chain(layers).collect();
+    let neuron = |layer: usize, idx: usize| {
+        vec2(
+            area.x + area.w * (layer as f32 + 0.5) / sizes.len() as f32,
+            area.y + area.h * (idx as f32 + 0.5) / sizes[layer] as f32,
+        )
+    };
+
+    // Each neuron's bias, followed by its incoming weights
+    let mut params = brain.weights_and_biases().into_iter();
+    for layer in 1..sizes.len() {
+        for to in 0..sizes[layer] {
+            params.next();
+            for from in 0..sizes[layer - 1] {
+                let weight = params.next().unwrap_or_default() as f32;
+                let color = if weight >= 0.0 {
+                    Color::new(0.16, 0.5, 0.73, weight.abs().min(1.0))
+                } else {
+                    Color::new(0.75, 0.22, 0.17, weight.abs().min(1.0))
+                };
+                let (start, end) = (neuron(layer - 1, from), neuron(layer, to));
+                draw_line(start.x, start.y, end.x, end.y, 1.0, color);
+            }
+        }
+    }
+    for (layer, &size) in sizes.iter().enumerate() {
+        for idx in 0..size {
+            let center = neuron(layer, idx);
+            draw_circle(center.x, center.y, 4.0, TEXT_COLOR);
+        }
+    }
+}
+
+fn window_conf() -> Conf {
+    Conf {
+        window_title: "vroom".to_string(),
+        window_width: 1280,
+        window_height: 800,
+        high_dpi: true,
+        ..Default::default()
+    }
+}
+
+#[macroquad::main(window_conf)]
+async fn main() {
+    let args = parse_args(env::args().skip(1)).unwrap_or_else(|err| {
+        eprintln!("{err}\n\n{USAGE}");
+        process::exit(1);
+    });
+    let config = match &args.config {
+        Some(path) => load_config(path).unwrap_or_else(|err| {
+            eprintln!("{err}");
+            process::exit(1);
+        }),
+        None => sim::Config::default(),
+    };
+
+    let mut rng = vroom_rand::seeded(args.seed);
+    let mut simulation = sim::Simulation::from_config(&mut rng, config);
+    let mut steps_per_frame = args.steps_per_frame;
+    let mut paused = false;
+    let mut selected: Option<usize> = None;
+
+    loop {
+        let view = WorldView::fit(simulation.world());
+
+        if is_key_pressed(KeyCode::Space) {
+            paused = !paused;
+        }
+        if is_key_pressed(KeyCode::Up) {
+            steps_per_frame = (steps_per_frame * 2).max(1);
+        }
+        if is_key_pressed(KeyCode::Down) {
+            steps_per_frame /= 2;
+        }
+        if is_key_pressed(KeyCode::Escape) {
+            selected = None;
+        }
+        if is_mouse_button_pressed(MouseButton::Left) {
+            let (x, y) = view.to_world(mouse_position().into());
+            if let Some(idx) = simulation.world().animal_at([x, y].into(), SELECT_RADIUS) {
+                selected = Some(idx);
+            }
+        }
+        if !paused {
+            for _ in 0..steps_per_frame {
+                simulation.step(&mut rng);
+            }
+        }
+
+        clear_background(BACKGROUND_COLOR);
+        let world = World::from_world(simulation.world(), true);
+        draw_world(&world, &view, selected);
+
+        let panel = screen_width() - PANEL_WIDTH - MARGIN;
+        let status = format!(
+            "generation {}  step {}  {} steps/frame{}",
+            simulation.generation(),
+            simulation.generation_steps(),
+            steps_per_frame,
+            if paused { "  (paused)" } else { "" }
+        );
+        draw_text(&status, panel, MARGIN + 14.0, 20.0, TEXT_COLOR);
+        draw_fitness_chart(
+            simulation.generation_statistics(),
+            Rect::new(panel, MARGIN + 56.0, PANEL_WIDTH, 220.0),
+        );
+
+        let brain_area = Rect::new(
+            panel,
+            MARGIN + 320.0,
+            PANEL_WIDTH,
+            screen_height() - 320.0 - 2.0 * MARGIN,
+        );
+        let animals = simulation.world().animals();
+        match selected.and_then(|idx| animals.get(idx)) {
+            Some(animal) => draw_brain(animal, "selected animal's brain", brain_area),
+            None => {
+                if let Some(champion) = simulation.champion() {
+                    draw_brain(champion, "champion's brain", brain_area);
+                }
+            }
+        }
+
+        next_frame().await
+    }
+}
@@ -0,0 +1,47 @@
+/*
+ * C API of the vroom simulation, implemented by libvroom_ffi (see
+ * libs/simulation_ffi/src/lib.rs, which documents every function).
+ *
+ * Handles are opaque and not thread-safe: use each from one thread at a
+ * time and free it with vroom_simulation_free. Functions that can fail
+ * return NULL or false and leave a message for vroom_last_error.
+ */
+
+#ifndef VROOM_H
+#define VROOM_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* World buffer layout: a header of width, height, number of animals and
+ * number of food, then x, y, rotation and hue of every animal, then x and
+ * y of every piece of food. */
+#define VROOM_WORLD_HEADER_LEN 4
+#define VROOM_ANIMAL_STRIDE 4
+#define VROOM_FOOD_STRIDE 2
+
+typedef struct VroomSimulation VroomSimulation;
+
+const char *vroom_last_error(void);
+
+VroomSimulation *vroom_simulation_new(uint64_t seed);
+VroomSimulation *vroom_simulation_from_config_json(const char *json, uint64_t seed);
+void vroom_simulation_free(VroomSimulation *simulation);
+
+bool vroom_simulation_step(VroomSimulation *simulation, uint32_t steps);
+uint32_t vroom_simulation_generation(const VroomSimulation *simulation);
+
+/* Returns the number of floats the world takes, writing them only if that
+ * fits into len; pass NULL to query the size. */
+size_t vroom_simulation_world_buffer(const VroomSimulation *simulation, float *buffer, size_t len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* VROOM_H */
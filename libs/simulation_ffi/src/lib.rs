@@ -0,0 +1,248 @@
+//! C ABI for embedding the simulation, e.g. in game engine plugins; see
+//! `include/vroom.h`.
+//!
+//! A simulation lives behind an opaque handle from `vroom_simulation_new`
+//! or `vroom_simulation_from_config_json` until it's passed to
+//! `vroom_simulation_free`. Functions that can fail return null or false
+//! and leave a message for `vroom_last_error`. Panics never cross the
+//! boundary.
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use vroom_rand::ChaCha8Rng;
+
+use lib_simulation as sim;
+
+/// Floats at the start of the world buffer: width, height, number of
+/// animals and number of food.
+pub const VROOM_WORLD_HEADER_LEN: usize = 4;
+/// Floats per animal in the world buffer: x, y, rotation and hue.
+pub const VROOM_ANIMAL_STRIDE: usize = 4;
+/// Floats per piece of food in the world buffer: x and y.
+pub const VROOM_FOOD_STRIDE: usize = 2;
+
+/// Opaque handle to a simulation and the RNG it's stepped with.
+pub struct VroomSimulation {
+    rng: ChaCha8Rng,
+    sim: sim::Simulation,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: impl Into<String>) {
+    // Messages come from Rust strings, which may contain NULs
+    let message = message.into().replace('\0', " ");
+    LAST_ERROR.with(|error| *error.borrow_mut() = CString::new(message).ok());
+}
+
+/// Runs `f`, turning a panic into `None` and an error message.
+fn catch<T>(f: impl FnOnce() -> T) -> Option<T> {
+    panic::catch_unwind(AssertUnwindSafe(f))
+        .map_err(|panic| {
+            let message = panic
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            set_last_error(format!("simulation panicked: {message}"));
+        })
+        .ok()
+}
+
+fn into_handle(mut rng: ChaCha8Rng, config: sim::Config) -> *mut VroomSimulation {
+    let sim = sim::Simulation::from_config(&mut rng, config);
+    Box::into_raw(Box::new(VroomSimulation { rng, sim }))
+}
+
+/// Message of the last error on this thread, or null if there was none. The
+/// string stays valid until the next failing call on this thread.
+#[no_mangle]
+pub extern "C" fn vroom_last_error() -> *const c_char {
+    LAST_ERROR.with(|error| {
+        error
+            .borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Creates a simulation with the default config. The same seed replays the
+/// same run.
+#[no_mangle]
+pub extern "C" fn vroom_simulation_new(seed: u64) -> *mut VroomSimulation {
+    catch(|| into_handle(vroom_rand::seeded(seed), sim::Config::default()))
+        .unwrap_or(ptr::null_mut())
+}
+
+/// Creates a simulation from a JSON config, or returns null if it's
+/// invalid.
+///
+/// # Safety
+///
+/// `json` must be a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn vroom_simulation_from_config_json(
+    json: *const c_char,
+    seed: u64,
+) -> *mut VroomSimulation {
+    if json.is_null() {
+        set_last_error("config is null");
+        return ptr::null_mut();
+    }
+    let config = match CStr::from_ptr(json).to_str() {
+        Ok(json) => sim::Config::from_json_str(json).map_err(|err| err.to_string()),
+        Err(err) => Err(err.to_string()),
+    };
+    match config {
+        Ok(config) => {
+            catch(|| into_handle(vroom_rand::seeded(seed), config)).unwrap_or(ptr::null_mut())
+        }
+        Err(err) => {
+            set_last_error(err);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Destroys a simulation. Null is ignored.
+///
+/// # Safety
+///
+/// `simulation` must be null or a handle that hasn't been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn vroom_simulation_free(simulation: *mut VroomSimulation) {
+    if !simulation.is_null() {
+        drop(Box::from_raw(simulation));
+    }
+}
+
+/// Runs `steps` steps. Returns false if the simulation failed, after which
+/// it should be freed.
+///
+/// # Safety
+///
+/// `simulation` must be a live handle, not used from another thread at the
+/// same time.
+#[no_mangle]
+pub unsafe extern "C" fn vroom_simulation_step(
+    simulation: *mut VroomSimulation,
+    steps: u32,
+) -> bool {
+    let simulation = &mut *simulation;
+    catch(|| {
+        for _ in 0..steps {
+            simulation.sim.step(&mut simulation.rng);
+        }
+    })
+    .is_some()
+}
+
+/// # Safety
+///
+/// `simulation` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn vroom_simulation_generation(simulation: *const VroomSimulation) -> u32 {
+    (*simulation).sim.generation()
+}
+
+/// Writes the world into `buffer` as floats: the header, then every animal,
+/// then every piece of food; see the `VROOM_*` constants for the layout.
+/// Returns the number of floats the world takes and writes nothing if
+/// that's more than `len`, so a null buffer queries the size.
+///
+/// # Safety
+///
+/// `simulation` must be a live handle and `buffer` must be null or valid
+/// for writing `len` floats.
+#[no_mangle]
+pub unsafe extern "C" fn vroom_simulation_world_buffer(
+    simulation: *const VroomSimulation,
+    buffer: *mut f32,
+    len: usize,
+) -> usize {
+    let world = (*simulation).sim.world();
+    let needed = world_buffer_len(world);
+    if !buffer.is_null() && needed <= len {
+        write_world(world, std::slice::from_raw_parts_mut(buffer, needed));
+    }
+    needed
+}
+
+fn world_buffer_len(world: &sim::World) -> usize {
+    VROOM_WORLD_HEADER_LEN
+        + VROOM_ANIMAL_STRIDE * world.animals().len()
+        + VROOM_FOOD_STRIDE * world.food().len()
+}
+
+fn write_world(world: &sim::World, buffer: &mut [f32]) {
+    let (header, rest) = buffer.split_at_mut(VROOM_WORLD_HEADER_LEN);
+    header.copy_from_slice(&[
+        world.width() as f32,
+        world.height() as f32,
+        world.animals().len() as f32,
+        world.food().len() as f32,
+    ]);
+
+    let (animals, food) = rest.split_at_mut(VROOM_ANIMAL_STRIDE * world.animals().len());
+    for (out, animal) in animals
+        .chunks_exact_mut(VROOM_ANIMAL_STRIDE)
+        .zip(world.animals())
+    {
+        out.copy_from_slice(&[
+            animal.position().x as f32,
+            animal.position().y as f32,
+            animal.rotation().angle() as f32,
+            animal.hue() as f32,
+        ]);
+    }
+    for (out, food) in food.chunks_exact_mut(VROOM_FOOD_STRIDE).zip(world.food()) {
+        out.copy_from_slice(&[food.position().x as f32, food.position().y as f32]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_world_buffer() {
+        let json = CString::new(r#"{"num_animals": 3, "num_food": 5}"#).unwrap();
+        unsafe {
+            let simulation = vroom_simulation_from_config_json(json.as_ptr(), 0);
+            assert!(!simulation.is_null());
+            assert!(vroom_simulation_step(simulation, 2));
+
+            let len = vroom_simulation_world_buffer(simulation, ptr::null_mut(), 0);
+            assert_eq!(
+                len,
+                VROOM_WORLD_HEADER_LEN + 3 * VROOM_ANIMAL_STRIDE + 5 * VROOM_FOOD_STRIDE
+            );
+            let mut buffer = vec![0.0; len];
+            vroom_simulation_world_buffer(simulation, buffer.as_mut_ptr(), len);
+            assert_eq!(buffer[2..4], [3.0, 5.0]);
+            let animal = &(*simulation).sim.world().animals()[1];
+            let offset = VROOM_WORLD_HEADER_LEN + VROOM_ANIMAL_STRIDE;
+            assert_eq!(buffer[offset], animal.position().x as f32);
+            assert_eq!(buffer[offset + 2], animal.rotation().angle() as f32);
+
+            vroom_simulation_free(simulation);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let json = CString::new(r#"{"num_animals": 0}"#).unwrap();
+        unsafe {
+            let simulation = vroom_simulation_from_config_json(json.as_ptr(), 0);
+            assert!(simulation.is_null());
+            let error = CStr::from_ptr(vroom_last_error()).to_str().unwrap();
+            assert!(error.contains("num_animals"), "{error}");
+            vroom_simulation_free(simulation);
+        }
+    }
+}
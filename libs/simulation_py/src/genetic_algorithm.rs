@@ -0,0 +1,191 @@
+use lib_reinforcement_learning::genetic_algorithm::{self as ga, Individual, PopulationInit};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use vroom_rand::RngCore;
+
+use lib_simulation as sim;
+
+use crate::value_error;
+
+/// The genetic algorithm the simulation evolves brains with, on its own,
+/// for optimizing genomes whose fitness is computed in Python.
+#[pyclass(unsendable)]
+pub struct GeneticAlgorithm {
+    rng: Box<dyn RngCore>,
+    ga: ga::DynGeneticAlgorithm,
+}
+
+/// A genome and the fitness Python gave it.
+struct Scored {
+    chromosome: ga::Chromosome,
+    fitness: f64,
+}
+
+impl Individual for Scored {
+    fn from_chromosome(chromosome: ga::Chromosome) -> Self {
+        Self {
+            chromosome,
+            fitness: 0.0,
+        }
+    }
+
+    fn as_chromosome(&self) -> &ga::Chromosome {
+        &self.chromosome
+    }
+
+    fn fitness(&self) -> f64 {
+        self.fitness
+    }
+}
+
+#[pymethods]
+impl GeneticAlgorithm {
+    /// Takes the same settings as the config's `mutation` section, with the
+    /// same defaults. Children average their parents' genomes with
+    /// probability `blend` and mix their genes uniformly otherwise.
+    #[new]
+    #[pyo3(signature = (rate=None, strength=None, blend=None, distinct_parents=false, seed=None))]
+    fn new(
+        rate: Option<f64>,
+        strength: Option<f64>,
+        blend: Option<f64>,
+        distinct_parents: bool,
+        seed: Option<u64>,
+    ) -> PyResult<Self> {
+        let defaults = sim::MutationConfig::default();
+        let mutation = sim::MutationConfig {
+            rate: rate.unwrap_or(defaults.rate),
+            strength: strength.unwrap_or(defaults.strength),
+            blend: blend.unwrap_or(defaults.blend),
+            ..defaults
+        };
+        let config = sim::Config {
+            mutation: mutation.clone(),
+            ..sim::Config::default()
+        };
+        config.validate().map_err(value_error)?;
+
+        let ga = ga::GeneticAlgorithm::new(
+            Box::new(ga::FitnessProportionateSelection::new()) as Box<dyn ga::Selection>,
+            Box::new(ga::EitherCrossover::new(
+                mutation.blend,
+                ga::BlendCrossover::mean(),
+                ga::UniformCrossover::new(),
+            )) as Box<dyn ga::Crossover>,
+            Box::new(ga::GaussianMutation::new(mutation.rate, mutation.strength))
+                as Box<dyn ga::Mutation>,
+        );
+        let ga = if distinct_parents {
+            ga.with_distinct_parents()
+        } else {
+            ga
+        };
+        Ok(Self {
+            rng: vroom_rand::seeded_or_random(seed),
+            ga,
+        })
+    }
+
+    /// `size` genomes of `num_genes` genes drawn uniformly from
+    /// [`low`, `high`].
+    #[pyo3(signature = (size, num_genes, low=-1.0, high=1.0))]
+    fn random_population(
+        &mut self,
+        size: usize,
+        num_genes: usize,
+        low: f64,
+        high: f64,
+    ) -> Vec<Vec<f64>> {
+        ga::RandomInit::new(num_genes, low..=high)
+            .init(&mut self.rng, size)
+            .into_iter()
+            .map(|chromosome| chromosome.into_iter().collect())
+            .collect()
+    }
+
+    /// Breeds a new generation of as many genomes from `genomes`, where
+    /// `fitnesses[i]` is the non-negative fitness of `genomes[i]`. If every
+    /// fitness is zero, parents are picked uniformly.
+    fn evolve(&mut self, genomes: Vec<Vec<f64>>, fitnesses: Vec<f64>) -> PyResult<Vec<Vec<f64>>> {
+        if genomes.is_empty() {
+            return Err(PyValueError::new_err("population must not be empty"));
+        }
+        if genomes.len() != fitnesses.len() {
+            return Err(PyValueError::new_err(format!(
+                "expected one fitness per genome, got {} genomes and {} fitnesses",
+                genomes.len(),
+                fitnesses.len()
+            )));
+        }
+        if genomes
+            .iter()
+            .any(|genome| genome.len() != genomes[0].len())
+        {
+            return Err(PyValueError::new_err("genomes must have the same length"));
+        }
+        if fitnesses
+            .iter()
+            .any(|&fitness| !(fitness >= 0.0 && fitness.is_finite()))
+        {
+            return Err(PyValueError::new_err(
+                "fitnesses must be finite and non-negative",
+            ));
+        }
+
+        let population: ga::Population<Scored> = genomes
+            .into_iter()
+            .zip(fitnesses)
+            .map(|(genome, fitness)| Scored {
+                chromosome: genome.into_iter().collect(),
+                fitness,
+            })
+            .collect();
+        Ok(self
+            .ga
+            .evolve(&mut self.rng, &population)
+            .into_iter()
+            .map(|child| child.chromosome.into_iter().collect())
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn genetic_algorithm(seed: u64) -> GeneticAlgorithm {
+        GeneticAlgorithm::new(None, None, None, false, Some(seed)).unwrap()
+    }
+
+    #[test]
+    fn test_evolve() {
+        let mut a = genetic_algorithm(1);
+        let mut b = genetic_algorithm(1);
+        let genomes = a.random_population(4, 3, -1.0, 1.0);
+        assert_eq!(genomes, b.random_population(4, 3, -1.0, 1.0));
+
+        let fitnesses = vec![1.0, 2.0, 0.0, 4.0];
+        let children = a.evolve(genomes.clone(), fitnesses.clone()).unwrap();
+        assert_eq!(children.len(), 4);
+        assert!(children.iter().all(|child| child.len() == 3));
+        assert_eq!(children, b.evolve(genomes.clone(), fitnesses).unwrap());
+
+        // Without any signal, parents are picked uniformly
+        assert_eq!(a.evolve(genomes, vec![0.0; 4]).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_evolve_errors() {
+        let mut ga = genetic_algorithm(1);
+        let genomes = ga.random_population(2, 3, -1.0, 1.0);
+
+        assert!(ga.evolve(vec![], vec![]).is_err());
+        assert!(ga.evolve(genomes.clone(), vec![1.0]).is_err());
+        assert!(ga
+            .evolve(vec![vec![0.0; 3], vec![0.0; 2]], vec![1.0; 2])
+            .is_err());
+        assert!(ga.evolve(genomes.clone(), vec![1.0, -1.0]).is_err());
+        assert!(ga.evolve(genomes, vec![1.0, f64::NAN]).is_err());
+        assert!(GeneticAlgorithm::new(Some(2.0), None, None, false, None).is_err());
+    }
+}
@@ -0,0 +1,455 @@
+//! Python bindings, mirroring the wasm API so experiments can be driven and
+//! analyzed from notebooks. Build with `maturin develop` in this directory,
+//! then `import vroom`.
+//!
+//! Structured values (worlds, statistics, configs) cross over as plain
+//! dicts and lists, in the same format the wasm bindings hand to JS.
+
+// `#[pymethods]` expands to conversions of `PyErr` into itself
+#![allow(clippy::useless_conversion)]
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use serde::Serialize;
+use vroom_rand::RngCore;
+
+use lib_simulation as sim;
+use sim::payload::{Brain, GenerationStatistics, Heatmap, World, WorldChange};
+
+mod genetic_algorithm;
+
+#[pymodule]
+fn vroom(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<Config>()?;
+    module.add_class::<Simulation>()?;
+    module.add_class::<genetic_algorithm::GeneticAlgorithm>()?;
+    Ok(())
+}
+
+/// A validated simulation config; see `lib_simulation::Config` for the
+/// fields.
+#[pyclass]
+#[derive(Clone)]
+pub struct Config {
+    config: sim::Config,
+}
+
+#[pymethods]
+impl Config {
+    /// The default config.
+    #[new]
+    fn new() -> Self {
+        Self {
+            config: sim::Config::default(),
+        }
+    }
+
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<Self> {
+        let config = sim::Config::from_json_str(json).map_err(value_error)?;
+        Ok(Self { config })
+    }
+
+    #[staticmethod]
+    fn from_toml(toml: &str) -> PyResult<Self> {
+        let config = sim::Config::from_toml_str(toml).map_err(value_error)?;
+        Ok(Self { config })
+    }
+
+    /// From a dict in the format of `to_dict`; missing fields keep their
+    /// defaults.
+    #[staticmethod]
+    fn from_dict(dict: &Bound<'_, PyAny>) -> PyResult<Self> {
+        let json = dict
+            .py()
+            .import_bound("json")?
+            .call_method1("dumps", (dict,))?;
+        Self::from_json(&json.extract::<String>()?)
+    }
+
+    fn to_json(&self) -> String {
+        serde_json::to_string(&self.config).unwrap()
+    }
+
+    fn to_dict(&self, py: Python<'_>) -> PyResult<PyObject> {
+        to_py(py, &self.config)
+    }
+
+    /// Describes every field: type, range, default and description.
+    #[staticmethod]
+    fn schema(py: Python<'_>) -> PyResult<PyObject> {
+        to_py(py, &sim::Config::schema())
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Config({})", self.to_json())
+    }
+}
+
+#[pyclass(unsendable)]
+pub struct Simulation {
+    /// Seeded when constructed with a seed, for reproducible runs;
+    /// otherwise by the operating system.
+    rng: Box<dyn RngCore>,
+    sim: sim::Simulation,
+}
+
+#[pymethods]
+impl Simulation {
+    /// Every constructor takes an optional `seed`. With one, the simulation
+    /// draws all of its randomness from a ChaCha RNG seeded with it, so the
+    /// same seed and inputs replay the same run.
+    #[new]
+    #[pyo3(signature = (config=None, seed=None))]
+    fn new(config: Option<Config>, seed: Option<u64>) -> Self {
+        let config = config.map(|config| config.config).unwrap_or_default();
+        let mut rng = vroom_rand::seeded_or_random(seed);
+        let sim = sim::Simulation::from_config(&mut rng, config);
+        Self { rng, sim }
+    }
+
+    /// Creates a simulation on one of the maps listed by `bundled_maps`.
+    #[staticmethod]
+    #[pyo3(signature = (name, config=None, seed=None))]
+    fn with_bundled_map(name: &str, config: Option<Config>, seed: Option<u64>) -> PyResult<Self> {
+        let map = sim::Map::bundled(name)
+            .ok_or_else(|| PyValueError::new_err(format!("unknown map: {name}")))?;
+        let config = config.map(|config| config.config).unwrap_or_default();
+        let mut rng = vroom_rand::seeded_or_random(seed);
+        let sim = sim::Simulation::from_map(&mut rng, &map, config);
+        Ok(Self { rng, sim })
+    }
+
+    #[staticmethod]
+    fn bundled_maps() -> Vec<String> {
+        sim::Map::bundled_names().map(String::from).collect()
+    }
+
+    fn config(&self) -> Config {
+        Config {
+            config: self.sim.config().clone(),
+        }
+    }
+
+    /// The world in the wasm `render_state` format.
+    #[pyo3(signature = (debug_vision=false))]
+    fn world(&self, py: Python<'_>, debug_vision: bool) -> PyResult<PyObject> {
+        to_py(py, &World::from_world(self.sim.world(), debug_vision))
+    }
+
+    fn generation(&self) -> u32 {
+        self.sim.generation()
+    }
+
+    fn generation_steps(&self) -> u32 {
+        self.sim.generation_steps()
+    }
+
+    fn rollout(&self) -> u32 {
+        self.sim.rollout()
+    }
+
+    /// Steps left before the current generation evolves, at most; `None`
+    /// when generations only end manually.
+    fn steps_remaining_in_generation(&self) -> Option<u32> {
+        self.sim.steps_remaining_in_generation()
+    }
+
+    fn step(&mut self) {
+        self.sim.step(&mut self.rng);
+    }
+
+    /// Like `step`, returning what changed as a list of world changes.
+    fn step_diff(&mut self, py: Python<'_>) -> PyResult<PyObject> {
+        let before = sim::WorldOutline::of(self.sim.world());
+        self.sim.step(&mut self.rng);
+        let after = self.sim.world();
+        let changes: Vec<WorldChange> = sim::WorldDiff::since(&before, after)
+            .changes()
+            .iter()
+            .map(|change| WorldChange::from_change(change, after))
+            .collect();
+        to_py(py, &changes)
+    }
+
+    /// Runs `generations` whole generations and returns their statistics.
+    /// `progress`, if given, is called as `progress(done, statistics)` after
+    /// each generation.
+    #[pyo3(signature = (generations, progress=None))]
+    fn train(
+        &mut self,
+        py: Python<'_>,
+        generations: u32,
+        progress: Option<PyObject>,
+    ) -> PyResult<PyObject> {
+        let mut history = Vec::with_capacity(generations as usize);
+        for done in 1..=generations {
+            let stats = GenerationStatistics::from(self.sim.train(&mut self.rng));
+            if let Some(progress) = &progress {
+                progress.call1(py, (done, to_py(py, &stats)?))?;
+            }
+            history.push(stats);
+        }
+        to_py(py, &history)
+    }
+
+    fn evolve(&mut self) {
+        self.sim.evolve(&mut self.rng);
+    }
+
+    /// Ends the current generation at the next step.
+    fn force_evolve(&mut self) {
+        self.sim.force_evolve();
+    }
+
+    /// Every animal's brain inputs, for driving the animals with an
+    /// external policy instead of their brains; see `act`.
+    fn observe(&mut self) -> Vec<Vec<sim::Real>> {
+        self.sim.observe(&mut self.rng)
+    }
+
+    /// Applies one list of brain outputs per animal.
+    fn act(&mut self, actions: Vec<Vec<sim::Real>>) -> PyResult<()> {
+        let num_animals = self.sim.world().animals().len();
+        let (_, layers) = self.sim.config().brain_topology();
+        let outputs = layers.last().copied().unwrap_or_default();
+        if actions.len() != num_animals || actions.iter().any(|action| action.len() != outputs) {
+            return Err(PyValueError::new_err(format!(
+                "expected {num_animals} actions of {outputs} outputs each"
+            )));
+        }
+        self.sim.act(&actions);
+        Ok(())
+    }
+
+    /// Fitness of every animal so far this generation.
+    fn fitnesses(&self) -> Vec<f64> {
+        self.sim.fitnesses()
+    }
+
+    fn prev_generation_statistics(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let stats = self
+            .sim
+            .prev_generation_statistics()
+            .map(GenerationStatistics::from);
+        to_py(py, &stats)
+    }
+
+    /// Statistics of every finished generation.
+    fn generation_statistics(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let history: Vec<_> = self
+            .sim
+            .generation_statistics()
+            .iter()
+            .map(GenerationStatistics::from)
+            .collect();
+        to_py(py, &history)
+    }
+
+    /// Steps animals spent in each cell of the world this generation so
+    /// far: `cols`, `rows` and `counts` row after row.
+    fn heatmap(&self, py: Python<'_>) -> PyResult<PyObject> {
+        to_py(py, &Heatmap::from(self.sim.heatmap()))
+    }
+
+    fn prev_heatmap(&self, py: Python<'_>) -> PyResult<PyObject> {
+        to_py(py, &self.sim.prev_heatmap().map(Heatmap::from))
+    }
+
+    fn reset_heatmap(&mut self) {
+        self.sim.reset_heatmap();
+    }
+
+    /// The full statistics history as CSV, one row per generation.
+    fn statistics_csv(&self) -> String {
+        self.sim.statistics_csv()
+    }
+
+    /// See `lib_simulation::Simulation::state_hash`.
+    fn state_hash(&self) -> u64 {
+        self.sim.state_hash()
+    }
+
+    /// Changes how many steps each generation lasts, from the next step on.
+    fn set_generation_length(&mut self, steps: u32) -> PyResult<()> {
+        self.sim.set_generation_length(steps).map_err(value_error)
+    }
+
+    fn add_food(&mut self, x: sim::Real, y: sim::Real) {
+        self.sim.add_food([x, y].into());
+    }
+
+    fn clear_food(&mut self) {
+        self.sim.clear_food();
+    }
+
+    fn genome_length(&self) -> usize {
+        self.sim.config().genome_length()
+    }
+
+    /// Adds an animal with the given genome and returns its id.
+    fn add_animal_with_brain(&mut self, genome: Vec<f64>) -> PyResult<u64> {
+        let len = genome.len();
+        self.sim
+            .add_animal_with_brain(&mut self.rng, genome)
+            .ok_or_else(|| {
+                PyValueError::new_err(format!(
+                    "expected {} genes, got {len}",
+                    self.sim.config().genome_length()
+                ))
+            })
+    }
+
+    /// Removes the animal with `id`, as in `render_state`, until the next
+    /// generation. The animals after it shift down, which `step_diff`
+    /// doesn't report, so re-read `render_state` after a kill.
+    fn kill_animal(&mut self, id: u64) -> bool {
+        self.sim.kill_animal(id)
+    }
+
+    /// The current generation's fittest animal's brain as JSON, for
+    /// `load_brain_json`.
+    fn best_brain_json(&self) -> Option<String> {
+        let brain = Brain::champion(&self.sim)?;
+        Some(serde_json::to_string(&brain).unwrap())
+    }
+
+    /// Starts a new population seeded from a brain exported with
+    /// `best_brain_json`, here or in the browser.
+    fn load_brain_json(&mut self, json: &str) -> PyResult<()> {
+        let brain: Brain = serde_json::from_str(json).map_err(value_error)?;
+        let config = brain
+            .into_brain_config(self.sim.config())
+            .map_err(value_error)?;
+        self.sim.reseed(&mut self.rng, config).map_err(value_error)
+    }
+
+    /// The full simulation state, compatible with the wasm `export_state`.
+    fn export_state<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new_bound(py, &self.sim.to_snapshot())
+    }
+
+    /// Replaces the simulation state with one produced by `export_state`.
+    /// Raises `ValueError` when it was saved by a newer version or was
+    /// modified.
+    fn import_state(&mut self, bytes: &[u8]) -> PyResult<()> {
+        self.sim = sim::Simulation::from_snapshot(bytes).map_err(value_error)?;
+        Ok(())
+    }
+}
+
+fn value_error(err: impl ToString) -> PyErr {
+    PyValueError::new_err(err.to_string())
+}
+
+/// Converts through JSON, so values look the same as in the browser.
+fn to_py(py: Python<'_>, value: &impl Serialize) -> PyResult<PyObject> {
+    let json = serde_json::to_string(value).unwrap();
+    let value = py.import_bound("json")?.call_method1("loads", (json,))?;
+    Ok(value.unbind())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> Config {
+        Config::from_json(r#"{ "num_animals": 4, "num_food": 8, "generation_steps": 20 }"#).unwrap()
+    }
+
+    #[test]
+    fn test_config() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dict = config().to_dict(py).unwrap();
+            let config = Config::from_dict(dict.bind(py)).unwrap();
+            assert_eq!(config.config.num_animals, 4);
+
+            let err = Config::from_json(r#"{ "num_animals": 0 }"#).err().unwrap();
+            assert!(err.is_instance_of::<PyValueError>(py));
+        });
+    }
+
+    #[test]
+    fn test_seeded_runs_match() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let mut a = Simulation::new(Some(config()), Some(1));
+            let mut b = Simulation::new(Some(config()), Some(1));
+            a.step();
+            b.step_diff(py).unwrap();
+            assert_eq!(a.state_hash(), b.state_hash());
+
+            let history = a.train(py, 2, None).unwrap();
+            assert_eq!(history.bind(py).len().unwrap(), 2);
+            assert_eq!(a.generation(), 2);
+        });
+    }
+
+    #[test]
+    fn test_train_raises_progress_errors() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let mut simulation = Simulation::new(Some(config()), Some(1));
+            let progress = py
+                .eval_bound("lambda done, stats: 1 / 0", None, None)
+                .unwrap();
+
+            let err = simulation
+                .train(py, 3, Some(progress.unbind()))
+                .unwrap_err();
+            assert!(err.is_instance_of::<pyo3::exceptions::PyZeroDivisionError>(py));
+            assert_eq!(simulation.generation(), 1);
+        });
+    }
+
+    #[test]
+    fn test_external_policy() {
+        let mut simulation = Simulation::new(Some(config()), Some(1));
+        let observations = simulation.observe();
+        assert_eq!(observations.len(), 4);
+
+        assert!(simulation.act(vec![vec![1.0, 0.0]; 3]).is_err());
+        assert!(simulation.act(vec![vec![1.0]; 4]).is_err());
+        simulation.act(vec![vec![1.0, 0.0]; 4]).unwrap();
+    }
+
+    #[test]
+    fn test_animals() {
+        let mut simulation = Simulation::new(Some(config()), Some(1));
+        let genome = vec![0.0; simulation.genome_length()];
+
+        assert_eq!(simulation.add_animal_with_brain(genome).unwrap(), 4);
+        assert!(simulation.add_animal_with_brain(vec![0.0]).is_err());
+        assert!(simulation.kill_animal(0));
+        assert!(simulation.kill_animal(4));
+        assert!(!simulation.kill_animal(0));
+    }
+
+    #[test]
+    fn test_export_import_state() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let mut simulation = Simulation::new(Some(config()), Some(1));
+            simulation.step();
+            let state = simulation.export_state(py);
+
+            let mut restored = Simulation::new(None, Some(2));
+            restored.import_state(state.as_bytes()).unwrap();
+            assert_eq!(restored.state_hash(), simulation.state_hash());
+            let err = restored.import_state(b"{").unwrap_err();
+            assert!(err.is_instance_of::<PyValueError>(py));
+        });
+    }
+
+    #[test]
+    fn test_brain_json() {
+        let mut simulation = Simulation::new(Some(config()), Some(1));
+        let brain = simulation.best_brain_json().unwrap();
+
+        simulation.load_brain_json(&brain).unwrap();
+        assert_eq!(simulation.generation(), 0);
+        assert!(simulation.load_brain_json("{}").is_err());
+    }
+}
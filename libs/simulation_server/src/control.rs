@@ -0,0 +1,207 @@
+//! HTTP API for orchestrating headless runs, e.g. from scripts or notebooks.
+//! Requests are handled on the thread that owns the simulation, between
+//! frames, and call into the same `Simulation` methods as the wasm bindings.
+
+use serde::Serialize;
+use tiny_http::{Header, Method, Request, Response};
+
+use lib_simulation as sim;
+use sim::payload::{Brain, GenerationStatistics};
+
+use crate::Runner;
+
+pub(crate) const ENDPOINTS: &str = "\
+  GET  /status           whether the run is paused and how far along it is
+  POST /start            resumes stepping
+  POST /pause            stops stepping; frontends keep receiving frames
+  POST /stop             shuts the server down
+  GET  /config           the simulation config as JSON
+  PUT  /config           starts over from a JSON config, with the same seed
+  GET  /statistics       statistics of every finished generation as JSON
+  GET  /statistics.csv   the same as CSV
+  GET  /champion         the fittest brain of the current generation
+  PUT  /champion         starts over with a population seeded from a brain";
+
+/// A response to a control request.
+#[derive(Debug)]
+pub(crate) struct Reply {
+    pub(crate) status: u16,
+    pub(crate) content_type: &'static str,
+    pub(crate) body: String,
+}
+
+#[derive(Serialize)]
+struct Status {
+    paused: bool,
+    generation: u32,
+    generation_steps: u32,
+}
+
+impl Reply {
+    fn json(value: &impl Serialize) -> Self {
+        Self {
+            status: 200,
+            content_type: "application/json",
+            body: serde_json::to_string(value).unwrap(),
+        }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: serde_json::json!({ "error": message.into() }).to_string(),
+        }
+    }
+
+    fn status(runner: &Runner) -> Self {
+        Self::json(&Status {
+            paused: runner.paused,
+            generation: runner.simulation.generation(),
+            generation_steps: runner.simulation.generation_steps(),
+        })
+    }
+}
+
+/// Answers `request`, reading its body first.
+pub(crate) fn handle(runner: &mut Runner, mut request: Request) {
+    let mut body = String::new();
+    let reply = match request.as_reader().read_to_string(&mut body) {
+        Ok(_) => route(runner, request.method(), request.url(), &body),
+        Err(err) => Reply::error(400, err.to_string()),
+    };
+
+    let content_type = Header::from_bytes("Content-Type", reply.content_type).unwrap();
+    let response = Response::from_string(reply.body)
+        .with_status_code(reply.status)
+        .with_header(content_type);
+    if let Err(err) = request.respond(response) {
+        eprintln!("failed to answer a control request: {err}");
+    }
+}
+
+pub(crate) fn route(runner: &mut Runner, method: &Method, url: &str, body: &str) -> Reply {
+    let path = url.split('?').next().unwrap_or_default();
+    match (method, path) {
+        (Method::Get, "/status") => Reply::status(runner),
+        (Method::Post, "/start") => {
+            runner.paused = false;
+            Reply::status(runner)
+        }
+        (Method::Post, "/pause") => {
+            runner.paused = true;
+            Reply::status(runner)
+        }
+        (Method::Post, "/stop") => {
+            runner.stopped = true;
+            Reply::status(runner)
+        }
+        (Method::Get, "/config") => Reply::json(runner.simulation.config()),
+        (Method::Put, "/config") => match sim::Config::from_json_str(body) {
+            Ok(config) => {
+                let mut rng = vroom_rand::seeded(runner.seed);
+                runner.simulation = sim::Simulation::from_config(&mut rng, config);
+                runner.rng = rng;
+                runner.restarted = true;
+                Reply::status(runner)
+            }
+            Err(err) => Reply::error(400, err.to_string()),
+        },
+        (Method::Get, "/statistics") => Reply::json(
+            &runner
+                .simulation
+                .generation_statistics()
+                .iter()
+                .map(GenerationStatistics::from)
+                .collect::<Vec<_>>(),
+        ),
+        (Method::Get, "/statistics.csv") => Reply {
+            status: 200,
+            content_type: "text/csv",
+            body: runner.simulation.statistics_csv(),
+        },
+        (Method::Get, "/champion") => match Brain::champion(&runner.simulation) {
+            Some(brain) => Reply::json(&brain),
+            None => Reply::error(404, "there are no animals"),
+        },
+        (Method::Put, "/champion") => {
+            let config = serde_json::from_str::<Brain>(body)
+                .map_err(|err| err.to_string())
+                .and_then(|brain| brain.into_brain_config(runner.simulation.config()));
+            let reseeded = config.and_then(|config| {
+                runner
+                    .simulation
+                    .reseed(&mut runner.rng, config)
+                    .map_err(|err| err.to_string())
+            });
+            match reseeded {
+                Ok(()) => {
+                    runner.restarted = true;
+                    Reply::status(runner)
+                }
+                Err(err) => Reply::error(400, err),
+            }
+        }
+        (
+            _,
+            "/status" | "/start" | "/pause" | "/stop" | "/config" | "/statistics"
+            | "/statistics.csv" | "/champion",
+        ) => Reply::error(405, format!("{method} is not allowed on {path}")),
+        _ => Reply::error(404, format!("no such endpoint: {path}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn request(runner: &mut Runner, method: Method, url: &str, body: &str) -> Value {
+        let reply = route(runner, &method, url, body);
+        assert_eq!(reply.status, 200, "{method} {url}: {}", reply.body);
+        serde_json::from_str(&reply.body).unwrap()
+    }
+
+    #[test]
+    fn test_route() {
+        let mut runner = Runner::new(sim::Config::default(), 0);
+
+        let status = request(&mut runner, Method::Post, "/pause", "");
+        assert_eq!(status["paused"], true);
+        assert!(runner.paused);
+        request(&mut runner, Method::Post, "/start", "");
+        assert!(!runner.paused);
+
+        runner.step(3);
+        let status = request(&mut runner, Method::Get, "/status", "");
+        assert_eq!(status["generation_steps"], 3);
+
+        let brain = request(&mut runner, Method::Get, "/champion", "");
+        request(&mut runner, Method::Put, "/champion", &brain.to_string());
+        assert!(runner.restarted);
+        assert_eq!(runner.simulation.generation_steps(), 0);
+
+        let mut config = request(&mut runner, Method::Get, "/config", "");
+        config["num_animals"] = 3.into();
+        request(&mut runner, Method::Put, "/config", &config.to_string());
+        assert_eq!(runner.simulation.world().animals().len(), 3);
+
+        let statistics = request(&mut runner, Method::Get, "/statistics", "");
+        assert_eq!(statistics, Value::Array(Vec::new()));
+
+        let invalid = route(
+            &mut runner,
+            &Method::Put,
+            "/config",
+            r#"{"num_animals": 0}"#,
+        );
+        assert_eq!(invalid.status, 400);
+        assert!(invalid.body.contains("num_animals"));
+        assert_eq!(runner.simulation.world().animals().len(), 3);
+        assert_eq!(route(&mut runner, &Method::Get, "/stop", "").status, 405);
+        assert_eq!(route(&mut runner, &Method::Get, "/nope", "").status, 404);
+
+        request(&mut runner, Method::Post, "/stop", "");
+        assert!(runner.stopped);
+    }
+}
@@ -0,0 +1,387 @@
+use std::io;
+use std::net::{TcpListener, TcpStream};
+use std::process::ExitCode;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use std::{env, thread};
+
+use serde::{Deserialize, Serialize};
+use tungstenite::{Message, WebSocket};
+use vroom_rand::ChaCha8Rng;
+
+use lib_simulation as sim;
+use sim::cli::{self, load_config, parse_value};
+use sim::payload::{Frame, FrameStream, GenerationStatistics};
+
+mod control;
+mod metrics;
+
+#[global_allocator]
+static ALLOCATOR: metrics::CountingAllocator = metrics::CountingAllocator;
+
+const USAGE: &str = "\
+usage: vroom-server [CONFIG] [options]
+
+Runs a simulation and streams it over WebSocket to any number of frontends.
+CONFIG is a .toml or .json simulation config; defaults are used if omitted.
+
+options:
+  --addr ADDR            address to listen on (default 127.0.0.1:8080)
+  --seed N               seed for the simulation (default 0)
+  --fps N                frames sent to each frontend per second (default 60)
+  --steps-per-frame N    simulation steps per frame (default 1)
+  --control ADDR         also serve the HTTP control API on ADDR
+  --metrics ADDR         also serve Prometheus metrics on http://ADDR/metrics
+
+Every frontend receives JSON text messages tagged with a \"type\":
+  {\"type\": \"frame\", ...}       a keyframe or delta, see payload::Frame
+  {\"type\": \"statistics\", ...}  statistics of each finished generation
+and can send:
+  {\"type\": \"ack\", \"frame\": N}  deltas are relative to the last acked frame
+  {\"type\": \"keyframe\"}         the next frame is a whole world
+
+The control API answers with JSON, errors as {\"error\": ...}:
+";
+
+struct Args {
+    config: Option<String>,
+    addr: String,
+    seed: u64,
+    fps: u32,
+    steps_per_frame: u32,
+    control: Option<String>,
+    metrics: Option<String>,
+}
+
+/// Message to a frontend.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServerMessage {
+    Frame(Frame),
+    Statistics {
+        generation: usize,
+        statistics: GenerationStatistics,
+    },
+}
+
+/// Message from a frontend.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    Ack { frame: u64 },
+    Keyframe,
+}
+
+fn parse_args(args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args {
+        config: None,
+        addr: "127.0.0.1:8080".to_string(),
+        seed: 0,
+        fps: 60,
+        steps_per_frame: 1,
+        control: None,
+        metrics: None,
+    };
+
+    parsed.config = cli::parse_args(args, |arg, value| {
+        match arg {
+            "--addr" => parsed.addr = value()?,
+            "--seed" => parsed.seed = parse_value(&value()?)?,
+            "--fps" => parsed.fps = parse_value(&value()?)?,
+            "--steps-per-frame" => parsed.steps_per_frame = parse_value(&value()?)?,
+            "--control" => parsed.control = Some(value()?),
+            "--metrics" => parsed.metrics = Some(value()?),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    })?;
+    if parsed.fps == 0 {
+        return Err("--fps must be at least 1".to_string());
+    }
+    Ok(parsed)
+}
+
+/// The simulation and how it's being run, shared by the frame loop and the
+/// control API.
+struct Runner {
+    simulation: sim::Simulation,
+    rng: ChaCha8Rng,
+    /// Seed the simulation was created from, reused when the config changes.
+    seed: u64,
+    paused: bool,
+    stopped: bool,
+    /// Set when the simulation starts over, so frontends get a keyframe and
+    /// the statistics of the new run from its first generation.
+    restarted: bool,
+    metrics: metrics::Metrics,
+}
+
+impl Runner {
+    fn new(config: sim::Config, seed: u64) -> Self {
+        let mut rng = vroom_rand::seeded(seed);
+        let simulation = sim::Simulation::from_config(&mut rng, config);
+        Self {
+            simulation,
+            rng,
+            seed,
+            paused: false,
+            stopped: false,
+            restarted: false,
+            metrics: metrics::Metrics::new(),
+        }
+    }
+
+    fn step(&mut self, steps: u32) {
+        let steps = if self.paused { 0 } else { steps };
+        for _ in 0..steps {
+            self.simulation.step(&mut self.rng);
+        }
+        self.metrics.record_steps(steps);
+    }
+}
+
+/// A connected frontend, served from the thread that owns the simulation
+/// through a non-blocking socket.
+struct Client {
+    socket: WebSocket<TcpStream>,
+    frames: FrameStream,
+    /// Generations whose statistics have been sent.
+    reported: usize,
+}
+
+impl Client {
+    fn new(socket: WebSocket<TcpStream>) -> io::Result<Self> {
+        socket.get_ref().set_nonblocking(true)?;
+        Ok(Self {
+            socket,
+            frames: FrameStream::new(),
+            reported: 0,
+        })
+    }
+
+    /// Starts the frontend over on a simulation that was replaced.
+    fn restart(&mut self) {
+        self.frames.request_keyframe();
+        self.reported = 0;
+    }
+
+    /// Handles what the frontend sent since the last call, then sends it a
+    /// frame and the statistics of any generations it hasn't seen yet,
+    /// starting with the first. Returns whether the frontend is still
+    /// connected.
+    fn update(&mut self, simulation: &sim::Simulation) -> Result<bool, String> {
+        loop {
+            match self.socket.read() {
+                Ok(Message::Text(text)) => match serde_json::from_str(&text) {
+                    Ok(ClientMessage::Ack { frame }) => {
+                        self.frames.ack(frame);
+                    }
+                    Ok(ClientMessage::Keyframe) => self.frames.request_keyframe(),
+                    Err(err) => eprintln!("ignoring message {text:?}: {err}"),
+                },
+                Ok(_) => {}
+                Err(err) if would_block(&err) => break,
+                Err(tungstenite::Error::ConnectionClosed) => return Ok(false),
+                Err(err) => return Err(err.to_string()),
+            }
+        }
+
+        let frame = self
+            .frames
+            .frame(simulation.world(), simulation.generation(), false);
+        self.send(&ServerMessage::Frame(frame))?;
+        let history = simulation.generation_statistics();
+        for (generation, stats) in history.iter().enumerate().skip(self.reported) {
+            self.send(&ServerMessage::Statistics {
+                generation,
+                statistics: GenerationStatistics::from(stats),
+            })?;
+        }
+        self.reported = history.len();
+        Ok(true)
+    }
+
+    /// Messages that don't fit into the socket yet stay queued and go out
+    /// with later ones.
+    fn send(&mut self, message: &ServerMessage) -> Result<(), String> {
+        let text = serde_json::to_string(message).unwrap();
+        match self.socket.send(Message::Text(text)) {
+            Err(err) if !would_block(&err) => Err(err.to_string()),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn would_block(err: &tungstenite::Error) -> bool {
+    matches!(err, tungstenite::Error::Io(err) if err.kind() == io::ErrorKind::WouldBlock)
+}
+
+/// Completes the WebSocket handshake with every incoming connection and
+/// hands the sockets over to the simulation thread.
+fn accept_clients(listener: TcpListener, clients: mpsc::Sender<WebSocket<TcpStream>>) {
+    for stream in listener.incoming() {
+        let socket = stream
+            .map_err(|err| err.to_string())
+            .and_then(|stream| tungstenite::accept(stream).map_err(|err| err.to_string()));
+        match socket {
+            Ok(socket) => {
+                if clients.send(socket).is_err() {
+                    return;
+                }
+            }
+            Err(err) => eprintln!("failed to accept a connection: {err}"),
+        }
+    }
+}
+
+fn run(args: Args) -> Result<(), String> {
+    let config = match &args.config {
+        Some(path) => load_config(path)?,
+        None => sim::Config::default(),
+    };
+    let mut runner = Runner::new(config, args.seed);
+    let interval = Duration::from_secs(1) / args.fps;
+
+    let listener = TcpListener::bind(&args.addr).map_err(|err| format!("{}: {err}", args.addr))?;
+    eprintln!("listening on ws://{}", args.addr);
+    let (sender, incoming) = mpsc::channel();
+    thread::spawn(move || accept_clients(listener, sender));
+    let control = match &args.control {
+        Some(addr) => {
+            let server = tiny_http::Server::http(addr).map_err(|err| format!("{addr}: {err}"))?;
+            eprintln!("control API on http://{addr}");
+            Some(server)
+        }
+        None => None,
+    };
+    let metrics = match &args.metrics {
+        Some(addr) => {
+            let server = tiny_http::Server::http(addr).map_err(|err| format!("{addr}: {err}"))?;
+            eprintln!("metrics on http://{addr}/metrics");
+            Some(server)
+        }
+        None => None,
+    };
+
+    let mut clients = Vec::new();
+    loop {
+        let deadline = Instant::now() + interval;
+        for socket in incoming.try_iter() {
+            match Client::new(socket) {
+                Ok(client) => clients.push(client),
+                Err(err) => eprintln!("failed to set up a connection: {err}"),
+            }
+        }
+
+        if let Some(control) = &control {
+            loop {
+                match control.try_recv() {
+                    Ok(Some(request)) => control::handle(&mut runner, request),
+                    Ok(None) => break,
+                    Err(err) => return Err(format!("control API: {err}")),
+                }
+            }
+        }
+        if let Some(metrics) = &metrics {
+            loop {
+                match metrics.try_recv() {
+                    Ok(Some(request)) => metrics::handle(&runner, request),
+                    Ok(None) => break,
+                    Err(err) => return Err(format!("metrics: {err}")),
+                }
+            }
+        }
+        if runner.stopped {
+            return Ok(());
+        }
+        if runner.restarted {
+            clients.iter_mut().for_each(Client::restart);
+            runner.restarted = false;
+        }
+
+        runner.step(args.steps_per_frame);
+        clients.retain_mut(|client| match client.update(&runner.simulation) {
+            Ok(connected) => connected,
+            Err(err) => {
+                eprintln!("dropping a frontend: {err}");
+                false
+            }
+        });
+
+        thread::sleep(deadline.saturating_duration_since(Instant::now()));
+    }
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{err}\n\n{USAGE}{}", control::ENDPOINTS);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn receive(socket: &mut WebSocket<impl io::Read + io::Write>) -> Value {
+        match socket.read().unwrap() {
+            Message::Text(text) => serde_json::from_str(&text).unwrap(),
+            message => panic!("expected text, got {message:?}"),
+        }
+    }
+
+    #[test]
+    fn test_client() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let frontend = thread::spawn(move || {
+            tungstenite::connect(format!("ws://{addr}")).map_err(|err| err.to_string())
+        });
+        let (stream, _) = listener.accept().unwrap();
+        let mut client = Client::new(tungstenite::accept(stream).unwrap()).unwrap();
+        let (mut frontend, _) = frontend.join().unwrap().unwrap();
+
+        let mut rng = vroom_rand::seeded(0);
+        let mut simulation = sim::Simulation::random(&mut rng, 2, 2);
+        assert!(client.update(&simulation).unwrap());
+        let first = receive(&mut frontend);
+        assert_eq!(first["type"], "frame");
+        assert_eq!(first["kind"], "Keyframe");
+        assert_eq!(first["world"]["animals"].as_array().unwrap().len(), 2);
+
+        simulation.step(&mut rng);
+
+        let ack = format!(r#"{{"type": "ack", "frame": {}}}"#, first["frame"]);
+        frontend.send(Message::Text(ack)).unwrap();
+        // Frames stay keyframes until the ack has arrived
+        let delta = loop {
+            assert!(client.update(&simulation).unwrap());
+            let frame = receive(&mut frontend);
+            if frame["kind"] == "Delta" {
+                break frame;
+            }
+            thread::sleep(Duration::from_millis(1));
+        };
+        assert_eq!(delta["base"], first["frame"]);
+
+        simulation.evolve(&mut rng);
+        assert!(client.update(&simulation).unwrap());
+        assert_eq!(receive(&mut frontend)["kind"], "Keyframe");
+        let statistics = receive(&mut frontend);
+        assert_eq!(statistics["type"], "statistics");
+        assert_eq!(statistics["generation"], 0);
+        assert!(statistics["statistics"]["max_fitness"].is_number());
+    }
+}
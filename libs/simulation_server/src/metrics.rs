@@ -0,0 +1,215 @@
+//! Prometheus metrics for monitoring long runs, e.g. in Grafana: throughput,
+//! the fitness and diversity of the last finished generation, and how much
+//! the process allocates.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use tiny_http::{Header, Method, Request, Response};
+
+use crate::Runner;
+
+/// How long steps are counted for before the rate is updated.
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// The system allocator, counting allocations for `vroom_allocations_total`.
+pub(crate) struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(new_size as u64, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+/// Step counts of a run, kept across restarts.
+pub(crate) struct Metrics {
+    steps: u64,
+    steps_per_second: f64,
+    window_start: Instant,
+    window_steps: u64,
+}
+
+impl Metrics {
+    pub(crate) fn new() -> Self {
+        Self {
+            steps: 0,
+            steps_per_second: 0.0,
+            window_start: Instant::now(),
+            window_steps: 0,
+        }
+    }
+
+    /// Called every frame, with no steps while paused, so the rate drops.
+    pub(crate) fn record_steps(&mut self, steps: u32) {
+        self.steps += u64::from(steps);
+        let elapsed = self.window_start.elapsed();
+        if elapsed >= RATE_WINDOW {
+            self.steps_per_second = (self.steps - self.window_steps) as f64 / elapsed.as_secs_f64();
+            self.window_start = Instant::now();
+            self.window_steps = self.steps;
+        }
+    }
+}
+
+/// Answers a scrape of `/metrics`; any other request gets a 404.
+pub(crate) fn handle(runner: &Runner, request: Request) {
+    let response = if *request.method() == Method::Get && request.url() == "/metrics" {
+        let content_type = Header::from_bytes("Content-Type", "text/plain; version=0.0.4").unwrap();
+        Response::from_string(render(runner)).with_header(content_type)
+    } else {
+        Response::from_string("metrics are served on GET /metrics").with_status_code(404)
+    };
+    if let Err(err) = request.respond(response) {
+        eprintln!("failed to answer a metrics request: {err}");
+    }
+}
+
+/// The metrics in the Prometheus text format.
+pub(crate) fn render(runner: &Runner) -> String {
+    let simulation = &runner.simulation;
+    let mut metrics = vec![
+        (
+            "vroom_steps_total",
+            "counter",
+            "Simulation steps run.",
+            runner.metrics.steps as f64,
+        ),
+        (
+            "vroom_steps_per_second",
+            "gauge",
+            "Steps run per second over the last second.",
+            runner.metrics.steps_per_second,
+        ),
+        (
+            "vroom_paused",
+            "gauge",
+            "Whether stepping is paused.",
+            f64::from(u8::from(runner.paused)),
+        ),
+        (
+            "vroom_generation",
+            "gauge",
+            "Current generation.",
+            f64::from(simulation.generation()),
+        ),
+        (
+            "vroom_generation_steps",
+            "gauge",
+            "Steps into the current generation.",
+            f64::from(simulation.generation_steps()),
+        ),
+        (
+            "vroom_allocations_total",
+            "counter",
+            "Heap allocations, including reallocations.",
+            ALLOCATIONS.load(Ordering::Relaxed) as f64,
+        ),
+        (
+            "vroom_allocated_bytes_total",
+            "counter",
+            "Bytes requested by heap allocations.",
+            ALLOCATED_BYTES.load(Ordering::Relaxed) as f64,
+        ),
+    ];
+    if let Some(stats) = simulation.prev_generation_statistics() {
+        metrics.extend([
+            (
+                "vroom_max_fitness",
+                "gauge",
+                "Max fitness of the last finished generation.",
+                stats.max_fitness,
+            ),
+            (
+                "vroom_mean_fitness",
+                "gauge",
+                "Mean fitness of the last finished generation.",
+                stats.mean_fitness,
+            ),
+            (
+                "vroom_min_fitness",
+                "gauge",
+                "Min fitness of the last finished generation.",
+                stats.min_fitness,
+            ),
+            (
+                "vroom_std_fitness",
+                "gauge",
+                "Standard deviation of fitness in the last finished generation.",
+                stats.std_fitness,
+            ),
+            (
+                "vroom_mean_pairwise_distance",
+                "gauge",
+                "Mean distance between genomes of the last finished generation.",
+                stats.mean_pairwise_distance,
+            ),
+            (
+                "vroom_mean_gene_variance",
+                "gauge",
+                "Mean variance of each gene in the last finished generation.",
+                stats.mean_gene_variance,
+            ),
+        ]);
+    }
+
+    let mut out = String::new();
+    for (name, kind, help, value) in metrics {
+        writeln!(out, "# HELP {name} {help}").unwrap();
+        writeln!(out, "# TYPE {name} {kind}").unwrap();
+        writeln!(out, "{name} {value}").unwrap();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lib_simulation as sim;
+
+    fn value(metrics: &str, name: &str) -> Option<f64> {
+        metrics.lines().find_map(|line| {
+            let (metric, value) = line.split_once(' ')?;
+            (metric == name).then(|| value.parse().unwrap())
+        })
+    }
+
+    #[test]
+    fn test_render() {
+        let config = sim::Config {
+            generation_steps: 5,
+            ..sim::Config::default()
+        };
+        let mut runner = Runner::new(config, 0);
+        runner.step(6);
+
+        let metrics = render(&runner);
+        assert_eq!(value(&metrics, "vroom_steps_total"), Some(6.0));
+        assert_eq!(value(&metrics, "vroom_generation"), Some(1.0));
+        assert!(value(&metrics, "vroom_mean_fitness").is_some());
+        assert!(value(&metrics, "vroom_allocations_total").unwrap() > 0.0);
+        assert!(metrics.contains("# TYPE vroom_steps_total counter"));
+    }
+}
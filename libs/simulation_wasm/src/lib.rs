@@ -1,57 +1,236 @@
-use rand::{rngs::ThreadRng, thread_rng};
+use nalgebra as na;
 use serde::Serialize;
 use serde_wasm_bindgen::to_value;
+use vroom_rand::RngCore;
 use wasm_bindgen::prelude::*;
 
 use lib_simulation as sim;
+use sim::payload::{
+    Brain, Food, FrameStream, GenerationStatistics, Heatmap, Vision, World, WorldChange,
+};
 
 #[wasm_bindgen]
 pub struct Simulation {
-    rng: ThreadRng,
+    /// Seeded when constructed with a seed, for reproducible runs;
+    /// otherwise by the operating system.
+    rng: Box<dyn RngCore>,
     sim: sim::Simulation,
+    /// Independent worlds stepped alongside `sim` by `step_all`, e.g. to
+    /// compare configs side by side. Arena 0 is `sim` itself.
+    arenas: Vec<sim::Simulation>,
+    debug_vision: bool,
+    /// State serialized by the last `num_state_chunks`, handed out by
+    /// `export_state_chunk`.
+    exported_state: Vec<u8>,
+    frames: FrameStream,
 }
 
-#[derive(Clone, Debug, Serialize)]
-pub struct GenerationStatistics {
-    max_fitness: f64,
-    min_fitness: f64,
-    mean_fitness: f64,
-    std_fitness: f64,
-}
+/// Size of each chunk returned by `export_state_chunk`, except the last.
+const STATE_CHUNK_SIZE: usize = 1 << 20;
 
+/// Per-gene-position mean and standard deviation across one generation's
+/// population, for a heatmap of which brain weights have converged.
 #[derive(Clone, Debug, Serialize)]
-pub struct World {
-    animals: Vec<Animal>,
-    food: Vec<Food>,
+pub struct GeneStatistics {
+    mean: Vec<f64>,
+    std: Vec<f64>,
 }
 
 #[derive(Clone, Debug, Serialize)]
-pub struct Animal {
-    x: f64,
-    y: f64,
-    rotation: f64,
+pub struct InspectState {
+    animals: Vec<AnimalDetails>,
+    food: Vec<Food>,
 }
 
 #[derive(Clone, Debug, Serialize)]
-pub struct Food {
-    x: f64,
-    y: f64,
+pub struct AnimalDetails {
+    id: u64,
+    x: sim::Real,
+    y: sim::Real,
+    rotation: sim::Real,
+    speed: f64,
+    consumed: u32,
+    carrying: bool,
+    deposited: u32,
+    vision: Vision,
 }
 
 #[wasm_bindgen]
 impl Simulation {
+    /// Every constructor takes an optional `seed`. With one, the simulation
+    /// draws all of its randomness from a ChaCha RNG seeded with it, so the
+    /// same seed and inputs replay the same run.
     #[wasm_bindgen(constructor)]
-    pub fn new() -> Self {
-        let mut rng = thread_rng();
-        let num_animals = 32;
-        let num_food = 128;
-        let sim = sim::Simulation::random(&mut rng, num_animals, num_food);
-        Self { rng, sim }
+    pub fn new(seed: Option<u64>) -> Self {
+        Self::with_game_mode(sim::GameMode::default(), seed)
+    }
+
+    /// Creates a simulation for the given objective: `"eat"` or `"forage"`.
+    pub fn with_mode(mode: &str, seed: Option<u64>) -> Result<Simulation, JsError> {
+        Ok(Self::with_game_mode(parse_game_mode(mode)?, seed))
+    }
+
+    /// Creates a simulation on one of the maps listed by `bundled_maps`.
+    pub fn with_bundled_map(
+        name: &str,
+        mode: &str,
+        seed: Option<u64>,
+    ) -> Result<Simulation, JsError> {
+        let map =
+            sim::Map::bundled(name).ok_or_else(|| JsError::new(&format!("unknown map: {name}")))?;
+        Ok(Self::with_map(&map, parse_game_mode(mode)?, seed))
+    }
+
+    /// Creates a simulation on a map described in JSON.
+    pub fn with_map_json(json: &str, mode: &str, seed: Option<u64>) -> Result<Simulation, JsError> {
+        let map = sim::Map::from_json_str(json)?;
+        Ok(Self::with_map(&map, parse_game_mode(mode)?, seed))
+    }
+
+    /// Creates a simulation on a map described in TOML.
+    pub fn with_map_toml(toml: &str, mode: &str, seed: Option<u64>) -> Result<Simulation, JsError> {
+        let map = sim::Map::from_toml_str(toml)?;
+        Ok(Self::with_map(&map, parse_game_mode(mode)?, seed))
+    }
+
+    /// Creates a simulation from a JSON config; see `Config` for the fields.
+    pub fn with_config_json(json: &str, seed: Option<u64>) -> Result<Simulation, JsError> {
+        Ok(Self::with_config(sim::Config::from_json_str(json)?, seed))
+    }
+
+    /// Creates a simulation from a TOML config; see `Config` for the fields.
+    pub fn with_config_toml(toml: &str, seed: Option<u64>) -> Result<Simulation, JsError> {
+        Ok(Self::with_config(sim::Config::from_toml_str(toml)?, seed))
+    }
+
+    /// Describes every config field (type, range, default, description) so
+    /// the settings panel can be generated; see `lib_simulation::Config::schema`.
+    pub fn config_schema() -> Result<JsValue, JsError> {
+        to_js(&sim::Config::schema())
+    }
+
+    pub fn bundled_maps() -> Vec<String> {
+        sim::Map::bundled_names().map(String::from).collect()
+    }
+
+    /// Minimal per-frame payload: the world size, positions and rotations.
+    pub fn render_state(&self) -> Result<JsValue, JsError> {
+        let world = World::from_world(self.sim.world(), self.debug_vision);
+        to_js(&world)
+    }
+
+    /// Detailed payload for inspecting the world, meant to be requested on
+    /// demand rather than every frame.
+    pub fn inspect_state(&self) -> Result<JsValue, JsError> {
+        let state = InspectState::from(self.sim.world());
+        to_js(&state)
+    }
+
+    /// The world as a new numbered frame: the changes since the last frame
+    /// acknowledged with `ack_frame`, or the whole world in the
+    /// `render_state` format when there is none, a keyframe was requested
+    /// or a new generation started. Deltas are much smaller than snapshots
+    /// of worlds with lots of food, but don't carry debug vision.
+    pub fn frame(&mut self) -> Result<JsValue, JsError> {
+        let frame = self
+            .frames
+            .frame(self.sim.world(), self.sim.generation(), self.debug_vision);
+        to_js(&frame)
+    }
+
+    /// Tells `frame` that the frontend has applied `frame`, so later
+    /// deltas can be relative to it. Returns false for frames that can't
+    /// be acknowledged any more, see `FrameStream::ack`.
+    pub fn ack_frame(&mut self, frame: u64) -> bool {
+        self.frames.ack(frame)
+    }
+
+    /// Makes the next `frame` a keyframe, e.g. after the frontend lost
+    /// track of the world.
+    pub fn request_keyframe(&mut self) {
+        self.frames.request_keyframe();
+    }
+
+    /// When enabled, `render_state()` includes each animal's eye geometry and
+    /// current receptor activations.
+    pub fn set_debug_vision(&mut self, debug_vision: bool) {
+        self.debug_vision = debug_vision;
+    }
+
+    /// Returns the index of the animal nearest to `(x, y)` within `radius`,
+    /// in world coordinates.
+    pub fn animal_at(&self, x: sim::Real, y: sim::Real, radius: sim::Real) -> Option<usize> {
+        self.sim.world().animal_at(na::Point2::new(x, y), radius)
+    }
+
+    /// Drops a piece of food at `(x, y)`, in world coordinates.
+    pub fn add_food(&mut self, x: sim::Real, y: sim::Real) {
+        self.sim.add_food(na::Point2::new(x, y));
+    }
+
+    pub fn clear_food(&mut self) {
+        self.sim.clear_food();
+    }
+
+    /// Number of genes `add_animal_with_brain` expects.
+    pub fn genome_length(&self) -> usize {
+        self.sim.config().genome_length()
+    }
+
+    /// Inserts an animal built from a saved genome, returning its id.
+    pub fn add_animal_with_brain(&mut self, genome: Vec<f64>) -> Result<u64, JsError> {
+        let len = genome.len();
+        self.sim
+            .add_animal_with_brain(&mut self.rng, genome)
+            .ok_or_else(|| {
+                JsError::new(&format!(
+                    "expected {} genes, got {len}",
+                    self.sim.config().genome_length()
+                ))
+            })
+    }
+
+    /// The current generation's fittest animal's brain as JSON, for
+    /// `load_brain_json`.
+    pub fn best_brain_json(&self) -> Result<Option<String>, JsError> {
+        Brain::champion(&self.sim)
+            .map(|brain| serde_json::to_string(&brain))
+            .transpose()
+            .map_err(|err| JsError::new(&format!("failed to serialize brain: {err}")))
+    }
+
+    /// Starts a new population seeded from a brain exported with
+    /// `best_brain_json`. The brain's inputs and outputs must match the
+    /// current game mode, eye and control scheme.
+    pub fn load_brain_json(&mut self, json: &str) -> Result<(), JsError> {
+        let brain: Brain = serde_json::from_str(json)?;
+        let config = brain
+            .into_brain_config(self.sim.config())
+            .map_err(|err| JsError::new(&err))?;
+        self.sim.reseed(&mut self.rng, config)?;
+        self.request_keyframe();
+        Ok(())
+    }
+
+    /// Removes the animal with `id`, as in `inspect_state`, until the next
+    /// generation; its fitness so far still counts at evolution time. The
+    /// animals after it shift down, so the next `frame` is a keyframe.
+    pub fn kill_animal(&mut self, id: u64) -> bool {
+        let killed = self.sim.kill_animal(id);
+        if killed {
+            self.request_keyframe();
+        }
+        killed
     }
 
-    pub fn world(&self) -> JsValue {
-        let world = World::from(self.sim.world());
-        to_value(&world).unwrap()
+    /// See `lib_simulation::Simulation::state_hash`.
+    pub fn state_hash(&self) -> u64 {
+        self.sim.state_hash()
+    }
+
+    /// See `lib_simulation::World::hash`.
+    pub fn world_hash(&self) -> u64 {
+        self.sim.world().hash()
     }
 
     pub fn generation(&self) -> u32 {
@@ -62,55 +241,406 @@ impl Simulation {
         self.sim.generation_steps()
     }
 
-    pub fn prev_generation_statistics(&self) -> JsValue {
-        if let Some(stats) = self.sim.prev_generation_statistics() {
-            let stats = GenerationStatistics::from(stats);
-            to_value(&stats).unwrap()
-        } else {
-            let stats: Option<GenerationStatistics> = None;
-            to_value(&stats).unwrap()
-        }
+    pub fn rollout(&self) -> u32 {
+        self.sim.rollout()
+    }
+
+    /// Steps left before the current generation evolves, at most; see
+    /// `lib_simulation::Simulation::steps_remaining_in_generation`.
+    /// `undefined` when generations only end manually.
+    pub fn steps_remaining_in_generation(&self) -> Option<u32> {
+        self.sim.steps_remaining_in_generation()
+    }
+
+    pub fn prev_generation_statistics(&self) -> Result<JsValue, JsError> {
+        let stats = self
+            .sim
+            .prev_generation_statistics()
+            .map(GenerationStatistics::from);
+        to_js(&stats)
+    }
+
+    /// Grid of how many steps animals spent in each part of the world this
+    /// generation so far, as `cols`, `rows` and `counts` row after row.
+    pub fn heatmap(&self) -> Result<JsValue, JsError> {
+        to_js(&Heatmap::from(self.sim.heatmap()))
+    }
+
+    /// `heatmap` of the previous generation; `null` during the first.
+    pub fn prev_heatmap(&self) -> Result<JsValue, JsError> {
+        to_js(&self.sim.prev_heatmap().map(Heatmap::from))
+    }
+
+    /// Starts `heatmap` over, e.g. after changing the world mid-generation.
+    pub fn reset_heatmap(&mut self) {
+        self.sim.reset_heatmap();
+    }
+
+    /// Gene statistics of `generation`, or of the latest generation when
+    /// omitted; `null` if that generation hasn't finished yet or is older
+    /// than `genome_history` generations.
+    pub fn gene_statistics(&self, generation: Option<usize>) -> Result<JsValue, JsError> {
+        let history = self.sim.generation_statistics();
+        let stats = match generation {
+            Some(generation) => history.get(generation),
+            None => history.last(),
+        };
+        let stats = stats
+            .filter(|stats| !stats.genes.is_empty())
+            .map(|stats| GeneStatistics::from(&stats.genes));
+        to_js(&stats)
+    }
+
+    /// The full statistics history as CSV, one row per generation, ready to
+    /// be downloaded.
+    pub fn statistics_csv(&self) -> String {
+        self.sim.statistics_csv()
+    }
+
+    pub fn set_min_speed(&mut self, min_speed: f64) -> Result<(), JsError> {
+        self.update_physics(|physics| physics.min_speed = min_speed)
+    }
+
+    pub fn set_max_speed(&mut self, max_speed: f64) -> Result<(), JsError> {
+        self.update_physics(|physics| physics.max_speed = max_speed)
+    }
+
+    pub fn set_max_accel(&mut self, max_accel: f64) -> Result<(), JsError> {
+        self.update_physics(|physics| physics.max_accel = max_accel)
+    }
+
+    pub fn set_max_angular_accel(&mut self, max_angular_accel: f64) -> Result<(), JsError> {
+        self.update_physics(|physics| physics.max_angular_accel = max_angular_accel)
+    }
+
+    /// Makes every animal remember its last `length` positions, included as
+    /// `trail` in the animals of `render_state` and keyframes so the
+    /// frontend can draw motion trails. Zero turns trails off.
+    pub fn set_trail_length(&mut self, length: usize) -> Result<(), JsError> {
+        Ok(self.sim.set_trail_length(length)?)
+    }
+
+    /// Changes how many steps each generation lasts, from the next step on.
+    pub fn set_generation_length(&mut self, steps: u32) -> Result<(), JsError> {
+        Ok(self.sim.set_generation_length(steps)?)
+    }
+
+    /// Replaces the genetic operators from a JSON `OperatorConfig`, e.g.
+    /// `{"selection": "Tournament"}`, used from the next evolution on.
+    pub fn set_operators_json(&mut self, json: &str) -> Result<(), JsError> {
+        let operators: sim::OperatorConfig = serde_json::from_str(json)?;
+        Ok(self.sim.set_operators(operators)?)
     }
 
     pub fn step(&mut self) {
         self.sim.step(&mut self.rng);
     }
-}
 
-impl From<&sim::GenerationStatistics> for GenerationStatistics {
-    fn from(value: &sim::GenerationStatistics) -> Self {
-        GenerationStatistics {
-            max_fitness: value.max_fitness,
-            min_fitness: value.min_fitness,
-            mean_fitness: value.mean_fitness,
-            std_fitness: value.std_fitness,
+    /// Like `step`, returning what changed as a list of `WorldChange`s so
+    /// the frontend can patch its copy of the world instead of reading
+    /// `render_state`. Hues change when a generation ends, which the list
+    /// doesn't cover; reread `render_state` then.
+    pub fn step_diff(&mut self) -> Result<JsValue, JsError> {
+        let before = sim::WorldOutline::of(self.sim.world());
+        self.sim.step(&mut self.rng);
+        let after = self.sim.world();
+        let changes: Vec<WorldChange> = sim::WorldDiff::since(&before, after)
+            .changes()
+            .iter()
+            .map(|change| WorldChange::from_change(change, after))
+            .collect();
+        to_js(&changes)
+    }
+
+    /// Name of the phase `step_phase` runs next: `"EatFood"`,
+    /// `"ProcessBrains"`, `"MoveAnimals"` or `"Evolve"`.
+    pub fn phase(&self) -> Result<JsValue, JsError> {
+        to_js(&self.sim.phase())
+    }
+
+    /// Runs the next phase of the current step and returns its name, so
+    /// the frontend can change the world in between phases.
+    pub fn step_phase(&mut self) -> Result<JsValue, JsError> {
+        to_js(&self.sim.step_phase(&mut self.rng))
+    }
+
+    /// Runs only the eating phase, outside of the step sequence: the step
+    /// counter and `phase()` are left alone.
+    pub fn eat_food(&mut self) {
+        self.sim.eat_food(&mut self.rng);
+    }
+
+    /// Like `eat_food`, for the brain phase.
+    pub fn process_brains(&mut self) {
+        self.sim.process_brains(&mut self.rng);
+    }
+
+    /// Like `eat_food`, for the movement phase.
+    pub fn move_animals(&mut self) {
+        self.sim.move_animals();
+    }
+
+    /// Ends the current generation now, evolving a new population.
+    pub fn evolve(&mut self) {
+        self.sim.evolve(&mut self.rng);
+    }
+
+    /// Makes the next step evolve, regardless of the generation end policy.
+    pub fn force_evolve(&mut self) {
+        self.sim.force_evolve();
+    }
+
+    /// Adds an arena running its own population from a JSON config and
+    /// returns its index. Arena 0 is this simulation's main world.
+    pub fn add_arena_config_json(&mut self, json: &str) -> Result<usize, JsError> {
+        let config = sim::Config::from_json_str(json)?;
+        let arena = sim::Simulation::from_config(&mut self.rng, config);
+        self.arenas.push(arena);
+        Ok(self.arenas.len())
+    }
+
+    /// Number of arenas, including the main world.
+    pub fn num_arenas(&self) -> usize {
+        self.arenas.len() + 1
+    }
+
+    /// Removes arena `idx`; later arenas shift down by one. The main world
+    /// can't be removed.
+    pub fn remove_arena(&mut self, idx: usize) -> Result<(), JsError> {
+        if idx == 0 || idx > self.arenas.len() {
+            return Err(JsError::new(&format!("no removable arena {idx}")));
         }
+        self.arenas.remove(idx - 1);
+        Ok(())
     }
-}
 
-impl From<&sim::World> for World {
-    fn from(world: &sim::World) -> Self {
-        let animals = world.animals().iter().map(Animal::from).collect();
-        let food = world.food().iter().map(Food::from).collect();
-        Self { animals, food }
+    /// Same payload as `render_state`, for arena `idx`.
+    pub fn world(&self, idx: usize) -> Result<JsValue, JsError> {
+        let arena = self.arena(idx)?;
+        let world = World::from_world(arena.world(), self.debug_vision);
+        to_js(&world)
+    }
+
+    /// Same as `prev_generation_statistics`, for arena `idx`.
+    pub fn arena_generation_statistics(&self, idx: usize) -> Result<JsValue, JsError> {
+        let stats = self
+            .arena(idx)?
+            .prev_generation_statistics()
+            .map(GenerationStatistics::from);
+        to_js(&stats)
+    }
+
+    /// Steps every arena once.
+    pub fn step_all(&mut self) {
+        self.sim.step(&mut self.rng);
+        for arena in &mut self.arenas {
+            arena.step(&mut self.rng);
+        }
+    }
+
+    /// Serializes the full simulation state, including every brain and the
+    /// statistics history, into bytes that can be transferred to or from a
+    /// Web Worker. Only covers the main world, not extra arenas. See
+    /// `lib_simulation::Simulation::to_snapshot`.
+    pub fn export_state(&self) -> Vec<u8> {
+        self.sim.to_snapshot()
+    }
+
+    /// Replaces the simulation state with one produced by `export_state`,
+    /// failing with the reason when it was saved by a newer version or
+    /// was modified.
+    pub fn import_state(&mut self, bytes: &[u8]) -> Result<(), JsError> {
+        self.sim = sim::Simulation::from_snapshot(bytes)?;
+        self.request_keyframe();
+        Ok(())
+    }
+
+    /// Serializes the state like `export_state` and returns how many chunks
+    /// of at most 1 MiB it takes, so large states can be streamed out (e.g.
+    /// into IndexedDB) with `export_state_chunk` without one huge copy into
+    /// JS. Chunks always come from the state as of the latest call.
+    /// Concatenated, they can be passed to `import_state`.
+    pub fn num_state_chunks(&mut self) -> usize {
+        self.exported_state = self.sim.to_snapshot();
+        self.exported_state.len().div_ceil(STATE_CHUNK_SIZE)
+    }
+
+    /// Chunk `idx` of the state serialized by `num_state_chunks`.
+    pub fn export_state_chunk(&self, idx: usize) -> Result<Vec<u8>, JsError> {
+        self.exported_state
+            .chunks(STATE_CHUNK_SIZE)
+            .nth(idx)
+            .map(<[u8]>::to_vec)
+            .ok_or_else(|| JsError::new(&format!("no state chunk {idx}")))
+    }
+
+    /// Runs `generations` whole generations and returns their statistics.
+    /// `progress`, if given, is called as `progress(done, statistics)` after
+    /// each generation; an exception it throws stops training and is
+    /// rethrown, with the generations before it kept.
+    pub fn train(
+        &mut self,
+        generations: u32,
+        progress: Option<js_sys::Function>,
+    ) -> Result<JsValue, JsValue> {
+        let mut history = Vec::with_capacity(generations as usize);
+        for done in 1..=generations {
+            let stats = GenerationStatistics::from(self.sim.train(&mut self.rng));
+            if let Some(progress) = &progress {
+                progress.call2(&JsValue::NULL, &done.into(), &to_js(&stats)?)?;
+            }
+            history.push(stats);
+        }
+        Ok(to_js(&history)?)
     }
 }
 
-impl From<&sim::Animal> for Animal {
-    fn from(animal: &sim::Animal) -> Self {
+impl Simulation {
+    fn with_game_mode(mode: sim::GameMode, seed: Option<u64>) -> Self {
+        Self::with_config(
+            sim::Config {
+                mode,
+                ..sim::Config::default()
+            },
+            seed,
+        )
+    }
+
+    fn arena(&self, idx: usize) -> Result<&sim::Simulation, JsError> {
+        match idx {
+            0 => Ok(&self.sim),
+            _ => self
+                .arenas
+                .get(idx - 1)
+                .ok_or_else(|| JsError::new(&format!("no arena {idx}"))),
+        }
+    }
+
+    fn update_physics(
+        &mut self,
+        update: impl FnOnce(&mut sim::PhysicsConfig),
+    ) -> Result<(), JsError> {
+        let mut physics = self.sim.config().physics.clone();
+        update(&mut physics);
+        Ok(self.sim.set_physics(physics)?)
+    }
+
+    fn with_config(config: sim::Config, seed: Option<u64>) -> Self {
+        let mut rng = vroom_rand::seeded_or_random(seed);
+        let sim = sim::Simulation::from_config(&mut rng, config);
         Self {
-            x: animal.position().x,
-            y: animal.position().y,
-            rotation: animal.rotation().angle(),
+            rng,
+            sim,
+            arenas: Vec::new(),
+            debug_vision: false,
+            exported_state: Vec::new(),
+            frames: FrameStream::default(),
         }
     }
 }
 
-impl From<&sim::Food> for Food {
-    fn from(food: &sim::Food) -> Self {
+impl Simulation {
+    fn with_map(map: &sim::Map, mode: sim::GameMode, seed: Option<u64>) -> Self {
+        let mut rng = vroom_rand::seeded_or_random(seed);
+        let config = sim::Config {
+            mode,
+            ..sim::Config::default()
+        };
+        let sim = sim::Simulation::from_map(&mut rng, map, config);
         Self {
-            x: food.position().x,
-            y: food.position().y,
+            rng,
+            sim,
+            arenas: Vec::new(),
+            debug_vision: false,
+            exported_state: Vec::new(),
+            frames: FrameStream::default(),
         }
     }
 }
+
+/// Runs when the module is instantiated. Panics, e.g. from bugs in the
+/// simulation, still abort the module, but at least log their message and
+/// location to the browser console instead of an opaque `unreachable`.
+#[wasm_bindgen(start)]
+pub fn start() {
+    console_error_panic_hook::set_once();
+}
+
+/// Logs the simulation's `tracing` spans and events up to `max_level`
+/// (`"error"` to `"trace"`, default `"debug"`) to the browser console, and
+/// marks spans on the performance timeline. Works once per page.
+#[cfg(feature = "tracing")]
+#[wasm_bindgen]
+pub fn enable_tracing(max_level: Option<String>) -> Result<(), JsError> {
+    use tracing_subscriber::layer::SubscriberExt;
+
+    let max_level = match max_level {
+        Some(level) => level
+            .parse()
+            .map_err(|_| JsError::new(&format!("unknown tracing level: {level}")))?,
+        None => tracing::Level::DEBUG,
+    };
+    let config = tracing_wasm::WASMLayerConfigBuilder::new()
+        .set_max_level(max_level)
+        .build();
+    let subscriber =
+        tracing_subscriber::Registry::default().with(tracing_wasm::WASMLayer::new(config));
+    tracing::subscriber::set_global_default(subscriber)
+        .map_err(|_| JsError::new("tracing is already enabled"))
+}
+
+/// Converts `value` for JS, failing with what couldn't be converted rather
+/// than panicking, which would leave the whole module unusable.
+fn to_js<T: Serialize + ?Sized>(value: &T) -> Result<JsValue, JsError> {
+    to_value(value).map_err(|err| {
+        JsError::new(&format!(
+            "failed to convert {} to JS: {err}",
+            std::any::type_name::<T>()
+        ))
+    })
+}
+
+fn parse_game_mode(mode: &str) -> Result<sim::GameMode, JsError> {
+    match mode {
+        "eat" => Ok(sim::GameMode::Eat),
+        "forage" => Ok(sim::GameMode::Forage),
+        _ => Err(JsError::new(&format!("unknown game mode: {mode}"))),
+    }
+}
+
+impl Default for Simulation {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+impl From<&sim::GeneStatistics> for GeneStatistics {
+    fn from(value: &sim::GeneStatistics) -> Self {
+        GeneStatistics {
+            mean: value.mean.clone(),
+            std: value.std.clone(),
+        }
+    }
+}
+
+impl From<&sim::World> for InspectState {
+    fn from(world: &sim::World) -> Self {
+        let animals = world
+            .animals()
+            .iter()
+            .map(|animal| AnimalDetails {
+                id: animal.id(),
+                x: animal.position().x,
+                y: animal.position().y,
+                rotation: animal.rotation().angle(),
+                speed: animal.speed(),
+                consumed: animal.consumed(),
+                carrying: animal.carrying(),
+                deposited: animal.deposited(),
+                vision: Vision::from(animal),
+            })
+            .collect();
+        let food = world.food().iter().map(Food::from).collect();
+        Self { animals, food }
+    }
+}
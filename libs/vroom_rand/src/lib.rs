@@ -0,0 +1,34 @@
+//! Random number generation as the workspace's public APIs take it.
+//!
+//! Everything that takes or hands out a generator uses these re-exports,
+//! so depending on this crate, rather than on a particular `rand` version,
+//! keeps downstream code building when the workspace upgrades `rand`.
+
+pub use rand::{RngCore, SeedableRng};
+pub use rand_chacha::ChaCha8Rng;
+
+/// Generator for reproducible runs, the same on every platform for a given
+/// `seed`.
+pub fn seeded(seed: u64) -> ChaCha8Rng {
+    ChaCha8Rng::seed_from_u64(seed)
+}
+
+/// `seeded(seed)` with a seed, otherwise a generator seeded by the
+/// operating system.
+pub fn seeded_or_random(seed: Option<u64>) -> Box<dyn RngCore> {
+    match seed {
+        Some(seed) => Box::new(seeded(seed)),
+        None => Box::new(rand::thread_rng()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seeded() {
+        let mut a = seeded_or_random(Some(3));
+        assert_eq!(a.next_u64(), seeded(3).next_u64());
+    }
+}
@@ -2,11 +2,26 @@ import * as sim from "lib_simulation_wasm";
 import { SimulationView } from "./simulation";
 
 const simulation = new sim.Simulation();
+simulation.set_debug_vision(true);
 
 const simulationView = new SimulationView(
   document.getElementById("simulation")
 );
 
+const SELECTION_RADIUS = 0.03;
+let selectedAnimal = undefined;
+
+document.getElementById("simulation").addEventListener("click", (event) => {
+  const { x, y } = simulationView.toWorldCoordinates(event);
+  selectedAnimal = simulation.animal_at(x, y, SELECTION_RADIUS);
+});
+
+document.getElementById("train").addEventListener("click", () => {
+  simulation.train(10, (done, stats) => {
+    console.log(`Trained generation ${done}/10`, stats);
+  });
+});
+
 function setControllerText(text) {
   const controllerTextElement = document.getElementById("controllerText");
   const formattedText = text.replace(/\n/g, "<br>");
@@ -14,7 +29,6 @@ function setControllerText(text) {
 }
 
 function redraw() {
-  simulationView.reset();
   for (let i = 0; i < 1; i++) {
     simulation.step();
   }
@@ -23,15 +37,30 @@ function redraw() {
   let min_fitness = null;
   let mean_fitness = null;
   let std_fitness = null;
+  let mean_pairwise_distance = null;
+  let mean_gene_variance = null;
   if (stats) {
     max_fitness = stats.max_fitness;
     min_fitness = stats.min_fitness;
     mean_fitness = stats.mean_fitness;
     std_fitness = stats.std_fitness;
+    mean_pairwise_distance = stats.mean_pairwise_distance;
+    mean_gene_variance = stats.mean_gene_variance;
   }
 
-  simulationView.drawAnimals(simulation.world().animals);
-  simulationView.drawFood(simulation.world().food);
+  const world = simulation.render_state();
+  simulationView.reset(world.width, world.height);
+  if (world.walls) {
+    simulationView.drawWalls(world.walls);
+  }
+  if (world.nest) {
+    simulationView.drawNest(world.nest);
+  }
+  if (selectedAnimal !== undefined && world.animals[selectedAnimal].vision) {
+    simulationView.drawVision(world.animals[selectedAnimal]);
+  }
+  simulationView.drawAnimals(world.animals, selectedAnimal);
+  simulationView.drawFood(world.food);
   requestAnimationFrame(redraw);
 
   let text = `Generation: ${simulation.generation()}\n`;
@@ -41,6 +70,21 @@ function redraw() {
   text += `Min fitness: ${min_fitness}\n`;
   text += `Mean fitness: ${mean_fitness}\n`;
   text += `Std fitness: ${std_fitness}\n`;
+  text += `Mean pairwise distance: ${mean_pairwise_distance}\n`;
+  text += `Mean gene variance: ${mean_gene_variance}\n`;
+  if (stats) {
+    const behavior = stats.behavior;
+    text += `Mean distance traveled: ${behavior.mean_distance_traveled}\n`;
+    text += `Mean speed: ${behavior.mean_speed}\n`;
+    text += `Mean turn entropy: ${behavior.mean_turn_entropy}\n`;
+    text += `Mean time to first food: ${behavior.mean_time_to_first_food}\n`;
+    text += `Fed fraction: ${behavior.fed_fraction}\n`;
+    text += `Mean meal interval: ${behavior.mean_meal_interval}\n`;
+    const utilization = behavior.receptor_utilization
+      .map((fraction) => fraction.toFixed(2))
+      .join(" ");
+    text += `Receptor utilization: ${utilization}\n`;
+  }
   setControllerText(text);
 }
 
@@ -3,24 +3,33 @@ export class SimulationView {
     this.el = el;
   }
 
-  reset() {
+  // Fits a world of the given size into the window, keeping its aspect
+  // ratio.
+  reset(worldWidth, worldHeight) {
     const pixelRatio = window.devicePixelRatio || 1;
 
-    const size = Math.min(window.innerWidth - 500, window.innerHeight - 50);
+    const scale = Math.min(
+      (window.innerWidth - 500) / worldWidth,
+      (window.innerHeight - 50) / worldHeight
+    );
+    const width = worldWidth * scale;
+    const height = worldHeight * scale;
 
-    this.el.width = size * pixelRatio;
-    this.el.height = size * pixelRatio;
-    this.el.style.width = size + "px";
-    this.el.style.height = size + "px";
+    this.worldWidth = worldWidth;
+    this.worldHeight = worldHeight;
+    this.scale = scale * pixelRatio;
+    this.el.width = width * pixelRatio;
+    this.el.height = height * pixelRatio;
+    this.el.style.width = width + "px";
+    this.el.style.height = height + "px";
 
     this.ctxt = this.el.getContext("2d");
-    this.ctxt.clearRect(0, 0, this.el.width, this.el.width);
+    this.ctxt.clearRect(0, 0, this.el.width, this.el.height);
   }
 
-  fillAnimal(x, y, rotation) {
-    const ANIMAL_SIZE = 0.01;
-    const ANIMAL_COLOR = "#758b9e";
-    let size = ANIMAL_SIZE * this.el.width;
+  fillAnimal(x, y, radius, rotation, hue, selected) {
+    const SELECTED_ANIMAL_COLOR = "#c0392b";
+    let size = radius * this.scale;
     let headAngle = rotation;
     let leg1Angle = rotation + (14 * Math.PI) / 18; // +140 degrees
     let leg2Angle = rotation - (14 * Math.PI) / 18; // -140 degrees
@@ -42,33 +51,111 @@ export class SimulationView {
       x + Math.cos(headAngle) * size,
       y + Math.sin(headAngle) * size
     );
-    this.ctxt.fillStyle = ANIMAL_COLOR;
+    this.ctxt.fillStyle = selected
+      ? SELECTED_ANIMAL_COLOR
+      : `hsl(${hue}, 35%, 50%)`;
     this.ctxt.fill();
   }
 
-  drawAnimals(animals) {
-    for (const animal of animals) {
+  drawAnimals(animals, selectedAnimal) {
+    animals.forEach((animal, idx) => {
       this.fillAnimal(
-        animal.x * this.el.width,
-        animal.y * this.el.height,
-        animal.rotation
+        animal.x * this.scale,
+        animal.y * this.scale,
+        animal.radius,
+        animal.rotation,
+        animal.hue,
+        idx === selectedAnimal
       );
-    }
+    });
+  }
+
+  // Draws the vision cone of an animal, shading each receptor's sector by
+  // how close the food it sees is.
+  drawVision(animal) {
+    const VISION_COLOR = "rgba(192, 57, 43, 0.08)";
+    const RECEPTOR_COLOR = "rgba(192, 57, 43, 0.35)";
+    const { fov_range, fov_angle, receptors } = animal.vision;
+    const x = animal.x * this.scale;
+    const y = animal.y * this.scale;
+    const range = fov_range * this.scale;
+    const start = animal.rotation - fov_angle / 2;
+
+    this.ctxt.beginPath();
+    this.ctxt.moveTo(x, y);
+    this.ctxt.arc(x, y, range, start, start + fov_angle);
+    this.ctxt.closePath();
+    this.ctxt.fillStyle = VISION_COLOR;
+    this.ctxt.fill();
+
+    const anglePerReceptor = fov_angle / receptors.length;
+    receptors.forEach((activation, idx) => {
+      if (activation > 1.0) {
+        return;
+      }
+      const receptorStart = start + idx * anglePerReceptor;
+      this.ctxt.beginPath();
+      this.ctxt.moveTo(x, y);
+      this.ctxt.arc(
+        x,
+        y,
+        activation * range,
+        receptorStart,
+        receptorStart + anglePerReceptor
+      );
+      this.ctxt.closePath();
+      this.ctxt.fillStyle = RECEPTOR_COLOR;
+      this.ctxt.fill();
+    });
+  }
+
+  // Converts a mouse event into world coordinates.
+  toWorldCoordinates(event) {
+    const rect = this.el.getBoundingClientRect();
+    return {
+      x: ((event.clientX - rect.left) / rect.width) * this.worldWidth,
+      y: ((event.clientY - rect.top) / rect.height) * this.worldHeight,
+    };
   }
 
-  fillFood(x, y) {
-    const FOOD_SIZE = 0.003;
+  fillFood(x, y, radius) {
     const FOOD_COLOR = "#b4a794";
-    let size = FOOD_SIZE * this.el.width;
+    let size = radius * this.scale;
     this.ctxt.beginPath();
     this.ctxt.arc(x, y, size, 0, 2 * Math.PI);
     this.ctxt.fillStyle = FOOD_COLOR;
     this.ctxt.fill();
   }
 
+  drawWalls(walls) {
+    const WALL_COLOR = "#333";
+    this.ctxt.beginPath();
+    for (const wall of walls) {
+      this.ctxt.moveTo(wall.x1 * this.scale, wall.y1 * this.scale);
+      this.ctxt.lineTo(wall.x2 * this.scale, wall.y2 * this.scale);
+    }
+    this.ctxt.strokeStyle = WALL_COLOR;
+    this.ctxt.lineWidth = 2;
+    this.ctxt.stroke();
+  }
+
+  drawNest(nest) {
+    const NEST_COLOR = "rgba(117, 139, 158, 0.3)";
+    this.ctxt.beginPath();
+    this.ctxt.arc(
+      nest.x * this.scale,
+      nest.y * this.scale,
+      nest.radius * this.scale,
+      0,
+      2 * Math.PI
+    );
+    this.ctxt.fillStyle = NEST_COLOR;
+    this.ctxt.fill();
+  }
+
   drawFood(food) {
     for (const f of food) {
-      this.fillFood(f.x * this.el.width, f.y * this.el.height);
+      this.fillFood(f.x * this.scale, f.y * this.scale, f.radius);
     }
   }
 }